@@ -0,0 +1,152 @@
+//! The Python bindings for the mapping core of the `custom_project` filter,
+//! so the Python Regolith filters can route the files to the packs using the
+//! same rules as the filter.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use cpf::config::Config;
+use cpf::mapping::{self, Pack};
+use cpf::plan::PlannedFile;
+use cpf::CpfError;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+create_exception!(
+    custom_project, CustomProjectError, PyException,
+    "The error of the filter. The exit code of the filter for the error is \
+    stored in the `exit_code` attribute."
+);
+
+/// Converts the error of the filter to the Python exception.
+fn to_py_err(error: CpfError) -> PyErr {
+    let exit_code = error.exit_code();
+    let err = CustomProjectError::new_err(error.to_string());
+    Python::with_gil(|py| {
+        err.value_bound(py).setattr("exit_code", exit_code)
+    }).map(|_| err).unwrap_or_else(|e| e)
+}
+
+/// Returns the path as a string with "/" as the separator, so the paths are
+/// the same on all platforms.
+fn slash_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Returns the path to the pack file (e.g. "BP/entities/zombie.bpe.json")
+/// that the file is mapped to by the rules or None if it can't be mapped.
+///
+/// Arguments:
+/// - `path` - the path to the file relative to its root
+/// - `export_map` - the rules, e.g. `{".bpe.json": "BP/entities"}`
+#[pyfunction]
+fn get_target_path(
+    path: PathBuf, export_map: HashMap<String, String>,
+) -> Option<String> {
+    mapping::get_target_path_from_hash_map(&path, &export_map)
+        .map(|target| slash_path(&target))
+}
+
+/// A file of the data directory together with its target in the packs.
+#[pyclass(name = "PlannedFile", module = "custom_project", frozen, get_all)]
+struct PyPlannedFile {
+    /// The path to the source file.
+    source: String,
+    /// The path to the pack file or None if the file can't be mapped.
+    target: Option<String>,
+    /// The rule that mapped the file (e.g. ".bpe.json").
+    rule: Option<String>,
+    /// "BP", "RP" or None if the target isn't in one of the packs.
+    pack: Option<String>,
+    /// The directory of the pack that contains the target (e.g.
+    /// "entities").
+    category: Option<String>,
+}
+
+#[pymethods]
+impl PyPlannedFile {
+    fn __repr__(&self) -> String {
+        format!(
+            "PlannedFile(source={:?}, target={:?})", self.source, self.target
+        )
+    }
+}
+
+impl From<PlannedFile> for PyPlannedFile {
+    fn from(file: PlannedFile) -> PyPlannedFile {
+        let target = file.target.as_ref();
+        PyPlannedFile {
+            source: slash_path(&file.source),
+            target: target.map(|t| slash_path(&t.path)),
+            rule: target.map(|t| t.rule.clone()),
+            pack: target.and_then(|t| match t.pack {
+                Pack::Behavior => Some("BP".to_string()),
+                Pack::Resource => Some("RP".to_string()),
+                Pack::Other => None,
+            }),
+            category: target.map(|t| t.category.clone()),
+        }
+    }
+}
+
+/// Lists the files of the data directory and maps them to the packs with
+/// the rules from the config file, without copying anything.
+#[pyclass(name = "Planner", module = "custom_project")]
+struct PyPlanner {
+    working_dir: PathBuf,
+    config: Config,
+}
+
+#[pymethods]
+impl PyPlanner {
+    /// Loads the config file of the filter from the working directory of
+    /// Regolith (the current directory by default).
+    #[new]
+    #[pyo3(signature = (working_dir = PathBuf::new()))]
+    fn new(working_dir: PathBuf) -> PyResult<PyPlanner> {
+        let config = Config::load(&working_dir).map_err(to_py_err)?;
+        Ok(PyPlanner { working_dir, config })
+    }
+
+    /// Creates the planner with the config file from a string, without
+    /// reading it from the data directory.
+    #[staticmethod]
+    #[pyo3(signature = (text, working_dir = PathBuf::new()))]
+    fn from_config_text(
+        text: &str, working_dir: PathBuf,
+    ) -> PyResult<PyPlanner> {
+        let config = Config::parse(text).map_err(to_py_err)?;
+        Ok(PyPlanner { working_dir, config })
+    }
+
+    /// Lists and maps the files of all of the roots.
+    fn plan(&self, py: Python<'_>) -> PyResult<Vec<PyPlannedFile>> {
+        let planner =
+            cpf::Planner::from_config(&self.working_dir, &self.config);
+        let files = py.allow_threads(|| planner.plan()).map_err(to_py_err)?;
+        Ok(files.into_iter().map(PyPlannedFile::from).collect())
+    }
+
+    /// Maps a list of paths (relative to the root) without accessing the
+    /// file system.
+    fn plan_paths(
+        &self, root: &str, paths: Vec<PathBuf>,
+    ) -> Vec<PyPlannedFile> {
+        cpf::Planner::from_config(&self.working_dir, &self.config)
+            .plan_paths(root, &paths)
+            .into_iter()
+            .map(PyPlannedFile::from)
+            .collect()
+    }
+}
+
+#[pymodule]
+fn custom_project(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(get_target_path, m)?)?;
+    m.add_class::<PyPlanner>()?;
+    m.add_class::<PyPlannedFile>()?;
+    m.add(
+        "CustomProjectError", m.py().get_type_bound::<CustomProjectError>()
+    )?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+import json
+
+import pytest
+
+import custom_project
+
+RULES = {".bpe.json": "BP/entities"}
+
+
+def write_project(working_dir, files):
+    data = working_dir / "data" / "custom_project"
+    config = {"roots": ["src"], "extensions_map": RULES}
+    data.mkdir(parents=True)
+    (data / "config.json").write_text(json.dumps(config))
+    for path, content in files.items():
+        source = data / "src" / path
+        source.parent.mkdir(parents=True, exist_ok=True)
+        source.write_text(content)
+
+
+def test_get_target_path_uses_the_rules():
+    target = custom_project.get_target_path("mobs/zombie/_.bpe.json", RULES)
+    assert target == "BP/entities/mobs/zombie.bpe.json"
+    assert custom_project.get_target_path("notes.txt", RULES) is None
+
+
+def test_planner_maps_the_files_of_the_data_directory(tmp_path):
+    write_project(tmp_path, {"zombie.bpe.json": "{}", "notes.txt": ""})
+    files = custom_project.Planner(tmp_path).plan()
+    targets = {f.source.rsplit("/", 1)[-1]: f for f in files}
+    zombie = targets["zombie.bpe.json"]
+    assert zombie.target == "BP/entities/zombie.bpe.json"
+    assert (zombie.rule, zombie.pack, zombie.category) == (
+        ".bpe.json", "BP", "entities"
+    )
+    assert targets["notes.txt"].target is None
+    # Nothing is copied
+    assert not (tmp_path / "BP").exists()
+
+
+def test_planner_maps_the_paths_without_the_file_system():
+    planner = custom_project.Planner.from_config_text(
+        json.dumps({"roots": ["src"], "extensions_map": RULES})
+    )
+    [file] = planner.plan_paths("src", ["zombie/_.bpe.json"])
+    assert file.target == "BP/entities/zombie.bpe.json"
+
+
+def test_errors_carry_the_exit_code(tmp_path):
+    with pytest.raises(custom_project.CustomProjectError) as error:
+        custom_project.Planner(tmp_path)
+    assert error.value.exit_code == 2
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{resource_reference, MapResult, Mapper, TargetInfo};
+use crate::plan::PlannedFile;
+use crate::png::{self, Image};
+
+/// The name of the rule of the atlases generated by the TextureAtlases.
+pub static ATLAS_RULE: &str = "(atlas)";
+
+/// The extension of the folders with the sprites of the atlases (e.g.
+/// "buttons.atlas").
+static ATLAS_EXTENSION: &str = "atlas";
+
+/// The suffix of the names of the files with the UVs of the sprites.
+static UV_SUFFIX: &str = ".atlas.json";
+
+/// Returns the name of the atlas of the sprites in the folder or None if
+/// the folder isn't an atlas folder.
+fn atlas_name(folder: &Path) -> Option<&str> {
+    let name = folder.file_name()?.to_str()?;
+    name.strip_suffix(ATLAS_EXTENSION)?.strip_suffix('.')
+        .filter(|name| !name.is_empty())
+}
+
+/// Reads the sprites (the PNG files) of the atlas folder sorted by their
+/// names.
+fn read_sprites(folder: &Path) -> Result<Vec<(String, Image)>, String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(folder)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file()
+            && path.extension().is_some_and(|e| e == "png"))
+        .collect();
+    paths.sort();
+    paths.iter().map(|path| {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let image = fs::read(path).map_err(|e| e.to_string())
+            .and_then(|data| png::decode(&data))
+            .map_err(|e| format!("\"{}\": {}", path.display(), e))?;
+        Ok((name, image))
+    }).collect()
+}
+
+/// Places the sprites of the sizes on the rows of the atlas, the tallest
+/// first. Returns the size of the atlas (rounded up to the powers of two)
+/// and the positions of the sprites.
+fn layout(sizes: &[(u32, u32)]) -> ((u32, u32), Vec<(u32, u32)>) {
+    let area: u64 = sizes.iter().map(|(w, h)| *w as u64 * *h as u64).sum();
+    let widest = sizes.iter().map(|(w, _)| *w).max().unwrap_or(1);
+    let width = widest.max((area as f64).sqrt().ceil() as u32)
+        .next_power_of_two();
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(sizes[*i].1));
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for i in order {
+        let (w, h) = sizes[i];
+        if x + w > width {
+            (x, y, row_height) = (0, y + row_height, 0);
+        }
+        positions[i] = (x, y);
+        x += w;
+        row_height = row_height.max(h);
+    }
+    let height = (y + row_height).max(1).next_power_of_two();
+    ((width, height), positions)
+}
+
+/// Stitches the sprites in the ".atlas" folders (e.g.
+/// "ui/buttons.atlas/ok.png") into single textures named after the folders
+/// (e.g. "RP/textures/ui/buttons.png") with the JSON files describing the
+/// UVs of the sprites (e.g. "RP/textures/ui/buttons.atlas.json"). The
+/// sprites aren't copied to the packs.
+pub struct TextureAtlases;
+
+impl Mapper for TextureAtlases {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The first sprite of every atlas and its target
+        let mut atlases: HashMap<PathBuf, (PathBuf, PathBuf)> = HashMap::new();
+        files.retain(|file| {
+            let (folder, target) = match (file.source.parent(), &file.target) {
+                (Some(folder), Some(target)) if file.source.extension()
+                    .is_some_and(|e| e == "png")
+                    && atlas_name(folder).is_some() => (folder, target),
+                _ => return true,
+            };
+            let atlas = atlases.entry(folder.to_path_buf()).or_insert_with(
+                || (file.source.clone(), target.path.clone())
+            );
+            if file.source < atlas.0 {
+                *atlas = (file.source.clone(), target.path.clone());
+            }
+            false
+        });
+        let mut atlases: Vec<_> = atlases.into_iter().collect();
+        atlases.sort();
+        for (folder, (source, target)) in atlases {
+            let name = atlas_name(&folder).unwrap();
+            let dir = match target.parent().and_then(|p| p.parent()) {
+                Some(dir) => dir,
+                None => continue,
+            };
+            let names = [
+                format!("{}.png", name), format!("{}{}", name, UV_SUFFIX),
+            ];
+            for file_name in names {
+                files.push(PlannedFile {
+                    source: source.clone(),
+                    target: Some(
+                        TargetInfo::new(ATLAS_RULE, dir.join(file_name))
+                    ),
+                });
+            }
+        }
+    }
+}
+
+/// Generates the texture of the atlas or the JSON file with the UVs of its
+/// sprites, based on the name of the target. The atlas uses all of the PNG
+/// files of the folder of the source.
+///
+/// # Arguments
+/// - `source` - the path to a sprite of the atlas
+/// - `target` - the path to the target of the atlas
+pub fn atlas_contents(
+    source: &Path, target: &Path,
+) -> Result<Vec<u8>, String> {
+    let folder = source.parent().ok_or("The sprite isn't in a folder.")?;
+    let sprites = read_sprites(folder)?;
+    let sizes: Vec<(u32, u32)> = sprites.iter()
+        .map(|(_, image)| (image.width, image.height))
+        .collect();
+    let ((width, height), positions) = layout(&sizes);
+    let name = target.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    match name.strip_suffix(UV_SUFFIX) {
+        Some(base_name) => {
+            let mut uvs = serde_json::Map::new();
+            for ((name, image), (x, y)) in sprites.iter().zip(&positions) {
+                uvs.insert(name.clone(), serde_json::json!({
+                    "uv": [x, y],
+                    "uv_size": [image.width, image.height],
+                }));
+            }
+            let texture = resource_reference(
+                &target.with_file_name(base_name), "textures"
+            );
+            let json = serde_json::json!({
+                "texture": texture,
+                "size": [width, height],
+                "sprites": uvs,
+            });
+            Ok(format!("{:#}\n", json).into_bytes())
+        },
+        None => {
+            let mut atlas = Image::new(width, height);
+            for ((_, image), (x, y)) in sprites.iter().zip(&positions) {
+                atlas.paste(image, *x, *y);
+            }
+            Ok(png::encode(&atlas))
+        },
+    }
+}
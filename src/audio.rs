@@ -0,0 +1,127 @@
+/// The number of the bytes at the start of the audio files that are read to
+/// find their format.
+pub const HEADER_SIZE: u64 = 64 * 1024;
+
+/// The sample rates that play correctly on all of the platforms.
+static SAFE_SAMPLE_RATES: &[u32] = &[22050, 32000, 44100, 48000];
+
+/// The maximal number of the channels that play correctly on all of the
+/// platforms.
+const MAX_CHANNELS: u32 = 2;
+
+/// The format of the audio file found in its header.
+struct AudioFormat {
+    channels: u32,
+    sample_rate: u32,
+}
+
+/// Reads the little-endian u16 at the offset.
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads the little-endian u32 at the offset.
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Finds the format of the .ogg file from the header of its first stream.
+/// Bedrock only plays the Vorbis streams, the Opus streams are rejected.
+fn ogg_format(data: &[u8]) -> Result<AudioFormat, String> {
+    let broken = || "The file isn't a valid Ogg file.".to_string();
+    if !data.starts_with(b"OggS") {
+        return Err(broken());
+    }
+    // The first packet follows the header of the page and its segment
+    // table
+    let segments = *data.get(26).ok_or_else(broken)? as usize;
+    let packet = data.get(27 + segments..).ok_or_else(broken)?;
+    if packet.starts_with(b"\x01vorbis") {
+        return Ok(AudioFormat {
+            channels: *packet.get(11).ok_or_else(broken)? as u32,
+            sample_rate: u32_at(packet, 12).ok_or_else(broken)?,
+        });
+    }
+    if packet.starts_with(b"OpusHead") {
+        return Err(
+            "The file is encoded with Opus, which Bedrock doesn't play. \
+            Encode it with Vorbis.".to_string()
+        );
+    }
+    Err("The file isn't encoded with Vorbis, which Bedrock expects."
+        .to_string())
+}
+
+/// Finds the format of the .wav file from its "fmt " chunk. Bedrock only
+/// plays the uncompressed PCM files reliably.
+fn wav_format(data: &[u8]) -> Result<AudioFormat, String> {
+    let broken = || "The file isn't a valid WAV file.".to_string();
+    if !data.starts_with(b"RIFF") || data.get(8..12) != Some(b"WAVE") {
+        return Err(broken());
+    }
+    let mut offset = 12;
+    loop {
+        let id = data.get(offset..offset + 4).ok_or_else(broken)?;
+        let size = u32_at(data, offset + 4).ok_or_else(broken)? as usize;
+        if id != b"fmt " {
+            // The chunks are padded to an even size
+            offset += 8 + size + size % 2;
+            continue;
+        }
+        let chunk = data.get(offset + 8..).ok_or_else(broken)?;
+        let mut encoding = u16_at(chunk, 0).ok_or_else(broken)?;
+        if encoding == 0xfffe {
+            // WAVE_FORMAT_EXTENSIBLE stores the encoding in the sub-format
+            encoding = u16_at(chunk, 24).ok_or_else(broken)?;
+        }
+        if encoding != 1 {
+            return Err(format!(
+                "The file isn't encoded with PCM (format tag {}), which \
+                Bedrock doesn't play reliably. Encode it as PCM.",
+                encoding
+            ));
+        }
+        return Ok(AudioFormat {
+            channels: u16_at(chunk, 2).ok_or_else(broken)? as u32,
+            sample_rate: u32_at(chunk, 4).ok_or_else(broken)?,
+        });
+    }
+}
+
+/// Checks the format of the .ogg or .wav file. Returns the descriptions of
+/// the problems (e.g. an Opus file or a sample rate that misbehaves on some
+/// platforms).
+///
+/// # Arguments
+/// - `extension` - the extension of the file ("ogg" or "wav")
+/// - `header` - the start of the file (at least `HEADER_SIZE` bytes unless
+///   the file is shorter)
+pub fn audio_problems(extension: &str, header: &[u8]) -> Vec<String> {
+    let format = match extension {
+        "ogg" => ogg_format(header),
+        "wav" => wav_format(header),
+        _ => return Vec::new(),
+    };
+    let format = match format {
+        Ok(format) => format,
+        Err(message) => return vec![message],
+    };
+    let mut problems = Vec::new();
+    if !SAFE_SAMPLE_RATES.contains(&format.sample_rate) {
+        problems.push(format!(
+            "The sample rate {} Hz misbehaves on some platforms. Use 44100 \
+            or 48000 Hz.",
+            format.sample_rate
+        ));
+    }
+    if format.channels > MAX_CHANNELS {
+        problems.push(format!(
+            "The file has {} channels, which misbehave on some platforms. \
+            Use mono or stereo.",
+            format.channels
+        ));
+    }
+    problems
+}
@@ -0,0 +1,739 @@
+use std::collections::{BTreeMap, HashMap};
+use std::iter::Peekable;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::CharIndices;
+
+use crate::config::ScriptBundle;
+use crate::index::slash_path;
+use crate::mapping::{resource_dir, MapResult, Mapper, Pack, TargetInfo};
+use crate::plan::PlannedFile;
+use crate::typescript::rewrite_specifier;
+
+/// The name of the rule of the bundles generated by the ScriptBundler.
+pub static BUNDLE_RULE: &str = "(bundle)";
+
+/// The functions of the bundle that load the modules and define their
+/// exports.
+static RUNTIME: &str = "\
+const __modules = {};
+const __cache = {};
+function __require(id) {
+    if (!(id in __cache)) {
+        __cache[id] = {};
+        __modules[id](__cache[id]);
+    }
+    return __cache[id];
+}
+function __export(exports, getters) {
+    for (const name in getters) {
+        Object.defineProperty(
+            exports, name, {get: getters[name], enumerable: true}
+        );
+    }
+}
+function __exportAll(exports, module) {
+    for (const name in module) {
+        if (name !== \"default\" && !(name in exports)) {
+            Object.defineProperty(
+                exports, name, {get: () => module[name], enumerable: true}
+            );
+        }
+    }
+}
+";
+
+/// Bundles the scripts of the behavior pack into their entry (e.g.
+/// "BP/scripts/main.js"). The scripts imported by the entry (directly or
+/// through the other scripts) are bundled and the other scripts are left
+/// out. See `bundle_contents`.
+pub struct ScriptBundler {
+    settings: ScriptBundle,
+}
+
+impl ScriptBundler {
+    /// Creates the bundler with the entry and the external modules from the
+    /// settings.
+    pub fn new(settings: ScriptBundle) -> ScriptBundler {
+        ScriptBundler { settings }
+    }
+}
+
+impl Mapper for ScriptBundler {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The scripts by the "scripts" directories
+        let mut scripts: BTreeMap<PathBuf, Vec<(PathBuf, PathBuf)>> =
+            BTreeMap::new();
+        for file in files.iter() {
+            let target = match &file.target {
+                Some(target) if target.pack == Pack::Behavior
+                    && target.path.extension().is_some_and(|e| e == "js") => {
+                    target
+                },
+                _ => continue,
+            };
+            if let Some(dir) = resource_dir(&target.path, "scripts") {
+                scripts.entry(dir).or_default()
+                    .push((file.source.clone(), target.path.clone()));
+            }
+        }
+        let entry: PathBuf = self.settings.entry.split('/').collect();
+        for (dir, mut inputs) in scripts {
+            let entry = dir.join(&entry);
+            let source = match inputs.iter().find(|(_, t)| *t == entry) {
+                Some((source, _)) => source.clone(),
+                None => continue,
+            };
+            inputs.sort_by(|a, b| a.1.cmp(&b.1));
+            files.retain(|file| !file.target_path()
+                .is_some_and(|t| inputs.iter().any(|(_, i)| i == t)));
+            files.push(PlannedFile {
+                source,
+                target: Some(
+                    TargetInfo::new(BUNDLE_RULE, entry).with_inputs(inputs)
+                ),
+            });
+        }
+    }
+}
+
+/// Returns the string literal at the end of the statement (e.g. "./a.js" of
+/// `import { x } from "./a.js"`) and the text before it.
+fn trailing_literal(statement: &str) -> Option<(&str, &str)> {
+    let quote = statement.chars().last()
+        .filter(|c| *c == '"' || *c == '\'')?;
+    let body = &statement[..statement.len() - 1];
+    let start = body.rfind(quote)?;
+    Some((body[..start].trim_end(), &body[start + 1..]))
+}
+
+/// Converts the list of the bindings of an import or an export (e.g.
+/// "{ a, b as c }") into the pairs of the names (e.g. ("a", "a") and ("b",
+/// "c")).
+fn parse_bindings(list: &str) -> Result<Vec<(String, String)>, String> {
+    let inner = list.trim().strip_prefix('{')
+        .and_then(|list| list.strip_suffix('}'))
+        .ok_or_else(|| format!("Invalid list \"{}\".", list.trim()))?;
+    Ok(inner.split(',')
+        .map(str::trim)
+        .filter(|binding| !binding.is_empty())
+        .map(|binding| match binding.split_once(" as ") {
+            Some((name, alias)) => {
+                (name.trim().to_string(), alias.trim().to_string())
+            },
+            None => (binding.to_string(), binding.to_string()),
+        })
+        .collect())
+}
+
+/// Checks if the line starts a static import or export statement that is
+/// transformed by the bundler (not the dynamic "import()" or the exported
+/// declarations).
+fn is_module_statement(line: &str) -> bool {
+    let starts = |keyword: &str, next: &[char]| {
+        line.strip_prefix(keyword)
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(|c| next.contains(&c) || c.is_whitespace())
+    };
+    let export_list = line.strip_prefix("export")
+        .is_some_and(|rest| {
+            let rest = rest.trim_start();
+            rest.starts_with('{') || rest.starts_with('*')
+        });
+    starts("import", &['{', '*', '"', '\'']) || export_list
+}
+
+/// Checks if the module statement is complete (it can span several lines).
+fn is_complete(statement: &str) -> bool {
+    let statement = statement.trim().trim_end_matches(';').trim_end();
+    if statement.ends_with(['"', '\'']) {
+        return true;
+    }
+    // The export lists without the "from"
+    statement.starts_with("export") && statement.ends_with('}')
+}
+
+/// The template literal or the substitution of a template literal (with
+/// the depth of the braces inside of it) that a line of a script ends in.
+enum Nesting {
+    Template,
+    Substitution(usize),
+}
+
+/// Tracks the block comments and the template literals that span several
+/// lines of a script, so the lines inside of them (e.g. a line of a
+/// multiline string that starts with "import") aren't transformed.
+#[derive(Default)]
+pub struct Lexer {
+    in_comment: bool,
+    nesting: Vec<Nesting>,
+}
+
+impl Lexer {
+    /// Checks if the next line starts in the code.
+    pub fn in_code(&self) -> bool {
+        !self.in_comment
+            && !matches!(self.nesting.last(), Some(Nesting::Template))
+    }
+
+    /// Updates the state with the line of the script. Returns the byte
+    /// ranges of the contents (without the quotes) of the string literals in
+    /// the code of the line.
+    pub fn scan(&mut self, line: &str) -> Vec<Range<usize>> {
+        let mut literals = Vec::new();
+        let mut chars = line.char_indices().peekable();
+        let next_is = |chars: &mut Peekable<CharIndices>, c: char| {
+            chars.next_if(|(_, next)| *next == c).is_some()
+        };
+        while let Some((i, c)) = chars.next() {
+            if self.in_comment {
+                if c == '*' && next_is(&mut chars, '/') {
+                    self.in_comment = false;
+                }
+                continue;
+            }
+            match (self.nesting.last_mut(), c) {
+                (Some(Nesting::Template), '\\') => {
+                    chars.next();
+                },
+                (Some(Nesting::Template), '`') => {
+                    self.nesting.pop();
+                },
+                (Some(Nesting::Template), '$')
+                    if next_is(&mut chars, '{') =>
+                {
+                    self.nesting.push(Nesting::Substitution(0));
+                },
+                (Some(Nesting::Template), _) => {},
+                (_, '/') if next_is(&mut chars, '/') => break,
+                (_, '/') if next_is(&mut chars, '*') => {
+                    self.in_comment = true;
+                },
+                (_, '"' | '\'') => {
+                    while let Some((j, next)) = chars.next() {
+                        match next {
+                            '\\' => {
+                                chars.next();
+                            },
+                            next if next == c => {
+                                literals.push(i + 1..j);
+                                break;
+                            },
+                            _ => {},
+                        }
+                    }
+                },
+                (_, '`') => self.nesting.push(Nesting::Template),
+                (Some(Nesting::Substitution(depth)), '{') => *depth += 1,
+                (Some(Nesting::Substitution(0)), '}') => {
+                    self.nesting.pop();
+                },
+                (Some(Nesting::Substitution(depth)), '}') => *depth -= 1,
+                _ => {},
+            }
+        }
+        literals
+    }
+}
+
+/// Returns the names declared by the declarators of the variable declaration
+/// (e.g. "a" and "b" of "a = f(1, 2), b = 2" of "export const a = f(1, 2),
+/// b = 2"). Returns an error for the destructuring declarations and the
+/// declarators that continue on the next lines.
+fn declarator_names(declarators: &str) -> Result<Vec<String>, String> {
+    let mut parts = vec![String::new()];
+    let (mut depth, mut quote, mut escaped) = (0usize, None, false);
+    let mut chars = declarators.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {},
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '/') if chars.peek() == Some(&'/') => break,
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                parts.push(String::new());
+                continue;
+            },
+            _ => {},
+        }
+        parts.last_mut().unwrap().push(c);
+    }
+    parts.iter()
+        .map(|part| {
+            let name: String = part.trim_start().chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+                .collect();
+            match name.is_empty() {
+                true => Err(format!(
+                    "Unsupported export of \"{}\" (export the destructured \
+                    names and the declarations that span several lines \
+                    separately).",
+                    declarators.trim()
+                )),
+                false => Ok(name),
+            }
+        })
+        .collect()
+}
+
+/// A module of the bundle transformed into the body of a function.
+#[derive(Default)]
+struct Module {
+    lines: Vec<String>,
+    /// The exported names and the expressions of their values.
+    exports: Vec<(String, String)>,
+    /// The expressions of the modules whose exports are exported.
+    star_exports: Vec<String>,
+}
+
+/// Collects the modules of the bundle.
+struct Bundler<'a> {
+    /// The sources of the modules by their paths relative to the "scripts"
+    /// directory (e.g. "lib/util.js").
+    sources: HashMap<String, PathBuf>,
+    settings: &'a ScriptBundle,
+    /// The names of the variables with the external modules.
+    externals: Vec<(String, String)>,
+    /// The paths of the modules to bundle in the order of discovery.
+    queue: Vec<String>,
+}
+
+impl Bundler<'_> {
+    /// Returns the expression of the imported module. The relative modules
+    /// are added to the bundle.
+    fn module(&mut self, id: &str, specifier: &str) -> Result<String, String> {
+        if self.settings.externals.iter().any(|e| e == specifier) {
+            let count = self.externals.len();
+            let name = match self.externals.iter()
+                .find(|(s, _)| s == specifier)
+            {
+                Some((_, name)) => name.clone(),
+                None => {
+                    let name = format!("__external{}", count);
+                    self.externals.push((specifier.into(), name.clone()));
+                    name
+                },
+            };
+            return Ok(name);
+        }
+        if !specifier.starts_with("./") && !specifier.starts_with("../") {
+            return Err(format!(
+                "The module \"{}\" isn't a relative path or an external \
+                module.",
+                specifier
+            ));
+        }
+        let specifier = rewrite_specifier(specifier);
+        let mut parts: Vec<&str> = id.split('/').collect();
+        parts.pop();
+        for part in specifier.split('/') {
+            match part {
+                "." | "" => {},
+                ".." => {
+                    parts.pop().ok_or_else(|| format!(
+                        "The module \"{}\" is outside of the scripts.",
+                        specifier
+                    ))?;
+                },
+                part => parts.push(part),
+            }
+        }
+        let resolved = parts.join("/");
+        if !self.sources.contains_key(&resolved) {
+            return Err(format!("The module \"{}\" isn't found.", specifier));
+        }
+        if !self.queue.contains(&resolved) {
+            self.queue.push(resolved.clone());
+        }
+        Ok(format!("__require({:?})", resolved))
+    }
+
+    /// Transforms the import statement into the declarations of the
+    /// imported names.
+    fn import(
+        &mut self, id: &str, statement: &str, module: &mut Module,
+    ) -> Result<(), String> {
+        let rest = statement["import".len()..].trim();
+        let (clause, specifier) = trailing_literal(rest)
+            .ok_or_else(|| format!("Invalid import \"{}\".", statement))?;
+        let expression = self.module(id, specifier)?;
+        let clause = clause.strip_suffix("from").unwrap_or(clause).trim();
+        if clause.is_empty() {
+            module.lines.push(format!("{};", expression));
+            return Ok(());
+        }
+        // The default import comes first (e.g. "d, { a }" or "d, * as ns")
+        let (default, rest) = match clause.starts_with(['{', '*']) {
+            true => (None, clause),
+            false => match clause.split_once(',') {
+                Some((default, rest)) => (Some(default.trim()), rest.trim()),
+                None => (Some(clause), ""),
+            },
+        };
+        if let Some(default) = default {
+            module.lines.push(
+                format!("const {} = {}.default;", default, expression)
+            );
+        }
+        if let Some(namespace) = rest.strip_prefix('*') {
+            let name = namespace.trim().strip_prefix("as")
+                .ok_or_else(|| format!("Invalid import \"{}\".", statement))?;
+            module.lines.push(
+                format!("const {} = {};", name.trim(), expression)
+            );
+        } else if !rest.is_empty() {
+            let bindings: Vec<String> = parse_bindings(rest)?.into_iter()
+                .map(|(name, alias)| match name == alias {
+                    true => name,
+                    false => format!("{}: {}", name, alias),
+                })
+                .collect();
+            module.lines.push(format!(
+                "const {{ {} }} = {};", bindings.join(", "), expression
+            ));
+        }
+        Ok(())
+    }
+
+    /// Transforms the export list (e.g. "export { a, b as c }") or the
+    /// export from another module (e.g. `export * from "./a.js"`) into the
+    /// exports of the module.
+    fn export(
+        &mut self, id: &str, statement: &str, module: &mut Module,
+    ) -> Result<(), String> {
+        let rest = statement["export".len()..].trim();
+        let (clause, source) = match trailing_literal(rest) {
+            Some((clause, specifier)) => {
+                let clause = clause.strip_suffix("from")
+                    .ok_or_else(|| {
+                        format!("Invalid export \"{}\".", statement)
+                    })?
+                    .trim();
+                (clause, Some(self.module(id, specifier)?))
+            },
+            None => (rest, None),
+        };
+        if let Some(namespace) = clause.strip_prefix('*') {
+            let source = source
+                .ok_or_else(|| format!("Invalid export \"{}\".", statement))?;
+            match namespace.trim().strip_prefix("as") {
+                Some(name) => {
+                    module.exports.push((name.trim().to_string(), source));
+                },
+                None => module.star_exports.push(source),
+            }
+            return Ok(());
+        }
+        for (name, alias) in parse_bindings(clause)? {
+            let value = match &source {
+                Some(source) => format!("{}.{}", source, name),
+                None => name,
+            };
+            module.exports.push((alias, value));
+        }
+        Ok(())
+    }
+
+    /// Transforms the exported declaration (e.g. "export const a = 1") or
+    /// the default export into the declaration and the export.
+    fn declaration(
+        &mut self, line: &str, module: &mut Module,
+    ) -> Result<String, String> {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let rest = line.trim_start()["export".len()..].trim_start();
+        if let Some(value) = rest.strip_prefix("default") {
+            let value = value.trim_start();
+            let named = ["async function", "function", "class"].iter()
+                .find_map(|k| declared_name(value, k))
+                .filter(|name| !name.is_empty());
+            let name = match named {
+                Some(name) => {
+                    module.exports.push(("default".into(), name));
+                    return Ok(format!("{}{}", indent, value));
+                },
+                None => "__default",
+            };
+            module.exports.push(("default".into(), name.into()));
+            return Ok(format!("{}const {} = {}", indent, name, value));
+        }
+        // The variable declarations can declare several names (e.g.
+        // "export const a = 1, b = 2")
+        let variables = ["const", "let", "var"].iter()
+            .find(|k| declared_name(rest, k).is_some());
+        if let Some(keyword) = variables {
+            for name in declarator_names(&rest[keyword.len()..])? {
+                module.exports.push((name.clone(), name));
+            }
+            return Ok(format!("{}{}", indent, rest));
+        }
+        let name = ["async function", "function", "class"].iter()
+            .find_map(|k| declared_name(rest, k))
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| {
+                format!("Unsupported export \"{}\".", line.trim())
+            })?;
+        module.exports.push((name.clone(), name));
+        Ok(format!("{}{}", indent, rest))
+    }
+
+    /// Transforms the module into the body of its function.
+    fn transform(&mut self, id: &str, text: &str) -> Result<Module, String> {
+        let mut module = Module::default();
+        let mut lines = text.lines().enumerate();
+        let mut lexer = Lexer::default();
+        while let Some((i, line)) = lines.next() {
+            let error = |e: String| {
+                format!("\"{}\" line {}: {}", id, i + 1, e)
+            };
+            // The lines inside of the block comments and the template
+            // literals are kept as they are
+            let in_code = lexer.in_code();
+            lexer.scan(line);
+            if !in_code {
+                module.lines.push(line.to_string());
+                continue;
+            }
+            let trimmed = line.trim();
+            if !is_module_statement(trimmed) {
+                let line = match trimmed.starts_with("export ") {
+                    true => self.declaration(line, &mut module)
+                        .map_err(error)?,
+                    false => self.dynamic_imports(id, line).map_err(error)?,
+                };
+                module.lines.push(line);
+                continue;
+            }
+            let mut statement = trimmed.to_string();
+            while !is_complete(&statement) {
+                let (_, next) = lines.next().ok_or_else(|| {
+                    error("The statement isn't complete.".into())
+                })?;
+                lexer.scan(next);
+                statement.push(' ');
+                statement.push_str(next.trim());
+            }
+            let statement = statement.trim_end_matches(';').trim_end();
+            match statement.starts_with("import") {
+                true => self.import(id, statement, &mut module),
+                false => self.export(id, statement, &mut module),
+            }.map_err(error)?;
+        }
+        Ok(module)
+    }
+
+    /// Replaces the dynamic imports of the relative modules (e.g.
+    /// `import("./a.js")`) with the bundled modules.
+    fn dynamic_imports(
+        &mut self, id: &str, line: &str,
+    ) -> Result<String, String> {
+        let mut result = String::new();
+        let mut rest = line;
+        while let Some(start) = rest.find("import(") {
+            let after = &rest[start + "import(".len()..];
+            let literal = after.trim_start();
+            let quote = literal.chars().next()
+                .filter(|c| *c == '"' || *c == '\'');
+            let call = quote.and_then(|quote| {
+                let (specifier, tail) = literal[1..].split_once(quote)?;
+                let tail = tail.trim_start().strip_prefix(')')?;
+                Some((specifier, tail))
+            });
+            match call {
+                Some((specifier, tail)) if specifier.starts_with('.') => {
+                    result.push_str(&rest[..start]);
+                    result.push_str(&format!(
+                        "Promise.resolve({})", self.module(id, specifier)?
+                    ));
+                    rest = tail;
+                },
+                _ => {
+                    result.push_str(&rest[..start + "import(".len()]);
+                    rest = after;
+                },
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+}
+
+/// Returns the name declared after the keyword (e.g. "a" of "const a = 1"
+/// for "const") or None if the declaration doesn't start with the keyword.
+/// The name is empty for the anonymous functions and classes and the
+/// destructuring declarations.
+fn declared_name(declaration: &str, keyword: &str) -> Option<String> {
+    let rest = declaration.strip_prefix(keyword)?;
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    // The generator functions (e.g. "function* numbers")
+    let rest = rest.trim_start().trim_start_matches('*').trim_start();
+    Some(rest.chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+        .collect())
+}
+
+/// Bundles the scripts imported by the entry (the target) into a single
+/// script. Every module becomes a function that is run when the module is
+/// imported for the first time. The exports are defined as the getters, so
+/// the circular imports work like in the separate modules. The external
+/// modules (e.g. "@minecraft/server") are imported by the bundle. The
+/// modules with top-level "await" aren't supported.
+///
+/// # Arguments
+/// - `target` - the target of the entry with the scripts as the inputs
+/// - `settings` - the settings with the external modules
+/// - `read` - returns the script of the source file (e.g. the compiled
+///   TypeScript file)
+pub fn bundle_contents(
+    target: &TargetInfo, settings: &ScriptBundle,
+    read: impl Fn(&Path) -> Result<String, String>,
+) -> Result<Vec<u8>, String> {
+    let scripts_dir = resource_dir(&target.path, "scripts")
+        .ok_or("The bundle isn't in the \"scripts\" directory.")?;
+    let id = |path: &Path| -> Option<String> {
+        Some(slash_path(path.strip_prefix(&scripts_dir).ok()?))
+    };
+    let entry = id(&target.path).ok_or("Invalid entry of the bundle.")?;
+    let mut bundler = Bundler {
+        sources: target.inputs.iter()
+            .filter_map(|(source, path)| Some((id(path)?, source.clone())))
+            .collect(),
+        settings,
+        externals: Vec::new(),
+        queue: vec![entry.clone()],
+    };
+    let mut modules = Vec::new();
+    let mut i = 0;
+    while let Some(id) = bundler.queue.get(i).cloned() {
+        i += 1;
+        let source = bundler.sources[&id].clone();
+        let text = read(&source)
+            .map_err(|e| format!("\"{}\": {}", source.display(), e))?;
+        let module = bundler.transform(
+            &id, text.trim_start_matches('\u{feff}')
+        )?;
+        modules.push((id, module));
+    }
+    let mut bundle = String::new();
+    for (specifier, name) in &bundler.externals {
+        bundle.push_str(
+            &format!("import * as {} from {:?};\n", name, specifier)
+        );
+    }
+    bundle.push_str(RUNTIME);
+    for (id, module) in modules {
+        bundle.push_str(&format!("__modules[{:?}] = (__exports) => {{\n", id));
+        if !module.exports.is_empty() {
+            bundle.push_str("__export(__exports, {\n");
+            for (name, value) in &module.exports {
+                bundle.push_str(
+                    &format!("    {:?}: () => {},\n", name, value)
+                );
+            }
+            bundle.push_str("});\n");
+        }
+        for source in &module.star_exports {
+            bundle.push_str(&format!("__exportAll(__exports, {});\n", source));
+        }
+        for line in &module.lines {
+            bundle.push_str(line);
+            bundle.push('\n');
+        }
+        bundle.push_str("};\n");
+    }
+    bundle.push_str(&format!("__require({:?});\n", entry));
+    Ok(bundle.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bundles the scripts (the paths relative to the "scripts" directory
+    /// and their contents) into "main.js".
+    fn bundled(scripts: &[(&str, &str)]) -> Result<String, String> {
+        let inputs = scripts.iter()
+            .map(|(path, _)| {
+                (PathBuf::from(path), Path::new("BP/scripts").join(path))
+            })
+            .collect();
+        let target = TargetInfo::new(BUNDLE_RULE, "BP/scripts/main.js".into())
+            .with_inputs(inputs);
+        let sources: HashMap<PathBuf, String> = scripts.iter()
+            .map(|(path, text)| (PathBuf::from(path), text.to_string()))
+            .collect();
+        let bundle = bundle_contents(
+            &target, &ScriptBundle::default(),
+            |source| Ok(sources[source].clone()),
+        )?;
+        Ok(String::from_utf8(bundle).unwrap())
+    }
+
+    #[test]
+    fn transforms_the_imports_and_the_exports() {
+        let bundle = bundled(&[
+            ("main.js", "\
+import { world } from \"@minecraft/server\";
+import d, { a as b } from \"./lib.js\";
+import * as all from \"./lib.js\";
+export * from \"./lib.js\";
+"),
+            ("lib.js", "\
+export default function main() {}
+export const a = 1;
+"),
+        ]).unwrap();
+        assert!(bundle.starts_with(
+            "import * as __external0 from \"@minecraft/server\";\n"
+        ));
+        assert!(bundle.contains(
+            "const { world } = __external0;\n\
+            const d = __require(\"lib.js\").default;\n\
+            const { a: b } = __require(\"lib.js\");\n\
+            const all = __require(\"lib.js\");\n"
+        ), "{}", bundle);
+        assert!(bundle.contains(
+            "__exportAll(__exports, __require(\"lib.js\"));"
+        ));
+        assert!(bundle.contains("    \"default\": () => main,\n"));
+        assert!(bundle.contains("    \"a\": () => a,\n"));
+    }
+
+    #[test]
+    fn exports_all_of_the_declared_names() {
+        let bundle = bundled(&[
+            ("main.js", "export const a = f(1, \"2,\"), b = [3, 4];"),
+        ]).unwrap();
+        assert!(bundle.contains("    \"a\": () => a,\n"));
+        assert!(bundle.contains("    \"b\": () => b,\n"));
+        for script in ["export const { a } = b;", "export let a = 1,"] {
+            let e = bundled(&[("main.js", script)]).err().unwrap();
+            assert!(e.contains("Unsupported export"), "{}", e);
+        }
+    }
+
+    #[test]
+    fn keeps_the_comments_and_the_template_literals() {
+        let script = "\
+const text = `
+import { x } from \"./missing.js\";
+${`
+export { y };
+`}`;
+/*
+export { z };
+*/
+";
+        let bundle = bundled(&[("main.js", script)]).unwrap();
+        assert!(bundle.contains(script), "{}", bundle);
+    }
+}
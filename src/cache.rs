@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::project_dir;
+use crate::error::CpfError;
+
+/// The path to the cache file relative to the root of the Regolith project.
+static CACHE_FILE_PATH: &str = ".regolith/cache/custom_project/hashes.json";
+
+/// The cache of the hashes of the source files from the previous run. It's
+/// used to skip copying the files that didn't change even when their
+/// modification times are unreliable (e.g. after a git checkout).
+pub struct HashCache {
+    path: PathBuf,
+    previous: HashMap<String, u64>,
+    current: Mutex<HashMap<String, u64>>,
+}
+
+impl HashCache {
+    /// Returns the default path to the cache file in the root of the
+    /// Regolith project (see config::project_dir).
+    ///
+    /// # Arguments
+    /// - `working_dir` - the path to the working directory of regolith
+    pub fn default_path(working_dir: &Path) -> PathBuf {
+        project_dir(working_dir).join(CACHE_FILE_PATH)
+    }
+
+    /// Loads the cache from the file. A missing or invalid cache file results
+    /// in an empty cache.
+    pub fn load(path: &Path) -> HashCache {
+        let previous = fs::read_to_string(path).ok()
+            .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+            .and_then(|v| {
+                v["hashes"].as_object()?.iter()
+                    .map(|(k, v)| {
+                        let hash = u64::from_str_radix(v.as_str()?, 16).ok()?;
+                        Some((k.clone(), hash))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        HashCache {
+            path: path.to_path_buf(),
+            previous,
+            current: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks if the source file had the same hash in the previous run.
+    pub fn is_unchanged(&self, source: &Path, hash: u64) -> bool {
+        self.previous.get(&source.display().to_string()) == Some(&hash)
+    }
+
+    /// Records the hash of the source file for the next run. It's only
+    /// recorded when the target has the content of the source, so the files
+    /// that failed to copy are copied again in the next run.
+    pub fn record(&self, source: &Path, hash: u64) {
+        self.current.lock().unwrap()
+            .insert(source.display().to_string(), hash);
+    }
+
+    /// Saves the hashes recorded in this run to the cache file. The hashes
+    /// of the existing files that weren't checked in this run (e.g. the ones
+    /// with the targets that are up to date) are kept from the previous run.
+    pub fn save(&self) -> Result<(), CpfError> {
+        let mut current = self.current.lock().unwrap().clone();
+        for (key, hash) in &self.previous {
+            if !current.contains_key(key) && Path::new(key).exists() {
+                current.insert(key.clone(), *hash);
+            }
+        }
+        let hashes: serde_json::Map<String, serde_json::Value> = current
+            .iter()
+            .map(|(k, v)| (k.clone(), format!("{:016x}", v).into()))
+            .collect();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| CpfError::io(e, parent))?;
+        }
+        let text = format!("{:#}\n", serde_json::json!({"hashes": hashes}));
+        fs::write(&self.path, text)
+            .map_err(|e| CpfError::io(e, &self.path))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_hashes_of_the_files_that_were_not_checked() {
+        let dir = std::env::temp_dir()
+            .join(format!("cpf_cache_keep_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (source, removed) = (dir.join("a.json"), dir.join("b.json"));
+        fs::write(&source, "{}").unwrap();
+        fs::write(&removed, "{}").unwrap();
+        let path = dir.join("hashes.json");
+        let cache = HashCache::load(&path);
+        assert!(!cache.is_unchanged(&source, 1));
+        cache.record(&source, 1);
+        cache.record(&removed, 2);
+        cache.save().unwrap();
+        // The second run skips both of the files before checking them
+        fs::remove_file(&removed).unwrap();
+        HashCache::load(&path).save().unwrap();
+        let cache = HashCache::load(&path);
+        assert!(cache.is_unchanged(&source, 1));
+        assert!(!cache.is_unchanged(&removed, 2));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::CpfError;
+
+/// The name of the data directory of the filter in the Regolith project.
+static FILTER_NAME: &str = "custom_project";
+
+/// Runs a git command in the directory and returns the listed paths.
+fn git_paths(
+    dir: &Path, args: &[&str],
+) -> Result<Vec<PathBuf>, CpfError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Unable to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(CpfError::Other(format!(
+            "git {} failed: {}",
+            args.join(" "), String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Returns the paths of the data files changed since the git revision,
+/// including the untracked files. The paths are relative to the data
+/// directory of the filter (e.g. "src/zombie/_.bpe.json"). Git is run in the
+/// root of the Regolith project, so it also works in the temporary
+/// directory of Regolith.
+///
+/// # Arguments
+/// - `project_dir` - the path to the root of the Regolith project
+/// - `rev` - the git revision to compare the files with
+pub fn changed_since(
+    project_dir: &Path, rev: &str,
+) -> Result<HashSet<PathBuf>, CpfError> {
+    let mut paths = git_paths(
+        project_dir, &["diff", "--name-only", "--no-renames", rev, "--"]
+    )?;
+    paths.extend(git_paths(
+        project_dir,
+        &["ls-files", "--others", "--exclude-standard", "--full-name"]
+    )?);
+    // The data directory can be anywhere in the project (e.g.
+    // "packs/data/custom_project")
+    Ok(paths.iter()
+        .filter_map(|p| {
+            let mut components = p.components();
+            components.find(|c| c.as_os_str() == FILTER_NAME)?;
+            Some(components.collect::<PathBuf>())
+        })
+        .collect())
+}
@@ -0,0 +1,659 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::audio::{audio_problems, HEADER_SIZE};
+use crate::config::Config;
+use crate::feature::feature_folders;
+use crate::feature_rules::check_feature_rules;
+use crate::index::{slash_path, ProjectIndex};
+use crate::items::check_items;
+use crate::longpath::{installed_path_length, WINDOWS_MAX_PATH};
+use crate::mapping::{
+    non_utf8_error, unmapped_message, Mapper, Pack, TargetInfo,
+};
+use crate::mcfunction::check_functions;
+use crate::nbt::validate_structure;
+use crate::plan::{find_case_conflicts, find_conflicts, PlannedFile};
+use crate::recipes::check_recipes;
+use crate::references::{
+    check_geometries_and_textures, check_loot_tables, check_particles,
+    check_render_controllers,
+};
+use crate::report::Diagnostic;
+use crate::spawn_rules::check_spawn_rules;
+use crate::trading::check_trading;
+use crate::vanilla::{overridden_entity, vanilla_entity};
+use crate::text::{is_parsed_text, strip_bom, strip_json_comments};
+
+pub use crate::report::Severity;
+
+/// A problem found in one of the source files by the validation passes.
+pub type Problem = Diagnostic;
+
+/// Checks if the source file is mapped to the packs.
+fn check_mapped(
+    file: &PlannedFile, mapper: &dyn Mapper, problems: &mut Vec<Problem>,
+) {
+    if file.target.is_none() {
+        problems.push(Problem::new(
+            Severity::Warning, &file.source,
+            unmapped_message(&file.source, mapper),
+        ));
+    }
+}
+
+/// Checks if the path of the source file is valid UTF-8. Such files are
+/// copied but they can't be referenced from the other files of the packs.
+fn check_utf8_name(file: &PlannedFile, problems: &mut Vec<Problem>) {
+    if let Some(message) = non_utf8_error(&file.source) {
+        problems.push(Problem::new(Severity::Warning, &file.source, message));
+    }
+}
+
+/// The categories of the packs with the files usually named after the
+/// identifiers of the content, which are lowercase.
+static IDENTIFIER_CATEGORIES: &[&str] = &[
+    "entities", "entity", "items", "blocks", "attachables", "spawn_rules",
+    "recipes", "features", "feature_rules",
+];
+
+/// The longest file name that works reliably with the game and the tools
+/// (e.g. the ZIP tools used for the .mcpack files).
+pub static MAX_FILE_NAME_LENGTH: usize = 80;
+
+/// Checks if the name of the target breaks the constraints of the game or
+/// its tools: the spaces in the paths of the functions, the uppercase
+/// letters in the names derived from the identifiers, the non-ASCII
+/// characters in the paths of the textures and the names that are too long.
+fn check_file_name(file: &PlannedFile, problems: &mut Vec<Problem>) {
+    let target = match &file.target {
+        Some(target) => target,
+        None => return,
+    };
+    let path = target.path.to_string_lossy();
+    let name = target.path.file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    let mut warn = |message: String| problems.push(
+        Problem::new(Severity::Warning, &file.source, message)
+    );
+    if target.category == "functions" && path.contains(' ') {
+        warn(format!(
+            "The path of the function \"{}\" contains spaces, so it can't \
+            be called with the /function command.", path
+        ));
+    }
+    if IDENTIFIER_CATEGORIES.contains(&target.category.as_str())
+        && name.chars().any(char::is_uppercase)
+    {
+        warn(format!(
+            "The name \"{}\" contains uppercase letters. The identifiers \
+            are lowercase, so the names based on them should be too.", name
+        ));
+    }
+    if target.category == "textures" && !path.is_ascii() {
+        warn(format!(
+            "The path of the texture \"{}\" contains non-ASCII \
+            characters, which the game fails to load on some platforms.",
+            path
+        ));
+    }
+    if name.chars().count() > MAX_FILE_NAME_LENGTH {
+        warn(format!(
+            "The name \"{}\" is longer than {} characters.",
+            name, MAX_FILE_NAME_LENGTH
+        ));
+    }
+}
+
+/// The syntax problem of a JSON file.
+pub enum JsonProblem {
+    /// The file is empty or it ends in the middle of a value (e.g. a file
+    /// that wasn't fully saved). The game reports such files with errors
+    /// that don't help finding them.
+    Incomplete(Problem),
+    /// The file has a syntax error.
+    Invalid(Problem),
+}
+
+impl JsonProblem {
+    /// Returns the problem to report.
+    pub fn problem(self) -> Problem {
+        match self {
+            JsonProblem::Incomplete(problem)
+            | JsonProblem::Invalid(problem) => problem,
+        }
+    }
+}
+
+/// Returns the syntax problem of the JSON text or None if it's valid.
+///
+/// # Arguments
+/// - `source` - the path to the file
+/// - `text` - the content of the file without the BOM
+fn json_problem(source: &Path, text: &str) -> Option<JsonProblem> {
+    // The game accepts the comments
+    let text = strip_json_comments(text);
+    if text.trim().is_empty() {
+        return Some(JsonProblem::Incomplete(Problem::new(
+            Severity::Error, source, "The JSON file is empty."
+        )));
+    }
+    let e = serde_json::from_str::<serde_json::Value>(&text).err()?;
+    Some(match e.is_eof() {
+        true => JsonProblem::Incomplete(Problem::new(
+            Severity::Error, source,
+            "The JSON file is truncated (it ends in the middle of a value).",
+        ).at(e.line(), e.column())),
+        false => JsonProblem::Invalid(Problem::new(
+            Severity::Error, source, format!("Invalid JSON: {}", e),
+        ).at(e.line(), e.column())),
+    })
+}
+
+/// Checks the syntax of the JSON file. Returns the problem or None if the
+/// file isn't a JSON file or it's valid.
+///
+/// # Arguments
+/// - `source` - the path to the file
+pub fn check_json_source(source: &Path) -> Option<JsonProblem> {
+    if source.extension().is_none_or(|e| e != "json") {
+        return None;
+    }
+    // The files that can't be read are reported when they're copied
+    let text = fs::read_to_string(source).ok()?;
+    json_problem(source, text.trim_start_matches('\u{feff}'))
+}
+
+/// Checks if the JSON files that are mapped to the packs are valid JSON.
+fn check_json_syntax(file: &PlannedFile, problems: &mut Vec<Problem>) {
+    if file.target.is_none()
+        || file.source.extension().is_none_or(|e| e != "json")
+    {
+        return;
+    }
+    let text = match fs::read_to_string(&file.source) {
+        // The BOMs are reported by check_bom
+        Ok(text) => text.trim_start_matches('\u{feff}').to_string(),
+        Err(e) => {
+            problems.push(Problem::new(
+                Severity::Error, &file.source,
+                format!("Unable to read the file: {}", e),
+            ));
+            return;
+        },
+    };
+    problems.extend(
+        json_problem(&file.source, &text).map(JsonProblem::problem)
+    );
+}
+
+/// Checks if the JSON and .lang files start with a UTF-8 BOM, which breaks
+/// some of the parsers.
+fn check_bom(file: &PlannedFile, problems: &mut Vec<Problem>) {
+    if file.target.is_none() || !is_parsed_text(&file.source) {
+        return;
+    }
+    let starts_with_bom = fs::read(&file.source)
+        .is_ok_and(|data| strip_bom(&data).is_some());
+    if starts_with_bom {
+        problems.push(Problem::new(
+            Severity::Warning, &file.source,
+            "The file starts with a UTF-8 BOM, which breaks some of the \
+            parsers. It's removed from the target unless \"bom\" is set to \
+            \"preserve\" in the config file.",
+        ));
+    }
+}
+
+/// Checks if the .lang file is encoded in UTF-8. The game reads the .lang
+/// files as UTF-8, so the files in other encodings (e.g. UTF-16 exported
+/// from Excel or Crowdin) render as mojibake. Returns the problem with the
+/// position of the first invalid sequence or None if the file isn't a .lang
+/// file or it's valid.
+///
+/// # Arguments
+/// - `source` - the path to the file
+pub fn check_lang_encoding(source: &Path) -> Option<Problem> {
+    if source.extension().is_none_or(|e| e != "lang") {
+        return None;
+    }
+    // The files that can't be read are reported when they're copied
+    let data = fs::read(source).ok()?;
+    if data.starts_with(&[0xff, 0xfe]) || data.starts_with(&[0xfe, 0xff]) {
+        return Some(Problem::new(
+            Severity::Warning, source,
+            "The file is encoded in UTF-16, but the game reads the .lang \
+            files as UTF-8. Save it as UTF-8.",
+        ));
+    }
+    let error = std::str::from_utf8(&data).err()?;
+    let valid = &data[..error.valid_up_to()];
+    let line = valid.iter().filter(|b| **b == b'\n').count() + 1;
+    let line_start = valid.iter().rposition(|b| *b == b'\n')
+        .map_or(0, |i| i + 1);
+    Some(Problem::new(
+        Severity::Warning, source,
+        format!(
+            "The file isn't valid UTF-8 (invalid byte 0x{:02x} at byte {} \
+            of the file), so the text renders as mojibake in the game. Save \
+            it as UTF-8.",
+            data[valid.len()], valid.len(),
+        ),
+    ).at(line, valid.len() - line_start + 1))
+}
+
+/// Checks if the .mcstructure file is a Bedrock structure (uncompressed
+/// little-endian NBT with the expected keys). Returns the problem or None if
+/// the file isn't a .mcstructure file or it's valid. The Java Edition .nbt
+/// files and the corrupted exports are reported.
+///
+/// # Arguments
+/// - `source` - the path to the file
+pub fn check_structure(source: &Path) -> Option<Problem> {
+    if source.extension().is_none_or(|e| e != "mcstructure") {
+        return None;
+    }
+    // The files that can't be read are reported when they're copied
+    let data = fs::read(source).ok()?;
+    let message = validate_structure(&data).err()?;
+    Some(Problem::new(Severity::Warning, source, message))
+}
+
+/// Checks if the .ogg and .wav files use the formats that Bedrock plays on
+/// all of the platforms (Vorbis, PCM and the common sample rates and channel
+/// counts). Returns the list of the problems.
+///
+/// # Arguments
+/// - `source` - the path to the file
+pub fn check_audio(source: &Path) -> Vec<Problem> {
+    let extension = match source.extension().and_then(|e| e.to_str()) {
+        Some(extension @ ("ogg" | "wav")) => extension,
+        _ => return Vec::new(),
+    };
+    // Only the header is needed and the audio files can be large
+    let mut header = Vec::new();
+    let read = fs::File::open(source)
+        .and_then(|f| f.take(HEADER_SIZE).read_to_end(&mut header));
+    if read.is_err() {
+        // The files that can't be read are reported when they're copied
+        return Vec::new();
+    }
+    audio_problems(extension, &header).into_iter()
+        .map(|message| Problem::new(Severity::Warning, source, message))
+        .collect()
+}
+
+/// Checks if the file overrides the vanilla content in the game, either by
+/// the path of the target (e.g. "BP/entities/zombie.json") or by defining
+/// an entity with a vanilla identifier (e.g. "minecraft:zombie"). Returns
+/// the problem or None if the file doesn't override the vanilla content or
+/// the override is allowed.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target of the file
+/// - `allowed` - the targets and the identifiers allowed to override the
+///   vanilla content (the "vanilla_overrides" setting)
+pub fn check_vanilla_override(
+    source: &Path, target: &TargetInfo, allowed: &[String],
+) -> Option<Problem> {
+    let key = slash_path(&target.path);
+    let entity = overridden_entity(target).or_else(|| {
+        if source.extension().is_none_or(|e| e != "json") {
+            return None;
+        }
+        match content_identifier(source)? {
+            ("minecraft:entity" | "minecraft:client_entity", identifier) => {
+                vanilla_entity(&identifier)
+            },
+            _ => None,
+        }
+    })?;
+    let identifier = format!("minecraft:{}", entity);
+    if allowed.contains(&key) || allowed.contains(&identifier) {
+        return None;
+    }
+    Some(Problem::new(
+        Severity::Warning, source,
+        format!(
+            "The file overrides the vanilla \"{}\" (the target \"{}\"). Add \
+            the target or the identifier to \"vanilla_overrides\" in the \
+            config file if it's intended.",
+            identifier, key
+        ),
+    ))
+}
+
+/// Checks if the files that are mapped to the packs override the vanilla
+/// files (see check_vanilla_override).
+fn check_vanilla_overrides(
+    file: &PlannedFile, config: &Config, problems: &mut Vec<Problem>,
+) {
+    if let Some(target) = &file.target {
+        problems.extend(check_vanilla_override(
+            &file.source, target, &config.vanilla_overrides
+        ));
+    }
+}
+
+/// Checks if the path of the target would exceed the Windows path length
+/// limit when the pack is installed in the com.mojang folder (e.g.
+/// "...\com.mojang\development_behavior_packs\<pack>\entities\..."),
+/// where the game fails to load it. Returns the problem or None if the path
+/// is short enough or the target isn't in the packs.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target of the file
+pub fn check_installed_path(
+    source: &Path, target: &TargetInfo,
+) -> Option<Problem> {
+    let packs_dir = match target.pack {
+        Pack::Behavior => "development_behavior_packs",
+        Pack::Resource => "development_resource_packs",
+        Pack::Other => return None,
+    };
+    let path_in_pack: PathBuf = target.path.iter().skip(1).collect();
+    let length = installed_path_length(packs_dir, &path_in_pack);
+    if length < WINDOWS_MAX_PATH {
+        return None;
+    }
+    let longest = path_in_pack.iter()
+        .max_by_key(|c| c.len())?
+        .to_string_lossy();
+    Some(Problem::new(
+        Severity::Warning, source,
+        format!(
+            "The path of the target is about {} characters long when the \
+            pack is installed in the \"{}\" folder of com.mojang on Windows, \
+            which exceeds the limit of {} characters. Use shorter folder or \
+            file names (e.g. \"{}\").",
+            length, packs_dir, WINDOWS_MAX_PATH, longest
+        ),
+    ))
+}
+
+/// The names reserved for the devices on Windows. The files with these names
+/// (with any extension, e.g. "con.json") can't be created there.
+static RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5",
+    "com6", "com7", "com8", "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5",
+    "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// The characters that can't be used in the file names on Windows.
+static INVALID_CHARACTERS: &[char] = &[
+    '<', '>', ':', '"', '|', '?', '*', '\\',
+];
+
+/// Checks if the names of the folders and the file of the target work on all
+/// of the platforms. The names reserved for the devices on Windows, the
+/// characters invalid on Windows and the names ending with a dot or a space
+/// otherwise only fail when the teammates on that platform pull the pack.
+/// Returns the list of the problems.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target of the file
+pub fn check_portable_name(
+    source: &Path, target: &TargetInfo,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for component in target.path.iter() {
+        let name = component.to_string_lossy();
+        let base = name.split('.').next().unwrap_or_default();
+        let mut warn = |reason: &str| problems.push(Problem::new(
+            Severity::Warning, source,
+            format!("The name \"{}\" of the target {}.", name, reason),
+        ));
+        if RESERVED_NAMES.contains(&base.to_lowercase().as_str()) {
+            warn("is reserved for a device on Windows");
+        }
+        if name.contains(INVALID_CHARACTERS)
+            || name.chars().any(|c| c.is_control())
+        {
+            warn("contains characters that are invalid on Windows");
+        }
+        if name.ends_with('.') || name.ends_with(' ') {
+            warn("ends with a dot or a space, which Windows removes");
+        }
+    }
+    problems
+}
+
+/// Checks the target of the source file that the filter copies but reports
+/// (e.g. the paths that are too long or the names that are reserved on
+/// Windows). Returns the list of the problems.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target of the file
+pub fn check_target(source: &Path, target: &TargetInfo) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    problems.extend(check_installed_path(source, target));
+    problems.extend(check_portable_name(source, target));
+    problems
+}
+
+/// Checks the contents of the source file that the filter copies but
+/// reports (e.g. the .lang files that aren't UTF-8). Returns the list of the
+/// problems.
+///
+/// # Arguments
+/// - `source` - the path to the file
+pub fn check_contents(source: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    problems.extend(check_lang_encoding(source));
+    problems.extend(check_structure(source));
+    problems.extend(check_audio(source));
+    problems
+}
+
+/// Checks the contents and the targets of the files that are mapped to the
+/// packs (see check_contents and check_target).
+fn check_file_contents(file: &PlannedFile, problems: &mut Vec<Problem>) {
+    if let Some(target) = &file.target {
+        problems.extend(check_contents(&file.source));
+        problems.extend(check_target(&file.source, target));
+    }
+}
+
+/// Checks if multiple source files are mapped to the same target.
+fn check_conflicts(files: &[PlannedFile], problems: &mut Vec<Problem>) {
+    for (target, sources) in find_conflicts(files) {
+        for i in &sources[1..] {
+            problems.push(Problem::new(
+                Severity::Error, &files[*i].source, format!(
+                    "Mapped to \"{}\" which is already mapped from \"{}\".",
+                    target.display(), files[sources[0]].source.display()
+                ),
+            ));
+        }
+    }
+}
+
+/// Checks if the targets of the source files differ only by case.
+fn check_case_conflicts(files: &[PlannedFile], problems: &mut Vec<Problem>) {
+    for group in find_case_conflicts(files) {
+        let first = &files[group[0]];
+        for i in &group[1..] {
+            problems.push(Problem::new(
+                Severity::Error, &files[*i].source, format!(
+                    "Mapped to \"{}\" which differs only by case from \
+                    \"{}\" mapped from \"{}\".",
+                    files[*i].target_path().unwrap().display(),
+                    first.target_path().unwrap().display(),
+                    first.source.display()
+                ),
+            ));
+        }
+    }
+}
+
+/// The top-level keys of the pack files that define content with an
+/// identifier in "description.identifier".
+static IDENTIFIED_KINDS: &[&str] = &[
+    "minecraft:entity", "minecraft:client_entity", "minecraft:item",
+    "minecraft:block", "minecraft:attachable",
+];
+
+/// Returns the kind (the top-level key, e.g. "minecraft:entity") and the
+/// identifier of the content defined in the JSON file or None if the file
+/// doesn't define any content with an identifier.
+fn content_identifier(path: &Path) -> Option<(&'static str, String)> {
+    let text = fs::read_to_string(path).ok()?;
+    let json: serde_json::Value =
+        serde_json::from_str(&strip_json_comments(&text)).ok()?;
+    IDENTIFIED_KINDS.iter().find_map(|kind| {
+        let identifier = json[kind]["description"]["identifier"].as_str()?;
+        Some((*kind, identifier.to_string()))
+    })
+}
+
+/// Checks if multiple JSON files of the same pack define the same
+/// identifier (e.g. two entities "foo:zombie"). Only one of the definitions
+/// would be used by the game.
+fn check_duplicate_identifiers(
+    files: &[PlannedFile], problems: &mut Vec<Problem>
+) {
+    let mut defined: HashMap<(Pack, &str, String), &Path> = HashMap::new();
+    for file in files {
+        let pack = match &file.target {
+            Some(target) => target.pack,
+            None => continue,
+        };
+        if file.source.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let (kind, identifier) = match content_identifier(&file.source) {
+            Some(content) => content,
+            None => continue,
+        };
+        let key = (pack, kind, identifier);
+        match defined.get(&key) {
+            Some(first) => problems.push(Problem::new(
+                Severity::Error, &file.source, format!(
+                    "The identifier \"{}\" of \"{}\" is already defined \
+                    in \"{}\".",
+                    key.2, kind, first.display()
+                ),
+            )),
+            None => {
+                defined.insert(key, &file.source);
+            },
+        }
+    }
+}
+
+/// Checks if the files of every feature folder define the same feature: the
+/// identifiers of their content (e.g. the entity and the client entity)
+/// have to be the same. The files of the feature folders that are mapped to
+/// the same target are reported as the conflicts.
+fn check_feature_folders(files: &[PlannedFile], problems: &mut Vec<Problem>) {
+    for indices in feature_folders(files).into_values() {
+        let identifiers: Vec<(&Path, String)> = indices.iter()
+            .map(|i| &files[*i].source)
+            .filter(|source| source.extension().is_some_and(|e| e == "json"))
+            .filter_map(|source| {
+                Some((source.as_path(), content_identifier(source)?.1))
+            })
+            .collect();
+        let (first, identifier) = match identifiers.first() {
+            Some(first) => first,
+            None => continue,
+        };
+        for (source, other) in &identifiers[1..] {
+            if other != identifier {
+                problems.push(Problem::new(
+                    Severity::Warning, source, format!(
+                        "Defines \"{}\" but \"{}\" from the same feature \
+                        folder defines \"{}\".",
+                        other, first.display(), identifier
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Runs all of the validation passes over the planned files without writing
+/// anything and returns the list of the problems found.
+///
+/// # Arguments
+/// - `files` - the planned files to validate
+/// - `mapper` - the mapper that mapped the files
+/// - `config` - the config of the filter
+pub fn check(
+    files: &[PlannedFile], mapper: &dyn Mapper, config: &Config,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for file in files {
+        check_mapped(file, mapper, &mut problems);
+        check_utf8_name(file, &mut problems);
+        check_file_name(file, &mut problems);
+        check_json_syntax(file, &mut problems);
+        problems.extend(config.plugins.validate(&file.source));
+        check_bom(file, &mut problems);
+        check_file_contents(file, &mut problems);
+        check_vanilla_overrides(file, config, &mut problems);
+    }
+    check_conflicts(files, &mut problems);
+    check_case_conflicts(files, &mut problems);
+    check_duplicate_identifiers(files, &mut problems);
+    if config.feature_folders {
+        check_feature_folders(files, &mut problems);
+    }
+    // The passes that check the references between the files
+    let index = ProjectIndex::new(files);
+    check_geometries_and_textures(&index, &mut problems);
+    check_render_controllers(&index, &mut problems);
+    check_recipes(&index, &mut problems);
+    check_loot_tables(&index, &mut problems);
+    check_trading(&index, &mut problems);
+    check_spawn_rules(&index, &mut problems);
+    check_feature_rules(&index, &mut problems);
+    check_functions(
+        &index, config.min_engine_version.as_deref(), &mut problems
+    );
+    check_particles(&index, &mut problems);
+    check_items(&index, &mut problems);
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns whether the JSON text is valid, incomplete or invalid.
+    fn json_kind(text: &str) -> &'static str {
+        match json_problem(Path::new("a.json"), text) {
+            None => "valid",
+            Some(JsonProblem::Incomplete(_)) => "incomplete",
+            Some(JsonProblem::Invalid(_)) => "invalid",
+        }
+    }
+
+    #[test]
+    fn accepts_the_json_files_with_comments() {
+        assert_eq!(json_kind("// The zombie\n{\"a\": 1}"), "valid");
+        assert_eq!(json_kind("{\"a\": /* one */ 1}"), "valid");
+        assert_eq!(json_kind("{\"a\": \"http://x\"}"), "valid");
+        // The files with only comments are empty
+        let problem = json_problem(Path::new("a.json"), "// {\"a\": 1}\n")
+            .unwrap().problem();
+        assert_eq!(problem.message, "The JSON file is empty.");
+    }
+
+    #[test]
+    fn reports_the_empty_truncated_and_invalid_json_files() {
+        assert_eq!(json_kind(""), "incomplete");
+        assert_eq!(json_kind("{\"a\": [1, "), "incomplete");
+        assert_eq!(json_kind("{\"a\": 1,}"), "invalid");
+        let problem = json_problem(Path::new("a.json"), "{\n  \"a\" 1\n}")
+            .unwrap().problem();
+        assert_eq!((problem.line, problem.column), (Some(2), Some(7)));
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+/// The parsed command line arguments of the filter.
+///
+/// The arguments are split into positional arguments and options. Options
+/// start with `--` and can either be flags (`--force`) or take a value
+/// (`--root shared` or `--root=shared`). Which options take a value is
+/// decided by the caller.
+pub struct Args {
+    pub positional: Vec<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    /// Parses the arguments (without the program name).
+    ///
+    /// # Arguments
+    /// - `args` - the arguments to parse
+    /// - `value_options` - the names of the options (without the `--`
+    ///   prefix) that take a value
+    pub fn parse(
+        args: &[String], value_options: &[&str]
+    ) -> Result<Args, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let name = match arg.strip_prefix("--") {
+                Some(name) if !name.is_empty() => name,
+                _ => {
+                    positional.push(arg.clone());
+                    continue;
+                }
+            };
+            if let Some((name, value)) = name.split_once('=') {
+                options.insert(name.to_string(), Some(value.to_string()));
+            } else if value_options.contains(&name) {
+                let value = iter.next().ok_or_else(
+                    || format!("Option \"--{}\" requires a value", name)
+                )?;
+                options.insert(name.to_string(), Some(value.clone()));
+            } else {
+                options.insert(name.to_string(), None);
+            }
+        }
+        Ok(Args { positional, options })
+    }
+
+    /// Returns true if the flag was passed.
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    /// Returns the value of the option if it was passed with a value.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name)?.as_deref()
+    }
+}
@@ -0,0 +1,1128 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::CpfError;
+use crate::hooks::Hooks;
+use crate::mapping;
+use crate::plugin::{self, Plugin, PluginAction, Plugins};
+use crate::preprocess::is_valid_name;
+use crate::recipes::parse_version;
+use crate::rename::RenameRule;
+use crate::text::{BomPolicy, LineEndings};
+
+pub type ExtExportMap = HashMap<String, String>;
+pub static FILTER_DATA_PATH: &str = "data/custom_project";
+pub static EXPORT_FILES_MAP: &str = "data/custom_project/config.json";
+/// The default maximal depth of the directories in the roots.
+pub static DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Returns the path to the root of the Regolith project. Regolith runs the
+/// filters in a temporary directory and provides the path to the root of the
+/// project in the `ROOT_DIR` environment variable. If it's not set, the
+/// working directory is used as the root of the project.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+pub fn project_dir(working_dir: &Path) -> PathBuf {
+    match std::env::var_os("ROOT_DIR") {
+        Some(root) => PathBuf::from(root),
+        None => working_dir.to_path_buf(),
+    }
+}
+
+/// The way the files are transferred from the data directory to the packs.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum CopyMode {
+    /// The files are copied.
+    #[default]
+    Copy,
+    /// The files are moved (renamed) into place. The source files are removed
+    /// from the data directory.
+    Move,
+    /// The targets are hard links to the source files.
+    Hardlink,
+    /// The targets are symbolic links to the source files.
+    Symlink,
+}
+
+impl CopyMode {
+    /// Returns the mode with the given name as used in the config file.
+    pub fn from_name(name: &str) -> Option<CopyMode> {
+        match name {
+            "copy" => Some(CopyMode::Copy),
+            "move" => Some(CopyMode::Move),
+            "hardlink" => Some(CopyMode::Hardlink),
+            "symlink" => Some(CopyMode::Symlink),
+            _ => None,
+        }
+    }
+}
+
+/// What to do when the target of a file already exists in the packs and its
+/// content is different from the source file.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// The target is overwritten.
+    Always,
+    /// The target is kept and the file is skipped with a warning.
+    #[default]
+    Never,
+    /// The target is only overwritten if the source file is newer.
+    Newer,
+    /// The filter fails.
+    Error,
+}
+
+impl OverwritePolicy {
+    /// Returns the policy with the given name as used in the config file.
+    pub fn from_name(name: &str) -> Option<OverwritePolicy> {
+        match name {
+            "always" => Some(OverwritePolicy::Always),
+            "never" => Some(OverwritePolicy::Never),
+            "newer" => Some(OverwritePolicy::Newer),
+            "error" => Some(OverwritePolicy::Error),
+            _ => None,
+        }
+    }
+}
+
+/// What to do when multiple source files are mapped to the same target.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// The first file is copied and the others are skipped with a warning.
+    #[default]
+    Warn,
+    /// The filter fails.
+    Error,
+}
+
+impl ConflictPolicy {
+    /// Returns the policy with the given name as used in the config file.
+    pub fn from_name(name: &str) -> Option<ConflictPolicy> {
+        match name {
+            "warn" => Some(ConflictPolicy::Warn),
+            "error" => Some(ConflictPolicy::Error),
+            _ => None,
+        }
+    }
+}
+
+/// The packs of an additional output of the filter (e.g. a companion debug
+/// pack). The paths are relative to the working directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputPacks {
+    /// The path to the behavior pack of the output.
+    pub bp: String,
+    /// The path to the resource pack of the output.
+    pub rp: String,
+}
+
+/// Parses the "outputs" property of the config file. Returns None if it's
+/// invalid.
+fn parse_outputs(
+    value: &serde_json::Value,
+) -> Option<HashMap<String, OutputPacks>> {
+    if value.is_null() {
+        return Some(HashMap::new());
+    }
+    value.as_object()?.iter()
+        .map(|(name, packs)| Some((name.clone(), OutputPacks {
+            bp: packs["BP"].as_str()?.to_string(),
+            rp: packs["RP"].as_str()?.to_string(),
+        })))
+        .collect()
+}
+
+/// The settings of bundling the scripts of the behavior pack into a single
+/// file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptBundle {
+    /// The entry of the scripts relative to the "scripts" directory (e.g.
+    /// "main.js"), replaced with the bundle.
+    pub entry: String,
+    /// The modules that are imported by the bundle instead of being bundled
+    /// (e.g. "@minecraft/server").
+    pub externals: Vec<String>,
+}
+
+impl Default for ScriptBundle {
+    fn default() -> ScriptBundle {
+        ScriptBundle {
+            entry: "main.js".into(),
+            externals: vec![
+                "@minecraft/server".into(), "@minecraft/server-ui".into(),
+            ],
+        }
+    }
+}
+
+/// Parses the optional boolean property of the config file.
+///
+/// # Arguments
+/// - `config` - the config file
+/// - `key` - the name of the property
+/// - `default` - the value used when the property isn't set
+fn parse_bool(
+    config: &serde_json::Value, key: &str, default: bool,
+) -> Result<bool, CpfError> {
+    match &config[key] {
+        serde_json::Value::Null => Ok(default),
+        serde_json::Value::Bool(b) => Ok(*b),
+        _ => Err(property_error(key)),
+    }
+}
+
+/// Parses the "bundle_scripts" property of the config file: false (or
+/// null), true for the default settings or an object with the "entry" and
+/// the "externals". Returns None if it's invalid.
+fn parse_bundle(value: &serde_json::Value) -> Option<Option<ScriptBundle>> {
+    let object = match value {
+        serde_json::Value::Null | serde_json::Value::Bool(false) => {
+            return Some(None);
+        },
+        serde_json::Value::Bool(true) => {
+            return Some(Some(ScriptBundle::default()));
+        },
+        serde_json::Value::Object(object) => object,
+        _ => return None,
+    };
+    let mut bundle = ScriptBundle::default();
+    if let Some(entry) = object.get("entry") {
+        bundle.entry = entry.as_str()?.to_string();
+    }
+    if let Some(externals) = object.get("externals") {
+        bundle.externals = externals.as_array()?.iter()
+            .map(|v| Some(v.as_str()?.to_string()))
+            .collect::<Option<_>>()?;
+    }
+    Some(Some(bundle))
+}
+
+/// Resolves the target of a rule tagged with the name of an output (e.g.
+/// "debug:BP/functions") to the path in the packs of the output (e.g.
+/// "debug_packs/BP/functions"). The targets without the tag are returned
+/// unchanged.
+///
+/// # Arguments
+/// - `target` - the target of the rule from the config file
+/// - `outputs` - the additional outputs from the config file
+fn resolve_output(
+    target: &str, outputs: &HashMap<String, OutputPacks>,
+) -> Result<String, CpfError> {
+    let (name, path) = match target.split_once(':') {
+        Some(tagged) => tagged,
+        None => return Ok(target.to_string()),
+    };
+    let packs = outputs.get(name).ok_or_else(|| CpfError::Config(format!(
+        "The target \"{}\" in the config file uses the output \"{}\" that \
+        isn't defined in the \"outputs\"", target, name
+    )))?;
+    let path = PathBuf::from(path);
+    let mut components = path.components();
+    let pack = match components.next().map(|c| c.as_os_str()) {
+        Some(pack) if pack == "BP" => &packs.bp,
+        Some(pack) if pack == "RP" => &packs.rp,
+        _ => return Err(CpfError::Config(format!(
+            "The target \"{}\" in the config file must start with \"BP\" \
+            or \"RP\" after the name of the output", target
+        ))),
+    };
+    Ok(Path::new(pack).join(components.as_path()).to_string_lossy()
+        .into_owned())
+}
+
+/// Returns the error for a property of the config file that can't
+/// be parsed.
+fn property_error(name: &str) -> CpfError {
+    CpfError::Config(format!(
+        "Failed to parse \"{}\" property in config file: \"{}\"",
+        name, EXPORT_FILES_MAP
+    ))
+}
+
+/// Parses the "plugins" property of the config file. The paths to the
+/// modules must be relative paths to the ".wasm" files in the data
+/// directory.
+fn parse_plugins(value: &serde_json::Value) -> Option<Vec<Plugin>> {
+    let strings = |value: &serde_json::Value| -> Option<Vec<String>> {
+        value.as_array()?.iter()
+            .map(|v| Some(v.as_str()?.to_string()))
+            .collect()
+    };
+    let parse_plugin = |value: &serde_json::Value| -> Option<Plugin> {
+        let module = PathBuf::from(value["module"].as_str()?);
+        if !plugin::is_valid_module_path(&module) {
+            return None;
+        }
+        let actions = strings(&value["actions"])?.iter()
+            .map(|name| PluginAction::from_name(name))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Plugin {
+            module,
+            extensions: strings(&value["extensions"])?,
+            actions,
+        })
+    };
+    match value {
+        serde_json::Value::Null => Some(Vec::new()),
+        value => value.as_array()?.iter().map(parse_plugin).collect(),
+    }
+}
+
+/// The configuration of the filter loaded from the config file in the data
+/// directory of the filter.
+pub struct Config {
+    /// The map of file extensions and the export targets from the
+    /// "extensions_map" (without the built-in rules).
+    pub export_map: ExtExportMap,
+    /// The list of paths, relative to the filter data path, that serve as the
+    /// root of the source files to be copied to RP and BP.
+    pub roots: Vec<String>,
+    /// The namespace of the project used for generating new content.
+    pub namespace: Option<String>,
+    /// The way the files are transferred to the packs.
+    pub mode: CopyMode,
+    /// Whether the symlink mode should only be used outside of the temporary
+    /// directory of Regolith. The files are copied instead in Regolith.
+    pub symlinks_outside_regolith_only: bool,
+    /// Whether to clone the files using copy-on-write on the file systems
+    /// that support it.
+    pub reflink: bool,
+    /// Whether to replace the targets with identical contents with hard
+    /// links to a single file.
+    pub dedupe: bool,
+    /// What to do when the targets already exist.
+    pub overwrite: OverwritePolicy,
+    /// What to do when multiple source files are mapped to the same target.
+    pub conflicts: ConflictPolicy,
+    /// Whether to follow the symbolic links in the data directory.
+    pub follow_symlinks: bool,
+    /// The maximal depth of the directories in the roots.
+    pub max_depth: usize,
+    /// Whether to copy the modification times and the permissions of the
+    /// source files to the targets.
+    pub preserve_metadata: bool,
+    /// The number of threads used for walking the directories and copying
+    /// the files. 0 means the number of the available CPUs.
+    pub jobs: usize,
+    /// Whether the built-in mapping rules are used. The rules from the
+    /// "extensions_map" override them.
+    pub default_mappings: bool,
+    /// The WASM plugins that map, transform and validate the files.
+    pub plugins: Plugins,
+    /// Whether the files from the "BP" and "RP" folders of the roots are
+    /// copied to the same paths in the packs.
+    pub passthrough: bool,
+    /// Whether the files from the "SHARED" folders of the roots are copied
+    /// to the same paths in both of the packs.
+    pub shared: bool,
+    /// The directory of the packs that the files not matched by any rule are
+    /// copied to or None if they're skipped.
+    pub fallback: Option<String>,
+    /// Whether the ".json" files that aren't matched by any rule are mapped
+    /// based on their content (e.g. the "minecraft:entity" key).
+    pub sniff_json: bool,
+    /// Whether the folders with the files named only with the extensions
+    /// (e.g. "zombie/_.bpe.json") are the folders of a single feature, so
+    /// the name of the folder is the base name of every file inside.
+    pub feature_folders: bool,
+    /// Whether the textures mapped directly to "RP/textures" are routed to
+    /// the "entity", "items" or "blocks" subfolder based on the files next
+    /// to them.
+    pub texture_routing: bool,
+    /// Whether the name of the root is added as a subfolder of the export
+    /// directory to the targets of the files mapped by the extension rules
+    /// (e.g. "RP/models/entity/combat/zombie.geo.json" for the "combat"
+    /// root).
+    pub root_subfolders: bool,
+    /// Whether the sprites in the ".atlas" folders are stitched into the
+    /// atlas textures.
+    pub texture_atlases: bool,
+    /// Whether the numeric ordering prefixes (e.g. "010_") are stripped from
+    /// the names of the targets.
+    pub strip_order_prefixes: bool,
+    /// Whether the variants of the textures declared in the ".variants.json"
+    /// files are generated.
+    pub texture_variants: bool,
+    /// Whether the flipbook textures are generated from the folders with the
+    /// frames.
+    pub flipbooks: bool,
+    /// Whether the sounds in the "events" folders of the entities are added
+    /// to the sound definitions and bound to the events of the entities.
+    pub sound_events: bool,
+    /// Whether the .lang files are generated from the translation tables
+    /// (the ".lang.csv" and ".lang.tsv" files).
+    pub lang_tables: bool,
+    /// Whether the trade tables are compiled from the spreadsheets with the
+    /// trades (the ".tt.csv" files).
+    pub trade_tables: bool,
+    /// Whether the loot tables are compiled from the spreadsheets with the
+    /// loot (the ".lt.csv" files).
+    pub loot_tables: bool,
+    /// Whether the dialogues are compiled from the Markdown files (the
+    /// ".d.md" files).
+    pub markdown_dialogue: bool,
+    /// Whether the recipes are compiled from the files in the shorthand
+    /// format (the ".recipes" files).
+    pub recipe_shorthand: bool,
+    /// The folders of the functions (relative to the "functions" directory)
+    /// that get the umbrella functions calling all of their functions, or
+    /// None if the umbrella functions aren't generated. The folders of the
+    /// annotated functions get them too.
+    pub umbrella_functions: Option<Vec<String>>,
+    /// Whether the functions annotated with "# @tick" are added to the
+    /// generated "tick.json".
+    pub tick_annotations: bool,
+    /// The rules that rename the targets, applied in order.
+    pub rename: Vec<RenameRule>,
+    /// The additional outputs of the filter by their names. The targets of
+    /// the rules tagged with the names (e.g. "debug:BP/functions") are
+    /// already resolved to the paths of the outputs.
+    pub outputs: HashMap<String, OutputPacks>,
+    /// The external commands run around the copying of the files.
+    pub hooks: Hooks,
+    /// What to do with the byte order marks of the JSON and .lang files.
+    pub bom: BomPolicy,
+    /// The line endings of the JSON, .lang and .mcfunction files.
+    pub line_endings: LineEndings,
+    /// The targets (e.g. "BP/entities/zombie.json") and the identifiers (e.g.
+    /// "minecraft:zombie") that intentionally override the vanilla content.
+    /// The other overrides are reported.
+    pub vanilla_overrides: Vec<String>,
+    /// Whether the preprocessor directives of the .mcfunction files (the
+    /// includes, the macros and the constants) are expanded.
+    pub preprocess_functions: bool,
+    /// The constants substituted for the "${NAME}" references in the
+    /// preprocessed .mcfunction files by their names.
+    pub constants: HashMap<String, String>,
+    /// The oldest version of the game that the packs support (e.g. [1, 19,
+    /// 0]). The commands of the functions that aren't available in this
+    /// version are reported.
+    pub min_engine_version: Option<Vec<u32>>,
+    /// The command that compiles the TypeScript files mapped to the scripts
+    /// of the behavior pack (e.g. `npx esbuild "$CPF_SOURCE"`) or None if
+    /// they aren't compiled.
+    pub typescript: Option<String>,
+    /// The settings of bundling the scripts of the behavior pack into their
+    /// entry or None if they aren't bundled.
+    pub bundle_scripts: Option<ScriptBundle>,
+    /// The versions of the script modules of the game (e.g. "1.11.0" for
+    /// "@minecraft/server") added to the dependencies of the manifest of the
+    /// behavior pack with the scripts, together with the script module.
+    pub script_dependencies: HashMap<String, String>,
+}
+
+impl Config {
+    /// Loads the config file from the data directory of the filter.
+    ///
+    /// # Arguments
+    /// - `working_dir` - the path to the working directory of regolith
+    pub fn load(working_dir: &Path) -> Result<Config, CpfError> {
+        let export_map_path = working_dir.join(EXPORT_FILES_MAP);
+        let mut config = match fs::read_to_string(export_map_path) {
+            Ok(s) => Config::parse(&s)?,
+            Err(e) => return Err(CpfError::Config(format!(
+                "Unable to read \"{}\": {}", EXPORT_FILES_MAP, e
+            ))),
+        };
+        // The modules of the plugins and the roots are relative to the
+        // data directory
+        let data_dir = working_dir.join(FILTER_DATA_PATH);
+        let plugins = &mut config.plugins;
+        plugins.roots = config.roots.iter()
+            .map(|root| data_dir.join(root))
+            .collect();
+        for plugin in &mut plugins.plugins {
+            plugin.module = data_dir.join(&plugin.module);
+            plugin::check_module(&plugin.module).map_err(CpfError::Config)?;
+        }
+        Ok(config)
+    }
+
+    /// Parses the text of the config file without accessing the file system
+    /// (e.g. in the WebAssembly builds for the web tools).
+    ///
+    /// # Arguments
+    /// - `text` - the content of the config file
+    pub fn parse(text: &str) -> Result<Config, CpfError> {
+        let config: serde_json::Value = serde_json::from_str(text)
+            .map_err(|e| CpfError::Config(
+                format!("Unable to parse \"{}\": {}", EXPORT_FILES_MAP, e)
+            ))?;
+        let default_mappings = parse_bool(&config, "default_mappings", false)?;
+        let outputs = parse_outputs(&config["outputs"])
+            .ok_or_else(|| property_error("outputs"))?;
+        // Get extensions_map from the config. It's optional when the
+        // built-in rules are used.
+        let mut export_map: ExtExportMap = match &config["extensions_map"] {
+            serde_json::Value::Null if default_mappings => ExtExportMap::new(),
+            value => value.as_object()
+                .and_then(|m| m.iter()
+                    .map(|(k, v)| {
+                        Some((k.to_string(), v.as_str()?.to_string()))
+                    })
+                    .collect::<Option<ExtExportMap>>())
+                .ok_or_else(|| property_error("extensions_map"))?,
+        };
+        for target in export_map.values_mut() {
+            *target = resolve_output(target, &outputs)?;
+        }
+        // Get roots from the config
+        let roots: Vec<String> = config["roots"].as_array()
+            .and_then(|r| r.iter()
+                .map(|v| Some(v.as_str()?.to_string()))
+                .collect())
+            .ok_or_else(|| property_error("roots"))?;
+        // Get the optional namespace from the config
+        let namespace = match &config["namespace"] {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(s) => Some(s.clone()),
+            _ => return Err(property_error("namespace")),
+        };
+        // Get the optional mode from the config
+        let mode = match &config["mode"] {
+            serde_json::Value::Null => CopyMode::default(),
+            serde_json::Value::String(s) => CopyMode::from_name(s)
+                .ok_or_else(|| property_error("mode"))?,
+            _ => return Err(property_error("mode")),
+        };
+        let symlinks_outside_regolith_only = parse_bool(
+            &config, "symlinks_outside_regolith_only", true
+        )?;
+        let reflink = parse_bool(&config, "reflink", true)?;
+        let dedupe = parse_bool(&config, "dedupe", false)?;
+        let overwrite = match &config["overwrite"] {
+            serde_json::Value::Null => OverwritePolicy::default(),
+            serde_json::Value::String(s) => OverwritePolicy::from_name(s)
+                .ok_or_else(|| property_error("overwrite"))?,
+            _ => return Err(property_error("overwrite")),
+        };
+        let conflicts = match &config["conflicts"] {
+            serde_json::Value::Null => ConflictPolicy::default(),
+            serde_json::Value::String(s) => ConflictPolicy::from_name(s)
+                .ok_or_else(|| property_error("conflicts"))?,
+            _ => return Err(property_error("conflicts")),
+        };
+        let follow_symlinks = parse_bool(&config, "follow_symlinks", true)?;
+        let max_depth = match &config["max_depth"] {
+            serde_json::Value::Null => DEFAULT_MAX_DEPTH,
+            value => value.as_u64()
+                .ok_or_else(|| property_error("max_depth"))? as usize,
+        };
+        let preserve_metadata =
+            parse_bool(&config, "preserve_metadata", false)?;
+        let jobs = match &config["jobs"] {
+            serde_json::Value::Null => 0,
+            value => value.as_u64()
+                .ok_or_else(|| property_error("jobs"))? as usize,
+        };
+        // The paths are resolved by Config::load
+        let plugins = Plugins::new(
+            parse_plugins(&config["plugins"])
+                .ok_or_else(|| property_error("plugins"))?,
+            Vec::new(),
+        );
+        let passthrough = parse_bool(&config, "passthrough", false)?;
+        let shared = parse_bool(&config, "shared", false)?;
+        let fallback = match &config["fallback"] {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(s) => Some(resolve_output(s, &outputs)?),
+            _ => return Err(property_error("fallback")),
+        };
+        let sniff_json = parse_bool(&config, "sniff_json", false)?;
+        let feature_folders = parse_bool(&config, "feature_folders", false)?;
+        let texture_routing = parse_bool(&config, "texture_routing", false)?;
+        let root_subfolders = parse_bool(&config, "root_subfolders", false)?;
+        let texture_atlases = parse_bool(&config, "texture_atlases", false)?;
+        let strip_order_prefixes =
+            parse_bool(&config, "strip_order_prefixes", false)?;
+        let texture_variants = parse_bool(&config, "texture_variants", false)?;
+        let flipbooks = parse_bool(&config, "flipbooks", false)?;
+        let sound_events = parse_bool(&config, "sound_events", false)?;
+        let lang_tables = parse_bool(&config, "lang_tables", false)?;
+        let trade_tables = parse_bool(&config, "trade_tables", false)?;
+        let loot_tables = parse_bool(&config, "loot_tables", false)?;
+        let markdown_dialogue =
+            parse_bool(&config, "markdown_dialogue", false)?;
+        let recipe_shorthand = parse_bool(&config, "recipe_shorthand", false)?;
+        let umbrella_functions = match &config["umbrella_functions"] {
+            serde_json::Value::Null | serde_json::Value::Bool(false) => None,
+            serde_json::Value::Bool(true) => Some(Vec::new()),
+            value => Some(value.as_array()
+                .and_then(|o| o.iter()
+                    .map(|v| Some(v.as_str()?.to_string()))
+                    .collect())
+                .ok_or_else(|| property_error("umbrella_functions"))?),
+        };
+        let tick_annotations = parse_bool(&config, "tick_annotations", false)?;
+        let rename = RenameRule::from_json(
+            &config["rename"], namespace.as_deref()
+        ).ok_or_else(|| property_error("rename"))?;
+        let hooks = Hooks::from_json(&config["hooks"])
+            .ok_or_else(|| property_error("hooks"))?;
+        let bom = match &config["bom"] {
+            serde_json::Value::Null => BomPolicy::default(),
+            serde_json::Value::String(s) => BomPolicy::from_name(s)
+                .ok_or_else(|| property_error("bom"))?,
+            _ => return Err(property_error("bom")),
+        };
+        let line_endings = match &config["line_endings"] {
+            serde_json::Value::Null => LineEndings::default(),
+            serde_json::Value::String(s) => LineEndings::from_name(s)
+                .ok_or_else(|| property_error("line_endings"))?,
+            _ => return Err(property_error("line_endings")),
+        };
+        let vanilla_overrides = match &config["vanilla_overrides"] {
+            serde_json::Value::Null => Vec::new(),
+            value => value.as_array()
+                .and_then(|o| o.iter()
+                    .map(|v| Some(v.as_str()?.to_string()))
+                    .collect())
+                .ok_or_else(|| property_error("vanilla_overrides"))?,
+        };
+        let preprocess_functions =
+            parse_bool(&config, "preprocess_functions", false)?;
+        let constants = match &config["constants"] {
+            serde_json::Value::Null => HashMap::new(),
+            value => value.as_object()
+                .and_then(|o| o.iter()
+                    .map(|(name, value)| {
+                        let value = match value {
+                            serde_json::Value::String(s) => s.clone(),
+                            serde_json::Value::Number(n) => n.to_string(),
+                            serde_json::Value::Bool(b) => b.to_string(),
+                            _ => return None,
+                        };
+                        match is_valid_name(name) {
+                            true => Some((name.clone(), value)),
+                            false => None,
+                        }
+                    })
+                    .collect())
+                .ok_or_else(|| property_error("constants"))?,
+        };
+        let min_engine_version = match &config["min_engine_version"] {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(s) => Some(
+                parse_version(s)
+                    .ok_or_else(|| property_error("min_engine_version"))?
+            ),
+            value => Some(value.as_array()
+                .and_then(|o| o.iter()
+                    .map(|v| u32::try_from(v.as_u64()?).ok())
+                    .collect::<Option<Vec<u32>>>())
+                .filter(|version| !version.is_empty())
+                .ok_or_else(|| property_error("min_engine_version"))?),
+        };
+        let typescript = match &config["typescript"] {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(s) => Some(s.clone()),
+            _ => return Err(property_error("typescript")),
+        };
+        let bundle_scripts = parse_bundle(&config["bundle_scripts"])
+            .ok_or_else(|| property_error("bundle_scripts"))?;
+        let script_dependencies = match &config["script_dependencies"] {
+            serde_json::Value::Null => HashMap::new(),
+            value => value.as_object()
+                .and_then(|o| o.iter()
+                    .map(|(name, version)| Some(
+                        (name.clone(), version.as_str()?.to_string())
+                    ))
+                    .collect())
+                .ok_or_else(|| property_error("script_dependencies"))?,
+        };
+        Ok(Config {
+            export_map, roots, namespace, mode,
+            symlinks_outside_regolith_only, reflink, dedupe, overwrite,
+            conflicts, follow_symlinks, max_depth, preserve_metadata, jobs,
+            default_mappings, plugins, passthrough, shared, fallback,
+            sniff_json, feature_folders, texture_routing, root_subfolders,
+            texture_atlases, strip_order_prefixes, texture_variants, flipbooks,
+            sound_events, lang_tables, trade_tables, loot_tables,
+            markdown_dialogue, recipe_shorthand, umbrella_functions,
+            tick_annotations, rename, outputs, hooks, bom, line_endings,
+            vanilla_overrides, preprocess_functions, constants,
+            min_engine_version, typescript, bundle_scripts,
+            script_dependencies,
+        })
+    }
+
+    /// Returns a builder for constructing the config in code instead of
+    /// loading it from the config file.
+    pub fn builder() -> SettingsBuilder {
+        SettingsBuilder::new()
+    }
+
+    /// Returns the rules of the "extensions_map" together with the built-in
+    /// rules if they're enabled. The rules from the "extensions_map"
+    /// override the built-in rules with the same extensions.
+    pub fn rules(&self) -> ExtExportMap {
+        let mut rules = match self.default_mappings {
+            true => mapping::default_export_map(),
+            false => ExtExportMap::new(),
+        };
+        rules.extend(self.export_map.clone());
+        rules
+    }
+}
+
+impl Default for Config {
+    /// Returns the config with the default values of the optional settings
+    /// of the config file, no rules and no roots.
+    fn default() -> Config {
+        Config {
+            export_map: ExtExportMap::new(),
+            roots: Vec::new(),
+            namespace: None,
+            mode: CopyMode::default(),
+            symlinks_outside_regolith_only: true,
+            reflink: true,
+            dedupe: false,
+            overwrite: OverwritePolicy::default(),
+            conflicts: ConflictPolicy::default(),
+            follow_symlinks: true,
+            max_depth: DEFAULT_MAX_DEPTH,
+            preserve_metadata: false,
+            jobs: 0,
+            default_mappings: false,
+            plugins: Plugins::default(),
+            passthrough: false,
+            shared: false,
+            fallback: None,
+            sniff_json: false,
+            feature_folders: false,
+            texture_routing: false,
+            root_subfolders: false,
+            texture_atlases: false,
+            strip_order_prefixes: false,
+            texture_variants: false,
+            flipbooks: false,
+            sound_events: false,
+            lang_tables: false,
+            trade_tables: false,
+            loot_tables: false,
+            markdown_dialogue: false,
+            recipe_shorthand: false,
+            umbrella_functions: None,
+            tick_annotations: false,
+            rename: Vec::new(),
+            outputs: HashMap::new(),
+            hooks: Hooks::default(),
+            bom: BomPolicy::default(),
+            line_endings: LineEndings::default(),
+            vanilla_overrides: Vec::new(),
+            preprocess_functions: false,
+            constants: HashMap::new(),
+            min_engine_version: None,
+            typescript: None,
+            bundle_scripts: None,
+            script_dependencies: HashMap::new(),
+        }
+    }
+}
+
+/// Constructs the config of the filter in code (e.g. when the filter is
+/// embedded in other build tools) instead of loading it from the config
+/// file. The settings that aren't set have the same default values as in the
+/// config file.
+///
+/// ```
+/// use custom_project::config::{Config, OverwritePolicy};
+///
+/// let config = Config::builder()
+///     .root("src")
+///     .rule(".bpe.json", "BP/entities")
+///     .overwrite(OverwritePolicy::Always)
+///     .after_hook("python pack.py")
+///     .build()
+///     .unwrap();
+/// assert_eq!(config.export_map[".bpe.json"], "BP/entities");
+/// ```
+#[derive(Default)]
+pub struct SettingsBuilder {
+    config: Config,
+}
+
+impl SettingsBuilder {
+    /// Creates a builder with the default settings.
+    pub fn new() -> SettingsBuilder {
+        SettingsBuilder::default()
+    }
+
+    /// Adds a root (a path relative to the filter data path) of the source
+    /// files.
+    pub fn root(mut self, root: &str) -> SettingsBuilder {
+        self.config.roots.push(root.to_string());
+        self
+    }
+
+    /// Adds a rule that maps the files with the extension to the target
+    /// directory. The target can be tagged with the name of an output (e.g.
+    /// "debug:BP/functions").
+    pub fn rule(mut self, extension: &str, target: &str) -> SettingsBuilder {
+        self.config.export_map.insert(
+            extension.to_string(), target.to_string()
+        );
+        self
+    }
+
+    /// Sets whether the built-in mapping rules are used.
+    pub fn default_mappings(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.default_mappings = enabled;
+        self
+    }
+
+    /// Sets whether the files from the "BP" and "RP" folders of the roots
+    /// are copied to the same paths in the packs.
+    pub fn passthrough(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.passthrough = enabled;
+        self
+    }
+
+    /// Enables or disables copying the "SHARED" folders of the roots to both
+    /// of the packs.
+    pub fn shared(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.shared = enabled;
+        self
+    }
+
+    /// Sets the directory of the packs that the files not matched by any
+    /// rule are copied to.
+    pub fn fallback(mut self, target: &str) -> SettingsBuilder {
+        self.config.fallback = Some(target.to_string());
+        self
+    }
+
+    /// Enables or disables mapping the ".json" files that aren't matched by
+    /// any rule based on their content (see `Config::sniff_json`).
+    pub fn sniff_json(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.sniff_json = enabled;
+        self
+    }
+
+    /// Enables or disables the feature folders (see
+    /// `Config::feature_folders`).
+    pub fn feature_folders(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.feature_folders = enabled;
+        self
+    }
+
+    /// Enables or disables the routing of the textures (see
+    /// `Config::texture_routing`).
+    pub fn texture_routing(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.texture_routing = enabled;
+        self
+    }
+
+    /// Enables or disables the subfolders of the roots (see
+    /// `Config::root_subfolders`).
+    pub fn root_subfolders(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.root_subfolders = enabled;
+        self
+    }
+
+    /// Enables or disables stitching the sprites into the atlas textures
+    /// (see `Config::texture_atlases`).
+    pub fn texture_atlases(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.texture_atlases = enabled;
+        self
+    }
+
+    /// Enables or disables stripping the numeric ordering prefixes from the
+    /// names of the targets (see `Config::strip_order_prefixes`).
+    pub fn strip_order_prefixes(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.strip_order_prefixes = enabled;
+        self
+    }
+
+    /// Enables or disables generating the variants of the textures (see
+    /// `Config::texture_variants`).
+    pub fn texture_variants(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.texture_variants = enabled;
+        self
+    }
+
+    /// Enables or disables generating the flipbook textures (see
+    /// `Config::flipbooks`).
+    pub fn flipbooks(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.flipbooks = enabled;
+        self
+    }
+
+    /// Enables or disables wiring the sounds of the events of the entities
+    /// (see `Config::sound_events`).
+    pub fn sound_events(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.sound_events = enabled;
+        self
+    }
+
+    /// Enables or disables generating the .lang files from the translation
+    /// tables (see `Config::lang_tables`).
+    pub fn lang_tables(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.lang_tables = enabled;
+        self
+    }
+
+    /// Enables or disables compiling the trade tables from the spreadsheets
+    /// (see `Config::trade_tables`).
+    pub fn trade_tables(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.trade_tables = enabled;
+        self
+    }
+
+    /// Enables or disables compiling the loot tables from the spreadsheets
+    /// (see `Config::loot_tables`).
+    pub fn loot_tables(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.loot_tables = enabled;
+        self
+    }
+
+    /// Enables or disables compiling the dialogues from the Markdown files
+    /// (see `Config::markdown_dialogue`).
+    pub fn markdown_dialogue(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.markdown_dialogue = enabled;
+        self
+    }
+
+    /// Enables or disables compiling the recipes from the files in the
+    /// shorthand format (see `Config::recipe_shorthand`).
+    pub fn recipe_shorthand(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.recipe_shorthand = enabled;
+        self
+    }
+
+    /// Enables generating the umbrella functions for the folders of the
+    /// functions (see `Config::umbrella_functions`).
+    pub fn umbrella_functions(
+        mut self, folders: Vec<String>,
+    ) -> SettingsBuilder {
+        self.config.umbrella_functions = Some(folders);
+        self
+    }
+
+    /// Enables or disables adding the annotated functions to the
+    /// "tick.json" (see `Config::tick_annotations`).
+    pub fn tick_annotations(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.tick_annotations = enabled;
+        self
+    }
+
+    /// Adds a rule that renames the targets. The rules are applied in the
+    /// order they're added.
+    pub fn rename_rule(mut self, rule: RenameRule) -> SettingsBuilder {
+        self.config.rename.push(rule);
+        self
+    }
+
+    /// Adds an additional output of the filter.
+    pub fn output(
+        mut self, name: &str, packs: OutputPacks,
+    ) -> SettingsBuilder {
+        self.config.outputs.insert(name.to_string(), packs);
+        self
+    }
+
+    /// Sets the namespace of the project used for generating new content.
+    pub fn namespace(mut self, namespace: &str) -> SettingsBuilder {
+        self.config.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Sets the way the files are transferred to the packs.
+    pub fn mode(mut self, mode: CopyMode) -> SettingsBuilder {
+        self.config.mode = mode;
+        self
+    }
+
+    /// Sets what to do when the targets already exist.
+    pub fn overwrite(mut self, policy: OverwritePolicy) -> SettingsBuilder {
+        self.config.overwrite = policy;
+        self
+    }
+
+    /// Sets what to do when multiple source files are mapped to the same
+    /// target.
+    pub fn conflicts(mut self, policy: ConflictPolicy) -> SettingsBuilder {
+        self.config.conflicts = policy;
+        self
+    }
+
+    /// Sets whether the targets with identical contents are replaced with
+    /// hard links to a single file.
+    pub fn dedupe(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.dedupe = enabled;
+        self
+    }
+
+    /// Sets whether the files are cloned using copy-on-write when possible.
+    pub fn reflink(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.reflink = enabled;
+        self
+    }
+
+    /// Sets whether the symbolic links in the data directory are followed.
+    pub fn follow_symlinks(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.follow_symlinks = enabled;
+        self
+    }
+
+    /// Sets the maximal depth of the directories in the roots.
+    pub fn max_depth(mut self, max_depth: usize) -> SettingsBuilder {
+        self.config.max_depth = max_depth;
+        self
+    }
+
+    /// Sets whether the modification times and the permissions of the
+    /// source files are copied to the targets.
+    pub fn preserve_metadata(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.preserve_metadata = enabled;
+        self
+    }
+
+    /// Sets the number of threads (0 means the number of the CPUs).
+    pub fn jobs(mut self, jobs: usize) -> SettingsBuilder {
+        self.config.jobs = jobs;
+        self
+    }
+
+    /// Sets what to do with the byte order marks of the JSON and .lang
+    /// files.
+    pub fn bom(mut self, policy: BomPolicy) -> SettingsBuilder {
+        self.config.bom = policy;
+        self
+    }
+
+    /// Sets the line endings of the JSON, .lang and .mcfunction files.
+    pub fn line_endings(mut self, endings: LineEndings) -> SettingsBuilder {
+        self.config.line_endings = endings;
+        self
+    }
+
+    /// Allows the target (e.g. "BP/entities/zombie.json") or the identifier
+    /// (e.g. "minecraft:zombie") to override the vanilla content without a
+    /// warning.
+    pub fn vanilla_override(mut self, target: &str) -> SettingsBuilder {
+        self.config.vanilla_overrides.push(target.to_string());
+        self
+    }
+
+    /// Enables or disables expanding the preprocessor directives of the
+    /// .mcfunction files (see `Config::preprocess_functions`).
+    pub fn preprocess_functions(mut self, enabled: bool) -> SettingsBuilder {
+        self.config.preprocess_functions = enabled;
+        self
+    }
+
+    /// Adds a constant substituted for the "${NAME}" references in the
+    /// preprocessed .mcfunction files.
+    pub fn constant(mut self, name: &str, value: &str) -> SettingsBuilder {
+        self.config.constants.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the oldest version of the game that the packs support (see
+    /// `Config::min_engine_version`).
+    pub fn min_engine_version(mut self, version: &[u32]) -> SettingsBuilder {
+        self.config.min_engine_version = Some(version.to_vec());
+        self
+    }
+
+    /// Sets the command that compiles the TypeScript files (see
+    /// `Config::typescript`).
+    pub fn typescript(mut self, command: &str) -> SettingsBuilder {
+        self.config.typescript = Some(command.to_string());
+        self
+    }
+
+    /// Enables bundling the scripts of the behavior pack into their entry
+    /// (see `Config::bundle_scripts`).
+    pub fn bundle_scripts(mut self, bundle: ScriptBundle) -> SettingsBuilder {
+        self.config.bundle_scripts = Some(bundle);
+        self
+    }
+
+    /// Adds the script module of the game with its version to the manifest
+    /// of the behavior pack (see `Config::script_dependencies`).
+    pub fn script_dependency(
+        mut self, name: &str, version: &str,
+    ) -> SettingsBuilder {
+        self.config.script_dependencies
+            .insert(name.to_string(), version.to_string());
+        self
+    }
+
+    /// Replaces all of the hooks.
+    pub fn hooks(mut self, hooks: Hooks) -> SettingsBuilder {
+        self.config.hooks = hooks;
+        self
+    }
+
+    /// Adds a command run before walking the data directory.
+    pub fn before_hook(mut self, command: &str) -> SettingsBuilder {
+        self.config.hooks.before.push(command.to_string());
+        self
+    }
+
+    /// Adds a command run after copying the files.
+    pub fn after_hook(mut self, command: &str) -> SettingsBuilder {
+        self.config.hooks.after.push(command.to_string());
+        self
+    }
+
+    /// Adds a command run after copying each file.
+    pub fn per_file_hook(mut self, command: &str) -> SettingsBuilder {
+        self.config.hooks.per_file.push(command.to_string());
+        self
+    }
+
+    /// Builds the config. The targets tagged with the names of the outputs
+    /// are resolved like in the config file.
+    pub fn build(self) -> Result<Config, CpfError> {
+        let mut config = self.config;
+        for target in config.export_map.values_mut() {
+            *target = resolve_output(target, &config.outputs)?;
+        }
+        if let Some(fallback) = &config.fallback {
+            config.fallback = Some(resolve_output(fallback, &config.outputs)?);
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_optional_settings_with_their_defaults() {
+        let config = Config::parse(
+            r#"{"roots": ["src"], "extensions_map": {}}"#
+        ).unwrap();
+        assert_eq!(config.roots, ["src"]);
+        assert!(config.follow_symlinks && config.reflink);
+        assert!(!config.shared && !config.dedupe);
+        let config = Config::parse(
+            r#"{
+                "roots": [], "extensions_map": {},
+                "shared": true, "reflink": false
+            }"#
+        ).unwrap();
+        assert!(config.shared && !config.reflink);
+    }
+
+    #[test]
+    fn reports_the_invalid_properties() {
+        let error = |text: &str| match Config::parse(text) {
+            Err(CpfError::Config(message)) => message,
+            _ => panic!("The config \"{}\" is valid.", text),
+        };
+        let config = |property: &str| format!(
+            r#"{{"roots": [], "extensions_map": {{}}, {}}}"#, property
+        );
+        assert!(error(&config(r#""shared": 1"#)).contains("\"shared\""));
+        assert!(error(&config(r#""plugins": [{"module": "a.wasm"}]"#))
+            .contains("\"plugins\""));
+        assert!(error("{").contains("Unable to parse"));
+    }
+}
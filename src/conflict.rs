@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+use crate::error::CpfError;
+use crate::plan::PlannedFile;
+use crate::report;
+
+/// Checks if the conflicts can be resolved interactively. The interactive
+/// mode requires a terminal and is disabled on CI (when the `CI` environment
+/// variable is set).
+pub fn can_prompt() -> bool {
+    io::stdin().is_terminal() && std::env::var_os("CI").is_none()
+}
+
+/// Resolves the conflicts between the source files that are mapped to the
+/// same targets by keeping the first file. Returns the indices of the files
+/// that shouldn't be copied.
+///
+/// # Arguments
+/// - `files` - the planned files
+/// - `conflicts` - the conflicting targets with the indices of the files
+///   that are mapped to them
+pub fn keep_first(
+    files: &[PlannedFile], conflicts: &[(PathBuf, Vec<usize>)],
+) -> HashSet<usize> {
+    let mut skipped = HashSet::new();
+    for (target, sources) in conflicts {
+        for i in &sources[1..] {
+            report::warning(&files[*i].source, format!(
+                "Skipped because \"{}\" is already mapped from \"{}\".",
+                target.display(), files[sources[0]].source.display()
+            ));
+            skipped.insert(*i);
+        }
+    }
+    skipped
+}
+
+/// Creates the error listing all of the source files of the conflicting
+/// targets.
+///
+/// # Arguments
+/// - `files` - the planned files
+/// - `conflicts` - the conflicting targets with the indices of the files
+///   that are mapped to them
+pub fn conflicts_error(
+    files: &[PlannedFile], conflicts: &[(PathBuf, Vec<usize>)],
+) -> CpfError {
+    let mut message = String::from(
+        "Multiple files are mapped to the same targets:"
+    );
+    let mut targets = Vec::new();
+    for (target, sources) in conflicts {
+        message.push_str(&format!("\n  \"{}\" from:", target.display()));
+        for i in sources {
+            message.push_str(
+                &format!("\n    \"{}\"", files[*i].source.display())
+            );
+        }
+        let sources = sources.iter().map(|i| files[*i].source.clone());
+        targets.push((target.clone(), sources.collect()));
+    }
+    CpfError::Conflict { targets, message }
+}
+
+/// Creates the error listing the targets that differ only by the case of
+/// the letters together with their source files.
+///
+/// # Arguments
+/// - `files` - the planned files
+/// - `conflicts` - the groups of the indices of the files with the targets
+///   that differ only by case
+pub fn case_conflicts_error(
+    files: &[PlannedFile], conflicts: &[Vec<usize>],
+) -> CpfError {
+    let mut message = String::from(
+        "Some targets differ only by case and would collide on Windows and \
+        macOS:"
+    );
+    let mut targets = Vec::new();
+    for (n, group) in conflicts.iter().enumerate() {
+        // Separate the groups with empty lines
+        if n > 0 {
+            message.push('\n');
+        }
+        for i in group {
+            let file = &files[*i];
+            let target = file.target_path().unwrap();
+            message.push_str(&format!(
+                "\n  \"{}\" from \"{}\"",
+                target.display(), file.source.display()
+            ));
+            targets.push((target.clone(), vec![file.source.clone()]));
+        }
+    }
+    CpfError::Conflict { targets, message }
+}
+
+/// Asks the user how to resolve the conflicts between the source files that
+/// are mapped to the same targets. Returns the indices of the files that
+/// shouldn't be copied.
+///
+/// # Arguments
+/// - `files` - the planned files
+/// - `conflicts` - the conflicting targets with the indices of the files
+///   that are mapped to them
+pub fn resolve_interactively(
+    files: &[PlannedFile], conflicts: &[(PathBuf, Vec<usize>)],
+) -> Result<HashSet<usize>, CpfError> {
+    let mut skipped = HashSet::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    for (target, sources) in conflicts {
+        println!(
+            "CONFLICT! {} files are mapped to \"{}\":",
+            sources.len(), target.display()
+        );
+        for (n, i) in sources.iter().enumerate() {
+            println!("  [{}] \"{}\"", n + 1, files[*i].source.display());
+        }
+        loop {
+            print!(
+                "Keep [1-{}], [s]kip all or [a]bort? ", sources.len()
+            );
+            io::stdout().flush()?;
+            let answer = match lines.next() {
+                Some(line) => line?,
+                None => return Err("Aborted (no input).".into()),
+            };
+            let answer = answer.trim();
+            if answer == "s" {
+                skipped.extend(sources.iter().copied());
+                break;
+            }
+            if answer == "a" {
+                return Err("Aborted by the user.".into());
+            }
+            match answer.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= sources.len() => {
+                    let kept = sources[n - 1];
+                    skipped.extend(
+                        sources.iter().copied().filter(|i| *i != kept)
+                    );
+                    break;
+                },
+                _ => println!("Invalid answer: \"{}\"", answer),
+            }
+        }
+    }
+    Ok(skipped)
+}
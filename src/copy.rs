@@ -0,0 +1,1357 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use crate::bundle::{bundle_contents, BUNDLE_RULE};
+use crate::cache::HashCache;
+use crate::check::{
+    check_contents, check_json_source, check_target, check_vanilla_override,
+    JsonProblem,
+};
+use crate::config::{
+    ConflictPolicy, CopyMode, OverwritePolicy, ScriptBundle,
+    FILTER_DATA_PATH,
+};
+use crate::conflict;
+use crate::dedupe::dedupe;
+use crate::diff::unified_diff;
+use crate::error::CpfError;
+use crate::generate::{generated_contents, is_generated, keeps_source};
+use crate::hash::hash_file;
+use crate::hooks::{run_hooks, Hooks};
+use crate::longpath::extended_path;
+use crate::mapping::{
+    resource_dir, unmapped_message, Mapper, Pack, SHARED_RULE,
+};
+use crate::manifest::{remove_target, Manifest, ManifestEntry};
+use crate::pack_manifest::{
+    script_manifest_contents, PACK_MANIFEST_NAME, SCRIPT_MANIFEST_RULE,
+};
+use crate::plugin::Plugins;
+use crate::preprocess::preprocess_function;
+use crate::reflink::reflink;
+use crate::report;
+use crate::retry::with_retries;
+use crate::stream::{stream_copy, LARGE_FILE_SIZE};
+use crate::text::{
+    has_line_endings, is_parsed_text, normalize_line_endings, strip_bom,
+    BomPolicy, LineEndings,
+};
+use crate::timings::Timings;
+use crate::typescript::{compile_script, TYPESCRIPT_RULE};
+use crate::plan::{
+    find_case_conflicts, find_conflicts, plan_paths, walk_root, CopyOperation,
+    PlannedFile, WalkOptions
+};
+
+/// The options that control how the files are copied.
+#[derive(Clone, Default)]
+pub struct CopyOptions {
+    /// What to do when the targets already exist.
+    pub overwrite: OverwritePolicy,
+    /// What to do when multiple files are mapped to the same target and
+    /// they can't be resolved interactively.
+    pub conflicts: ConflictPolicy,
+    /// The options that control how the directories of the roots are
+    /// walked.
+    pub walk: WalkOptions,
+    /// Whether to copy the modification times and the permissions of the
+    /// source files to the targets.
+    pub preserve_metadata: bool,
+    /// The report of the time spent in the phases of copying and on the
+    /// individual files.
+    pub timings: Option<Arc<Timings>>,
+    /// The paths (relative to the data directory) of the only source files
+    /// that should be copied. The other files are treated as unchanged.
+    pub changed: Option<Arc<HashSet<PathBuf>>>,
+    /// The paths (relative to the data directory) of the files to process
+    /// instead of walking the roots (see plan_file_list). The targets of the
+    /// other files from the previous run are kept.
+    pub files: Option<Arc<Vec<PathBuf>>>,
+    /// Whether to print the differences between the existing targets and the
+    /// source files.
+    pub diff: bool,
+    /// Whether to ask the user which file to keep when multiple files are
+    /// mapped to the same target. Without it the first file is copied and
+    /// the others are skipped.
+    pub interactive: bool,
+    /// The number of threads used for copying the files. 0 means the number
+    /// of the available CPUs.
+    pub jobs: usize,
+    /// Whether to silently skip the targets that are up to date (see
+    /// is_up_to_date).
+    pub incremental: bool,
+    /// The cache of the hashes of the source files from the previous run.
+    /// The existing targets of the unchanged source files are skipped
+    /// silently.
+    pub cache: Option<Arc<HashCache>>,
+    /// Whether to remove the targets produced by the previous run that are
+    /// no longer generated.
+    pub sync: bool,
+    /// The way the files are transferred to the packs.
+    pub mode: CopyMode,
+    /// Whether to clone the files using copy-on-write when copying them on
+    /// file systems that support it.
+    pub reflink: bool,
+    /// Whether to replace the targets with identical contents with hard
+    /// links to a single file.
+    pub dedupe: bool,
+    /// The plugins that transform and validate the files.
+    pub plugins: Arc<Plugins>,
+    /// The external commands run around the copying of the files.
+    pub hooks: Hooks,
+    /// What to do with the byte order marks of the JSON and .lang files.
+    pub bom: BomPolicy,
+    /// The line endings of the JSON, .lang and .mcfunction files.
+    pub line_endings: LineEndings,
+    /// The targets and the identifiers that intentionally override the
+    /// vanilla content.
+    pub vanilla_overrides: Vec<String>,
+    /// Whether the preprocessor directives of the .mcfunction files are
+    /// expanded.
+    pub preprocess_functions: bool,
+    /// The constants of the preprocessed .mcfunction files.
+    pub constants: HashMap<String, String>,
+    /// The command that compiles the TypeScript files or None if they
+    /// aren't compiled.
+    pub typescript: Option<String>,
+    /// The settings of bundling the scripts or None if they aren't bundled.
+    pub bundle_scripts: Option<ScriptBundle>,
+    /// The versions of the script modules of the game added to the manifest
+    /// of the behavior pack with the scripts.
+    pub script_dependencies: HashMap<String, String>,
+}
+
+/// The result of copying a single file.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CopyResult {
+    /// The file was copied to the target.
+    Copied,
+    /// The target is up to date, the file wasn't copied.
+    Unchanged,
+    /// The target already exists, the file wasn't copied.
+    Exists,
+    /// Copying the file failed.
+    Skipped,
+}
+
+/// Checks if the source and the target have the same size.
+fn same_size(source: &Path, target: &Path) -> bool {
+    match (fs::metadata(source), fs::metadata(target)) {
+        (Ok(source), Ok(target)) => source.len() == target.len(),
+        _ => false,
+    }
+}
+
+/// Checks if the target is up to date with the source file. The target is
+/// up to date if it has the same size as the source and it isn't older than
+/// the source.
+fn is_up_to_date(source: &Path, target: &Path) -> bool {
+    let (source, target) = match (fs::metadata(source), fs::metadata(target)) {
+        (Ok(source), Ok(target)) => (source, target),
+        _ => return false,
+    };
+    if source.len() != target.len() {
+        return false;
+    }
+    match (source.modified(), target.modified()) {
+        (Ok(source), Ok(target)) => target >= source,
+        _ => false,
+    }
+}
+
+/// Checks if the source file was modified after the target.
+fn is_newer(source: &Path, target: &Path) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified());
+    match (modified(source), modified(target)) {
+        (Ok(source), Ok(target)) => source > target,
+        _ => false,
+    }
+}
+
+/// Prints a unified diff between the existing target and the source file.
+/// Nothing is printed if the files are identical.
+fn print_diff(source: &Path, target: &Path) {
+    let (old, new) = match (fs::read(target), fs::read(source)) {
+        (Ok(old), Ok(new)) => (old, new),
+        _ => return,
+    };
+    if old == new {
+        return;
+    }
+    let old_name = target.display().to_string();
+    let new_name = source.display().to_string();
+    match (String::from_utf8(old), String::from_utf8(new)) {
+        (Ok(old), Ok(new)) => {
+            let diff = unified_diff(&old, &new, &old_name, &new_name);
+            if let Some(diff) = diff {
+                print!("{}", diff);
+            }
+        },
+        _ => println!("Binary files {} and {} differ", old_name, new_name),
+    }
+}
+
+/// Returns the path to the temporary file next to the target used for
+/// writing the target atomically.
+fn temp_path(target: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(target.file_name().unwrap_or_default());
+    name.push(".cpf_tmp");
+    target.with_file_name(name)
+}
+
+/// Copies the contents of the file to the path that doesn't exist.
+fn write_contents(
+    source: &Path, target: &Path, allow_reflink: bool
+) -> io::Result<()> {
+    if allow_reflink && reflink(source, target).unwrap_or(false) {
+        return Ok(());
+    }
+    let large = fs::metadata(source)?.len() >= LARGE_FILE_SIZE;
+    if large || !allow_reflink {
+        return stream_copy(source, target, large);
+    }
+    fs::copy(source, target).map(|_| ())
+}
+
+/// Copies the file. If `allow_reflink` is true, the file is cloned using
+/// copy-on-write when possible. The large files are streamed with a report
+/// of the progress. The file is written to a temporary file in the target
+/// directory and renamed into place, so an interrupted run never leaves a
+/// half-written target. Renaming replaces the existing target instead of
+/// writing to it, so the files linked to it (e.g. the deduplicated targets
+/// or the sources linked in the hardlink mode) don't change.
+fn copy_contents(
+    source: &Path, target: &Path, allow_reflink: bool
+) -> io::Result<()> {
+    let temp = temp_path(target);
+    // Remove the leftovers of an interrupted run
+    if temp.symlink_metadata().is_ok() {
+        fs::remove_file(&temp)?;
+    }
+    let result = write_contents(source, &temp, allow_reflink)
+        .and_then(|_| fs::rename(&temp, target));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Writes the content to the path atomically through a temporary file (see
+/// copy_contents).
+fn write_atomically(data: &[u8], target: &Path) -> io::Result<()> {
+    let temp = temp_path(target);
+    let result = fs::write(&temp, data)
+        .and_then(|_| fs::rename(&temp, target));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Returns the content that should be written to the target instead of the
+/// content of the source file (e.g. without the BOM or with normalized line
+/// endings) or None if the file is copied without changes. The BOMs are
+/// reported.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `options` - the options that control how the file is copied
+fn converted_contents(
+    source: &Path, options: &CopyOptions,
+) -> Option<Vec<u8>> {
+    let bom = is_parsed_text(source);
+    let line_endings = options.line_endings != LineEndings::Keep
+        && has_line_endings(source);
+    if !bom && !line_endings {
+        return None;
+    }
+    let data = fs::read(source).ok()?;
+    // The linked targets share the content with the sources
+    let convert = matches!(options.mode, CopyMode::Copy | CopyMode::Move);
+    let mut converted: Option<Vec<u8>> = None;
+    if let Some(stripped) = strip_bom(&data).filter(|_| bom) {
+        match convert && options.bom == BomPolicy::Strip {
+            true => {
+                report::warning(
+                    source,
+                    "The file starts with a UTF-8 BOM, which breaks some of \
+                    the parsers. It's removed from the target.",
+                );
+                converted = Some(stripped.to_vec());
+            },
+            false => report::warning(
+                source,
+                "The file starts with a UTF-8 BOM, which breaks some of the \
+                parsers.",
+            ),
+        }
+    }
+    if line_endings && convert {
+        let current = converted.as_deref().unwrap_or(&data);
+        if let Some(normalized) = normalize_line_endings(
+            current, options.line_endings
+        ) {
+            converted = Some(normalized);
+        }
+    }
+    converted
+}
+
+/// Returns the .mcfunction file with the preprocessor directives expanded
+/// (see `preprocess_function`) or None if the file isn't preprocessed.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `options` - the options that control how the file is copied
+fn preprocessed_contents(
+    source: &Path, options: &CopyOptions,
+) -> Option<Result<Vec<u8>, String>> {
+    if !options.preprocess_functions
+        || source.extension().is_none_or(|e| e != "mcfunction")
+    {
+        return None;
+    }
+    Some(preprocess_function(source, &options.constants).map(|text| {
+        let data = text.into_bytes();
+        normalize_line_endings(&data, options.line_endings).unwrap_or(data)
+    }))
+}
+
+/// Returns the script compiled from the TypeScript file (see
+/// `compile_script`) or None if the file isn't compiled. The targets that
+/// are newer than their sources aren't compiled again.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `operation` - the operation of the file
+/// - `target` - the path to the target
+/// - `options` - the options that control how the file is copied
+fn compiled_contents(
+    working_dir: &Path, operation: &CopyOperation, target: &Path,
+    options: &CopyOptions,
+) -> Option<Result<Vec<u8>, String>> {
+    let command = options.typescript.as_ref()
+        .filter(|_| operation.target.rule == TYPESCRIPT_RULE)?;
+    if options.incremental && target.exists()
+        && !is_newer(&operation.source, target)
+    {
+        return Some(fs::read(target).map_err(|e| e.to_string()));
+    }
+    Some(compile_script(&operation.source, command, working_dir))
+}
+
+/// Returns the bundle of the scripts (see `bundle_contents`) or None if the
+/// target isn't a bundle. The TypeScript files are compiled first.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `operation` - the operation of the file
+/// - `options` - the options that control how the file is copied
+fn bundled_contents(
+    working_dir: &Path, operation: &CopyOperation, options: &CopyOptions,
+) -> Option<Result<Vec<u8>, String>> {
+    let settings = options.bundle_scripts.as_ref()
+        .filter(|_| operation.target.rule == BUNDLE_RULE)?;
+    Some(bundle_contents(&operation.target, settings, |source| {
+        match (&options.typescript, source.extension()) {
+            (Some(command), Some(e)) if e == "ts" => {
+                let script = compile_script(source, command, working_dir)?;
+                String::from_utf8(script).map_err(|e| e.to_string())
+            },
+            _ => fs::read_to_string(source).map_err(|e| e.to_string()),
+        }
+    }))
+}
+
+/// Returns the manifest of the behavior pack with the script module (see
+/// `script_manifest_contents`) or None if the target isn't a manifest of a
+/// pack with the scripts.
+fn script_manifest(
+    operation: &CopyOperation, options: &CopyOptions,
+) -> Option<Result<Vec<u8>, String>> {
+    if operation.target.rule != SCRIPT_MANIFEST_RULE {
+        return None;
+    }
+    let dependencies = script_dependencies(options);
+    Some(fs::read_to_string(&operation.source)
+        .map_err(|e| e.to_string())
+        .and_then(|text| {
+            let entry = script_entry(options);
+            Ok(script_manifest_contents(&text, &entry, &dependencies)?
+                .unwrap_or_else(|| text.into_bytes()))
+        }))
+}
+
+/// Returns the names and the versions of the script modules of the game
+/// from the options, sorted by the names.
+fn script_dependencies(options: &CopyOptions) -> Vec<(&str, &str)> {
+    let mut dependencies: Vec<(&str, &str)> = options.script_dependencies
+        .iter()
+        .map(|(name, version)| (name.as_str(), version.as_str()))
+        .collect();
+    dependencies.sort();
+    dependencies
+}
+
+/// Returns the entry of the script module relative to the pack: the entry
+/// of the bundle or "scripts/main.js".
+fn script_entry(options: &CopyOptions) -> String {
+    match &options.bundle_scripts {
+        Some(bundle) => format!("scripts/{}", bundle.entry),
+        None => "scripts/main.js".to_string(),
+    }
+}
+
+/// Checks if the directory contains scripts (".js" files), including its
+/// subdirectories.
+fn has_scripts(dir: &Path) -> bool {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    entries.flatten().any(|entry| {
+        let path = entry.path();
+        match path.is_dir() {
+            true => has_scripts(&path),
+            false => path.extension().is_some_and(|e| e == "js"),
+        }
+    })
+}
+
+/// Adds the script module and the dependencies on the script modules of the
+/// game to the manifests of the behavior packs with the scripts (see
+/// `script_manifest_contents`). The packs are the ones with the scripts
+/// copied by the operations and the "BP" directory. The manifests generated
+/// by the operations are skipped. Returns the paths to the updated
+/// manifests relative to the working directory.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `operations` - the executed operations
+/// - `options` - the options with the versions of the modules
+fn update_script_manifests(
+    working_dir: &Path, operations: &[CopyOperation], options: &CopyOptions,
+) -> Vec<PathBuf> {
+    if options.script_dependencies.is_empty() {
+        return Vec::new();
+    }
+    let dependencies = script_dependencies(options);
+    let entry = script_entry(options);
+    let mut packs: Vec<PathBuf> = operations.iter()
+        .filter(|op| op.target.pack == Pack::Behavior)
+        .filter_map(|op| resource_dir(&op.target.path, "scripts"))
+        .filter_map(|dir| Some(dir.parent()?.to_path_buf()))
+        .collect();
+    if has_scripts(&working_dir.join("BP").join("scripts")) {
+        packs.push(PathBuf::from("BP"));
+    }
+    packs.sort();
+    packs.dedup();
+    let mut updated = Vec::new();
+    for pack in packs {
+        let path = pack.join(PACK_MANIFEST_NAME);
+        let generated = operations.iter().any(|op| op.target.path == path
+            && op.target.rule == SCRIPT_MANIFEST_RULE);
+        if generated {
+            continue;
+        }
+        let text = match fs::read_to_string(working_dir.join(&path)) {
+            Ok(text) => text,
+            Err(_) => {
+                report::general_warning(format!(
+                    "The scripts of {} need the script module, but the pack \
+                    doesn't have the manifest.", pack.display()
+                ));
+                continue;
+            },
+        };
+        let result = script_manifest_contents(&text, &entry, &dependencies)
+            .and_then(|contents| match contents {
+                Some(data) => write_atomically(&data, &working_dir.join(&path))
+                    .map(|_| true)
+                    .map_err(|e| e.to_string()),
+                None => Ok(false),
+            });
+        match result {
+            Ok(true) => updated.push(path),
+            Ok(false) => {},
+            Err(e) => report::warning(&working_dir.join(&path), format!(
+                "Unable to add the script module to the manifest: {}", e
+            )),
+        }
+    }
+    updated
+}
+
+/// Sets the modification time and the permissions of the target to the ones
+/// of the source file.
+fn copy_metadata(source: &Path, target: &Path) -> io::Result<()> {
+    let metadata = fs::metadata(source)?;
+    // Set the time first, the permissions can make the file read-only
+    fs::File::options().write(true).open(target)?
+        .set_modified(metadata.modified()?)?;
+    fs::set_permissions(target, metadata.permissions())
+}
+
+/// Moves the file. If the file can't be renamed (e.g. because the target is
+/// on a different file system), it's copied and then removed.
+fn move_file(source: &Path, target: &Path) -> io::Result<()> {
+    if target.symlink_metadata().is_ok() {
+        fs::remove_file(target)?;
+    }
+    match fs::rename(source, target) {
+        Ok(_) => Ok(()),
+        Err(_) => {
+            copy_contents(source, target, false)?;
+            fs::remove_file(source)
+        }
+    }
+}
+
+/// Creates a hard link to the source file. If the hard link can't be
+/// created (e.g. because the target is on a different file system), the file
+/// is copied.
+fn hard_link_file(source: &Path, target: &Path) -> io::Result<()> {
+    if target.symlink_metadata().is_ok() {
+        fs::remove_file(target)?;
+    }
+    match fs::hard_link(source, target) {
+        Ok(_) => Ok(()),
+        Err(_) => copy_contents(source, target, false),
+    }
+}
+
+/// Creates a symbolic link to the absolute path of the source file. If the
+/// link can't be created (e.g. because of missing permissions on Windows),
+/// the file is copied.
+fn symlink_file(source: &Path, target: &Path) -> io::Result<()> {
+    if target.symlink_metadata().is_ok() {
+        fs::remove_file(target)?;
+    }
+    let source_abs = fs::canonicalize(source)?;
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(&source_abs, target);
+    #[cfg(windows)]
+    let result = std::os::windows::fs::symlink_file(&source_abs, target);
+    #[cfg(not(any(unix, windows)))]
+    let result: io::Result<()> = Err(io::ErrorKind::Unsupported.into());
+    match result {
+        Ok(_) => Ok(()),
+        Err(_) => copy_contents(source, target, false),
+    }
+}
+
+/// Checks if the working directory is the temporary directory of Regolith
+/// (e.g. ".regolith/tmp" in the project or "regolith/.../tmp" in the app
+/// data).
+pub fn in_regolith_tmp(working_dir: &Path) -> bool {
+    let working_dir = match fs::canonicalize(
+        if working_dir.as_os_str().is_empty() { Path::new(".") }
+        else { working_dir }
+    ) {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+    working_dir.file_name().is_some_and(|n| n == "tmp")
+        && working_dir.ancestors().skip(1).any(|a| {
+            a.file_name().is_some_and(
+                |n| n.to_string_lossy().to_lowercase().contains("regolith")
+            )
+        })
+}
+
+/// Checks if the existing target can be overwritten according to the
+/// overwrite policy. Prints a warning if the target is skipped.
+fn can_overwrite(
+    source: &Path, target_path: &Path, policy: OverwritePolicy,
+) -> Result<bool, CpfError> {
+    match policy {
+        OverwritePolicy::Always => Ok(true),
+        OverwritePolicy::Never => {
+            report::warning(target_path, "The file already exists. Skipped.");
+            Ok(false)
+        },
+        OverwritePolicy::Newer => {
+            let target = extended_path(target_path);
+            if is_newer(source, &target) {
+                return Ok(true);
+            }
+            report::warning(
+                target_path, "The file isn't older than its source. Skipped.",
+            );
+            Ok(false)
+        },
+        OverwritePolicy::Error => {
+            Err(CpfError::Overwrite(target_path.to_path_buf()))
+        },
+    }
+}
+
+/// Writes the content of a file transformed by the plugins to its target.
+/// The transformed files are always written (never moved or linked) through
+/// a temporary file, so the source files stay unchanged.
+fn write_transformed(
+    fp: &Path, target_path: &Path, content: Result<Vec<u8>, String>,
+    options: &CopyOptions,
+) -> Result<CopyResult, CpfError> {
+    let content = match content {
+        Ok(content) => content,
+        Err(e) => {
+            report::warning(
+                fp, format!("Unable to transform the file: {}. Skipped.", e)
+            );
+            return Ok(CopyResult::Skipped);
+        },
+    };
+    let target = extended_path(target_path);
+    if target.exists() {
+        if fs::read(&target).is_ok_and(|old| old == content) {
+            return Ok(CopyResult::Unchanged);
+        }
+        if !can_overwrite(fp, target_path, options.overwrite)? {
+            return Ok(CopyResult::Exists);
+        }
+    }
+    fs::create_dir_all(target.parent().unwrap())
+        .map_err(|e| CpfError::copy(e, fp, target_path))?;
+    let temp = temp_path(&target);
+    let result = with_retries(|| {
+        fs::write(&temp, &content).and_then(|_| fs::rename(&temp, &target))
+    });
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        report::warning(
+            target_path, format!("Unable to write the file: {}", e)
+        );
+        return Ok(CopyResult::Skipped);
+    }
+    Ok(CopyResult::Copied)
+}
+
+/// Prints the warning about the planned file that can't be mapped to the
+/// packs.
+///
+/// # Arguments
+/// - `file` - the planned file
+/// - `mapper` - the mapper that can't map the file, used for suggesting the
+///   extension
+pub fn warn_unmapped(file: &PlannedFile, mapper: &dyn Mapper) {
+    report::warning(
+        &file.source,
+        format!("{} Skipped.", unmapped_message(&file.source, mapper)),
+    );
+}
+
+/// Copies a single file to its target. Files whose targets already exist are
+/// handled according to the overwrite policy from the options.
+///
+/// # Arguments
+/// - `working_dir` - the working directory of the script, the target paths
+///   of the exporter are relative to this directory
+/// - `operation` - the operation with the file to copy and its target
+/// - `options` - the options that control how the file is copied
+pub fn copy_file(
+    working_dir: &Path, operation: &CopyOperation, options: &CopyOptions,
+) -> Result<CopyResult, CpfError> {
+    let fp = &operation.source;
+    let target_path = working_dir.join(&operation.target.path);
+
+    if let Some(content) = options.plugins.transform(fp) {
+        return write_transformed(fp, &target_path, content, options);
+    }
+
+    // Use the extended-length paths on Windows so the deeply nested targets
+    // don't fail with cryptic errors
+    let (source, target) = (extended_path(fp), extended_path(&target_path));
+
+    // Copy file
+    let exists = target.exists();
+    let start = Instant::now();
+    // The generated targets don't depend only on their sources, so they're
+    // always generated and compared with the existing targets
+    // The preprocessed functions depend on the files they include
+    // The compiled scripts are only compiled again when they're outdated
+    let generated = generated_contents(operation)
+        .or_else(|| preprocessed_contents(&source, options))
+        .or_else(|| {
+            compiled_contents(working_dir, operation, &target, options)
+        })
+        .or_else(|| bundled_contents(working_dir, operation, options))
+        .or_else(|| script_manifest(operation, options));
+    if let (Some(timings), Some(_)) = (&options.timings, &generated) {
+        timings.part("copying", "generating", start);
+    }
+    let (generated, converted) = match generated {
+        Some(Ok(data)) => (true, Some(data)),
+        Some(Err(e)) => {
+            report::warning(fp, format!(
+                "Unable to generate \"{}\": {}", target_path.display(), e
+            ));
+            return Ok(CopyResult::Skipped);
+        },
+        None => (false, converted_contents(&source, options)),
+    };
+    if exists && !generated && options.incremental
+        && is_up_to_date(&source, &target)
+    {
+        return Ok(CopyResult::Unchanged);
+    }
+    let mut source_hash = None;
+    if let Some(cache) = options.cache.as_ref().filter(|_| !generated) {
+        if let Ok(hash) = hash_file(&source) {
+            source_hash = Some(hash);
+            if exists && cache.is_unchanged(fp, hash)
+                && same_size(&source, &target)
+            {
+                cache.record(fp, hash);
+                return Ok(CopyResult::Unchanged);
+            }
+        }
+    }
+    // The hash is recorded only when the target matches the source
+    let record = |result: CopyResult| {
+        if let (Some(cache), Some(hash)) = (&options.cache, source_hash) {
+            cache.record(fp, hash);
+        }
+        result
+    };
+    // Don't touch the targets with identical content to preserve their
+    // modification times
+    if let (true, Some(data)) = (exists, &converted) {
+        if fs::read(&target).is_ok_and(|t| &t == data) {
+            return Ok(record(CopyResult::Unchanged));
+        }
+    } else if exists && same_size(&source, &target) {
+        let source_hash = source_hash.or_else(|| hash_file(&source).ok());
+        let target_hash = hash_file(&target).ok();
+        if source_hash.is_some() && source_hash == target_hash {
+            return Ok(record(CopyResult::Unchanged));
+        }
+    }
+    if exists && options.diff {
+        print_diff(fp, &target_path);
+    }
+    if exists && !can_overwrite(&source, &target_path, options.overwrite)? {
+        return Ok(CopyResult::Exists);
+    }
+    fs::create_dir_all(target.parent().unwrap())
+        .map_err(|e| CpfError::copy(e, fp, &target_path))?;
+    // The shared files have two targets and the other targets are generated
+    // from some of the files, so they can't be moved
+    let mode = match options.mode {
+        CopyMode::Move if operation.target.rule == SHARED_RULE
+            || keeps_source(operation) => CopyMode::Copy,
+        mode => mode,
+    };
+    // The locked files are retried before reporting the failure
+    let result = with_retries(|| match (&converted, mode) {
+        (Some(data), CopyMode::Move) => write_atomically(data, &target)
+            .and_then(|_| fs::remove_file(&source)),
+        (Some(data), _) => write_atomically(data, &target),
+        (None, CopyMode::Copy) => {
+            copy_contents(&source, &target, options.reflink)
+        },
+        (None, CopyMode::Move) => move_file(&source, &target),
+        (None, CopyMode::Hardlink) => hard_link_file(&source, &target),
+        (None, CopyMode::Symlink) => symlink_file(&source, &target),
+    });
+    if let Err(e) = result {
+        report::warning(fp, format!(
+            "Unable to copy the file to \"{}\": {}",
+            target_path.display(), e
+        ));
+        return Ok(CopyResult::Skipped);
+    }
+    // The moved and the linked files already share the metadata with the
+    // sources
+    if options.preserve_metadata && mode == CopyMode::Copy {
+        if let Err(e) = copy_metadata(&source, &target) {
+            report::warning(fp, format!(
+                "Unable to copy the timestamps and the permissions of the \
+                file to \"{}\": {}",
+                target_path.display(), e
+            ));
+        }
+    }
+    Ok(record(CopyResult::Copied))
+}
+
+/// Maps the listed files (relative to the data directory) without walking
+/// the roots. The files are mapped relative to the first root that contains
+/// them. The generated targets (e.g. the atlases) of the listed files need
+/// all of their inputs, so the roots of such files are walked and only the
+/// inputs of these targets are kept. The files outside of the roots and the
+/// files that don't exist are skipped with a warning.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `mapper` - the mapper that maps the source files to the targets
+/// - `roots` - the list of paths, relative to the filter data path, that
+///   serve as the root of the source files
+/// - `paths` - the paths to the files relative to the data directory
+/// - `options` - the options that control how the directories are walked
+fn plan_file_list(
+    working_dir: &Path, mapper: &dyn Mapper, roots: &[String],
+    paths: &[PathBuf], options: &WalkOptions,
+) -> Result<Vec<PlannedFile>, CpfError> {
+    let data_dir = working_dir.join(FILTER_DATA_PATH);
+    let mut by_root: Vec<Vec<PathBuf>> = vec![Vec::new(); roots.len()];
+    for path in paths {
+        let root = roots.iter().enumerate()
+            .find_map(|(i, root)| Some((i, path.strip_prefix(root).ok()?)));
+        match root {
+            Some(_) if !data_dir.join(path).is_file() => report::warning(
+                &data_dir.join(path), "The file doesn't exist. Skipped."
+            ),
+            Some((i, relative)) => by_root[i].push(relative.to_path_buf()),
+            None => report::warning(
+                path, "The file isn't in any of the roots. Skipped."
+            ),
+        }
+    }
+    let mut result = Vec::new();
+    for (root, paths) in roots.iter().zip(by_root) {
+        if paths.is_empty() {
+            continue;
+        }
+        let root_dir = data_dir.join(root);
+        let files = plan_paths(mapper, &root_dir, &paths);
+        let generated: HashSet<PathBuf> = files.iter()
+            .filter_map(|file| file.target.as_ref())
+            .filter(|target| is_generated(&target.rule))
+            .map(|target| target.path.clone())
+            .collect();
+        if generated.is_empty() {
+            result.extend(files);
+            continue;
+        }
+        let listed: HashSet<PathBuf> = files.into_iter()
+            .map(|file| file.source)
+            .collect();
+        let mut root_files = walk_root(working_dir, mapper, root, options)?;
+        mapper.group(&root_dir, &mut root_files);
+        result.extend(root_files.into_iter().filter(|file| {
+            listed.contains(&file.source) || file.target.as_ref()
+                .is_some_and(|target| generated.contains(&target.path))
+        }));
+    }
+    Ok(result)
+}
+
+/// Lists and maps the files of the roots (or only the listed files) and
+/// resolves the conflicts between the files mapped to the same targets.
+/// Returns the operations that copy the files to the packs. The files that
+/// can't be mapped are skipped with a warning.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `mapper` - the mapper that maps the source files to the targets
+/// - `roots` - the list of paths, relative to the filter data path, that
+///   serve as the root of the source files to be copied to RP and BP.
+/// - `options` - the options that control how the files are copied
+pub fn plan_operations(
+    working_dir: &Path, mapper: &dyn Mapper, roots: &[String],
+    options: &CopyOptions,
+) -> Result<Vec<CopyOperation>, CpfError> {
+    let phase = |name, start| {
+        if let Some(timings) = &options.timings {
+            timings.phase(name, start);
+        }
+    };
+    let mut files = Vec::new();
+    match &options.files {
+        Some(paths) => {
+            println!("Copying {} listed files", paths.len());
+            let start = Instant::now();
+            files = plan_file_list(
+                working_dir, mapper, roots, paths, &options.walk
+            )?;
+            phase("walking and mapping", start);
+        },
+        None => for root in roots {
+            let root_dir = working_dir.join(FILTER_DATA_PATH).join(root);
+            println!("Copying files from \"{}\"", root_dir.display());
+            let start = Instant::now();
+            let mut root_files =
+                walk_root(working_dir, mapper, root, &options.walk)?;
+            phase("walking and mapping", start);
+            // The generated targets (e.g. the atlases) are planned here
+            let start = Instant::now();
+            mapper.group(&root_dir, &mut root_files);
+            phase("grouping", start);
+            files.extend(root_files);
+        },
+    }
+    resolve_operations(mapper, files, options)
+}
+
+/// Validates the planned files and resolves the conflicts between the files
+/// mapped to the same targets. Returns the operations that copy the files to
+/// the packs. The files that can't be mapped are skipped with a warning.
+///
+/// # Arguments
+/// - `mapper` - the mapper that mapped the files, used for suggesting the
+///   extensions of the files that can't be mapped
+/// - `files` - the planned files (grouped by the mapper)
+/// - `options` - the options that control how the files are copied
+pub fn resolve_operations(
+    mapper: &dyn Mapper, files: Vec<PlannedFile>, options: &CopyOptions,
+) -> Result<Vec<CopyOperation>, CpfError> {
+    let phase = |name, start| {
+        if let Some(timings) = &options.timings {
+            timings.phase(name, start);
+        }
+    };
+    let start = Instant::now();
+    // The problems found by the plugins are only reported, the "check"
+    // command fails on them
+    for file in &files {
+        for problem in options.plugins.validate(&file.source) {
+            problem.emit();
+        }
+    }
+    phase("validating", start);
+    let start = Instant::now();
+    // The targets that differ only by case can't be resolved by skipping
+    // some of the files, the files have to be renamed
+    let case_conflicts = find_case_conflicts(&files);
+    if !case_conflicts.is_empty() {
+        return Err(conflict::case_conflicts_error(&files, &case_conflicts));
+    }
+    // Resolve the conflicts between the files mapped to the same targets
+    let conflicts = find_conflicts(&files);
+    let skipped = if conflicts.is_empty() {
+        HashSet::new()
+    } else if options.interactive && conflict::can_prompt() {
+        conflict::resolve_interactively(&files, &conflicts)?
+    } else if options.conflicts == ConflictPolicy::Error {
+        return Err(conflict::conflicts_error(&files, &conflicts));
+    } else {
+        if options.interactive {
+            report::general_warning(
+                "Unable to resolve the conflicts interactively outside of a \
+                terminal."
+            );
+        }
+        conflict::keep_first(&files, &conflicts)
+    };
+    let operations: Vec<CopyOperation> = files.iter().enumerate()
+        .filter(|(i, _)| !skipped.contains(i))
+        .filter_map(|(_, file)| {
+            let operation = file.operation();
+            if operation.is_none() {
+                warn_unmapped(file, mapper);
+            }
+            operation
+        })
+        .collect();
+    phase("resolving conflicts", start);
+    let start = Instant::now();
+    let mut invalid = Vec::new();
+    let operations: Vec<CopyOperation> = operations.into_iter()
+        // The suspicious contents (e.g. .lang files in other encodings) are
+        // copied, but reported
+        .inspect(|operation| {
+            for problem in check_contents(&operation.source) {
+                problem.emit();
+            }
+            for problem in check_target(&operation.source, &operation.target) {
+                problem.emit();
+            }
+            let overridden = check_vanilla_override(
+                &operation.source, &operation.target,
+                &options.vanilla_overrides,
+            );
+            if let Some(problem) = overridden {
+                problem.emit();
+            }
+        })
+        // The empty and truncated JSON files (e.g. not fully saved yet) are
+        // skipped, the other syntax errors make the filter fail
+        .filter(|operation| match check_json_source(&operation.source) {
+            Some(JsonProblem::Incomplete(mut problem)) => {
+                problem.message.push_str(" Skipped.");
+                problem.emit();
+                false
+            },
+            Some(JsonProblem::Invalid(problem)) => {
+                invalid.push(problem);
+                true
+            },
+            None => true,
+        })
+        .collect();
+    phase("validating", start);
+    if !invalid.is_empty() {
+        for problem in &invalid {
+            problem.emit();
+        }
+        return Err(CpfError::Invalid(format!(
+            "Found {} JSON file(s) with syntax errors. Nothing was copied.",
+            invalid.len()
+        )));
+    }
+    Ok(operations)
+}
+
+/// Checks if the target of the operation has to be produced again because
+/// its source or one of its inputs (e.g. the textures of an atlas) changed.
+/// The generated targets are also produced again when any of the changed
+/// files was removed, because it may have been one of their inputs.
+///
+/// # Arguments
+/// - `operation` - the operation of the target
+/// - `data_dir` - the path to the data directory
+/// - `changed` - the paths to the changed files relative to the data
+///   directory
+fn is_changed(
+    operation: &CopyOperation, data_dir: &Path, changed: &HashSet<PathBuf>,
+) -> bool {
+    let is_changed_file = |source: &Path| {
+        source.strip_prefix(data_dir).is_ok_and(|p| changed.contains(p))
+    };
+    is_changed_file(&operation.source)
+        || operation.target.inputs.iter()
+            .any(|(source, _)| is_changed_file(source))
+        || is_generated(&operation.target.rule)
+            && changed.iter().any(|path| !data_dir.join(path).exists())
+}
+
+/// Copies the files of the planned operations, records the manifest and
+/// runs the "after" hooks.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `operations` - the operations returned by plan_operations
+/// - `options` - the options that control how the files are copied
+pub fn execute_operations(
+    working_dir: &Path, operations: &[CopyOperation], options: &CopyOptions,
+) -> Result<(), CpfError> {
+    let phase = |name, start| {
+        if let Some(timings) = &options.timings {
+            timings.phase(name, start);
+        }
+    };
+    let start = Instant::now();
+    // Only the targets of the changed and the listed files are produced,
+    // the other targets are kept
+    let data_dir = working_dir.join(FILTER_DATA_PATH);
+    let listed: Option<HashSet<PathBuf>> = options.files.as_ref()
+        .map(|files| files.iter().cloned().collect());
+    let selected: Vec<bool> = operations.iter()
+        .map(|op| {
+            options.changed.as_ref()
+                .is_none_or(|changed| is_changed(op, &data_dir, changed))
+                && listed.as_ref()
+                    .is_none_or(|listed| is_changed(op, &data_dir, listed))
+        })
+        .collect();
+    let selected_operations: Vec<&CopyOperation> = operations.iter()
+        .zip(&selected)
+        .filter(|(_, selected)| **selected)
+        .map(|(op, _)| op)
+        .collect();
+    let mut copied = copy_files_parallel(
+        working_dir, &selected_operations, options
+    )?.into_iter();
+    let results: Vec<CopyResult> = selected.iter()
+        .map(|s| match s {
+            true => copied.next().unwrap(),
+            false => CopyResult::Unchanged,
+        })
+        .collect();
+    phase("copying", start);
+    let start = Instant::now();
+    if let Some(cache) = &options.cache {
+        if let Err(e) = cache.save() {
+            report::general_warning(
+                format!("Unable to save the cache: {}", e)
+            );
+        }
+    }
+    // Record the produced targets. The targets that already existed are
+    // only recorded if they were produced by the previous run.
+    let previous = Manifest::load(working_dir)?;
+    let mut manifest = Manifest::default();
+    for (op, result) in operations.iter().zip(results) {
+        let target = &op.target.path;
+        let previous_entry = previous.files.get(target)
+            .filter(|e| e.source == op.source);
+        let hash = match (result, previous_entry) {
+            // The source file doesn't exist anymore in the move mode
+            (CopyResult::Copied, _) => {
+                hash_file(&working_dir.join(target)).ok()
+            },
+            // The hash of the unchanged files from the previous run
+            (CopyResult::Unchanged, Some(entry)) => Some(entry.hash),
+            (CopyResult::Unchanged, None) if is_generated(&op.target.rule) => {
+                hash_file(&working_dir.join(target)).ok()
+            },
+            (CopyResult::Unchanged, None) => hash_file(&op.source).ok(),
+            // The target wasn't written, so it's still the one from the
+            // previous run
+            (CopyResult::Exists, _) | (CopyResult::Skipped, _) => {
+                if let Some(entry) = previous.files.get(target) {
+                    manifest.files.insert(target.clone(), entry.clone());
+                }
+                continue;
+            },
+        };
+        if let Some(hash) = hash {
+            manifest.files.insert(target.clone(), ManifestEntry {
+                source: op.source.clone(),
+                hash,
+                rule: op.target.rule.clone(),
+            });
+        }
+    }
+    // Only the listed files were processed, so the other targets are kept
+    if options.files.is_some() {
+        for (target, entry) in &previous.files {
+            if !manifest.files.contains_key(target) {
+                manifest.files.insert(target.clone(), entry.clone());
+            }
+        }
+    }
+    // Only the targets of the files that are no longer planned are removed
+    if options.sync {
+        let planned: HashSet<&PathBuf> = operations.iter()
+            .map(|op| &op.target.path)
+            .collect();
+        for target in previous.files.keys() {
+            if !planned.contains(target)
+                && !manifest.files.contains_key(target)
+            {
+                remove_target(working_dir, target);
+            }
+        }
+    }
+    // The updated manifests of the packs are recorded with their new hashes
+    for path in update_script_manifests(working_dir, operations, options) {
+        if let Some(entry) = manifest.files.get_mut(&path) {
+            if let Ok(hash) = hash_file(&working_dir.join(&path)) {
+                entry.hash = hash;
+            }
+        }
+    }
+    // The links and the symlinks already share the data with the sources
+    let copied = matches!(options.mode, CopyMode::Copy | CopyMode::Move);
+    if options.dedupe && copied {
+        dedupe(working_dir, &manifest);
+    }
+    manifest.save(working_dir)?;
+    phase("recording the manifest", start);
+    run_hooks(&options.hooks.after, working_dir, &[])
+}
+
+/// Copies the files from the data directory of the project files path to the
+/// target directories which are found based on the export map. Runs the
+/// "before" hooks, plans the operations and executes them.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `mapper` - the mapper that maps the source files to the targets
+/// - `roots` - the list of paths, relative to the filter data path, that
+///   serve as the root of the source files to be copied to RP and BP.
+/// - `options` - the options that control how the files are copied
+pub fn copy_files_by_roots(
+    working_dir: &Path, mapper: &dyn Mapper, roots: &[String],
+    options: &CopyOptions,
+) -> Result<(), CpfError> {
+    run_hooks(&options.hooks.before, working_dir, &[])?;
+    let operations = plan_operations(working_dir, mapper, roots, options)?;
+    execute_operations(working_dir, &operations, options)
+}
+
+/// Runs the per-file hooks for the copied file. The paths to the source and
+/// the target are passed in the CPF_SOURCE and CPF_TARGET environment
+/// variables.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `operation` - the operation of the copied file
+/// - `options` - the options that control how the files are copied
+fn run_file_hooks(
+    working_dir: &Path, operation: &CopyOperation, options: &CopyOptions,
+) -> Result<(), CpfError> {
+    let target = working_dir.join(&operation.target.path);
+    run_hooks(&options.hooks.per_file, working_dir, &[
+        ("CPF_SOURCE", &operation.source), ("CPF_TARGET", &target),
+    ])
+}
+
+/// Copies the planned files using multiple threads. The copying stops at the
+/// first error. Returns the results of copying the files in the same order
+/// as the files.
+///
+/// # Arguments
+/// - `working_dir` - the working directory of the script, the target paths
+///   of the exporter are relative to this directory
+/// - `files` - the operations of the files to copy
+/// - `options` - the options that control how the files are copied
+pub fn copy_files_parallel(
+    working_dir: &Path, files: &[&CopyOperation], options: &CopyOptions,
+) -> Result<Vec<CopyResult>, CpfError> {
+    let jobs = match options.jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        jobs => jobs,
+    }.min(files.len()).max(1);
+    let next = AtomicUsize::new(0);
+    let error: Mutex<Option<CpfError>> = Mutex::new(None);
+    let results: Mutex<Vec<CopyResult>> = Mutex::new(
+        vec![CopyResult::Skipped; files.len()]
+    );
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= files.len() {
+                    break;
+                }
+                let start = Instant::now();
+                let result = copy_file(working_dir, files[i], options)
+                    .and_then(|result| {
+                        if result == CopyResult::Copied {
+                            run_file_hooks(working_dir, files[i], options)?;
+                        }
+                        Ok(result)
+                    });
+                if let Some(timings) = &options.timings {
+                    timings.file(&files[i].source, start.elapsed());
+                }
+                match result {
+                    Ok(result) => results.lock().unwrap()[i] = result,
+                    Err(e) => {
+                        error.lock().unwrap().get_or_insert(e);
+                        // Stop the other threads
+                        next.store(files.len(), Ordering::Relaxed);
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    match error.into_inner().unwrap() {
+        Some(e) => Err(e),
+        None => Ok(results.into_inner().unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::lang::LANG_RULE;
+    use crate::mapping::TargetInfo;
+    use crate::MapperChain;
+
+    /// Returns the operation of the target generated from the inputs.
+    fn generated(
+        data_dir: &Path, source: &str, inputs: &[&str],
+    ) -> CopyOperation {
+        let inputs = inputs.iter()
+            .map(|input| (data_dir.join(input), PathBuf::from("RP/x")))
+            .collect();
+        CopyOperation {
+            source: data_dir.join(source),
+            target: TargetInfo::new(LANG_RULE, PathBuf::from("RP/texts/a"))
+                .with_inputs(inputs),
+        }
+    }
+
+    #[test]
+    fn selects_the_targets_with_changed_inputs() {
+        let data_dir = std::env::temp_dir()
+            .join(format!("cpf_copy_changed_{}", std::process::id()));
+        fs::create_dir_all(data_dir.join("src")).unwrap();
+        fs::write(data_dir.join("src/b.lang.csv"), "").unwrap();
+        let operation = generated(
+            &data_dir, "src/a.lang.csv", &["src/a.lang.csv", "src/b.lang.csv"]
+        );
+        let changed = |paths: &[&str]| -> HashSet<PathBuf> {
+            paths.iter().map(PathBuf::from).collect()
+        };
+        let is_selected = |paths: &[&str]| {
+            is_changed(&operation, &data_dir, &changed(paths))
+        };
+        assert!(is_selected(&["src/b.lang.csv"]));
+        assert!(!is_selected(&[]));
+        // The removed file could have been one of the inputs
+        assert!(is_selected(&["src/c.lang.csv"]));
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn groups_the_listed_files_with_the_other_files_of_the_roots() {
+        let working_dir = std::env::temp_dir()
+            .join(format!("cpf_copy_listed_{}", std::process::id()));
+        let root_dir = working_dir.join(FILTER_DATA_PATH).join("src");
+        fs::create_dir_all(&root_dir).unwrap();
+        fs::write(root_dir.join("a.lang.csv"), "key,en_US\na.name,A\n")
+            .unwrap();
+        fs::write(root_dir.join("b.lang.csv"), "key,en_US\nb.name,B\n")
+            .unwrap();
+        let config = Config::builder().root("src").lang_tables(true)
+            .build().unwrap();
+        let mapper = MapperChain::from_config(&config);
+        let options = CopyOptions {
+            files: Some(Arc::new(vec![PathBuf::from("src/b.lang.csv")])),
+            ..CopyOptions::default()
+        };
+        let operations = plan_operations(
+            &working_dir, &mapper, &config.roots, &options
+        ).unwrap();
+        execute_operations(&working_dir, &operations, &options).unwrap();
+        let table = fs::read_to_string(
+            working_dir.join("RP/texts/en_US.lang")
+        ).unwrap();
+        assert!(table.contains("a.name=A"), "{}", table);
+        assert!(table.contains("b.name=B"), "{}", table);
+        fs::remove_dir_all(&working_dir).unwrap();
+    }
+    #[test]
+    fn reports_the_paths_of_the_failed_operations() {
+        let working_dir = std::env::temp_dir()
+            .join(format!("cpf_copy_errors_{}", std::process::id()));
+        fs::create_dir_all(working_dir.join("src")).unwrap();
+        fs::write(working_dir.join("src/a.txt"), "a").unwrap();
+        fs::write(working_dir.join("src/b.txt"), "b").unwrap();
+        // The parent of the target is a file
+        fs::write(working_dir.join("RP"), "").unwrap();
+        let operation = CopyOperation {
+            source: working_dir.join("src/a.txt"),
+            target: TargetInfo::new("a", PathBuf::from("RP/a.txt")),
+        };
+        let options = CopyOptions {
+            conflicts: ConflictPolicy::Error,
+            ..CopyOptions::default()
+        };
+        match copy_file(&working_dir, &operation, &options) {
+            Err(CpfError::Io { source, target, .. }) => {
+                assert_eq!(source, Some(operation.source.clone()));
+                assert_eq!(target, Some(working_dir.join("RP/a.txt")));
+            },
+            _ => panic!("the copy didn't fail"),
+        }
+        let files = ["src/a.txt", "src/b.txt"].map(|source| PlannedFile {
+            source: working_dir.join(source),
+            target: Some(TargetInfo::new("a", PathBuf::from("RP/a.txt"))),
+        });
+        let mapper = MapperChain::from_config(&Config::default());
+        match resolve_operations(&mapper, files.to_vec(), &options) {
+            Err(CpfError::Conflict { targets, .. }) => assert_eq!(targets, [(
+                PathBuf::from("RP/a.txt"),
+                vec![files[0].source.clone(), files[1].source.clone()],
+            )]),
+            _ => panic!("the conflict wasn't reported"),
+        }
+        fs::remove_dir_all(&working_dir).unwrap();
+    }
+}
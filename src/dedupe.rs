@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::manifest::Manifest;
+use crate::report;
+
+/// The size of the buffer used for comparing the contents of the files.
+static COMPARE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Checks if both paths point to the same file on the disk (e.g. are hard
+/// links of each other).
+fn same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        false
+    }
+}
+
+/// Compares the contents of two files byte by byte.
+fn same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (fs::File::open(a)?, fs::File::open(b)?);
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+    let mut buf_a = vec![0u8; COMPARE_BUFFER_SIZE];
+    let mut buf_b = vec![0u8; COMPARE_BUFFER_SIZE];
+    loop {
+        let n = a.read(&mut buf_a)?;
+        if n == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buf_b[..n])?;
+        if buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Replaces the target with a hard link to the original file. The link is
+/// created under a temporary name first, so the target is never missing.
+fn replace_with_link(original: &Path, target: &Path) -> io::Result<()> {
+    let mut tmp_name = target.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".cpf_dedupe");
+    let tmp = target.with_file_name(tmp_name);
+    let _ = fs::remove_file(&tmp);
+    fs::hard_link(original, &tmp)?;
+    fs::rename(&tmp, target).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Replaces the targets from the manifest that have identical contents with
+/// hard links to a single file to reduce the size of the packs. The first
+/// target (in the order of the paths) of each group of identical files is
+/// kept. Prints the report of the deduplicated files.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `manifest` - the manifest with the targets produced by the filter
+pub fn dedupe(working_dir: &Path, manifest: &Manifest) {
+    let mut by_hash: HashMap<u64, Vec<&PathBuf>> = HashMap::new();
+    for (target, entry) in &manifest.files {
+        by_hash.entry(entry.hash).or_default().push(target);
+    }
+    let mut groups: Vec<Vec<&PathBuf>> = by_hash.into_values()
+        .filter(|targets| targets.len() > 1)
+        .collect();
+    groups.sort();
+    let (mut count, mut saved) = (0, 0);
+    for group in groups {
+        let original = working_dir.join(group[0]);
+        for target in &group[1..] {
+            let target_path = working_dir.join(target);
+            if same_file(&original, &target_path) {
+                continue;
+            }
+            // The hashes can collide, make sure the files are identical
+            match same_content(&original, &target_path) {
+                Ok(true) => {},
+                _ => continue,
+            }
+            let size = fs::metadata(&target_path).map_or(0, |m| m.len());
+            match replace_with_link(&original, &target_path) {
+                Ok(_) => {
+                    println!(
+                        "Deduplicated \"{}\" (linked to \"{}\")",
+                        target.display(), group[0].display()
+                    );
+                    count += 1;
+                    saved += size;
+                },
+                Err(e) => report::warning(
+                    target, format!("Unable to deduplicate: {}", e)
+                ),
+            }
+        }
+    }
+    if count > 0 {
+        println!("Deduplicated {} files, saved {} bytes.", count, saved);
+    }
+}
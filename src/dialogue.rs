@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::Path;
+
+use crate::mapping::{compiled_target, MapResult, Mapper};
+
+/// The name of the rule of the dialogues generated by the MarkdownDialogue.
+pub static DIALOGUE_RULE: &str = "(dialogue)";
+
+/// The extension of the Markdown files with the dialogues.
+static MARKDOWN_EXTENSION: &str = ".d.md";
+
+/// The extension of the generated dialogues.
+static TARGET_EXTENSION: &str = ".d.json";
+
+/// The directory of the dialogues in the packs.
+static DIALOGUE_DIR: &str = "BP/dialogue";
+
+/// The format version of the dialogues if the front matter doesn't have it.
+static FORMAT_VERSION: &str = "1.17.0";
+
+/// Compiles the Markdown files with the dialogues (the ".d.md" files) into
+/// the dialogues of the behavior pack (e.g. "villager.d.md" ->
+/// "BP/dialogue/villager.d.json"). See `compile` for the format.
+pub struct MarkdownDialogue;
+
+impl Mapper for MarkdownDialogue {
+    fn map(&self, path: &Path) -> MapResult {
+        compiled_target(
+            path, MARKDOWN_EXTENSION, DIALOGUE_DIR, TARGET_EXTENSION,
+            DIALOGUE_RULE,
+        )
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        vec![MARKDOWN_EXTENSION.to_string()]
+    }
+}
+
+/// A scene of the dialogue.
+#[derive(Default)]
+struct Scene {
+    tag: String,
+    npc_name: Option<String>,
+    text: Vec<String>,
+    on_open: Vec<String>,
+    on_close: Vec<String>,
+    buttons: Vec<(String, Vec<String>)>,
+}
+
+impl Scene {
+    /// Returns the JSON object of the scene.
+    fn to_json(&self, npc_name: Option<&str>) -> serde_json::Value {
+        let mut scene = serde_json::Map::new();
+        scene.insert("scene_tag".into(), self.tag.clone().into());
+        if let Some(name) = self.npc_name.as_deref().or(npc_name) {
+            scene.insert("npc_name".into(), name.into());
+        }
+        // The blank lines at the ends of the text are removed
+        let text = self.text.join("\n");
+        scene.insert("text".into(), text.trim_matches('\n').into());
+        if !self.on_open.is_empty() {
+            scene.insert(
+                "on_open_commands".into(), self.on_open.clone().into()
+            );
+        }
+        if !self.on_close.is_empty() {
+            scene.insert(
+                "on_close_commands".into(), self.on_close.clone().into()
+            );
+        }
+        let buttons: Vec<serde_json::Value> = self.buttons.iter()
+            .map(|(name, commands)| serde_json::json!({
+                "name": name, "commands": commands,
+            }))
+            .collect();
+        if !buttons.is_empty() {
+            scene.insert("buttons".into(), buttons.into());
+        }
+        scene.into()
+    }
+}
+
+/// Parses the button (e.g. "[Trade](/dialogue open @s @initiator shop)" or
+/// "[Bye]") from the item of the list. Returns the name and the command.
+fn parse_button(item: &str) -> Option<(String, Option<String>)> {
+    let rest = item.strip_prefix('[')?;
+    let (name, rest) = rest.split_once(']')?;
+    let command = match rest.trim() {
+        "" => None,
+        rest => Some(
+            rest.strip_prefix('(')?.strip_suffix(')')?.trim().to_string()
+        ),
+    };
+    Some((name.trim().to_string(), command))
+}
+
+/// Compiles the Markdown file into the dialogue. The file starts with the
+/// optional front matter between the "---" lines with the "npc_name" of all
+/// of the scenes and the "format_version". Every scene starts with a
+/// heading with its tag and optionally its NPC name (e.g. "# intro" or
+/// "# intro: Bob"). The lines of the scene are:
+/// - "> open <command>" and "> close <command>" - the commands run when the
+///   scene is opened or closed,
+/// - "- [<name>](<command>)" or "- [<name>]" - the buttons, with the more
+///   commands in the indented items (e.g. "  - /say bye"),
+/// - the other lines - the text of the scene.
+fn compile(text: &str) -> Result<serde_json::Value, String> {
+    let mut lines = text.lines().enumerate().peekable();
+    let mut npc_name = None;
+    let mut format_version = FORMAT_VERSION.to_string();
+    if lines.peek().is_some_and(|(_, line)| line.trim() == "---") {
+        lines.next();
+        loop {
+            let (i, line) = lines.next()
+                .ok_or("The front matter isn't closed with \"---\".")?;
+            let line = line.trim();
+            if line == "---" {
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let error = || format!("Line {}: Invalid front matter.", i + 1);
+            let (key, value) = line.split_once(':').ok_or_else(error)?;
+            let value = value.trim().trim_matches('"').to_string();
+            match key.trim() {
+                "npc_name" => npc_name = Some(value),
+                "format_version" => format_version = value,
+                _ => return Err(error()),
+            }
+        }
+    }
+    let mut scenes: Vec<Scene> = Vec::new();
+    for (i, line) in lines {
+        let error = |e: &str| format!("Line {}: {}", i + 1, e);
+        if let Some(heading) = line.strip_prefix("# ") {
+            let (tag, name) = match heading.split_once(':') {
+                Some((tag, name)) => (tag.trim(), Some(name.trim())),
+                None => (heading.trim(), None),
+            };
+            if tag.is_empty() || tag.contains(char::is_whitespace) {
+                return Err(error("The scene tag can't contain spaces."));
+            }
+            if scenes.iter().any(|scene| scene.tag == tag) {
+                return Err(error("The scene is already defined."));
+            }
+            scenes.push(Scene {
+                tag: tag.to_string(),
+                npc_name: name.map(str::to_string),
+                ..Scene::default()
+            });
+            continue;
+        }
+        let scene = match scenes.last_mut() {
+            Some(scene) => scene,
+            None if line.trim().is_empty() => continue,
+            None => return Err(error("The text isn't in a scene.")),
+        };
+        if let Some(quote) = line.strip_prefix('>') {
+            let (kind, command) = quote.trim().split_once(' ')
+                .ok_or_else(|| error("The command is empty."))?;
+            let command = command.trim().to_string();
+            match kind {
+                "open" => scene.on_open.push(command),
+                "close" => scene.on_close.push(command),
+                _ => return Err(error(
+                    "The commands have to start with \"open\" or \"close\"."
+                )),
+            }
+        } else if let Some(item) = line.strip_prefix("- ") {
+            let (name, command) = parse_button(item.trim())
+                .ok_or_else(|| error("Invalid button."))?;
+            scene.buttons.push((name, command.into_iter().collect()));
+        } else if let Some(item) = line.trim_start().strip_prefix("- ")
+            .filter(|_| line.starts_with(char::is_whitespace))
+        {
+            let (_, commands) = scene.buttons.last_mut()
+                .ok_or_else(|| error("The command isn't under a button."))?;
+            commands.push(item.trim().to_string());
+        } else {
+            scene.text.push(line.trim_end().to_string());
+        }
+    }
+    if scenes.is_empty() {
+        return Err("The dialogue doesn't have any scenes.".into());
+    }
+    let scenes: Vec<serde_json::Value> = scenes.iter()
+        .map(|scene| scene.to_json(npc_name.as_deref()))
+        .collect();
+    Ok(serde_json::json!({
+        "format_version": format_version,
+        "minecraft:npc_dialogue": {"scenes": scenes},
+    }))
+}
+
+/// Generates the dialogue from the Markdown file.
+///
+/// # Arguments
+/// - `source` - the path to the ".d.md" file
+pub fn dialogue_contents(source: &Path) -> Result<Vec<u8>, String> {
+    let text = fs::read_to_string(source).map_err(|e| e.to_string())?;
+    let json = compile(text.trim_start_matches('\u{feff}'))?;
+    Ok(format!("{:#}\n", json).into_bytes())
+}
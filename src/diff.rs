@@ -0,0 +1,130 @@
+/// The number of unchanged lines shown around the changes.
+static CONTEXT_LINES: usize = 3;
+
+/// The maximal number of cells of the LCS table. Larger files are reported
+/// as different without the detailed diff.
+static MAX_DIFF_CELLS: usize = 16_000_000;
+
+/// A single line of the diff.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Finds the line differences between the old and the new text using the
+/// longest common subsequence.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let (n, m) = (old.len(), new.len());
+    // lcs[i][j] is the length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            result.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            result.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            result.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    result
+}
+
+/// Creates a unified diff between the old and the new text. Returns None if
+/// the texts are identical.
+///
+/// # Arguments
+/// - `old` - the old text
+/// - `new` - the new text
+/// - `old_name` - the name of the old file used in the header of the diff
+/// - `new_name` - the name of the new file used in the header of the diff
+pub fn unified_diff(
+    old: &str, new: &str, old_name: &str, new_name: &str,
+) -> Option<String> {
+    if old == new {
+        return None;
+    }
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let mut result = format!("--- {}\n+++ {}\n", old_name, new_name);
+    if (old_lines.len() + 1) * (new_lines.len() + 1) > MAX_DIFF_CELLS {
+        result.push_str("Files are too large to show the differences.\n");
+        return Some(result);
+    }
+    let lines = diff_lines(&old_lines, &new_lines);
+    // Group the changes into hunks with CONTEXT_LINES of context
+    let changed: Vec<usize> = lines.iter().enumerate()
+        .filter(|(_, l)| !matches!(l, Line::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        // Only the line endings are different
+        result.push_str("Files differ only in the line endings.\n");
+        return Some(result);
+    }
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for i in changed {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + CONTEXT_LINES + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    // Line numbers before each line of the diff (old, new)
+    let mut numbers = Vec::with_capacity(lines.len());
+    let (mut old_no, mut new_no) = (1, 1);
+    for line in &lines {
+        numbers.push((old_no, new_no));
+        match line {
+            Line::Same(_) => {
+                old_no += 1;
+                new_no += 1;
+            },
+            Line::Removed(_) => old_no += 1,
+            Line::Added(_) => new_no += 1,
+        }
+    }
+    for (start, end) in hunks {
+        let hunk = &lines[start..end];
+        let old_len = hunk.iter()
+            .filter(|l| !matches!(l, Line::Added(_)))
+            .count();
+        let new_len = hunk.iter()
+            .filter(|l| !matches!(l, Line::Removed(_)))
+            .count();
+        let (old_start, new_start) = numbers[start];
+        result.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_len == 0 { old_start - 1 } else { old_start }, old_len,
+            if new_len == 0 { new_start - 1 } else { new_start }, new_len
+        ));
+        for line in hunk {
+            let (prefix, text) = match line {
+                Line::Same(t) => (' ', t),
+                Line::Removed(t) => ('-', t),
+                Line::Added(t) => ('+', t),
+            };
+            result.push(prefix);
+            result.push_str(text);
+            result.push('\n');
+        }
+    }
+    Some(result)
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::Path;
+
+use crate::config::{Config, EXPORT_FILES_MAP, FILTER_DATA_PATH};
+use crate::longpath::WINDOWS_MAX_PATH;
+use crate::plan;
+
+/// The result of a single diagnostic performed by the `doctor` subcommand.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Collects and prints the results of the diagnostics.
+struct Report {
+    errors: usize,
+}
+
+impl Report {
+    fn add(&mut self, status: Status, message: &str) {
+        let prefix = match status {
+            Status::Ok => "[OK]     ",
+            Status::Warning => "[WARNING]",
+            Status::Error => "[ERROR]  ",
+        };
+        if status == Status::Error {
+            self.errors += 1;
+        }
+        println!("{} {}", prefix, message);
+    }
+}
+
+/// Checks if the pack directory exists or can be created in the working
+/// directory.
+fn check_pack_dir(report: &mut Report, working_dir: &Path, name: &str) {
+    let path = working_dir.join(name);
+    if path.is_dir() {
+        report.add(Status::Ok, &format!("\"{}\" exists.", path.display()));
+    } else if path.exists() {
+        report.add(Status::Error, &format!(
+            "\"{}\" exists but it's not a directory.", path.display()
+        ));
+    } else {
+        // An empty path is the current directory
+        let dir = match working_dir.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => working_dir,
+        };
+        let writable = fs::metadata(dir)
+            .map(|m| !m.permissions().readonly())
+            .unwrap_or(false);
+        if writable {
+            report.add(Status::Ok, &format!(
+                "\"{}\" doesn't exist but it can be created.", path.display()
+            ));
+        } else {
+            report.add(Status::Error, &format!(
+                "\"{}\" doesn't exist and the working directory is not \
+                writable.", path.display()
+            ));
+        }
+    }
+}
+
+/// Runs the diagnostics of the common setup problems and prints the results.
+/// Returns the number of errors found.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+pub fn doctor(working_dir: &Path) -> usize {
+    let mut report = Report { errors: 0 };
+    report.add(Status::Ok, &format!(
+        "custom-project version {} ({} {}).",
+        env!("CARGO_PKG_VERSION"), std::env::consts::OS,
+        std::env::consts::ARCH
+    ));
+    // The working directory (an empty path is the current directory)
+    let abs_working_dir = match fs::canonicalize(
+        if working_dir.as_os_str().is_empty() { Path::new(".") }
+        else { working_dir }
+    ) {
+        Ok(p) => p,
+        Err(e) => {
+            report.add(Status::Error, &format!(
+                "Unable to access the working directory \"{}\": {}",
+                working_dir.display(), e
+            ));
+            return report.errors;
+        }
+    };
+    report.add(Status::Ok, &format!(
+        "Working directory: \"{}\".", abs_working_dir.display()
+    ));
+    // Regolith project (the project root) or the temporary directory of
+    // Regolith (the directory with RP, BP and data folders)
+    let regolith_config = working_dir.join("config.json");
+    let is_project_root = fs::read_to_string(&regolith_config).ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .is_some_and(|v| v.get("regolith").is_some());
+    if is_project_root {
+        report.add(Status::Ok, "The working directory is a Regolith project.");
+    } else if working_dir.join("data").is_dir() {
+        report.add(Status::Ok, "The working directory has a data folder.");
+    } else {
+        report.add(Status::Warning,
+            "The working directory doesn't look like a Regolith project (no \
+            \"config.json\" with the \"regolith\" property and no \"data\" \
+            folder).");
+    }
+    // The data folder of the filter and its config
+    let data_path = working_dir.join(FILTER_DATA_PATH);
+    if !data_path.is_dir() {
+        report.add(Status::Error, &format!(
+            "The data folder of the filter \"{}\" doesn't exist.",
+            data_path.display()
+        ));
+        return report.errors;
+    }
+    report.add(Status::Ok, &format!(
+        "The data folder of the filter \"{}\" exists.", data_path.display()
+    ));
+    let config = match Config::load(working_dir) {
+        Ok(config) => config,
+        Err(e) => {
+            report.add(Status::Error, &e.to_string());
+            return report.errors;
+        }
+    };
+    report.add(Status::Ok, &format!(
+        "The config file \"{}\" is valid ({} rules, {} roots).",
+        EXPORT_FILES_MAP, config.rules().len(), config.roots.len()
+    ));
+    if config.roots.is_empty() {
+        report.add(
+            Status::Warning, "The config file doesn't define any roots."
+        );
+    }
+    for root in &config.roots {
+        let root_path = data_path.join(root);
+        if root_path.is_dir() {
+            report.add(Status::Ok, &format!(
+                "The root \"{}\" exists.", root_path.display()
+            ));
+        } else {
+            report.add(Status::Error, &format!(
+                "The root \"{}\" doesn't exist.", root_path.display()
+            ));
+        }
+    }
+    // The packs
+    check_pack_dir(&mut report, working_dir, "RP");
+    check_pack_dir(&mut report, working_dir, "BP");
+    // Path lengths
+    let files = match plan::plan(working_dir, &config) {
+        Ok(files) => files,
+        Err(e) => {
+            report.add(Status::Error, &format!(
+                "Unable to list the files in the data folder: {}", e
+            ));
+            return report.errors;
+        }
+    };
+    let longest = files.iter()
+        .filter_map(|f| f.target_path())
+        .map(|t| abs_working_dir.join(t))
+        .max_by_key(|t| t.as_os_str().len());
+    match longest {
+        Some(longest) if longest.as_os_str().len() >= WINDOWS_MAX_PATH => {
+            report.add(Status::Warning, &format!(
+                "The longest target path has {} characters which exceeds the \
+                Windows limit of {} characters: \"{}\".",
+                longest.as_os_str().len(), WINDOWS_MAX_PATH, longest.display()
+            ));
+        },
+        Some(longest) => {
+            report.add(Status::Ok, &format!(
+                "The longest target path has {} characters.",
+                longest.as_os_str().len()
+            ));
+        },
+        None => {
+            report.add(Status::Warning,
+                "None of the files in the roots can be mapped to the packs.");
+        }
+    }
+    report.errors
+}
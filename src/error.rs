@@ -0,0 +1,133 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// The errors of the filter.
+#[derive(Debug)]
+pub enum CpfError {
+    /// The config file can't be read or it has invalid properties.
+    Config(String),
+    /// The source file can't be mapped to the packs.
+    Mapping {
+        /// The path to the source file.
+        path: PathBuf,
+        reason: String,
+    },
+    /// A file operation failed.
+    Io {
+        error: io::Error,
+        /// The path to the file that the operation failed on or the source
+        /// file of the copied file.
+        source: Option<PathBuf>,
+        /// The target of the copied file.
+        target: Option<PathBuf>,
+    },
+    /// Multiple source files are mapped to the same targets or the targets
+    /// differ only by case.
+    Conflict {
+        /// The conflicting targets with their source files.
+        targets: Vec<(PathBuf, Vec<PathBuf>)>,
+        message: String,
+    },
+    /// The existing target can't be overwritten.
+    Overwrite(PathBuf),
+    /// The data directory can't be walked (e.g. it's nested too deeply).
+    Walk(String),
+    /// A hook command can't be run or it failed.
+    Hook(String),
+    /// The source files are invalid (e.g. JSON files with syntax errors).
+    Invalid(String),
+    /// The other errors (e.g. git or the manifest).
+    Other(String),
+}
+
+impl CpfError {
+    /// Creates an IO error with the path to the file that the operation
+    /// failed on.
+    pub fn io(error: io::Error, path: impl Into<PathBuf>) -> CpfError {
+        CpfError::Io { error, source: Some(path.into()), target: None }
+    }
+
+    /// Creates an IO error of the file that can't be copied to its target.
+    pub fn copy(
+        error: io::Error, source: impl Into<PathBuf>,
+        target: impl Into<PathBuf>,
+    ) -> CpfError {
+        CpfError::Io {
+            error,
+            source: Some(source.into()),
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns the exit code of the process for the error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CpfError::Other(_) => 1,
+            CpfError::Config(_) => 2,
+            CpfError::Mapping { .. } => 3,
+            CpfError::Conflict { .. } | CpfError::Overwrite(_) => 4,
+            CpfError::Io { .. } | CpfError::Walk(_) => 5,
+            CpfError::Hook(_) => 6,
+            CpfError::Invalid(_) => 7,
+        }
+    }
+}
+
+impl fmt::Display for CpfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpfError::Config(message)
+            | CpfError::Conflict { message, .. }
+            | CpfError::Walk(message)
+            | CpfError::Hook(message)
+            | CpfError::Invalid(message)
+            | CpfError::Other(message) => write!(f, "{}", message),
+            CpfError::Mapping { path, reason } => write!(
+                f, "Unable to map \"{}\" to the pack file: {}",
+                path.display(), reason
+            ),
+            CpfError::Io { error, source, target } => match (source, target) {
+                (Some(source), Some(target)) => write!(
+                    f, "Unable to copy \"{}\" to \"{}\": {}",
+                    source.display(), target.display(), error
+                ),
+                (Some(path), None) | (None, Some(path)) => {
+                    write!(f, "\"{}\": {}", path.display(), error)
+                },
+                (None, None) => write!(f, "{}", error),
+            },
+            CpfError::Overwrite(target) => write!(
+                f, "File \"{}\" already exists and can't be overwritten.",
+                target.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CpfError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CpfError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CpfError {
+    fn from(error: io::Error) -> CpfError {
+        CpfError::Io { error, source: None, target: None }
+    }
+}
+
+impl From<String> for CpfError {
+    fn from(message: String) -> CpfError {
+        CpfError::Other(message)
+    }
+}
+
+impl From<&str> for CpfError {
+    fn from(message: &str) -> CpfError {
+        CpfError::Other(message.to_string())
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::CpfError;
+use crate::zip::ZipWriter;
+
+/// The names of the files created by the operating systems and the tools
+/// that shouldn't be part of the exported packs.
+static JUNK_FILES: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini"];
+
+/// The names of the directories that shouldn't be part of the exported
+/// packs.
+static JUNK_DIRS: &[&str] = &[".git", "__MACOSX"];
+
+/// Checks if the file or directory shouldn't be exported. The leftover
+/// temporary files of the filter are also skipped.
+fn is_junk(path: &Path, is_dir: bool) -> bool {
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    match is_dir {
+        true => JUNK_DIRS.contains(&name),
+        false => JUNK_FILES.contains(&name) || name.ends_with(".cpf_tmp"),
+    }
+}
+
+/// Recursively lists the files of the pack that should be exported. Returns
+/// the paths relative to the pack sorted by the paths, so the archives don't
+/// depend on the order of the files in the file system. The directories
+/// that are linked more than once (e.g. a symbolic link to a parent
+/// directory) are only listed the first time.
+///
+/// # Arguments
+/// - `pack_dir` - the path to the pack
+/// - `archive` - the canonical path to the archive that is written, which is
+///   skipped when the output directory is inside of the pack
+fn list_pack_files(
+    pack_dir: &Path, archive: &Path,
+) -> Result<Vec<PathBuf>, CpfError> {
+    let mut result = Vec::new();
+    let mut visited = HashSet::new();
+    let mut dirs = vec![pack_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let canonical = fs::canonicalize(&dir)
+            .map_err(|e| CpfError::io(e, &dir))?;
+        if !visited.insert(canonical.clone()) {
+            continue;
+        }
+        let entries = fs::read_dir(&dir).map_err(|e| CpfError::io(e, &dir))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| CpfError::io(e, &dir))?;
+            let path = entry.path();
+            let is_dir = path.is_dir();
+            if is_junk(&path, is_dir) {
+                continue;
+            }
+            if is_dir {
+                dirs.push(path);
+            } else if canonical.join(entry.file_name()) == archive {
+                continue;
+            } else if let Ok(relative) = path.strip_prefix(pack_dir) {
+                result.push(relative.to_path_buf());
+            }
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Adds the files of the pack to the archive.
+///
+/// # Arguments
+/// - `zip` - the archive
+/// - `archive` - the canonical path to the archive
+/// - `pack_dir` - the path to the pack
+/// - `prefix` - the directory of the archive that the files are added to
+///   (e.g. "MyProject_BP/") or an empty string for the root of the archive
+fn add_pack(
+    zip: &mut ZipWriter, archive: &Path, pack_dir: &Path, prefix: &str,
+) -> Result<(), CpfError> {
+    for file in list_pack_files(pack_dir, archive)? {
+        let source = pack_dir.join(&file);
+        let data = fs::read(&source).map_err(|e| CpfError::io(e, &source))?;
+        // The ZIP files always use "/" as the separator
+        let name = file.iter()
+            .map(|c| c.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip.add_file(&format!("{}{}", prefix, name), &data)
+            .map_err(|e| CpfError::io(e, &source))?;
+    }
+    Ok(())
+}
+
+/// Packages the RP and BP from the working directory into the archives that
+/// can be imported into Minecraft. Without `addon`, every pack is exported
+/// to its own "<name>_BP.mcpack" or "<name>_RP.mcpack" with the files of the
+/// pack in the root of the archive. With `addon`, both packs are exported to
+/// "<name>.mcaddon" in the "<name>_BP" and "<name>_RP" directories. The
+/// packs that don't exist are skipped. Returns the paths to the created
+/// archives.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory with the RP and BP
+/// - `out_dir` - the directory that the archives are written to
+/// - `name` - the name of the archives
+/// - `addon` - whether to export both packs to a single .mcaddon file
+pub fn export(
+    working_dir: &Path, out_dir: &Path, name: &str, addon: bool,
+) -> Result<Vec<PathBuf>, CpfError> {
+    let packs: Vec<(PathBuf, String)> = ["BP", "RP"].iter()
+        .map(|pack| (working_dir.join(pack), format!("{}_{}", name, pack)))
+        .filter(|(pack_dir, _)| pack_dir.is_dir())
+        .collect();
+    if packs.is_empty() {
+        return Err(CpfError::Other(format!(
+            "There are no packs to export in \"{}\".", working_dir.display()
+        )));
+    }
+    fs::create_dir_all(out_dir).map_err(|e| CpfError::io(e, out_dir))?;
+    // The output directory can be inside of the packs
+    let canonical_out = fs::canonicalize(out_dir)
+        .map_err(|e| CpfError::io(e, out_dir))?;
+    let mut archives = Vec::new();
+    if addon {
+        let file_name = format!("{}.mcaddon", name);
+        let path = out_dir.join(&file_name);
+        let mut zip = ZipWriter::create(&path)
+            .map_err(|e| CpfError::io(e, &path))?;
+        for (pack_dir, pack_name) in &packs {
+            add_pack(
+                &mut zip, &canonical_out.join(&file_name), pack_dir,
+                &format!("{}/", pack_name),
+            )?;
+        }
+        zip.finish().map_err(|e| CpfError::io(e, &path))?;
+        archives.push(path);
+    } else {
+        for (pack_dir, pack_name) in &packs {
+            let file_name = format!("{}.mcpack", pack_name);
+            let path = out_dir.join(&file_name);
+            let mut zip = ZipWriter::create(&path)
+                .map_err(|e| CpfError::io(e, &path))?;
+            add_pack(&mut zip, &canonical_out.join(&file_name), pack_dir, "")?;
+            zip.finish().map_err(|e| CpfError::io(e, &path))?;
+            archives.push(path);
+        }
+    }
+    Ok(archives)
+}
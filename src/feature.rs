@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{MapResult, Mapper, TargetInfo};
+use crate::plan::PlannedFile;
+
+/// Checks if the planned file is the marker of a feature folder: a file
+/// named only with the extension of its rule or with an underscore and the
+/// extension (e.g. "zombie/_.bpe.json").
+fn is_marker(file: &PlannedFile) -> bool {
+    let (name, rule) = match (file.source.file_name(), file.rule()) {
+        (Some(name), Some(rule)) if rule.starts_with('.') => (name, rule),
+        _ => return false,
+    };
+    name == rule || name == format!("_{}", rule).as_str()
+}
+
+/// Returns the feature folders of the planned files (the folders that
+/// contain a marker, see is_marker) with the indices of the files directly
+/// inside of them. The files are mapped by the extension rules.
+pub fn feature_folders(files: &[PlannedFile]) -> BTreeMap<&Path, Vec<usize>> {
+    let mut folders: BTreeMap<&Path, Vec<usize>> = BTreeMap::new();
+    for (i, file) in files.iter().enumerate() {
+        let is_rule = file.rule().is_some_and(|rule| rule.starts_with('.'));
+        if let (Some(folder), true) = (file.source.parent(), is_rule) {
+            folders.entry(folder).or_default().push(i);
+        }
+    }
+    folders.retain(|_, indices| indices.iter().any(|i| is_marker(&files[*i])));
+    folders
+}
+
+/// Treats the folders with the markers (e.g. "zombie/_.bpe.json") as the
+/// folders of a single feature: the name of the folder is the base name of
+/// every file inside, so "zombie/model.geo.json" is mapped to
+/// "RP/models/entity/zombie.geo.json" like "zombie/_.geo.json". When more
+/// than one file of the folder has the same rule, the names of the other
+/// files are appended to the name of the folder (e.g. "zombie/armor.geo.json"
+/// -> "RP/models/entity/zombie_armor.geo.json"), so they don't share the
+/// target. The files in the subfolders of the feature folders keep their
+/// paths.
+pub struct FeatureFolders;
+
+impl Mapper for FeatureFolders {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        let folders: Vec<(PathBuf, Vec<usize>)> = feature_folders(files)
+            .into_iter()
+            .map(|(folder, indices)| (folder.to_path_buf(), indices))
+            .collect();
+        for (folder, indices) in folders {
+            let name = match folder.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => continue,
+            };
+            // The number of the files of the folder with each rule
+            let mut rules: HashMap<String, usize> = HashMap::new();
+            for i in &indices {
+                if let Some(rule) = files[*i].rule() {
+                    *rules.entry(rule.to_string()).or_default() += 1;
+                }
+            }
+            for i in indices {
+                if is_marker(&files[i]) {
+                    continue;
+                }
+                let target = match &files[i].target {
+                    Some(target) => target,
+                    None => continue,
+                };
+                // The target of the file is in the folder of the feature
+                let base_path = match target.path.parent()
+                    .and_then(Path::parent)
+                {
+                    Some(base_path) => base_path,
+                    None => continue,
+                };
+                let file_name = files[i].source.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let stem = file_name.strip_suffix(target.rule.as_str())
+                    .unwrap_or(&file_name);
+                let base_name = match rules[&target.rule] {
+                    1 => format!("{}{}", name, target.rule),
+                    _ if stem == name => file_name.clone(),
+                    _ => format!("{}_{}{}", name, stem, target.rule),
+                };
+                let path = base_path.join(base_name);
+                files[i].target = Some(TargetInfo::new(&target.rule, path));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ExtExportMap;
+    use crate::mapping::RuleMapper;
+    use crate::plan::plan_paths;
+    use crate::MapperChain;
+
+    #[test]
+    fn maps_the_files_with_the_same_rule_to_different_targets() {
+        let rules: ExtExportMap = [
+            (".bpe.json", "BP/entities"), (".geo.json", "RP/models/entity"),
+        ].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let mapper = MapperChain::new()
+            .with(RuleMapper::new(rules))
+            .with(FeatureFolders);
+        let paths: Vec<PathBuf> = [
+            "zombie/_.bpe.json", "zombie/model.geo.json",
+            "husk/_.bpe.json", "husk/model.geo.json", "husk/armor.geo.json",
+            "husk/husk.geo.json",
+        ].iter().map(PathBuf::from).collect();
+        let targets: Vec<PathBuf> = plan_paths(&mapper, Path::new(""), &paths)
+            .into_iter()
+            .filter_map(|file| file.target.map(|target| target.path))
+            .collect();
+        let expected = [
+            "BP/entities/husk.bpe.json",
+            "RP/models/entity/husk_armor.geo.json",
+            "RP/models/entity/husk.geo.json",
+            "RP/models/entity/husk_model.geo.json",
+            "BP/entities/zombie.bpe.json",
+            "RP/models/entity/zombie.geo.json",
+        ];
+        assert_eq!(targets, expected.map(PathBuf::from));
+    }
+}
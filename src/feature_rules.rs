@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+
+use crate::index::{Document, ProjectIndex};
+use crate::mapping::Pack;
+use crate::report::{Diagnostic, Severity};
+
+/// Returns the identifier of the feature defined by the document (the
+/// "description" of a top-level "minecraft:*_feature" object, e.g. the
+/// "minecraft:ore_feature") or None if it isn't a feature.
+fn feature_identifier<'a>(document: &'a Document) -> Option<&'a str> {
+    document.json.as_object()?.iter()
+        .filter(|(key, _)| {
+            key.starts_with("minecraft:") && key.ends_with("_feature")
+        })
+        .find_map(|(_, feature)| feature["description"]["identifier"].as_str())
+}
+
+/// Checks if the name of the identifier (e.g. "iron_ore" of "ns:iron_ore")
+/// is the name of the target file without the extensions (e.g.
+/// "BP/features/iron_ore.f.json"). The game looks up the features by
+/// the names of their files.
+fn matches_file_name(document: &Document, identifier: &str) -> bool {
+    let name = identifier.rsplit(':').next().unwrap_or_default();
+    let file_name = document.target.path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    file_name.split('.').next() == Some(name)
+}
+
+/// Checks the features and the feature rules of the behavior pack: the
+/// identifiers of the features have to match the names of their files and
+/// the features placed by the feature rules ("places_feature") have to be
+/// defined in the behavior pack (or be vanilla features).
+pub fn check_feature_rules(
+    index: &ProjectIndex, problems: &mut Vec<Diagnostic>,
+) {
+    let mut features = HashSet::new();
+    for document in &index.documents {
+        if document.target.pack != Pack::Behavior {
+            continue;
+        }
+        let identifier = match feature_identifier(document) {
+            Some(identifier) => identifier,
+            None => continue,
+        };
+        features.insert(identifier);
+        if !matches_file_name(document, identifier) {
+            problems.push(Diagnostic::new(
+                Severity::Warning, document.source, format!(
+                    "The identifier of the feature \"{}\" doesn't match the \
+                    name of its file \"{}\".",
+                    identifier, document.target.path.display()
+                ),
+            ));
+        }
+    }
+    for document in index.with_key(Pack::Behavior, "minecraft:feature_rules")
+    {
+        let description = &document.json["minecraft:feature_rules"]
+            ["description"];
+        let message = match description["places_feature"].as_str() {
+            Some(feature) if features.contains(feature)
+                || feature.starts_with("minecraft:") => continue,
+            Some(feature) => format!(
+                "The feature rule places the feature \"{}\" which isn't \
+                defined in the behavior pack.",
+                feature
+            ),
+            None => "The feature rule doesn't have the \"places_feature\"."
+                .into(),
+        };
+        problems.push(
+            Diagnostic::new(Severity::Error, document.source, message)
+        );
+    }
+}
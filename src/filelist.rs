@@ -0,0 +1,29 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use crate::error::CpfError;
+
+/// Reads the list of the paths (relative to the data directory) of the files
+/// to process, one path per line. The empty lines are ignored.
+///
+/// # Arguments
+/// - `source` - the path to the file with the list or "-" for the standard
+///   input
+pub fn read_file_list(source: &str) -> Result<Vec<PathBuf>, CpfError> {
+    let text = match source {
+        "-" => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)
+                .map_err(|e| format!("Unable to read the file list: {}", e))?;
+            text
+        },
+        path => fs::read_to_string(path).map_err(|e| CpfError::io(e, path))?,
+    };
+    Ok(text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        // Fix the path separators (e.g "/" -> "\\")
+        .map(|line| PathBuf::from(line).iter().collect())
+        .collect())
+}
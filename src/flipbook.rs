@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{
+    resource_dir, resource_reference, MapResult, Mapper, TargetInfo,
+};
+use crate::plan::PlannedFile;
+use crate::png::{self, Image};
+
+/// The name of the rule of the files generated by the Flipbooks.
+pub static FLIPBOOK_RULE: &str = "(flipbook)";
+
+/// The prefix of the names of the frames (e.g. "frame_00.png").
+static FRAME_PREFIX: &str = "frame_";
+
+/// The name of the optional file with the properties of the entry of the
+/// flipbook in the frame folder (e.g. `{"ticks_per_frame": 2}`).
+static SETTINGS_NAME: &str = "flipbook.json";
+
+/// The name of the file that lists the flipbook textures.
+pub static FLIPBOOK_TEXTURES: &str = "flipbook_textures.json";
+
+/// Returns the number of the frame (e.g. 3 for "frame_03.png") or None if
+/// the file isn't a frame.
+fn frame_number(path: &Path) -> Option<u32> {
+    if path.extension().is_none_or(|e| e != "png") {
+        return None;
+    }
+    let digits = path.file_stem()?.to_str()?.strip_prefix(FRAME_PREFIX)?;
+    match !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        true => digits.parse().ok(),
+        false => None,
+    }
+}
+
+/// Reads the frames of the folder sorted by their numbers.
+fn read_frames(folder: &Path) -> Result<Vec<Image>, String> {
+    let mut frames: Vec<(u32, PathBuf)> = fs::read_dir(folder)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| Some((frame_number(&path)?, path)))
+        .collect();
+    frames.sort();
+    frames.iter().map(|(_, path)| {
+        fs::read(path).map_err(|e| e.to_string())
+            .and_then(|data| png::decode(&data))
+            .map_err(|e| format!("\"{}\": {}", path.display(), e))
+    }).collect()
+}
+
+/// Generates the flipbook textures from the folders with the frames (e.g.
+/// "lava_custom/frame_00.png", "lava_custom/frame_01.png"). The frames are
+/// stacked into a vertical strip named after the folder (e.g.
+/// "RP/textures/blocks/lava_custom.png") and the strip is added to the
+/// "flipbook_textures.json" of the textures directory. The frames and the
+/// "flipbook.json" files with the properties of the entries aren't copied.
+/// The "flipbook_textures.json" from the sources is extended instead of
+/// being replaced.
+pub struct Flipbooks;
+
+impl Mapper for Flipbooks {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The first frame of every flipbook and its target
+        let mut flipbooks: BTreeMap<PathBuf, (u32, PathBuf, PathBuf)> =
+            BTreeMap::new();
+        for file in files.iter() {
+            let (folder, target, number) = match (
+                file.source.parent(), &file.target, frame_number(&file.source)
+            ) {
+                (Some(folder), Some(target), Some(number)) => {
+                    (folder, target, number)
+                },
+                _ => continue,
+            };
+            let frame = (number, file.source.clone(), target.path.clone());
+            flipbooks.entry(folder.to_path_buf())
+                .and_modify(|first| if number < first.0 {
+                    *first = frame.clone()
+                })
+                .or_insert(frame);
+        }
+        files.retain(|file| {
+            let is_flipbook = file.source.parent()
+                .is_some_and(|folder| flipbooks.contains_key(folder));
+            let settings = file.source.file_name()
+                .is_some_and(|name| name == SETTINGS_NAME);
+            !is_flipbook
+                || !(settings || frame_number(&file.source).is_some())
+        });
+        // The strips by the directories of the textures
+        let mut strips: BTreeMap<PathBuf, Vec<(PathBuf, PathBuf)>> =
+            BTreeMap::new();
+        for (folder, (_, source, target)) in flipbooks {
+            let (name, dir) = match (
+                folder.file_name(), target.parent().and_then(|p| p.parent())
+            ) {
+                (Some(name), Some(dir)) => (name, dir),
+                _ => continue,
+            };
+            let mut path = dir.join(name);
+            path.as_mut_os_string().push(".png");
+            files.push(PlannedFile {
+                source: source.clone(),
+                target: Some(TargetInfo::new(FLIPBOOK_RULE, path.clone())),
+            });
+            if let Some(textures_dir) = resource_dir(&path, "textures") {
+                strips.entry(textures_dir).or_default().push((source, path));
+            }
+        }
+        for (textures_dir, inputs) in strips {
+            let path = textures_dir.join(FLIPBOOK_TEXTURES);
+            let existing = files.iter_mut()
+                .find(|file| file.target_path() == Some(&path));
+            let target = TargetInfo::new(FLIPBOOK_RULE, path)
+                .with_inputs(inputs);
+            match existing {
+                Some(file) => file.target = Some(target),
+                None => files.push(PlannedFile {
+                    source: target.inputs[0].0.clone(),
+                    target: Some(target),
+                }),
+            }
+        }
+    }
+}
+
+/// Generates the vertical strip of the frames of the flipbook. All of the
+/// frames must have the same size.
+///
+/// # Arguments
+/// - `source` - the path to a frame of the flipbook
+pub fn flipbook_contents(source: &Path) -> Result<Vec<u8>, String> {
+    let folder = source.parent().ok_or("The frame isn't in a folder.")?;
+    let frames = read_frames(folder)?;
+    let (width, height) = match frames.first() {
+        Some(frame) => (frame.width, frame.height),
+        None => return Err("The flipbook doesn't have any frames.".into()),
+    };
+    if frames.iter().any(|f| f.width != width || f.height != height) {
+        return Err("The frames of the flipbook have different sizes.".into());
+    }
+    let mut strip = Image::new(width, height * frames.len() as u32);
+    for (i, frame) in frames.iter().enumerate() {
+        strip.paste(frame, 0, height * i as u32);
+    }
+    Ok(png::encode(&strip))
+}
+
+/// Generates the "flipbook_textures.json" with the entries of the
+/// flipbooks added to the entries of the source file (if the source is the
+/// "flipbook_textures.json"). The entries of the same textures from the
+/// source are replaced.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target with the frames and the strips of the flipbooks
+///   as the inputs
+pub fn flipbook_textures_contents(
+    source: &Path, target: &TargetInfo,
+) -> Result<Vec<u8>, String> {
+    let mut entries = match source.file_name() {
+        Some(name) if name == FLIPBOOK_TEXTURES => {
+            let text = fs::read_to_string(source)
+                .map_err(|e| e.to_string())?;
+            match serde_json::from_str(text.trim_start_matches('\u{feff}')) {
+                Ok(serde_json::Value::Array(entries)) => entries,
+                _ => return Err("The source isn't a JSON array.".into()),
+            }
+        },
+        _ => Vec::new(),
+    };
+    for (frame, strip) in &target.inputs {
+        let reference = resource_reference(strip, "textures");
+        let tile = strip.file_stem().unwrap_or_default().to_string_lossy();
+        let mut entry = serde_json::Map::new();
+        entry.insert("flipbook_texture".into(), reference.clone().into());
+        entry.insert("atlas_tile".into(), tile.into_owned().into());
+        let settings = frame.with_file_name(SETTINGS_NAME);
+        if settings.is_file() {
+            let properties = fs::read_to_string(&settings).ok()
+                .and_then(|text| serde_json::from_str::<serde_json::Value>(
+                    text.trim_start_matches('\u{feff}')
+                ).ok())
+                .and_then(|json| json.as_object().cloned())
+                .ok_or_else(|| format!(
+                    "\"{}\" isn't a JSON object.", settings.display()
+                ))?;
+            entry.extend(properties);
+        }
+        entries.retain(|e| e["flipbook_texture"] != reference.as_str());
+        entries.push(entry.into());
+    }
+    Ok(format!("{:#}\n", serde_json::Value::Array(entries)).into_bytes())
+}
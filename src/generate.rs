@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use crate::atlas::{atlas_contents, ATLAS_RULE};
+use crate::bundle::BUNDLE_RULE;
+use crate::dialogue::{dialogue_contents, DIALOGUE_RULE};
+use crate::flipbook::{
+    flipbook_contents, flipbook_textures_contents, FLIPBOOK_RULE,
+    FLIPBOOK_TEXTURES,
+};
+use crate::lang::{lang_contents, LANG_RULE};
+use crate::loot_tables::{loot_table_contents, LOOT_TABLE_RULE};
+use crate::pack_manifest::SCRIPT_MANIFEST_RULE;
+use crate::plan::CopyOperation;
+use crate::recipe_shorthand::{recipe_contents, RECIPE_RULE};
+use crate::sounds::{sounds_contents, SOUNDS_RULE};
+use crate::tick::{tick_contents, TICK_RULE};
+use crate::trade_tables::{trade_table_contents, TRADE_TABLE_RULE};
+use crate::typescript::TYPESCRIPT_RULE;
+use crate::umbrella::{umbrella_contents, UMBRELLA_RULE};
+use crate::variants::{variant_contents, variants_path, VARIANT_RULE};
+
+/// Checks if the targets of the rule are generated from their sources
+/// instead of being copies of them.
+pub fn is_generated(rule: &str) -> bool {
+    [
+        VARIANT_RULE, ATLAS_RULE, FLIPBOOK_RULE, SOUNDS_RULE, LANG_RULE,
+        TRADE_TABLE_RULE, LOOT_TABLE_RULE, DIALOGUE_RULE, RECIPE_RULE,
+        UMBRELLA_RULE, TICK_RULE, TYPESCRIPT_RULE, BUNDLE_RULE,
+        SCRIPT_MANIFEST_RULE,
+    ].contains(&rule)
+}
+
+/// Generates the content of the target of the operation. Returns None if the
+/// target is a copy of the source or the error message if the content can't
+/// be generated.
+pub fn generated_contents(
+    operation: &CopyOperation,
+) -> Option<Result<Vec<u8>, String>> {
+    let (source, target) = (&operation.source, &operation.target.path);
+    match operation.target.rule.as_str() {
+        rule if rule == VARIANT_RULE => Some(variant_contents(source, target)),
+        rule if rule == ATLAS_RULE => Some(atlas_contents(source, target)),
+        rule if rule == FLIPBOOK_RULE => match target.file_name() {
+            Some(name) if name == FLIPBOOK_TEXTURES => Some(
+                flipbook_textures_contents(source, &operation.target)
+            ),
+            _ => Some(flipbook_contents(source)),
+        },
+        rule if rule == SOUNDS_RULE => {
+            Some(sounds_contents(source, &operation.target))
+        },
+        rule if rule == LANG_RULE => {
+            Some(lang_contents(source, &operation.target))
+        },
+        rule if rule == TRADE_TABLE_RULE => {
+            Some(trade_table_contents(source))
+        },
+        rule if rule == LOOT_TABLE_RULE => {
+            Some(loot_table_contents(source))
+        },
+        rule if rule == DIALOGUE_RULE => Some(dialogue_contents(source)),
+        rule if rule == RECIPE_RULE => Some(recipe_contents(source, target)),
+        rule if rule == UMBRELLA_RULE => {
+            Some(umbrella_contents(source, &operation.target))
+        },
+        rule if rule == TICK_RULE => {
+            Some(tick_contents(source, &operation.target))
+        },
+        _ => None,
+    }
+}
+
+/// Checks if the source of the operation is needed for generating the other
+/// targets, so it can't be moved.
+pub fn keeps_source(operation: &CopyOperation) -> bool {
+    is_generated(&operation.target.rule) || is_generator_source(
+        &operation.source
+    )
+}
+
+/// Checks if other targets are generated from the file.
+fn is_generator_source(source: &Path) -> bool {
+    source.extension().is_some_and(|e| e == "png")
+        && variants_path(source).is_file()
+}
@@ -0,0 +1,37 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+static FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+static FNV_PRIME: u64 = 0x100000001b3;
+
+/// Updates the 64-bit FNV-1a hash with the bytes. Unlike the hashers from the
+/// standard library, the result is stable between the versions of the
+/// program, so it can be stored in files.
+fn update(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Calculates the 64-bit FNV-1a hash of the content of the file.
+pub fn hash_file(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut hash = FNV_OFFSET_BASIS;
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hash = update(hash, &buffer[..n]);
+    }
+    Ok(hash)
+}
+
+/// Calculates the 64-bit FNV-1a hash of the bytes.
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    update(FNV_OFFSET_BASIS, bytes)
+}
@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::CpfError;
+
+/// The external commands run around the copying of the files.
+#[derive(Clone, Default)]
+pub struct Hooks {
+    /// The commands run before walking the data directory.
+    pub before: Vec<String>,
+    /// The commands run after all of the files are copied and the manifest
+    /// is recorded.
+    pub after: Vec<String>,
+    /// The commands run after copying each file. They can modify the target
+    /// in place (e.g. encode the audio files).
+    pub per_file: Vec<String>,
+}
+
+impl Hooks {
+    /// Parses the hooks from the "hooks" property of the config file.
+    /// Returns None if the property has invalid structure.
+    ///
+    /// # Arguments
+    /// - `value` - the value of the property
+    pub fn from_json(value: &serde_json::Value) -> Option<Hooks> {
+        let commands = |name: &str| match &value[name] {
+            serde_json::Value::Null => Some(Vec::new()),
+            serde_json::Value::Array(commands) => commands.iter()
+                .map(|c| Some(c.as_str()?.to_string()))
+                .collect(),
+            _ => None,
+        };
+        match value {
+            serde_json::Value::Null => Some(Hooks::default()),
+            serde_json::Value::Object(_) => Some(Hooks {
+                before: commands("before")?,
+                after: commands("after")?,
+                per_file: commands("per_file")?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Creates the process of the command run by the shell of the system in
+/// the working directory.
+///
+/// # Arguments
+/// - `command` - the command to run
+/// - `working_dir` - the path to the working directory of regolith
+/// - `env` - the additional environment variables of the command
+fn shell_command(
+    command: &str, working_dir: &Path, env: &[(&str, &Path)],
+) -> Command {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    };
+    // The empty working directory means the current directory
+    let working_dir = match working_dir.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => working_dir,
+    };
+    process.current_dir(working_dir).env("CPF_WORKING_DIR", working_dir);
+    for (name, value) in env {
+        process.env(name, value);
+    }
+    process
+}
+
+/// Runs the command using the shell of the system in the working directory.
+/// Returns an error if the command can't be started or it fails.
+///
+/// # Arguments
+/// - `command` - the command to run
+/// - `working_dir` - the path to the working directory of regolith
+/// - `env` - the additional environment variables of the command
+fn run_command(
+    command: &str, working_dir: &Path, env: &[(&str, &Path)],
+) -> Result<(), CpfError> {
+    let status = shell_command(command, working_dir, env).status()
+        .map_err(|e| CpfError::Hook(
+            format!("Unable to run the hook \"{}\": {}", command, e)
+        ))?;
+    if !status.success() {
+        return Err(CpfError::Hook(
+            format!("The hook \"{}\" failed with {}", command, status)
+        ));
+    }
+    Ok(())
+}
+
+/// Runs the command using the shell of the system in the working directory
+/// and returns its standard output. The error contains the standard error
+/// output of the failed command.
+///
+/// # Arguments
+/// - `command` - the command to run
+/// - `working_dir` - the path to the working directory of regolith
+/// - `env` - the additional environment variables of the command
+pub fn command_output(
+    command: &str, working_dir: &Path, env: &[(&str, &Path)],
+) -> Result<Vec<u8>, String> {
+    let output = shell_command(command, working_dir, env).output()
+        .map_err(|e| format!("Unable to run \"{}\": {}", command, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "\"{}\" failed with {}: {}", command, output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Runs the commands in order. Stops at the first command that fails.
+///
+/// # Arguments
+/// - `commands` - the commands to run
+/// - `working_dir` - the path to the working directory of regolith
+/// - `env` - the additional environment variables of the commands
+pub fn run_hooks(
+    commands: &[String], working_dir: &Path, env: &[(&str, &Path)],
+) -> Result<(), CpfError> {
+    for command in commands {
+        run_command(command, working_dir, env)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ExtExportMap, FILTER_DATA_PATH};
+use crate::mapping::get_target_path_from_hash_map;
+
+/// Recursively lists the files in the directory.
+fn list_files(
+    dir: &Path, result: &mut Vec<PathBuf>
+) -> Result<(), Box<dyn Error>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Failed to read directory: {}", dir.display());
+            return Err(Box::new(e));
+        }
+    };
+    for fp in entries {
+        let fp = fp?.path();
+        if fp.is_dir() {
+            list_files(&fp, result)?;
+        } else {
+            result.push(fp);
+        }
+    }
+    Ok(())
+}
+
+/// Finds the rule that could have produced the pack file and returns the
+/// path of the file in the data directory (relative to the root) that would
+/// be mapped back to the same pack file. Returns the extension of the rule
+/// and the path.
+///
+/// # Arguments
+/// - `pack_path` - the path to the pack file relative to the working
+///   directory (e.g. "BP/entities/zombie.json")
+/// - `export_map` - the map of file extensions and the export targets
+fn find_source_path<'a>(
+    pack_path: &Path, export_map: &'a ExtExportMap,
+) -> Option<(&'a str, PathBuf)> {
+    let file_name = pack_path.file_name()?.to_str()?;
+    let file_ext = pack_path.extension()?.to_str()?;
+    // Find the rule with the most specific target that matches the
+    // directory of the file and the extension of the file
+    let (extension, target) = export_map.iter()
+        .map(|(k, v)| {
+            (k.as_str(), PathBuf::from(v).iter().collect::<PathBuf>())
+        })
+        .filter(|(k, v)| {
+            pack_path.starts_with(v)
+                && k.rsplit('.').next() == Some(file_ext)
+        })
+        .max_by(|(ka, va), (kb, vb)| {
+            va.components().count().cmp(&vb.components().count())
+                .then(ka.len().cmp(&kb.len()))
+                .then(kb.cmp(ka))
+        })?;
+    // Add the compound extension to the name of the file
+    let new_name = if file_name.ends_with(extension) {
+        file_name.to_string()
+    } else {
+        let stem = file_name.strip_suffix(file_ext)?.trim_end_matches('.');
+        format!("{}.{}", stem, extension.trim_start_matches('.'))
+    };
+    let sub_path = pack_path.strip_prefix(&target).ok()?.parent()?;
+    Some((extension, sub_path.join(new_name)))
+}
+
+/// Restructures an existing resource pack and behavior pack into the layout
+/// of the data directory of the filter. The files are renamed using the
+/// compound extensions from the export map. The files placed directly in the
+/// target directories of the rules that share the same name (e.g.
+/// "zombie.bpe.json" and "zombie.rpe.json") are grouped into a folder named
+/// after the feature. The files that already exist in the data directory are
+/// skipped.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `export_map` - the map of file extensions and the export targets
+/// - `root` - the root directory (relative to the filter data path) to put
+///   the imported files in
+/// - `packs` - the list of the names of the packs used in the export map
+///   ("RP" or "BP") and the paths to the packs to import
+pub fn import(
+    working_dir: &Path, export_map: &ExtExportMap, root: &str,
+    packs: &[(&str, &Path)],
+) -> Result<(), Box<dyn Error>> {
+    // Find the paths in the data directory for all of the pack files
+    // (the pack file, the extension of the rule, the path in the data
+    // directory and the path where the file should be exported to)
+    let mut imported: Vec<(PathBuf, &str, PathBuf, PathBuf)> = Vec::new();
+    for (pack_name, pack_dir) in packs {
+        let mut files = Vec::new();
+        list_files(pack_dir, &mut files)?;
+        // Import in a stable order regardless of the platform
+        files.sort();
+        for fp in files {
+            let pack_path = Path::new(pack_name).join(
+                fp.strip_prefix(pack_dir)?
+            );
+            match find_source_path(&pack_path, export_map) {
+                Some((extension, source)) => {
+                    let expected = pack_path.with_file_name(
+                        source.file_name().unwrap()
+                    );
+                    imported.push((fp, extension, source, expected))
+                },
+                None => eprintln!(
+                    "Unable to map \"{}\" to the data directory. Skipped.",
+                    fp.display()
+                ),
+            }
+        }
+    }
+    // Count the files that share the same feature name (the name of the
+    // file without the compound extension) in the same directory
+    let feature_of = |extension: &str, source: &Path| -> Option<PathBuf> {
+        let name = source.file_name()?.to_str()?;
+        let feature = name.strip_suffix(extension)?.trim_end_matches('.');
+        Some(source.with_file_name(feature))
+    };
+    let mut features: HashMap<PathBuf, usize> = HashMap::new();
+    for (_, extension, source, _) in &imported {
+        if let Some(feature) = feature_of(extension, source) {
+            *features.entry(feature).or_insert(0) += 1;
+        }
+    }
+    // Copy the files
+    let root_dir = working_dir.join(FILTER_DATA_PATH).join(root);
+    for (fp, extension, source, expected) in imported {
+        // Group the features into folders. The files are named "_" +
+        // extension which makes the filter use the name of the folder.
+        let source = match feature_of(extension, &source) {
+            Some(feature) if features[&feature] > 1 => {
+                feature.join(format!("_{}", extension))
+            },
+            _ => source,
+        };
+        // Make sure that the file will be mapped back to the same path
+        let actual = get_target_path_from_hash_map(&source, export_map);
+        if actual.as_ref() != Some(&expected) {
+            eprintln!(
+                "WARNING! \"{}\" will be exported to \"{}\" instead of \
+                \"{}\".",
+                fp.display(),
+                actual.map_or(
+                    "nowhere".to_string(), |p| p.display().to_string()
+                ),
+                expected.display()
+            );
+        }
+        let target_path = root_dir.join(&source);
+        if target_path.exists() {
+            eprintln!(
+                "WARNING! File \"{}\" already exists. Skipped.",
+                target_path.display()
+            );
+            continue;
+        }
+        fs::create_dir_all(target_path.parent().unwrap())?;
+        fs::copy(&fp, &target_path)?;
+        println!(
+            "Imported \"{}\" as \"{}\"", fp.display(), target_path.display()
+        );
+    }
+    Ok(())
+}
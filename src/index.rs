@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::generate::is_generated;
+use crate::mapping::{Pack, TargetInfo};
+use crate::plan::PlannedFile;
+use crate::text::strip_json_comments;
+
+/// A JSON file of the project that is mapped to the packs.
+pub struct Document<'a> {
+    /// The path to the source file.
+    pub source: &'a Path,
+    /// The target of the file.
+    pub target: &'a TargetInfo,
+    /// The parsed content of the file.
+    pub json: serde_json::Value,
+}
+
+/// The index of the files that the filter produces, used by the validation
+/// passes that check the references between the files (e.g. the geometries
+/// used by the client entities). The JSON files are parsed once. The files
+/// with syntax errors are left out (they're reported by the syntax check).
+pub struct ProjectIndex<'a> {
+    /// The parsed JSON files.
+    pub documents: Vec<Document<'a>>,
+    /// The paths of all of the targets with "/" separators (e.g.
+    /// "RP/textures/entity/foo.png").
+    targets: HashSet<String>,
+    /// The planned files.
+    files: &'a [PlannedFile],
+}
+
+impl<'a> ProjectIndex<'a> {
+    /// Reads and parses the JSON files of the planned files that are mapped
+    /// to the packs.
+    pub fn new(files: &'a [PlannedFile]) -> ProjectIndex<'a> {
+        let mut documents = Vec::new();
+        let mut targets = HashSet::new();
+        for file in files {
+            let target = match &file.target {
+                Some(target) => target,
+                None => continue,
+            };
+            targets.insert(slash_path(&target.path));
+            // The content of the generated targets isn't the content of
+            // their sources
+            if is_generated(&target.rule)
+                || file.source.extension().is_none_or(|e| e != "json")
+            {
+                continue;
+            }
+            let json = fs::read_to_string(&file.source).ok()
+                .and_then(|text| serde_json::from_str(&strip_json_comments(
+                    text.trim_start_matches('\u{feff}')
+                )).ok());
+            if let Some(json) = json {
+                documents.push(Document {
+                    source: &file.source, target, json,
+                });
+            }
+        }
+        ProjectIndex { documents, targets, files }
+    }
+
+    /// Returns the documents with the top-level key (e.g.
+    /// "minecraft:client_entity") in the pack.
+    pub fn with_key<'b>(
+        &'b self, pack: Pack, key: &'b str,
+    ) -> impl Iterator<Item = &'b Document<'a>> + 'b {
+        self.documents.iter()
+            .filter(move |d| d.target.pack == pack && !d.json[key].is_null())
+    }
+
+    /// Returns the source files mapped to the pack with the extension of the
+    /// target (e.g. the "mcfunction" files) and their targets.
+    pub fn sources<'b>(
+        &'b self, pack: Pack, extension: &'b str,
+    ) -> impl Iterator<Item = (&'a Path, &'a TargetInfo)> + 'b {
+        self.files.iter()
+            .filter_map(|file| {
+                Some((file.source.as_path(), file.target.as_ref()?))
+            })
+            .filter(move |(_, target)| {
+                target.pack == pack
+                    && target.path.extension().is_some_and(|e| e == extension)
+            })
+    }
+
+    /// Checks if the filter produces the file of the pack. The path is
+    /// relative to the pack (e.g. "loot_tables/foo.json").
+    pub fn has_file(&self, pack: Pack, path: &str) -> bool {
+        let pack_dir = match pack {
+            Pack::Behavior => "BP",
+            Pack::Resource => "RP",
+            Pack::Other => return false,
+        };
+        self.targets.contains(&format!("{}/{}", pack_dir, path))
+    }
+
+    /// Checks if the filter produces the file of the pack with any of the
+    /// extensions (e.g. the textures are referenced without ".png").
+    pub fn has_file_with_extension(
+        &self, pack: Pack, path: &str, extensions: &[&str],
+    ) -> bool {
+        extensions.iter().any(|extension| {
+            self.has_file(pack, &format!("{}.{}", path, extension))
+        })
+    }
+}
+
+/// Returns the path with the "/" separators, the same on all of the
+/// platforms (e.g. "BP/entities/zombie.json").
+pub fn slash_path(path: &Path) -> String {
+    path.iter()
+        .map(|c| c.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,65 @@
+use crate::index::ProjectIndex;
+use crate::mapping::Pack;
+use crate::recipes::parse_version;
+use crate::report::{Diagnostic, Severity};
+
+/// The first format version of the items that only exist in the behavior
+/// pack (the resource pack items are the legacy format).
+static BEHAVIOR_ONLY_VERSION: &[u32] = &[1, 16, 100];
+
+/// The components of the legacy items that belong to the resource pack.
+static RESOURCE_COMPONENTS: &[&str] = &[
+    "minecraft:icon", "minecraft:render_offsets",
+];
+
+/// Returns the pack that the item (the JSON file with the "minecraft:item"
+/// key) belongs to based on its format: the items of the format version
+/// 1.16.100 and newer belong to the behavior pack and the legacy items with
+/// the icons belong to the resource pack. Returns None if the file isn't an
+/// item.
+pub fn item_pack(json: &serde_json::Value) -> Option<Pack> {
+    let item = json["minecraft:item"].as_object()?;
+    let version = json["format_version"].as_str().and_then(parse_version);
+    if version.is_some_and(|v| v.as_slice() >= BEHAVIOR_ONLY_VERSION) {
+        return Some(Pack::Behavior);
+    }
+    let components = item.get("components").and_then(|c| c.as_object());
+    let resource = components.is_some_and(|components| {
+        RESOURCE_COMPONENTS.iter().any(|c| components.contains_key(*c))
+    });
+    match resource {
+        true => Some(Pack::Resource),
+        false => Some(Pack::Behavior),
+    }
+}
+
+/// Returns the name of the pack for the messages.
+fn pack_name(pack: Pack) -> &'static str {
+    match pack {
+        Pack::Behavior => "behavior pack",
+        Pack::Resource => "resource pack",
+        Pack::Other => "other directory",
+    }
+}
+
+/// Checks if the items are mapped to the pack that matches their format
+/// (see item_pack), e.g. a ".rpi.json" file with an item of the format
+/// version 1.16.100 is ignored by the game.
+pub fn check_items(index: &ProjectIndex, problems: &mut Vec<Diagnostic>) {
+    for pack in [Pack::Behavior, Pack::Resource] {
+        for document in index.with_key(pack, "minecraft:item") {
+            let expected = match item_pack(&document.json) {
+                Some(expected) if expected != pack => expected,
+                _ => continue,
+            };
+            problems.push(Diagnostic::new(
+                Severity::Warning, document.source, format!(
+                    "The item uses the format of the {} but it's mapped to \
+                    the {} (\"{}\").",
+                    pack_name(expected), pack_name(pack),
+                    document.target.path.display()
+                ),
+            ));
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{MapResult, Mapper, TargetInfo};
+use crate::plan::PlannedFile;
+
+/// The name of the rule of the .lang files generated by the LangTables.
+pub static LANG_RULE: &str = "(lang)";
+
+/// The suffixes of the names of the translation tables and their
+/// delimiters.
+static TABLE_SUFFIXES: &[(&str, char)] = &[
+    (".lang.csv", ','), (".lang.tsv", '\t'),
+];
+
+/// The directory of the generated .lang files.
+static TEXTS_DIR: &[&str] = &["RP", "texts"];
+
+/// The language that comes first in the exported tables.
+static DEFAULT_LANGUAGE: &str = "en_US";
+
+/// Returns the delimiter of the table file (the tab for the ".tsv" files).
+fn delimiter(path: &Path) -> char {
+    match path.extension().is_some_and(|e| e == "tsv") {
+        true => '\t',
+        false => ',',
+    }
+}
+
+/// Returns the delimiter of the translation table or None if the file isn't
+/// a translation table.
+fn table_delimiter(path: &Path) -> Option<char> {
+    let name = path.file_name()?.to_str()?;
+    TABLE_SUFFIXES.iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|(_, delimiter)| *delimiter)
+}
+
+/// Parses the CSV (or TSV) table. The values can be quoted with the double
+/// quotes (with the quotes inside doubled). The empty lines are skipped.
+pub fn parse_table(
+    text: &str, delimiter: char,
+) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut value = String::new();
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if value.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        value.push('"');
+                    },
+                    Some('"') => break,
+                    Some(c) => {
+                        line += (c == '\n') as usize;
+                        value.push(c);
+                    },
+                    None => return Err(format!(
+                        "The quoted value at line {} isn't closed.", line
+                    )),
+                }
+            },
+            c if c == delimiter => row.push(std::mem::take(&mut value)),
+            '\r' => {},
+            '\n' => {
+                row.push(std::mem::take(&mut value));
+                if row.iter().any(|v| !v.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+                line += 1;
+            },
+            c => value.push(c),
+        }
+    }
+    row.push(value);
+    if row.iter().any(|v| !v.is_empty()) {
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Writes the CSV (or TSV) table. The values with the delimiters, the
+/// quotes or the line breaks are quoted.
+pub fn write_table(rows: &[Vec<String>], delimiter: char) -> String {
+    let mut text = String::new();
+    for row in rows {
+        let values: Vec<String> = row.iter().map(|value| {
+            match value.contains([delimiter, '"', '\n', '\r']) {
+                true => format!("\"{}\"", value.replace('"', "\"\"")),
+                false => value.clone(),
+            }
+        }).collect();
+        text.push_str(&values.join(&delimiter.to_string()));
+        text.push('\n');
+    }
+    text
+}
+
+/// Returns the key and the value of the line of the .lang file or None for
+/// the comments and the empty lines. The comments at the ends of the lines
+/// ("\t##") aren't part of the values.
+fn lang_entry(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_end_matches('\r');
+    if line.trim_start().starts_with("##") {
+        return None;
+    }
+    let (key, value) = line.split_once('=')?;
+    let value = value.split_once("\t##").map_or(value, |(v, _)| v);
+    Some((key.trim(), value.trim_end()))
+}
+
+/// Parses the entries of the .lang file in order.
+pub fn parse_lang(text: &str) -> Vec<(String, String)> {
+    text.trim_start_matches('\u{feff}').lines()
+        .filter_map(lang_entry)
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Sets the values of the entries of the .lang file. The lines of the keys
+/// that already exist are replaced in place (the comments of the file are
+/// kept) and the other entries are added at the end.
+pub fn update_lang(text: &str, entries: &[(String, String)]) -> String {
+    let values: HashMap<&str, &str> = entries.iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    let mut written: HashSet<&str> = HashSet::new();
+    let mut result = String::new();
+    for line in text.lines() {
+        match lang_entry(line).and_then(|(key, _)| {
+            Some((key, *values.get(key)?))
+        }) {
+            Some((key, value)) => {
+                result.push_str(&format!("{}={}\n", key, value));
+                written.insert(key);
+            },
+            None => {
+                result.push_str(line.trim_end_matches('\r'));
+                result.push('\n');
+            },
+        }
+    }
+    for (key, value) in entries {
+        if written.insert(key) {
+            result.push_str(&format!("{}={}\n", key, value));
+        }
+    }
+    result
+}
+
+/// Returns the translations of the language from the table (the first row
+/// is the header with the "key" column and the languages). The empty values
+/// are skipped.
+fn table_entries(
+    rows: &[Vec<String>], language: &str,
+) -> Vec<(String, String)> {
+    let column = match rows.first()
+        .and_then(|header| header.iter().position(|h| h.trim() == language))
+    {
+        Some(column) => column,
+        None => return Vec::new(),
+    };
+    rows.iter().skip(1)
+        .filter_map(|row| {
+            let key = row.first()?.trim();
+            let value = row.get(column)?;
+            match key.is_empty() || value.is_empty() {
+                true => None,
+                false => Some((key.to_string(), value.replace('\n', " "))),
+            }
+        })
+        .collect()
+}
+
+/// Reads the table file.
+fn read_table(
+    path: &Path, delimiter: char,
+) -> Result<Vec<Vec<String>>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_table(&text, delimiter)
+        .map_err(|e| format!("\"{}\": {}", path.display(), e))
+}
+
+/// Generates the .lang files from the translation tables (the ".lang.csv"
+/// and ".lang.tsv" files with the "key" column and a column for every
+/// language, e.g. "key,en_US,de_DE"). Every language is written to
+/// "RP/texts/<language>.lang". The .lang files of the sources mapped to the
+/// same targets are extended instead of being replaced. The tables aren't
+/// copied to the packs.
+pub struct LangTables;
+
+impl Mapper for LangTables {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The tables by the languages of their columns
+        let mut languages: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        files.retain(|file| {
+            let delimiter = match table_delimiter(&file.source) {
+                Some(delimiter) => delimiter,
+                None => return true,
+            };
+            // The invalid tables are reported when they're generated
+            let header = read_table(&file.source, delimiter).ok()
+                .and_then(|rows| rows.into_iter().next())
+                .unwrap_or_default();
+            for language in header.iter().skip(1) {
+                let language = language.trim();
+                if !language.is_empty() {
+                    languages.entry(language.to_string()).or_default()
+                        .push(file.source.clone());
+                }
+            }
+            false
+        });
+        let texts_dir: PathBuf = TEXTS_DIR.iter().collect();
+        for (language, tables) in languages {
+            let path = texts_dir.join(format!("{}.lang", language));
+            let inputs = tables.into_iter()
+                .map(|table| (table, path.clone()))
+                .collect();
+            let target = TargetInfo::new(LANG_RULE, path.clone())
+                .with_inputs(inputs);
+            match files.iter_mut()
+                .find(|file| file.target_path() == Some(&path))
+            {
+                Some(file) => file.target = Some(target),
+                None => files.push(PlannedFile {
+                    source: target.inputs[0].0.clone(),
+                    target: Some(target),
+                }),
+            }
+        }
+    }
+}
+
+/// Generates the .lang file of the language (the name of the target) from
+/// the translation tables of the inputs. If the source is a .lang file, its
+/// entries are extended.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target with the translation tables as the inputs
+pub fn lang_contents(
+    source: &Path, target: &TargetInfo,
+) -> Result<Vec<u8>, String> {
+    let language = target.path.file_stem().unwrap_or_default()
+        .to_string_lossy();
+    let mut text = match source.extension().is_some_and(|e| e == "lang") {
+        true => fs::read_to_string(source).map_err(|e| e.to_string())?
+            .trim_start_matches('\u{feff}').to_string(),
+        false => String::new(),
+    };
+    for (table, _) in &target.inputs {
+        let rows = read_table(table, table_delimiter(table).unwrap_or(','))?;
+        text = update_lang(&text, &table_entries(&rows, &language));
+    }
+    Ok(text.into_bytes())
+}
+
+/// Returns the .lang files of the planned files by their languages (the
+/// names of the files, e.g. "en_US"), sorted by their paths.
+fn lang_files(files: &[PlannedFile]) -> BTreeMap<String, Vec<&Path>> {
+    let mut languages: BTreeMap<String, Vec<&Path>> = BTreeMap::new();
+    for file in files {
+        if file.source.extension().is_none_or(|e| e != "lang") {
+            continue;
+        }
+        if let Some(language) = file.source.file_stem() {
+            languages.entry(language.to_string_lossy().into_owned())
+                .or_default().push(&file.source);
+        }
+    }
+    for paths in languages.values_mut() {
+        paths.sort();
+    }
+    languages
+}
+
+/// Exports the entries of the .lang files of the data directory to a single
+/// CSV (or TSV if the file has the ".tsv" extension) table with the "key"
+/// column and a column for every language ("en_US" first). The keys are in
+/// the order of their first appearance. Returns the number of the keys.
+///
+/// # Arguments
+/// - `files` - the planned files of the data directory
+/// - `path` - the path to the table
+pub fn export_table(
+    files: &[PlannedFile], path: &Path,
+) -> Result<usize, Box<dyn Error>> {
+    let mut languages: Vec<(String, Vec<&Path>)> = lang_files(files)
+        .into_iter().collect();
+    languages.sort_by_key(|(language, _)| language != DEFAULT_LANGUAGE);
+    let mut keys: Vec<String> = Vec::new();
+    let mut values: HashMap<(usize, String), String> = HashMap::new();
+    for (column, (_, paths)) in languages.iter().enumerate() {
+        for path in paths {
+            // The files in the other encodings are reported by the check
+            let data = fs::read(path).map_err(|e| format!(
+                "Unable to read \"{}\": {}", path.display(), e
+            ))?;
+            for (key, value) in parse_lang(&String::from_utf8_lossy(&data)) {
+                if !keys.contains(&key) {
+                    keys.push(key.clone());
+                }
+                values.entry((column, key)).or_insert(value);
+            }
+        }
+    }
+    let mut rows = vec![
+        std::iter::once("key".to_string())
+            .chain(languages.iter().map(|(language, _)| language.clone()))
+            .collect::<Vec<String>>()
+    ];
+    for key in &keys {
+        let mut row = vec![key.clone()];
+        for column in 0..languages.len() {
+            row.push(values.get(&(column, key.clone())).cloned()
+                .unwrap_or_default());
+        }
+        rows.push(row);
+    }
+    fs::write(path, write_table(&rows, delimiter(path)))?;
+    Ok(keys.len())
+}
+
+/// Imports the CSV (or TSV) table back to the .lang files of the data
+/// directory. The entries are written to the first .lang file of every
+/// language (see update_lang). The files of the new languages are created
+/// next to the first .lang file of the data directory or in the default
+/// directory if there aren't any. Returns the paths to the written files.
+///
+/// # Arguments
+/// - `files` - the planned files of the data directory
+/// - `path` - the path to the table
+/// - `default_dir` - the directory for the .lang files of the new languages
+///   if the data directory doesn't have any
+pub fn import_table(
+    files: &[PlannedFile], path: &Path, default_dir: &Path,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let rows = read_table(path, delimiter(path))?;
+    let header = rows.first().ok_or("The table is empty.")?;
+    let existing = lang_files(files);
+    let new_dir = existing.values().flatten().min()
+        .and_then(|p| p.parent())
+        .unwrap_or(default_dir);
+    let mut written = Vec::new();
+    for language in header.iter().skip(1).map(|l| l.trim()) {
+        if language.is_empty() {
+            continue;
+        }
+        let lang_path = match existing.get(language) {
+            Some(paths) => paths[0].to_path_buf(),
+            None => new_dir.join(format!("{}.lang", language)),
+        };
+        let text = match lang_path.exists() {
+            true => fs::read_to_string(&lang_path)?,
+            false => String::new(),
+        };
+        let updated = update_lang(
+            text.trim_start_matches('\u{feff}'),
+            &table_entries(&rows, language),
+        );
+        if updated != text {
+            fs::create_dir_all(lang_path.parent().unwrap())?;
+            fs::write(&lang_path, updated)?;
+            written.push(lang_path);
+        }
+    }
+    Ok(written)
+}
@@ -0,0 +1,423 @@
+//! Copies the files from the data directory of the `custom_project` Regolith
+//! filter to the resource pack and the behavior pack based on the compound
+//! extensions of the files (e.g. "zombie.bpe.json" -> "BP/entities").
+//!
+//! The library exposes the logic of the filter to other tools:
+//! - [`MapperChain`] maps the paths of the source files to the pack files
+//!   using a chain of [`Mapper`]s,
+//! - [`Planner`] lists the files of the data directory and maps them,
+//! - [`Executor`] plans the [`CopyOperation`]s and copies the files to the
+//!   packs.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use custom_project::{Executor, MapperChain, Planner};
+//! use custom_project::config::Config;
+//!
+//! let working_dir = Path::new("");
+//! let config = Config::load(working_dir).unwrap();
+//! let mapper = MapperChain::from_config(&config);
+//! println!("{:?}", mapper.target(Path::new("zombie/_.bpe.json")));
+//! let planner = Planner::from_config(working_dir, &config);
+//! for file in planner.plan().unwrap() {
+//!     println!("{:?} -> {:?}", file.source, file.target_path());
+//! }
+//! let executor = Executor::new(working_dir, Default::default());
+//! let operations = executor.plan(&mapper, &config.roots).unwrap();
+//! executor.execute_plan(&operations).unwrap();
+//! ```
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+mod atlas;
+mod audio;
+mod bundle;
+pub mod cache;
+pub mod changed;
+pub mod check;
+pub mod config;
+mod conflict;
+pub mod copy;
+mod dedupe;
+mod dialogue;
+mod diff;
+pub mod doctor;
+pub mod error;
+pub mod export;
+mod feature;
+mod feature_rules;
+pub mod filelist;
+mod flipbook;
+mod generate;
+pub mod hash;
+pub mod hooks;
+pub mod import;
+pub mod lang;
+mod items;
+mod index;
+mod longpath;
+mod loot_tables;
+pub mod manifest;
+pub mod mapping;
+mod mcfunction;
+mod nbt;
+mod notify;
+mod pack_manifest;
+pub mod plan;
+pub mod plugin;
+mod png;
+mod preprocess;
+mod recipe_shorthand;
+mod recipes;
+mod references;
+mod reflink;
+pub mod rename;
+pub mod report;
+mod routing;
+mod retry;
+pub mod scaffold;
+pub mod scoreboard;
+mod sniff;
+mod sounds;
+mod spreadsheet;
+mod spawn_rules;
+mod stream;
+pub mod text;
+mod tick;
+pub mod timings;
+mod trade_tables;
+mod trading;
+mod typescript;
+mod umbrella;
+mod variants;
+mod vanilla;
+pub mod watch;
+mod zip;
+
+use config::Config;
+pub use config::SettingsBuilder;
+use copy::{CopyOptions, CopyResult};
+use atlas::TextureAtlases;
+use bundle::ScriptBundler;
+use dialogue::MarkdownDialogue;
+use feature::FeatureFolders;
+use lang::LangTables;
+use loot_tables::LootTables;
+use flipbook::Flipbooks;
+use pack_manifest::ScriptManifest;
+use recipe_shorthand::RecipeShorthand;
+use rename::{OrderPrefixes, RenameMapper};
+use routing::TextureRouting;
+use sniff::ContentMapper;
+use sounds::SoundEvents;
+use tick::TickFunctions;
+use trade_tables::TradeTables;
+use typescript::TypeScript;
+use umbrella::UmbrellaFunctions;
+use variants::TextureVariants;
+pub use error::CpfError;
+pub use mapping::{MapResult, Mapper, TargetInfo};
+use mapping::{
+    FallbackMapper, PassthroughMapper, RootSubfolders, RuleMapper,
+    SharedMapper,
+};
+use plan::{PlannedFile, WalkOptions};
+pub use plan::CopyOperation;
+use plugin::PluginMapper;
+
+/// Maps the paths of the source files (relative to a root of the data
+/// directory) to the paths of the pack files using a chain of mappers. The
+/// first mapper that handles the file decides its target.
+#[derive(Clone, Default)]
+pub struct MapperChain {
+    mappers: Vec<Arc<dyn Mapper>>,
+}
+
+impl MapperChain {
+    /// Creates an empty chain that doesn't map any files.
+    pub fn new() -> MapperChain {
+        MapperChain::default()
+    }
+
+    /// Creates the chain of the mappers enabled in the config file. The
+    /// plugins and the rules of the "extensions_map" come first, so they
+    /// take precedence over the built-in mappers.
+    pub fn from_config(config: &Config) -> MapperChain {
+        let mut chain = MapperChain::new();
+        if config.plugins.maps_files() {
+            chain = chain.with(PluginMapper::new(config.plugins.clone()));
+        }
+        chain = chain.with(RuleMapper::new(config.export_map.clone()));
+        if config.default_mappings {
+            chain = chain.with(
+                RuleMapper::new(mapping::default_export_map())
+            );
+        }
+        if config.passthrough {
+            chain = chain.with(PassthroughMapper);
+        }
+        if config.shared {
+            chain = chain.with(SharedMapper);
+        }
+        if let Some(fallback) = &config.fallback {
+            chain = chain.with(FallbackMapper::new(fallback));
+        }
+        if config.sniff_json {
+            chain = chain.with(ContentMapper);
+        }
+        if config.feature_folders {
+            chain = chain.with(FeatureFolders);
+        }
+        // The textures are routed into the subfolders of the categories
+        // after adding the roots (e.g. "RP/textures/entity/combat")
+        if config.root_subfolders {
+            chain = chain.with(RootSubfolders::new(config.rules()));
+        }
+        if config.texture_routing {
+            chain = chain.with(TextureRouting);
+        }
+        if config.texture_atlases {
+            chain = chain.with(TextureAtlases);
+        }
+        if config.strip_order_prefixes {
+            chain = chain.with(OrderPrefixes);
+        }
+        if !config.rename.is_empty() {
+            chain = chain.with(RenameMapper::new(config.rename.clone()));
+        }
+        if config.texture_variants {
+            chain = chain.with(TextureVariants);
+        }
+        if config.flipbooks {
+            chain = chain.with(Flipbooks);
+        }
+        if config.sound_events {
+            chain = chain.with(SoundEvents);
+        }
+        if config.lang_tables {
+            chain = chain.with(LangTables);
+        }
+        if config.trade_tables {
+            chain = chain.with(TradeTables);
+        }
+        if config.loot_tables {
+            chain = chain.with(LootTables);
+        }
+        if config.markdown_dialogue {
+            chain = chain.with(MarkdownDialogue);
+        }
+        if config.recipe_shorthand {
+            chain = chain.with(RecipeShorthand);
+        }
+        if let Some(folders) = &config.umbrella_functions {
+            chain = chain.with(UmbrellaFunctions::new(folders.clone()));
+        }
+        if config.tick_annotations {
+            chain = chain.with(TickFunctions);
+        }
+        if config.typescript.is_some() {
+            chain = chain.with(TypeScript);
+        }
+        if let Some(bundle) = &config.bundle_scripts {
+            chain = chain.with(ScriptBundler::new(bundle.clone()));
+        }
+        if !config.script_dependencies.is_empty() {
+            chain = chain.with(ScriptManifest);
+        }
+        chain
+    }
+
+    /// Adds the mapper to the end of the chain.
+    pub fn with(mut self, mapper: impl Mapper + 'static) -> MapperChain {
+        self.mappers.push(Arc::new(mapper));
+        self
+    }
+
+    /// Returns the name of the rule that matches the file (e.g. the
+    /// extension) or None if no rule matches.
+    pub fn rule(&self, path: &Path) -> Option<String> {
+        self.target(path).map(|target| target.rule)
+    }
+
+    /// Returns the target of the pack file that the file is mapped to or None
+    /// if it can't be mapped.
+    pub fn target(&self, path: &Path) -> Option<TargetInfo> {
+        match self.map(path) {
+            MapResult::Mapped(target) => Some(target),
+            _ => None,
+        }
+    }
+
+    /// Returns the reason why the file can't be mapped.
+    pub fn skip_reason(&self, path: &Path) -> &'static str {
+        match self.map(path) {
+            MapResult::Skipped(reason) => reason,
+            _ => "None of the rules matches the file name.",
+        }
+    }
+}
+
+impl Mapper for MapperChain {
+    fn map(&self, path: &Path) -> MapResult {
+        for mapper in &self.mappers {
+            match mapper.map(path) {
+                MapResult::Unmatched => continue,
+                result => return result,
+            }
+        }
+        MapResult::Unmatched
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        self.mappers.iter().flat_map(|mapper| mapper.extensions()).collect()
+    }
+
+    fn group(&self, root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        for mapper in &self.mappers {
+            mapper.group(root_dir, files);
+        }
+    }
+}
+
+/// Lists the files of the roots of the data directory and maps them to the
+/// packs without copying anything.
+pub struct Planner {
+    working_dir: PathBuf,
+    mapper: MapperChain,
+    roots: Vec<String>,
+    options: WalkOptions,
+}
+
+impl Planner {
+    /// Creates a planner.
+    ///
+    /// # Arguments
+    /// - `working_dir` - the path to the working directory of regolith
+    /// - `mapper` - the mapper used for mapping the files
+    /// - `roots` - the list of paths, relative to the filter data path, that
+    ///   serve as the root of the source files
+    /// - `options` - the options that control how the directories are walked
+    pub fn new(
+        working_dir: &Path, mapper: MapperChain, roots: Vec<String>,
+        options: WalkOptions,
+    ) -> Planner {
+        Planner {
+            working_dir: working_dir.to_path_buf(),
+            mapper,
+            roots,
+            options,
+        }
+    }
+
+    /// Creates a planner with the roots, the rules and the options from the
+    /// config file.
+    pub fn from_config(working_dir: &Path, config: &Config) -> Planner {
+        Planner::new(
+            working_dir, MapperChain::from_config(config),
+            config.roots.clone(),
+            WalkOptions::from_config(config),
+        )
+    }
+
+    /// Returns the mapper used for mapping the files.
+    pub fn mapper(&self) -> &MapperChain {
+        &self.mapper
+    }
+
+    /// Lists and maps the files of all of the roots.
+    pub fn plan(&self) -> Result<Vec<PlannedFile>, CpfError> {
+        let mut result = Vec::new();
+        for root in &self.roots {
+            result.extend(self.plan_root(root)?);
+        }
+        Ok(result)
+    }
+
+    /// Maps a virtual list of files of a root without accessing the file
+    /// system (see `plan::plan_paths`).
+    ///
+    /// # Arguments
+    /// - `root` - the path relative to the filter data path that serves as
+    ///   the root of the files
+    /// - `paths` - the paths to the files relative to the root
+    pub fn plan_paths(
+        &self, root: &str, paths: &[PathBuf],
+    ) -> Vec<PlannedFile> {
+        let root_dir = self.working_dir.join(config::FILTER_DATA_PATH)
+            .join(root);
+        plan::plan_paths(&self.mapper, &root_dir, paths)
+    }
+
+    /// Lists and maps the files of a single root.
+    pub fn plan_root(
+        &self, root: &str,
+    ) -> Result<Vec<PlannedFile>, CpfError> {
+        plan::plan_root(
+            &self.working_dir, &self.mapper, root, &self.options
+        )
+    }
+}
+
+/// Copies the files from the data directory to the packs.
+pub struct Executor {
+    working_dir: PathBuf,
+    options: CopyOptions,
+}
+
+impl Executor {
+    /// Creates an executor.
+    ///
+    /// # Arguments
+    /// - `working_dir` - the path to the working directory of regolith
+    /// - `options` - the options that control how the files are copied
+    pub fn new(working_dir: &Path, options: CopyOptions) -> Executor {
+        Executor { working_dir: working_dir.to_path_buf(), options }
+    }
+
+    /// Plans and copies the files of all of the roots, resolves the
+    /// conflicts and records the manifest.
+    ///
+    /// # Arguments
+    /// - `mapper` - the mapper used for mapping the files
+    /// - `roots` - the list of paths, relative to the filter data path, that
+    ///   serve as the root of the source files
+    pub fn execute(
+        &self, mapper: &MapperChain, roots: &[String],
+    ) -> Result<(), CpfError> {
+        copy::copy_files_by_roots(
+            &self.working_dir, mapper, roots, &self.options
+        )
+    }
+
+    /// Lists and maps the files of the roots and resolves the conflicts
+    /// without copying anything. The operations can be inspected or modified
+    /// and then copied with `execute_plan`. Unlike `execute`, the "before"
+    /// hooks aren't run.
+    ///
+    /// # Arguments
+    /// - `mapper` - the mapper used for mapping the files
+    /// - `roots` - the list of paths, relative to the filter data path, that
+    ///   serve as the root of the source files
+    pub fn plan(
+        &self, mapper: &MapperChain, roots: &[String],
+    ) -> Result<Vec<CopyOperation>, CpfError> {
+        copy::plan_operations(
+            &self.working_dir, mapper, roots, &self.options
+        )
+    }
+
+    /// Copies the files of the operations, records the manifest and runs the
+    /// "after" hooks.
+    pub fn execute_plan(
+        &self, operations: &[CopyOperation],
+    ) -> Result<(), CpfError> {
+        copy::execute_operations(&self.working_dir, operations, &self.options)
+    }
+
+    /// Copies a single file.
+    pub fn copy_file(
+        &self, operation: &CopyOperation,
+    ) -> Result<CopyResult, CpfError> {
+        copy::copy_file(&self.working_dir, operation, &self.options)
+    }
+}
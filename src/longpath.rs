@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+/// The longest path that is safe to use on Windows without the extended
+/// length path prefix.
+pub static WINDOWS_MAX_PATH: usize = 260;
+
+/// Returns the path that can be used for accessing the file even if it's
+/// longer than the MAX_PATH limit of Windows. On Windows the long paths are
+/// converted to the absolute extended-length paths (prefixed with `\\?\`).
+/// The short paths and the paths on the other platforms are returned
+/// unchanged.
+#[cfg(windows)]
+pub fn extended_path(path: &Path) -> PathBuf {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    // The extended-length paths aren't normalized by Windows, the absolute
+    // path can't contain "." and ".." or forward slashes
+    let absolute = match std::path::absolute(path) {
+        Ok(absolute) => absolute,
+        Err(_) => return path.to_path_buf(),
+    };
+    // Leave some room for the names of the temporary files
+    if absolute.as_os_str().len() < WINDOWS_MAX_PATH - 20 {
+        return path.to_path_buf();
+    }
+    let prefix = match absolute.components().next() {
+        Some(Component::Prefix(prefix)) => prefix.kind(),
+        _ => return absolute,
+    };
+    let mut result = OsString::new();
+    match prefix {
+        Prefix::Disk(_) => {
+            result.push(r"\\?\");
+            result.push(absolute.as_os_str());
+        },
+        Prefix::UNC(_, _) => {
+            // \\server\share\... -> \\?\UNC\server\share\...
+            result.push(r"\\?\UNC\");
+            let s = absolute.to_string_lossy();
+            result.push(s.trim_start_matches('\\'));
+        },
+        // Already an extended-length path or a device path
+        _ => return absolute,
+    }
+    PathBuf::from(result)
+}
+
+/// Returns the path that can be used for accessing the file even if it's
+/// longer than the MAX_PATH limit of Windows. The paths are only changed on
+/// Windows.
+#[cfg(not(windows))]
+pub fn extended_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// The typical path to the com.mojang folder of Minecraft on Windows, where
+/// the packs are installed. The user name has 20 characters.
+static COM_MOJANG_DIR: &str = "C:\\Users\\____________________\\AppData\\\
+    Local\\Packages\\Microsoft.MinecraftUWP_8wekyb3d8bbwe\\LocalState\\games\\\
+    com.mojang";
+
+/// The assumed length of the name of the folder of the installed pack.
+const PACK_FOLDER_LENGTH: usize = 32;
+
+/// Returns the length of the path of the file when the pack is installed in
+/// the development pack folders of com.mojang on Windows.
+///
+/// # Arguments
+/// - `packs_dir` - the name of the folder of the packs in com.mojang (e.g.
+///   "development_behavior_packs")
+/// - `path_in_pack` - the path of the file relative to the pack
+pub fn installed_path_length(packs_dir: &str, path_in_pack: &Path) -> usize {
+    // The separators between the parts of the path
+    COM_MOJANG_DIR.len() + 1 + packs_dir.len() + 1 + PACK_FOLDER_LENGTH + 1
+        + path_in_pack.as_os_str().len()
+}
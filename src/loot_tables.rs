@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::mapping::{compiled_target, MapResult, Mapper};
+use crate::recipes::is_valid_identifier;
+use crate::spreadsheet::{columns, read_rows, Row};
+
+/// The name of the rule of the loot tables generated by the LootTables.
+pub static LOOT_TABLE_RULE: &str = "(loot table)";
+
+/// The extension of the spreadsheets with the loot.
+static TABLE_EXTENSION: &str = ".lt.csv";
+
+/// The extension of the generated loot tables.
+static TARGET_EXTENSION: &str = ".lt.json";
+
+/// The directory of the loot tables in the packs.
+static LOOT_TABLES_DIR: &str = "BP/loot_tables";
+
+/// The columns of the spreadsheets. The "pool" is required.
+static COLUMNS: &[&str] = &[
+    "pool", "rolls", "type", "name", "weight", "quality", "count", "data",
+    "functions",
+];
+
+/// The types of the entries of the pools.
+static ENTRY_TYPES: &[&str] = &["item", "loot_table", "empty"];
+
+/// Compiles the spreadsheets with the loot (the ".lt.csv" files) into the
+/// loot tables of the behavior pack (e.g. "chest.lt.csv" ->
+/// "BP/loot_tables/chest.lt.json"). Every row of the spreadsheet is an entry
+/// and the entries are grouped into the pools by the "pool" column.
+pub struct LootTables;
+
+impl Mapper for LootTables {
+    fn map(&self, path: &Path) -> MapResult {
+        compiled_target(
+            path, TABLE_EXTENSION, LOOT_TABLES_DIR, TARGET_EXTENSION,
+            LOOT_TABLE_RULE,
+        )
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        vec![TABLE_EXTENSION.to_string()]
+    }
+}
+
+/// Parses the functions of the entry: a JSON array of the functions (e.g.
+/// `[{"function": "set_damage", "damage": 0.5}]`) or the names of the
+/// functions without arguments separated with the semicolons (e.g.
+/// "enchant_randomly;furnace_smelt").
+fn parse_functions(value: &str) -> Result<Vec<serde_json::Value>, String> {
+    if value.starts_with('[') {
+        let functions: Vec<serde_json::Value> = serde_json::from_str(value)
+            .map_err(|e| format!("The functions aren't valid JSON: {}", e))?;
+        return match functions.iter().all(|f| f["function"].is_string()) {
+            true => Ok(functions),
+            false => Err("A function doesn't have the \"function\".".into()),
+        };
+    }
+    value.split(';').map(str::trim).filter(|name| !name.is_empty())
+        .map(|name| match is_valid_identifier(name) {
+            true => Ok(serde_json::json!({"function": name})),
+            false => Err(format!("\"{}\" isn't a valid function.", name)),
+        })
+        .collect()
+}
+
+/// Returns the entry of the row.
+fn parse_entry(row: &Row) -> Result<serde_json::Value, String> {
+    let entry_type = match row.get("type") {
+        "" => "item",
+        entry_type if ENTRY_TYPES.contains(&entry_type) => entry_type,
+        entry_type => return Err(format!(
+            "Unknown type of the entry \"{}\".", entry_type
+        )),
+    };
+    let mut entry = serde_json::Map::new();
+    entry.insert("type".into(), entry_type.into());
+    let name = row.get("name");
+    match entry_type {
+        "empty" if !name.is_empty() => {
+            return Err("The empty entry has a name.".into());
+        },
+        "empty" => {},
+        _ if name.is_empty() => {
+            return Err("The entry doesn't have a name.".into());
+        },
+        "item" if !is_valid_identifier(name) => {
+            return Err(format!("\"{}\" isn't a valid identifier.", name));
+        },
+        _ => { entry.insert("name".into(), name.into()); },
+    }
+    match row.integer("weight")? {
+        Some(0) => return Err("The \"weight\" has to be positive.".into()),
+        Some(weight) => { entry.insert("weight".into(), weight.into()); },
+        None => {},
+    }
+    match row.get("quality") {
+        "" => {},
+        quality => match quality.parse::<i64>() {
+            Ok(quality) => { entry.insert("quality".into(), quality.into()); },
+            Err(_) => {
+                return Err("The \"quality\" isn't an integer.".into());
+            },
+        },
+    }
+    let mut functions = Vec::new();
+    if let Some(count) = row.range("count")? {
+        functions.push(
+            serde_json::json!({"function": "set_count", "count": count})
+        );
+    }
+    if let Some(data) = row.integer("data")? {
+        functions.push(
+            serde_json::json!({"function": "set_data", "data": data})
+        );
+    }
+    functions.extend(parse_functions(row.get("functions"))?);
+    if !functions.is_empty() {
+        if entry_type != "item" {
+            return Err("Only the items can have functions.".into());
+        }
+        entry.insert("functions".into(), functions.into());
+    }
+    Ok(entry.into())
+}
+
+/// Compiles the rows of the spreadsheet (with the header) into the loot
+/// table. The empty rows are skipped.
+fn compile(rows: &[Vec<String>]) -> Result<serde_json::Value, String> {
+    let header = rows.first().ok_or("The spreadsheet is empty.")?;
+    let columns = columns(header, COLUMNS, &["pool"])?;
+    // The rolls and the entries by the numbers of the pools
+    type Pool = (Option<serde_json::Value>, Vec<serde_json::Value>);
+    let mut pools: BTreeMap<u64, Pool> = BTreeMap::new();
+    for (i, values) in rows.iter().enumerate().skip(1) {
+        let row = Row { columns: &columns, values };
+        if row.is_empty() {
+            continue;
+        }
+        let add_row = |e: String| format!("Row {}: {}", i + 1, e);
+        let number = row.integer("pool").map_err(add_row)?
+            .ok_or_else(|| add_row("The \"pool\" is empty.".into()))?;
+        let rolls = row.range("rolls").map_err(add_row)?;
+        let pool = pools.entry(number).or_default();
+        match (&pool.0, rolls) {
+            (Some(a), Some(b)) if *a != b => return Err(add_row(format!(
+                "The pool {} already has {} rolls.", number, a
+            ))),
+            (None, Some(rolls)) => pool.0 = Some(rolls),
+            _ => {},
+        }
+        pool.1.push(parse_entry(&row).map_err(add_row)?);
+    }
+    if pools.is_empty() {
+        return Err("The spreadsheet doesn't have any entries.".into());
+    }
+    let pools: Vec<serde_json::Value> = pools.into_values()
+        .map(|(rolls, entries)| serde_json::json!({
+            "rolls": rolls.unwrap_or_else(|| 1.into()),
+            "entries": entries,
+        }))
+        .collect();
+    Ok(serde_json::json!({"pools": pools}))
+}
+
+/// Generates the loot table from the spreadsheet.
+///
+/// # Arguments
+/// - `source` - the path to the ".lt.csv" file
+pub fn loot_table_contents(source: &Path) -> Result<Vec<u8>, String> {
+    let json = compile(&read_rows(source)?)?;
+    Ok(format!("{:#}\n", json).into_bytes())
+}
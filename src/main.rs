@@ -1,183 +1,607 @@
-use std::collections::HashMap;
 use std::error::Error;
-use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-type ExtExportMap = HashMap<String, String>;
-static FILTER_DATA_PATH: &str = "data/custom_project";
-static EXPORT_FILES_MAP: &str = "data/custom_project/config.json";
+mod cli;
 
-/// Gets the target locations for copying the project files to the RP adn BP
-/// based on the data in the map.
+use custom_project::{
+    cache, changed, check, config, copy, doctor, export, filelist, import,
+    lang, manifest, mapping, plan, report, scaffold, scoreboard, timings,
+    watch,
+    CpfError, Executor, MapperChain, Planner,
+};
+use cache::HashCache;
+use cli::Args;
+use config::{Config, CopyMode, FILTER_DATA_PATH};
+use copy::CopyOptions;
+use plan::WalkOptions;
+use timings::Timings;
+
+/// Loads the options that control how the files are copied from the
+/// command line arguments.
+///
+/// # Arguments
+/// - `args` - the command line arguments
+/// - `working_dir` - the path to the working directory of regolith
+fn copy_options(
+    args: &Args, working_dir: &Path
+) -> Result<CopyOptions, Box<dyn Error>> {
+    let jobs = match args.value("jobs") {
+        Some(jobs) => jobs.parse::<usize>().map_err(
+            |_| format!("Invalid --jobs value: \"{}\"", jobs)
+        )?,
+        None => 0,
+    };
+    Ok(CopyOptions {
+        diff: args.flag("diff"),
+        interactive: args.flag("interactive"),
+        jobs,
+        incremental: !args.flag("no-incremental"),
+        sync: args.flag("sync"),
+        cache: match args.flag("no-cache") {
+            true => None,
+            false => Some(Arc::new(HashCache::load(
+                &HashCache::default_path(working_dir)
+            ))),
+        },
+        changed: match args.value("changed-since") {
+            Some(rev) => Some(Arc::new(changed::changed_since(
+                &config::project_dir(working_dir), rev
+            )?)),
+            None => None,
+        },
+        files: match args.value("files-from") {
+            Some(source) => Some(Arc::new(
+                filelist::read_file_list(source)?
+            )),
+            None => None,
+        },
+        timings: match args.flag("timings") {
+            true => Some(Arc::new(Timings::default())),
+            false => None,
+        },
+        ..CopyOptions::default()
+    })
+}
+
+/// Returns the copy options with the settings from the config file applied.
 ///
 /// # Arguments
-/// - `fp` - the path to the file that needs to be copied relative to the
-///   data directory.
-fn get_target_path_from_hash_map(
-        fp: &Path, map: &ExtExportMap
-) -> Option<PathBuf> {
-    let file_name = fp.file_name()?.to_str()?;
-    // Find matching file extension and the export target
-    let (extension, target) = {
-        let result = map.iter()
-            .find(|(k, _)| file_name.ends_with(k.as_str()))?;
-        (result.0.as_str(), result.1.as_str())
-    };
-    // If the file name is just an extension or the file name is and underscore
-    // with extension (e.g _.bpe.json) then use the parent fodler as the
-    // actual file name.
-    let (base_name, base_path): (String, PathBuf);
-    if file_name == extension || file_name == &format!("_{}", extension) {
-        base_name = fp.parent()?.file_name()?.to_str()?.to_string()
-            + &extension;
-        base_path = fp.parent()?.parent()?.to_path_buf();
-    } else {
-        base_name = fp.file_name()?.to_str()?.to_string();
-        base_path = fp.parent()?.to_path_buf();
-    }
-    // Fix the path separators (e.g "/" -> "\\")
-    let target: PathBuf = PathBuf::from(target).iter().collect();
-    // Return
-    Some(target.join(base_path).join(base_name))
-}
-
-
-/// Recursively copies the files starting from the curr_dir with export paths
-/// relative to the root_dir generated based on the data in the
-/// export_map. This function is used in copy_files_by_roots function.
-/// 
+/// - `options` - the options loaded from the command line arguments
+/// - `config` - the config of the filter
+/// - `working_dir` - the path to the working directory of regolith
+fn apply_config(
+    options: &CopyOptions, config: &Config, working_dir: &Path
+) -> CopyOptions {
+    let mut mode = config.mode;
+    if mode == CopyMode::Symlink && config.symlinks_outside_regolith_only
+        && copy::in_regolith_tmp(working_dir)
+    {
+        println!(
+            "The symlink mode is disabled in the temporary directory of \
+            Regolith. Copying the files instead."
+        );
+        mode = CopyMode::Copy;
+    }
+    // The --jobs option overrides the config file
+    let jobs = match options.jobs {
+        0 => config.jobs,
+        jobs => jobs,
+    };
+    CopyOptions {
+        mode,
+        jobs,
+        reflink: config.reflink,
+        dedupe: config.dedupe,
+        overwrite: config.overwrite,
+        conflicts: config.conflicts,
+        walk: WalkOptions { jobs, ..WalkOptions::from_config(config) },
+        preserve_metadata: config.preserve_metadata,
+        plugins: Arc::new(config.plugins.clone()),
+        hooks: config.hooks.clone(),
+        bom: config.bom,
+        line_endings: config.line_endings,
+        vanilla_overrides: config.vanilla_overrides.clone(),
+        preprocess_functions: config.preprocess_functions,
+        constants: config.constants.clone(),
+        typescript: config.typescript.clone(),
+        bundle_scripts: config.bundle_scripts.clone(),
+        script_dependencies: config.script_dependencies.clone(),
+        ..options.clone()
+    }
+}
+
+/// Runs the filter - copies the files from the data directory to the packs.
+///
 /// # Arguments
-/// - `curr_dir` - the directory to copy the files from
-/// - `root_dir` - the root directory to copy the files to in most cases it
-///     will be the same as the curr_dir, it's used for the recursive call
-/// - `working_dir` - the working directory of the script, the target paths
-///     of the exporter are relative to this directory
-/// - `export_map` - the map of file extensions and rules to generate the
-///     export paths
-fn copy_files(
-    curr_dir: &Path, root_dir: &Path, working_dir: &Path,
-    export_map: &ExtExportMap,
+/// - `working_dir` - the path to the working directory of regolith
+/// - `options` - the options that control how the files are copied
+fn run(
+    working_dir: &Path, options: &CopyOptions
 ) -> Result<(), Box<dyn Error>> {
-    // Walk files in current directory
-    let dir = match fs::read_dir(curr_dir) {
-        Ok(dir) => dir,
-        Err(e) => {
-            eprintln!("Failed to read directory: {}", curr_dir.display());
-            return Err(Box::new(e));
+    let config = Config::load(working_dir)?;
+    let options = apply_config(options, &config, working_dir);
+    // Copy the files from the data directory to packs
+    println!("Copying files to packs...");
+    // The errors make the filter fail (e.g. the "error" overwrite policy)
+    Executor::new(working_dir, options.clone())
+        .execute(&MapperChain::from_config(&config), &config.roots)?;
+    if let Some(timings) = &options.timings {
+        timings.print();
+    }
+    Ok(())
+}
+
+/// Handles the `new <kind> <name>` subcommand which generates the
+/// conventional file set for new content in the data directory.
+fn cmd_new(args: &Args) -> Result<(), Box<dyn Error>> {
+    let (kind, name) = match args.positional.as_slice() {
+        [_, kind, name] => (kind, name),
+        _ => {
+            return Err(format!(
+                "Usage: custom-project new <{}> <name> [--root <root>] \
+                [--namespace <namespace>] [--working-dir <path>]",
+                scaffold::CONTENT_KINDS.join("|")
+            ).into());
         }
     };
-    for fp in dir {
-        let fp = fp?.path();
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let config = Config::load(&working_dir)?;
+    let namespace = args.value("namespace")
+        .or(config.namespace.as_deref())
+        .ok_or(
+            "The project namespace is not defined. Add the \"namespace\" \
+            property to the config file or use the --namespace option."
+        )?;
+    let root = match args.value("root") {
+        Some(root) => root,
+        None => config.roots.first().ok_or(
+            "The config file doesn't define any roots. Use the --root option."
+        )?,
+    };
+    scaffold::new_content(
+        &working_dir, root, &config.rules(), kind, namespace, name
+    )
+}
 
-        // Directory - recurse
-        if fp.is_dir() {
-            copy_files(&fp, root_dir, working_dir, export_map)?;
-            continue;
+/// Handles the `plan` subcommand which prints a table of every source file,
+/// the rule that matched it and its computed target without copying
+/// anything. The `--category <prefix>` option limits the table to the
+/// targets that start with the prefix (e.g. "BP/entities").
+fn cmd_plan(args: &Args) -> Result<(), Box<dyn Error>> {
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let config = Config::load(&working_dir)?;
+    let data_path = working_dir.join(FILTER_DATA_PATH);
+    let category = args.value("category").map(|c| {
+        // Fix the path separators (e.g "/" -> "\\")
+        PathBuf::from(c).iter().collect::<PathBuf>()
+    });
+    let rows: Vec<[String; 3]> = Planner::from_config(&working_dir, &config)
+        .plan()?
+        .into_iter()
+        .filter(|file| match (&category, file.target_path()) {
+            (None, _) => true,
+            (Some(category), Some(target)) => target.starts_with(category),
+            (Some(_), None) => false,
+        })
+        .map(|file| [
+            file.source.strip_prefix(&data_path)
+                .unwrap_or(&file.source).display().to_string(),
+            file.rule().unwrap_or("-").to_string(),
+            match file.target_path() {
+                Some(target) => target.display().to_string(),
+                None => "(unmapped)".to_string(),
+            },
+        ])
+        .collect();
+    // Print the table with the columns aligned
+    let header = ["SOURCE", "RULE", "TARGET"];
+    let mut widths = header.map(|h| h.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
         }
+    }
+    for row in std::iter::once(header.map(|h| h.to_string())).chain(rows) {
+        println!(
+            "{:<w0$}  {:<w1$}  {}",
+            row[0], row[1], row[2], w0=widths[0], w1=widths[1]
+        );
+    }
+    Ok(())
+}
+
+/// Handles the `check` subcommand which runs all of the validation passes
+/// over the data directory without copying anything. Returns an error if
+/// any of the passes found an error.
+fn cmd_check(args: &Args) -> Result<(), Box<dyn Error>> {
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let config = Config::load(&working_dir)?;
+    let planner = Planner::from_config(&working_dir, &config);
+    let files = planner.plan()?;
+    let problems = check::check(&files, planner.mapper(), &config);
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    let errors = problems.iter()
+        .filter(|p| p.severity == check::Severity::Error)
+        .count();
+    println!(
+        "Checked {} files: {} errors, {} warnings.",
+        files.len(), errors, problems.len() - errors
+    );
+    if errors > 0 {
+        return Err("The check failed.".into());
+    }
+    Ok(())
+}
 
-        // Not a directory - find the target and copy file
-        // Find the tartet
-        let root_len = root_dir.components().count();
-        let short_fp = fp.components().skip(root_len).collect::<PathBuf>();
-        let target_path = match get_target_path_from_hash_map(
-            &short_fp, export_map
-        ) {
-            Some(p) => working_dir.join(p),
-            None => {
-                eprintln!(
-                    "Unable to map \"{}\" to the pack file. Skipped.",
-                    fp.display()
-                );
-                continue;
+/// Handles the `scores` subcommand which prints the scoreboard objectives
+/// and the tags of the functions and the scripts of the behavior pack with
+/// the places where they're added and used. The objectives that are used
+/// but never added are reported as warnings.
+fn cmd_scores(args: &Args) -> Result<(), Box<dyn Error>> {
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let config = Config::load(&working_dir)?;
+    let files = Planner::from_config(&working_dir, &config).plan()?;
+    let report = scoreboard::scoreboard_report(&files);
+    let location = |(source, line): &scoreboard::Location| {
+        format!("{}:{}", source.display(), line)
+    };
+    for (title, names) in [
+        ("Objectives", &report.objectives), ("Tags", &report.tags)
+    ] {
+        println!("{}:", title);
+        for (name, usage) in names {
+            println!("  {}", name);
+            for (kind, locations) in [
+                ("added", &usage.added), ("used", &usage.used)
+            ] {
+                for l in locations {
+                    println!("    {}: {}", kind, location(l));
+                }
             }
-        };
+        }
+    }
+    for problem in report.problems() {
+        problem.emit();
+    }
+    Ok(())
+}
 
-        // Copy file
-        if target_path.exists() {
-            eprintln!(
-                "WARNING! File \"{}\" already exists. Skipped.",
-                target_path.display()
+/// Handles the `doctor` subcommand which diagnoses the common setup
+/// problems. Returns an error if any of the diagnostics failed.
+fn cmd_doctor(args: &Args) -> Result<(), Box<dyn Error>> {
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let errors = doctor::doctor(&working_dir);
+    if errors > 0 {
+        return Err(format!("Found {} error(s).", errors).into());
+    }
+    Ok(())
+}
+
+/// Handles the `import <rp> <bp>` subcommand which restructures an existing
+/// pack pair into the layout of the data directory.
+fn cmd_import(args: &Args) -> Result<(), Box<dyn Error>> {
+    let (rp, bp) = match args.positional.as_slice() {
+        [_, rp, bp] => (Path::new(rp), Path::new(bp)),
+        _ => {
+            return Err(
+                "Usage: custom-project import <rp> <bp> [--root <root>] \
+                [--working-dir <path>]".into()
             );
-            continue;
         }
-        fs::create_dir_all(target_path.parent().unwrap())?;
-        match fs::copy(&fp, &target_path) {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!(
-                    "WARNING! Unable to copy \"{}\" to \"{}\": {}",
-                    fp.display(), target_path.display(), e
-                );
-            }
+    };
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let config = Config::load(&working_dir)?;
+    let root = match args.value("root") {
+        Some(root) => root,
+        None => config.roots.first().ok_or(
+            "The config file doesn't define any roots. Use the --root option."
+        )?,
+    };
+    import::import(
+        &working_dir, &config.rules(), root, &[("RP", rp), ("BP", bp)]
+    )
+}
+
+/// Handles the `lang <export|import> <table>` subcommand which converts
+/// between the .lang files of the data directory and a single CSV (or TSV)
+/// table with a column for every language, so the translations can be
+/// edited in the spreadsheets.
+fn cmd_lang(args: &Args) -> Result<(), Box<dyn Error>> {
+    let (action, table) = match args.positional.as_slice() {
+        [_, action, table] if action == "export" || action == "import" => {
+            (action.as_str(), Path::new(table))
+        },
+        _ => {
+            return Err(
+                "Usage: custom-project lang <export|import> <table> \
+                [--root <root>] [--working-dir <path>]".into()
+            );
         }
+    };
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let config = Config::load(&working_dir)?;
+    let files = Planner::from_config(&working_dir, &config).plan()?;
+    if action == "export" {
+        let keys = lang::export_table(&files, table)?;
+        println!("Exported {} keys to \"{}\".", keys, table.display());
+        return Ok(());
+    }
+    let root = match args.value("root") {
+        Some(root) => root,
+        None => config.roots.first().ok_or(
+            "The config file doesn't define any roots. Use the --root option."
+        )?,
+    };
+    let default_dir = working_dir.join(FILTER_DATA_PATH).join(root);
+    for path in lang::import_table(&files, table, &default_dir)? {
+        println!("Updated \"{}\"", path.display());
     }
-    return Ok(());
+    Ok(())
 }
 
+/// Handles the `watch` subcommand which continuously copies the changed
+/// files from the data directory to the packs. The `--interval <ms>` option
+/// sets the time without changes after which the changes are copied.
+fn cmd_watch(args: &Args) -> Result<(), Box<dyn Error>> {
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let interval = match args.value("interval") {
+        Some(ms) => ms.parse::<u64>().map_err(
+            |_| format!("Invalid --interval value: \"{}\"", ms)
+        )?,
+        None => 500,
+    };
+    let config = Config::load(&working_dir)?;
+    let options = apply_config(
+        &copy_options(args, &working_dir)?, &config, &working_dir
+    );
+    watch::watch(
+        &working_dir, &config, Duration::from_millis(interval), &options
+    )
+}
 
-/// Copies the files from the data directory of the project files path to the
-/// target directories which are found based on the export map.
-///
-/// # Arguments
-/// - `working_dir` - the path to the working directory of regolith
-/// - `export_map` - the map that contains the mapping of file extensions to
-///     the target directories.
-/// - `roots` - the list of paths, relative to the filter data path, that
-///     serve as the root of the source files to be copied to RP and BP.
-fn copy_files_by_roots(
-    working_dir: &Path, export_map: &ExtExportMap, roots: &Vec<String>,
-) -> Result<(), Box<dyn Error>>{
-    for root in roots {
-        let root = working_dir.join(FILTER_DATA_PATH).join(root);
-        println!("Copying files from \"{}\"", root.display());
-        copy_files(&root, &root, working_dir, export_map)?;
+/// Handles the `explain <pack file>` subcommand which finds the source file
+/// that produced the pack file in the last run (recorded in the manifest)
+/// and the rule that matched it. If the pack file isn't in the manifest, the
+/// source files currently mapped to the pack file are listed instead.
+fn cmd_explain(args: &Args) -> Result<(), Box<dyn Error>> {
+    let pack_file = match args.positional.as_slice() {
+        [_, pack_file] => pack_file,
+        _ => {
+            return Err(
+                "Usage: custom-project explain <pack file> \
+                [--working-dir <path>]".into()
+            );
+        }
+    };
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let config = Config::load(&working_dir)?;
+    // Fix the path separators (e.g "/" -> "\\") and make the path relative
+    // to the working directory
+    let pack_file: PathBuf = PathBuf::from(pack_file).iter().collect();
+    let pack_file = pack_file.strip_prefix(&working_dir)
+        .unwrap_or(&pack_file).to_path_buf();
+    let manifest = manifest::Manifest::load(&working_dir)?;
+    if let Some(entry) = manifest.files.get(&pack_file) {
+        println!(
+            "\"{}\" was produced in the last run by:", pack_file.display()
+        );
+        println!("  source: \"{}\"", entry.source.display());
+        match config.rules().get(&entry.rule) {
+            Some(target) => println!(
+                "  rule: \"{}\" -> \"{}\"", entry.rule, target
+            ),
+            None => println!(
+                "  rule: \"{}\" (no longer in the config)", entry.rule
+            ),
+        }
+        println!("  hash: {:016x}", entry.hash);
+        return Ok(());
+    }
+    let files = Planner::from_config(&working_dir, &config).plan()?;
+    let sources: Vec<&plan::PlannedFile> = files.iter()
+        .filter(|f| f.target_path() == Some(&pack_file))
+        .collect();
+    if sources.is_empty() {
+        let note = if working_dir.join(&pack_file).exists() {
+            " The file is not generated by the filter."
+        } else {
+            ""
+        };
+        return Err(format!(
+            "No file from the data directory is mapped to \"{}\".{}",
+            pack_file.display(), note
+        ).into());
     }
-    return Ok(());
+    println!(
+        "\"{}\" is not in the manifest of the last run but it would be \
+        produced by:", pack_file.display()
+    );
+    let rules = config.rules();
+    for file in &sources {
+        let rule = file.rule().unwrap_or_default();
+        println!("  source: \"{}\"", file.source.display());
+        match rules.get(rule) {
+            Some(target) => println!(
+                "  rule: \"{}\" -> \"{}\"", rule, target
+            ),
+            None => println!("  rule: \"{}\"", rule),
+        }
+    }
+    if sources.len() > 1 {
+        println!(
+            "Multiple files are mapped to the same target. By default only \
+            the first one is copied."
+        );
+    }
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    let working_dir = match args.len() {
-        1 => PathBuf::from(""),
-        _ => PathBuf::from(&args[1]),
-    };
-    // Load JSON from EXPORT_FILES_MAP
-    let export_map_path = working_dir.join(EXPORT_FILES_MAP);
-    let config: serde_json::Value = match fs::read_to_string(export_map_path) {
-        Ok(s) => serde_json::from_str(&s)?,
-        Err(e) => {
-            eprintln!("Unable to read \"{}\": {}", EXPORT_FILES_MAP, e);
-            return Ok(());
+/// Handles the `map-path <path>` subcommand which prints the target of a
+/// single path (relative to the root) without accessing the data directory.
+fn cmd_map_path(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = match args.positional.as_slice() {
+        [_, path] => Path::new(path),
+        _ => {
+            return Err(
+                "Usage: custom-project map-path <path> [--working-dir <path>]"
+                .into()
+            );
         }
     };
-    // Get extensions_map from the config
-    let export_map: ExtExportMap = match config["extensions_map"].as_object() {
-        Some(m) =>  m.iter()
-        .map(|(k, v)| (k.to_string(), v.as_str().unwrap().to_string()))
-        .collect(),
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let config = Config::load(&working_dir)?;
+    let mapper = MapperChain::from_config(&config);
+    match mapper.target(path) {
+        Some(target) => println!("{}", target.path.display()),
         None => {
-            eprintln!(
-                "Failed to parse \"extensions_map\" property in config \
-                file: \"{}\"", EXPORT_FILES_MAP
+            return Err(CpfError::Mapping {
+                path: path.to_path_buf(),
+                reason: mapper.skip_reason(path).to_string(),
+            }.into());
+        }
+    }
+    Ok(())
+}
+
+/// Handles the `--list-mappings` option which prints the mapping rules from
+/// the config file (or the built-in rules with `--defaults`). The
+/// `--markdown` option prints them as a Markdown table for the
+/// documentation.
+fn cmd_list_mappings(args: &Args) -> Result<(), Box<dyn Error>> {
+    let export_map = match args.flag("defaults") {
+        true => mapping::default_export_map(),
+        false => {
+            let working_dir = PathBuf::from(
+                args.value("working-dir").unwrap_or("")
             );
-            return Ok(())
+            Config::load(&working_dir)?.rules()
         }
     };
-    // Get roots from the config
-    let roots: Vec<String> = match config["roots"].as_array() {
-        Some(r) => r.iter().map(|v| v.as_str().unwrap().to_string()).collect(),
-        None => {
-            eprintln!(
-                "Failed to parse \"roots\" property in config file: \"{}\"",
-                EXPORT_FILES_MAP);
-                return Ok(())
-            }
-        };
-    // Copy the files from the data directory to packs
-    println!("Copying files to packs...");
-    match copy_files_by_roots(&working_dir, &export_map, &roots) {
-        Ok(_) => {}
-        Err(err) => eprintln!("{}", err),
+    let rules = mapping::list_rules(&export_map);
+    if args.flag("markdown") {
+        println!("| Extension | Target | Description |");
+        println!("|-----------|--------|-------------|");
+        for rule in &rules {
+            println!(
+                "| `{}` | `{}` | {} |", rule.extension, rule.target,
+                rule.description.as_deref().unwrap_or("")
+            );
+        }
+        return Ok(());
+    }
+    let width = rules.iter()
+        .map(|rule| rule.extension.chars().count())
+        .chain(std::iter::once("EXTENSION".len()))
+        .max()
+        .unwrap_or(0);
+    let target_width = rules.iter()
+        .map(|rule| rule.target.chars().count())
+        .chain(std::iter::once("TARGET".len()))
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{:<w$}  {:<tw$}  DESCRIPTION", "EXTENSION", "TARGET",
+        w=width, tw=target_width
+    );
+    for rule in &rules {
+        println!(
+            "{:<w$}  {:<tw$}  {}", rule.extension, rule.target,
+            rule.description.as_deref().unwrap_or("-"),
+            w=width, tw=target_width
+        );
+    }
+    Ok(())
+}
+
+/// Handles the `clean` subcommand which removes all of the targets produced
+/// by the previous run (recorded in the manifest).
+fn cmd_clean(args: &Args) -> Result<(), Box<dyn Error>> {
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let manifest = manifest::Manifest::load(&working_dir)?;
+    for target in manifest.files.keys() {
+        manifest::remove_target(&working_dir, target);
+    }
+    manifest::Manifest::default().save(&working_dir)?;
+    println!("Removed {} targets.", manifest.files.len());
+    Ok(())
+}
+
+/// Handles the `export` subcommand which packages the RP and BP into the
+/// .mcpack files (or a single .mcaddon file with `--addon`).
+fn cmd_export(args: &Args) -> Result<(), Box<dyn Error>> {
+    let working_dir = PathBuf::from(args.value("working-dir").unwrap_or(""));
+    let out_dir = args.value("out").map(PathBuf::from)
+        .unwrap_or_else(|| working_dir.clone());
+    // The archives are named after the project by default
+    let project_dir = config::project_dir(&working_dir);
+    let project_dir = match project_dir.as_os_str().is_empty() {
+        true => PathBuf::from("."),
+        false => project_dir,
+    };
+    let project_name = std::fs::canonicalize(&project_dir)
+        .unwrap_or(project_dir)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned());
+    let name = match args.value("name") {
+        Some(name) => name.to_string(),
+        None => project_name.unwrap_or_else(|| "pack".to_string()),
+    };
+    for archive in export::export(
+        &working_dir, &out_dir, &name, args.flag("addon")
+    )? {
+        println!("Exported \"{}\"", archive.display());
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let raw_args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match Args::parse(&raw_args, &[
+        "working-dir", "root", "namespace", "category", "interval",
+        "jobs", "changed-since", "out", "name", "files-from",
+    ]) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    // Regolith shows the output of the filters, so the diagnostics are
+    // tagged with their severity and location
+    report::set_structured(
+        args.flag("structured") || report::under_regolith()
+    );
+    let result = match args.positional.first().map(|s| s.as_str()) {
+        _ if args.flag("list-mappings") => cmd_list_mappings(&args),
+        Some("new") => cmd_new(&args),
+        Some("plan") => cmd_plan(&args),
+        Some("check") => cmd_check(&args),
+        Some("scores") => cmd_scores(&args),
+        Some("doctor") => cmd_doctor(&args),
+        Some("import") => cmd_import(&args),
+        Some("lang") => cmd_lang(&args),
+        Some("watch") => cmd_watch(&args),
+        Some("explain") => cmd_explain(&args),
+        Some("map-path") => cmd_map_path(&args),
+        Some("clean") => cmd_clean(&args),
+        Some("export") => cmd_export(&args),
+        // The only argument of the default command is the working directory
+        working_dir => {
+            let working_dir = Path::new(working_dir.unwrap_or(""));
+            copy_options(&args, working_dir)
+                .and_then(|options| run(working_dir, &options))
+        },
+    };
+    if let Err(e) = result {
+        report::fatal(&e.to_string());
+        // The errors of the filter have their own exit codes
+        std::process::exit(
+            e.downcast_ref::<CpfError>().map_or(1, |e| e.exit_code())
+        );
     }
     Ok(())
 }
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::project_dir;
+use crate::error::CpfError;
+use crate::longpath::extended_path;
+use crate::mapping::non_utf8_error;
+use crate::report;
+use crate::retry::with_retries;
+
+/// The path to the manifest file relative to the root of the Regolith
+/// project. It's next to the cache of the hashes, so it outlives the
+/// temporary directory of Regolith.
+pub static MANIFEST_PATH: &str =
+    ".regolith/cache/custom_project/manifest.json";
+
+/// The path to the manifest file of the older versions of the filter
+/// relative to the working directory. It's read when the manifest doesn't
+/// exist yet.
+static LEGACY_MANIFEST_PATH: &str = ".cpf_manifest.json";
+
+/// A single file copied by the filter.
+#[derive(Clone)]
+pub struct ManifestEntry {
+    /// The path to the source file relative to the working directory.
+    pub source: PathBuf,
+    /// The hash of the content of the file (see hash::hash_file).
+    pub hash: u64,
+    /// The extension from the export map that matched the source file.
+    pub rule: String,
+}
+
+/// The record of the targets produced by the filter in the previous run. It's
+/// used to remove the targets that are no longer generated, to trace the
+/// targets back to their sources and to let other tools know which pack
+/// files are generated.
+#[derive(Default)]
+pub struct Manifest {
+    /// The files copied by the filter by the paths to their targets
+    /// relative to the working directory.
+    pub files: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+/// Converts a path to the string stored in the manifest (always with "/" as
+/// the separator).
+fn path_to_string(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Converts a path stored in the manifest to a path with the native
+/// separators.
+fn path_from_string(path: &str) -> PathBuf {
+    PathBuf::from(path).iter().collect()
+}
+
+impl Manifest {
+    /// Returns the path to the manifest file in the root of the Regolith
+    /// project (see config::project_dir).
+    ///
+    /// # Arguments
+    /// - `working_dir` - the path to the working directory of regolith
+    pub fn path(working_dir: &Path) -> PathBuf {
+        project_dir(working_dir).join(MANIFEST_PATH)
+    }
+
+    /// Loads the manifest of the project. The manifest of the older versions
+    /// of the filter from the working directory is loaded if the project
+    /// doesn't have one yet. A missing manifest results in an empty
+    /// manifest.
+    ///
+    /// # Arguments
+    /// - `working_dir` - the path to the working directory of regolith
+    pub fn load(working_dir: &Path) -> Result<Manifest, CpfError> {
+        let mut path = Manifest::path(working_dir);
+        if !path.exists() {
+            path = working_dir.join(LEGACY_MANIFEST_PATH);
+        }
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => return Ok(Manifest::default()),
+        };
+        let value: serde_json::Value = serde_json::from_str(&text)
+            .map_err(|e| format!(
+                "Unable to parse the manifest \"{}\": {}", path.display(), e
+            ))?;
+        let files = value["files"].as_array()
+            .and_then(|files| files.iter()
+                .map(|f| {
+                    let target = path_from_string(f["target"].as_str()?);
+                    let entry = ManifestEntry {
+                        source: path_from_string(f["source"].as_str()?),
+                        hash: u64::from_str_radix(
+                            f["hash"].as_str()?, 16
+                        ).ok()?,
+                        rule: f["rule"].as_str()?.to_string(),
+                    };
+                    Some((target, entry))
+                })
+                .collect())
+            .ok_or_else(|| format!(
+                "Failed to parse \"files\" property in the manifest: \"{}\"",
+                path.display()
+            ))?;
+        Ok(Manifest { files })
+    }
+
+    /// Saves the manifest to the root of the Regolith project and removes the
+    /// manifest of the older versions of the filter.
+    ///
+    /// # Arguments
+    /// - `working_dir` - the path to the working directory of regolith
+    pub fn save(&self, working_dir: &Path) -> Result<(), CpfError> {
+        let mut files: Vec<serde_json::Value> = Vec::new();
+        for (target, entry) in &self.files {
+            // JSON can't represent the paths that aren't valid UTF-8
+            let error = non_utf8_error(&entry.source)
+                .or_else(|| non_utf8_error(target));
+            if let Some(error) = error {
+                report::warning(target, format!(
+                    "Unable to record the file in the manifest. {}", error
+                ));
+                continue;
+            }
+            files.push(serde_json::json!({
+                "source": path_to_string(&entry.source),
+                "target": path_to_string(target),
+                "hash": format!("{:016x}", entry.hash),
+                "rule": entry.rule,
+            }));
+        }
+        let text = format!("{:#}\n", serde_json::json!({"files": files}));
+        let path = Manifest::path(working_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| CpfError::io(e, parent))?;
+        }
+        fs::write(&path, text).map_err(|e| CpfError::io(e, &path))?;
+        let _ = fs::remove_file(working_dir.join(LEGACY_MANIFEST_PATH));
+        Ok(())
+    }
+}
+
+/// Removes the target and its parent directories that become empty. The
+/// first component of the target (the pack directory) is never removed.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `target` - the path to the target relative to the working directory
+pub fn remove_target(working_dir: &Path, target: &Path) {
+    let target_path = working_dir.join(target);
+    match with_retries(|| fs::remove_file(extended_path(&target_path))) {
+        Ok(_) => println!("Removed \"{}\"", target_path.display()),
+        Err(e) => {
+            if target_path.exists() {
+                report::warning(
+                    &target_path, format!("Unable to remove: {}", e)
+                );
+            }
+            return;
+        }
+    }
+    let mut parent = target.parent();
+    while let Some(dir) = parent {
+        if dir.components().count() <= 1 {
+            break;
+        }
+        // Fails if the directory is not empty
+        if fs::remove_dir(extended_path(&working_dir.join(dir))).is_err() {
+            break;
+        }
+        parent = dir.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_the_legacy_manifest() {
+        let dir = std::env::temp_dir()
+            .join(format!("cpf_manifest_legacy_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(LEGACY_MANIFEST_PATH), r#"{"files": [{
+            "source": "data/custom_project/src/a.bp.json",
+            "target": "BP/a.json", "hash": "00000000000000ff",
+            "rule": "bp.json"
+        }]}"#).unwrap();
+        let manifest = Manifest::load(&dir).unwrap();
+        let entry = &manifest.files[Path::new("BP/a.json")];
+        assert_eq!(entry.hash, 0xff);
+        manifest.save(&dir).unwrap();
+        assert!(!dir.join(LEGACY_MANIFEST_PATH).exists());
+        assert!(Manifest::path(&dir).exists());
+        let manifest = Manifest::load(&dir).unwrap();
+        assert_eq!(manifest.files[Path::new("BP/a.json")].rule, "bp.json");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
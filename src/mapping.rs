@@ -0,0 +1,623 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::ExtExportMap;
+use crate::plan::PlannedFile;
+
+/// The built-in table of the mapping rules. It's the single source of the
+/// default rules, the `--list-mappings` output and the table in the README.
+static DEFAULT_MAPPINGS: &str = include_str!("mappings.json");
+
+/// A rule that maps the files with the extension to the export target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MappingRule {
+    /// The compound extension of the files (e.g. ".bpe.json").
+    pub extension: String,
+    /// The directory of the packs that the files are exported to (e.g.
+    /// "BP/entities").
+    pub target: String,
+    /// The description of the files matched by the rule or None for the
+    /// rules from the config file.
+    pub description: Option<String>,
+}
+
+/// Returns the rules from the built-in table.
+pub fn default_rules() -> Vec<MappingRule> {
+    let table: serde_json::Value = serde_json::from_str(DEFAULT_MAPPINGS)
+        .expect("The built-in mapping table is not valid JSON");
+    table.as_array()
+        .expect("The built-in mapping table is not an array")
+        .iter()
+        .map(|rule| MappingRule {
+            extension: rule["extension"].as_str()
+                .expect("Missing extension in the built-in mapping table")
+                .to_string(),
+            target: rule["target"].as_str()
+                .expect("Missing target in the built-in mapping table")
+                .to_string(),
+            description: rule["description"].as_str().map(str::to_string),
+        })
+        .collect()
+}
+
+/// Returns the export map of the built-in rules.
+pub fn default_export_map() -> ExtExportMap {
+    default_rules().into_iter()
+        .map(|rule| (rule.extension, rule.target))
+        .collect()
+}
+
+/// Returns the rules of the export map sorted by the export target and the
+/// extension. The descriptions are taken from the built-in table for the
+/// rules that weren't overridden in the config file.
+pub fn list_rules(map: &ExtExportMap) -> Vec<MappingRule> {
+    let defaults = default_rules();
+    let mut rules: Vec<MappingRule> = map.iter()
+        .map(|(extension, target)| MappingRule {
+            extension: extension.clone(),
+            target: target.clone(),
+            description: defaults.iter()
+                .find(|d| &d.extension == extension && &d.target == target)
+                .and_then(|d| d.description.clone()),
+        })
+        .collect();
+    rules.sort_by(|a, b| (&a.target, &a.extension)
+        .cmp(&(&b.target, &b.extension)));
+    rules
+}
+
+/// Finds the rule from the export map that matches the file. Returns the
+/// extension and the export target of the rule. If multiple extensions
+/// match the file, the longest one wins (e.g. ".geo.json" over ".json").
+///
+/// # Arguments
+/// - `fp` - the path to the file that needs to be copied relative to the
+///   data directory.
+pub fn find_rule<'a>(
+    fp: &Path, map: &'a ExtExportMap
+) -> Option<(&'a str, &'a str)> {
+    // Compare the bytes, so the names that aren't valid UTF-8 can be mapped
+    let file_name = fp.file_name()?.as_encoded_bytes();
+    map.iter()
+        .filter(|(k, _)| file_name.ends_with(k.as_bytes()))
+        .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then(b.cmp(a)))
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+}
+
+/// Gets the target locations for copying the project files to the RP adn BP
+/// based on the data in the map.
+///
+/// # Arguments
+/// - `fp` - the path to the file that needs to be copied relative to the
+///   data directory.
+pub fn get_target_path_from_hash_map(
+        fp: &Path, map: &ExtExportMap
+) -> Option<PathBuf> {
+    let file_name = fp.file_name()?;
+    // Find matching file extension and the export target
+    let (extension, target) = find_rule(fp, map)?;
+    // If the file name is just an extension or the file name is and underscore
+    // with extension (e.g _.bpe.json) then use the parent fodler as the
+    // actual file name.
+    let (base_name, base_path): (OsString, PathBuf);
+    let underscore_name = format!("_{}", extension);
+    if file_name == extension || file_name == underscore_name.as_str() {
+        let mut name = fp.parent()?.file_name()?.to_os_string();
+        name.push(extension);
+        base_name = name;
+        base_path = fp.parent()?.parent()?.to_path_buf();
+    } else {
+        base_name = file_name.to_os_string();
+        base_path = fp.parent()?.to_path_buf();
+    }
+    // Fix the path separators (e.g "/" -> "\\")
+    let target: PathBuf = PathBuf::from(target).iter().collect();
+    // Return
+    Some(target.join(base_path).join(base_name))
+}
+
+/// Returns the edit distance between the strings (the number of the
+/// insertions, deletions, substitutions and transpositions of the adjacent
+/// characters, so "pbe" is one edit away from "bpe").
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            let transposed = i > 1 && j > 1
+                && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1];
+            if transposed {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// The maximal edit distance between the compound extension of a file and
+/// the extension suggested for it. The short extensions only allow one edit
+/// (see suggest_extension).
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Suggests the known compound extension closest to the extension of the
+/// file that isn't mapped (e.g. ".bpe.json" for "zombie.pbe.json"). Only the
+/// extensions with the same last part (e.g. ".json") are suggested. Returns
+/// None if no extension is close enough.
+///
+/// # Arguments
+/// - `fp` - the path to the file
+/// - `extensions` - the known extensions (e.g. from `Mapper::extensions`)
+pub fn suggest_extension(fp: &Path, extensions: &[String]) -> Option<String> {
+    let file_name = fp.file_name()?.to_str()?;
+    let parts: Vec<&str> = file_name.split('.').collect();
+    extensions.iter()
+        .filter_map(|extension| {
+            let count = extension.split('.').count() - 1;
+            // The name has to keep a base name before the extension
+            if count < 2 || parts.len() <= count {
+                return None;
+            }
+            let actual = format!(
+                ".{}", parts[parts.len() - count..].join(".")
+            );
+            let same_last = actual.rsplit('.').next()
+                == extension.rsplit('.').next();
+            let distance = edit_distance(&actual, extension);
+            // One edit per three characters of the subextensions, so
+            // ".rpe.json" isn't a typo of ".r.json"
+            let subextension = extension.len()
+                - extension.rsplit('.').next().map_or(0, str::len);
+            let max_distance = (subextension / 3)
+                .clamp(1, MAX_SUGGESTION_DISTANCE);
+            let close = (1..=max_distance).contains(&distance);
+            match same_last && close {
+                true => Some((distance, extension)),
+                false => None,
+            }
+        })
+        .min_by(|(a, ea), (b, eb)| a.cmp(b).then(ea.cmp(eb)))
+        .map(|(_, extension)| extension.clone())
+}
+
+/// Returns the message for the file that can't be mapped to the packs with
+/// the suggestion of the extension if the file name looks like a typo.
+///
+/// # Arguments
+/// - `fp` - the path to the file
+/// - `mapper` - the mapper that can't map the file
+pub fn unmapped_message(fp: &Path, mapper: &dyn Mapper) -> String {
+    let message = "Unable to map the file to the pack file.";
+    match suggest_extension(fp, &mapper.extensions()) {
+        Some(extension) => {
+            format!("{} Did you mean \"{}\"?", message, extension)
+        },
+        None => message.to_string(),
+    }
+}
+
+/// Returns the reason why the file can't be mapped to the packs with the
+/// get_target_path_from_hash_map function.
+///
+/// # Arguments
+/// - `fp` - the path to the file relative to the data directory.
+pub fn skip_reason(fp: &Path, map: &ExtExportMap) -> &'static str {
+    if find_rule(fp, map).is_none() {
+        return "None of the extensions from the \"extensions_map\" matches \
+            the file name.";
+    }
+    "The file name consists of only the extension, so the name of the \
+    parent folder should be used but the file doesn't have a parent folder."
+}
+
+/// The name of the rule that mapped a file (e.g. the extension ".bpe.json"
+/// or "(passthrough)").
+pub type RuleId = String;
+
+/// The directory of the pack that the file is mapped to (e.g. "entities" or
+/// "textures"). The nested directories of the targets like
+/// "RP/models/entity" aren't part of the category.
+pub type Category = String;
+
+/// The pack that a file is mapped to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Pack {
+    /// The behavior pack ("BP").
+    Behavior,
+    /// The resource pack ("RP").
+    Resource,
+    /// A directory of the working directory that isn't one of the packs.
+    Other,
+}
+
+impl Pack {
+    /// Returns the pack of the target path relative to the working
+    /// directory.
+    pub fn from_path(path: &Path) -> Pack {
+        match path.components().next().map(|c| c.as_os_str()) {
+            Some(pack) if pack == "BP" => Pack::Behavior,
+            Some(pack) if pack == "RP" => Pack::Resource,
+            _ => Pack::Other,
+        }
+    }
+}
+
+/// The target of a mapped file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetInfo {
+    /// The pack of the target.
+    pub pack: Pack,
+    /// The directory of the pack that contains the target or an empty string
+    /// if the target is directly in the pack.
+    pub category: Category,
+    /// The path to the target relative to the working directory.
+    pub path: PathBuf,
+    /// The rule that mapped the file.
+    pub rule: RuleId,
+    /// The sources and the targets of the other files that the generated
+    /// target depends on (e.g. the flipbook textures listed in the
+    /// "flipbook_textures.json"). Empty for the other targets.
+    pub inputs: Vec<(PathBuf, PathBuf)>,
+}
+
+impl TargetInfo {
+    /// Creates the target info of the path (relative to the working
+    /// directory) mapped by the rule.
+    pub fn new(rule: &str, path: PathBuf) -> TargetInfo {
+        let mut components = path.components();
+        components.next();
+        let category = match (components.next(), components.next()) {
+            (Some(category), Some(_)) => {
+                category.as_os_str().to_string_lossy().into_owned()
+            },
+            _ => Category::new(),
+        };
+        TargetInfo {
+            pack: Pack::from_path(&path),
+            category,
+            path,
+            rule: rule.to_string(),
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Sets the files that the generated target depends on.
+    pub fn with_inputs(
+        mut self, inputs: Vec<(PathBuf, PathBuf)>,
+    ) -> TargetInfo {
+        self.inputs = inputs;
+        self
+    }
+}
+
+/// Returns the directory of the target path up to the first directory with
+/// the name (e.g. "RP/textures" for "RP/textures/blocks/lava.png" and
+/// "textures") or None if the path doesn't have it.
+pub fn resource_dir(path: &Path, name: &str) -> Option<PathBuf> {
+    let mut dir = PathBuf::new();
+    for component in path.parent()?.components() {
+        dir.push(component);
+        if component == Component::Normal(name.as_ref()) {
+            return Some(dir);
+        }
+    }
+    None
+}
+
+/// Returns the path of the resource as it's referenced in the packs, from
+/// the directory with the name and without the extension (e.g.
+/// "textures/ui/buttons" for "RP/textures/ui/buttons.png" and "textures").
+/// The paths without the directory start after the pack.
+pub fn resource_reference(path: &Path, name: &str) -> String {
+    let path = path.with_extension("");
+    let components: Vec<&str> = path.components()
+        .filter_map(|c| match c {
+            Component::Normal(c) => c.to_str(),
+            _ => None,
+        })
+        .collect();
+    let start = components.iter().position(|c| *c == name)
+        .unwrap_or(1.min(components.len()));
+    components[start..].join("/")
+}
+
+/// The result of mapping a path with a [`Mapper`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MapResult {
+    /// The file is mapped to the target.
+    Mapped(TargetInfo),
+    /// The mapper handles the file but it can't be mapped. The next mappers
+    /// of the chain aren't used.
+    Skipped(&'static str),
+    /// The mapper doesn't handle the file, so the next mapper of the chain
+    /// is used.
+    Unmatched,
+}
+
+/// Maps the paths of the source files (relative to a root of the data
+/// directory) to the pack files. The mappers are resolved in a chain (see
+/// `MapperChain`), so every source of the rules is a separate mapper.
+pub trait Mapper: Send + Sync {
+    /// Maps the path of the source file relative to the root.
+    fn map(&self, path: &Path) -> MapResult;
+
+    /// Returns the compound extensions handled by the mapper, used for
+    /// suggesting the extensions of the files that aren't mapped.
+    fn extensions(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Adjusts the planned files of the root directory after all of them are
+    /// mapped, for the rules that depend on the other files or on the root
+    /// (e.g. the feature folders) or that map a file to multiple targets
+    /// (e.g. the shared folder). Does nothing by default.
+    fn group(&self, _root_dir: &Path, _files: &mut Vec<PlannedFile>) {}
+}
+
+/// Maps the file with the extension to the directory of the pack like the
+/// RuleMapper, with the extension of the target instead, for the files
+/// compiled into the pack files (e.g. "villager.tt.csv" ->
+/// "BP/trading/villager.tt.json").
+///
+/// # Arguments
+/// - `path` - the path to the file relative to the root
+/// - `extension` - the extension of the sources (e.g. ".tt.csv")
+/// - `dir` - the directory of the targets (e.g. "BP/trading")
+/// - `target_extension` - the extension of the targets (e.g. ".tt.json")
+/// - `rule` - the name of the rule of the targets
+pub fn compiled_target(
+    path: &Path, extension: &str, dir: &str, target_extension: &str,
+    rule: &str,
+) -> MapResult {
+    let map: ExtExportMap = HashMap::from([
+        (extension.to_string(), dir.to_string()),
+    ]);
+    let mut target = match get_target_path_from_hash_map(path, &map) {
+        Some(target) => target,
+        None => return MapResult::Unmatched,
+    };
+    let name = target.file_name().and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(extension))
+        .map(|name| format!("{}{}", name, target_extension));
+    match name {
+        Some(name) => target.set_file_name(name),
+        None => return MapResult::Unmatched,
+    }
+    MapResult::Mapped(TargetInfo::new(rule, target))
+}
+
+/// Maps the files using the extensions of the export map (e.g. the rules
+/// from the config file or the built-in rules).
+pub struct RuleMapper {
+    export_map: ExtExportMap,
+}
+
+impl RuleMapper {
+    /// Creates a mapper that uses the rules of the export map.
+    pub fn new(export_map: ExtExportMap) -> RuleMapper {
+        RuleMapper { export_map }
+    }
+
+    /// Returns the rules of the mapper.
+    pub fn export_map(&self) -> &ExtExportMap {
+        &self.export_map
+    }
+}
+
+impl Mapper for RuleMapper {
+    fn map(&self, path: &Path) -> MapResult {
+        let rule = match find_rule(path, &self.export_map) {
+            Some((extension, _)) => extension,
+            None => return MapResult::Unmatched,
+        };
+        match get_target_path_from_hash_map(path, &self.export_map) {
+            Some(target) => MapResult::Mapped(TargetInfo::new(rule, target)),
+            None => MapResult::Skipped(skip_reason(path, &self.export_map)),
+        }
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        self.export_map.keys().cloned().collect()
+    }
+}
+
+/// Maps the files from the "BP" and "RP" folders of the root to the same
+/// paths in the packs (e.g. "BP/manifest.json" -> "BP/manifest.json").
+pub struct PassthroughMapper;
+
+/// The name of the rule of the files mapped by the PassthroughMapper.
+pub static PASSTHROUGH_RULE: &str = "(passthrough)";
+
+impl Mapper for PassthroughMapper {
+    fn map(&self, path: &Path) -> MapResult {
+        let mut components = path.components();
+        match components.next() {
+            Some(pack) if pack.as_os_str() == "BP"
+                || pack.as_os_str() == "RP" => {},
+            _ => return MapResult::Unmatched,
+        }
+        if components.next().is_none() {
+            return MapResult::Unmatched;
+        }
+        MapResult::Mapped(
+            TargetInfo::new(PASSTHROUGH_RULE, path.to_path_buf())
+        )
+    }
+}
+
+/// Maps the files from the "SHARED" folder of the root to the same paths in
+/// both of the packs (e.g. "SHARED/texts/en_US.lang" -> "BP/texts/en_US.lang"
+/// and "RP/texts/en_US.lang") for the content that has to exist twice.
+pub struct SharedMapper;
+
+/// The name of the rule of the files mapped by the SharedMapper.
+pub static SHARED_RULE: &str = "(shared)";
+
+impl Mapper for SharedMapper {
+    fn map(&self, path: &Path) -> MapResult {
+        let mut components = path.components();
+        match components.next() {
+            Some(shared) if shared.as_os_str() == "SHARED" => {},
+            _ => return MapResult::Unmatched,
+        }
+        if components.clone().next().is_none() {
+            return MapResult::Unmatched;
+        }
+        MapResult::Mapped(TargetInfo::new(
+            SHARED_RULE, Path::new("BP").join(components.as_path())
+        ))
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        let mut result = Vec::with_capacity(files.len());
+        for file in files.drain(..) {
+            let copy = match &file.target {
+                Some(target) if target.rule == SHARED_RULE => {
+                    let path = target.path.strip_prefix("BP")
+                        .map(|path| Path::new("RP").join(path));
+                    path.ok().map(|path| PlannedFile {
+                        source: file.source.clone(),
+                        target: Some(TargetInfo::new(SHARED_RULE, path)),
+                    })
+                },
+                _ => None,
+            };
+            result.push(file);
+            result.extend(copy);
+        }
+        *files = result;
+    }
+}
+
+/// Maps all of the files to the same directory of the packs keeping their
+/// paths relative to the root. It's meant to be the last mapper of the
+/// chain.
+pub struct FallbackMapper {
+    target: PathBuf,
+}
+
+/// The name of the rule of the files mapped by the FallbackMapper.
+pub static FALLBACK_RULE: &str = "(fallback)";
+
+impl FallbackMapper {
+    /// Creates a mapper that maps the files to the target directory (e.g.
+    /// "RP/misc").
+    pub fn new(target: &str) -> FallbackMapper {
+        // Fix the path separators (e.g "/" -> "\\")
+        FallbackMapper { target: PathBuf::from(target).iter().collect() }
+    }
+}
+
+impl Mapper for FallbackMapper {
+    fn map(&self, path: &Path) -> MapResult {
+        MapResult::Mapped(
+            TargetInfo::new(FALLBACK_RULE, self.target.join(path))
+        )
+    }
+}
+
+/// Adds the name of the root (e.g. "combat") as a subfolder of the export
+/// directory of the rule to the targets of the files mapped by the extension
+/// rules, so the targets of the roots of a large project stay separated in
+/// the packs (e.g. "combat/zombie.geo.json" ->
+/// "RP/models/entity/combat/zombie.geo.json").
+pub struct RootSubfolders {
+    export_map: ExtExportMap,
+}
+
+impl RootSubfolders {
+    /// Creates the mapper for the rules of the export map.
+    pub fn new(export_map: ExtExportMap) -> RootSubfolders {
+        RootSubfolders { export_map }
+    }
+}
+
+impl Mapper for RootSubfolders {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        let name = match root_dir.file_name() {
+            Some(name) => name,
+            None => return,
+        };
+        for file in files.iter_mut() {
+            let (target, dir) = match &file.target {
+                Some(target) => match self.export_map.get(&target.rule) {
+                    Some(dir) => (target, dir),
+                    None => continue,
+                },
+                None => continue,
+            };
+            // Fix the path separators (e.g "/" -> "\\")
+            let dir: PathBuf = PathBuf::from(dir).iter().collect();
+            // The files mapped directly to the packs have no category
+            let rest = match target.path.strip_prefix(&dir) {
+                Ok(rest) if dir.components().count() > 1 => rest,
+                _ => continue,
+            };
+            let path = dir.join(name).join(rest);
+            file.target = Some(TargetInfo::new(&target.rule, path));
+        }
+    }
+}
+
+/// Returns the error message for the path that isn't valid UTF-8 or None if
+/// the path is valid. Such paths can be copied but they can't be referenced
+/// from the JSON files of the packs or stored in the manifest.
+///
+/// # Arguments
+/// - `path` - the path to check
+pub fn non_utf8_error(path: &Path) -> Option<String> {
+    if path.to_str().is_some() {
+        return None;
+    }
+    Some(format!(
+        "The path \"{}\" is not valid UTF-8 (the invalid characters are \
+        shown as \"\u{FFFD}\"). Rename the file using UTF-8 characters \
+        only.",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the target of the path mapped by the built-in rules.
+    fn default_target(path: &str) -> Option<PathBuf> {
+        match RuleMapper::new(default_export_map()).map(Path::new(path)) {
+            MapResult::Mapped(target) => Some(target.path),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn maps_the_compound_extensions_of_the_built_in_rules() {
+        let cases = [
+            ("chest.lt.json", "BP/loot_tables/chest.lt.json"),
+            ("frame.r.json", "BP/recipes/frame.r.json"),
+            ("zombie.sr.json", "BP/spawn_rules/zombie.sr.json"),
+            ("villager.tt.json", "BP/trading/villager.tt.json"),
+            ("ore.f.json", "BP/features/ore.f.json"),
+            ("ore.fr.json", "BP/feature_rules/ore.fr.json"),
+            ("smoke.p.json", "RP/particles/smoke.p.json"),
+        ];
+        for (source, target) in cases {
+            assert_eq!(default_target(source), Some(PathBuf::from(target)));
+        }
+        assert_eq!(default_target("chest.loot.json"), None);
+    }
+}
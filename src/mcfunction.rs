@@ -0,0 +1,429 @@
+use std::fs;
+
+use crate::index::ProjectIndex;
+use crate::mapping::Pack;
+use crate::recipes::parse_version;
+use crate::report::{Diagnostic, Severity};
+
+/// The table of the commands (or the commands with their first arguments,
+/// e.g. "execute as") and the versions of the game that added them.
+static COMMAND_VERSIONS: &str = include_str!("commands.json");
+
+/// The commands of Bedrock Edition that can be used in the functions.
+static COMMANDS: &[&str] = &[
+    "aimassist", "alwaysday", "camera", "camerashake", "clear",
+    "clearspawnpoint", "clone", "controlscheme", "damage", "daylock",
+    "dialogue", "difficulty", "effect", "enchant", "event", "execute", "fill",
+    "fog", "function", "gamemode", "gamerule", "give", "hud",
+    "inputpermission", "kill", "list", "locate", "loot", "me", "mobevent",
+    "msg", "music", "particle", "place", "playanimation", "playsound",
+    "recipe", "replaceitem", "ride", "say", "schedule", "scoreboard",
+    "scriptevent", "setblock", "setworldspawn", "spawnpoint",
+    "spreadplayers", "stopsound", "structure", "summon", "tag", "teleport",
+    "tell", "tellraw", "testfor", "testforblock", "testforblocks",
+    "tickingarea", "time", "title", "titleraw", "toggledownfall", "tp", "w",
+    "weather", "xp",
+];
+
+/// The variables of the target selectors (e.g. "@a").
+static SELECTOR_VARIABLES: &[&str] = &[
+    "@a", "@e", "@initiator", "@p", "@r", "@s",
+];
+
+/// The arguments of the target selectors.
+static SELECTOR_ARGUMENTS: &[&str] = &[
+    "c", "dx", "dy", "dz", "family", "has_property", "hasitem",
+    "haspermission", "l", "lm", "m", "name", "r", "rm", "rx", "rxm", "ry",
+    "rym", "scores", "tag", "type", "x", "y", "z",
+];
+
+/// The game modes of the "m" argument of the selectors.
+static GAME_MODES: &[&str] = &[
+    "0", "1", "2", "5", "a", "adventure", "c", "creative", "d", "default",
+    "s", "spectator", "survival",
+];
+
+/// The maximal length of a command in the functions (in characters).
+const MAX_COMMAND_LENGTH: usize = 32767;
+
+/// Checks if the brackets and the quotes of the command are balanced.
+/// Returns the 1-based column and the description of the first problem.
+fn bracket_problem(command: &str) -> Option<(usize, String)> {
+    let mut open: Vec<(usize, char)> = Vec::new();
+    let mut quote: Option<usize> = None;
+    let mut escaped = false;
+    for (column, c) in command.chars().enumerate().map(|(i, c)| (i + 1, c)) {
+        if quote.is_some() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => quote = None,
+                _ => {},
+            }
+            continue;
+        }
+        match c {
+            '"' => quote = Some(column),
+            '[' | '{' => open.push((column, c)),
+            ']' | '}' => {
+                let expected = if c == ']' { '[' } else { '{' };
+                match open.pop() {
+                    Some((_, opening)) if opening == expected => {},
+                    Some((column, opening)) => return Some((column, format!(
+                        "The '{}' is closed with '{}'.", opening, c
+                    ))),
+                    None => return Some((column, format!(
+                        "The '{}' doesn't have a matching opening bracket.",
+                        c
+                    ))),
+                }
+            },
+            _ => {},
+        }
+    }
+    if let Some(column) = quote {
+        return Some((column, "The string isn't closed.".into()));
+    }
+    open.pop().map(|(column, c)| {
+        (column, format!("The '{}' isn't closed.", c))
+    })
+}
+
+/// Splits the command into the words separated by whitespace outside of the
+/// strings and the brackets (e.g. "@e[type=zombie, r=5]" is one word).
+/// Returns the 1-based columns and the words.
+pub fn words(command: &str) -> Vec<(usize, &str)> {
+    let mut result = Vec::new();
+    let mut start = None;
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in command.char_indices() {
+        if quoted {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => {},
+            }
+            continue;
+        }
+        match c {
+            _ if c.is_whitespace() && depth == 0 => {
+                if let Some(s) = start.take() {
+                    result.push((s, &command[s..i]));
+                }
+                continue;
+            },
+            '"' => quoted = true,
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        result.push((s, &command[s..]));
+    }
+    result.into_iter()
+        .map(|(s, word)| (command[..s].chars().count() + 1, word))
+        .collect()
+}
+
+/// Splits the arguments of a selector (or the scores of its "scores"
+/// argument) by the commas outside of the brackets. Returns the byte
+/// offsets and the arguments.
+pub fn split_arguments(text: &str) -> Vec<(usize, &str)> {
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                result.push((start, &text[start..i]));
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    result.push((start, &text[start..]));
+    result
+}
+
+/// Checks if the value is an integer range of the "scores" argument (e.g.
+/// "5", "1..", "..10", "1..10" or "!1..3").
+fn is_valid_range(value: &str) -> bool {
+    let value = value.strip_prefix('!').unwrap_or(value).trim();
+    let is_integer = |text: &str| text.trim().parse::<i64>().is_ok();
+    match value.split_once("..") {
+        Some(("", "")) => false,
+        Some((min, max)) => {
+            (min.is_empty() || is_integer(min))
+                && (max.is_empty() || is_integer(max))
+        },
+        None => is_integer(value),
+    }
+}
+
+/// Checks the value of the argument of a selector. Returns the description
+/// of the problem.
+fn argument_problem(key: &str, value: &str) -> Option<String> {
+    let number = |text: &str| text.parse::<f64>().is_ok();
+    let valid = match key {
+        "x" | "y" | "z" => {
+            let offset = value.strip_prefix(['~', '^']).unwrap_or(value);
+            offset.is_empty() || number(offset)
+        },
+        "r" | "rm" | "rx" | "rxm" | "ry" | "rym" | "dx" | "dy" | "dz" => {
+            number(value)
+        },
+        "c" => value.parse::<i64>().is_ok_and(|c| c != 0),
+        "l" | "lm" => value.parse::<u64>().is_ok(),
+        "m" => GAME_MODES.contains(
+            &value.strip_prefix('!').unwrap_or(value).to_lowercase().as_str()
+        ),
+        "scores" => {
+            let scores = match value.strip_prefix('{')
+                .and_then(|v| v.strip_suffix('}'))
+            {
+                Some(scores) => scores,
+                None => return Some(
+                    "The \"scores\" have to be in the braces.".into()
+                ),
+            };
+            for (_, score) in split_arguments(scores) {
+                if score.trim().is_empty() {
+                    continue;
+                }
+                match score.split_once('=') {
+                    Some((_, range)) if is_valid_range(range) => {},
+                    _ => return Some(format!(
+                        "\"{}\" isn't a valid score range.", score.trim()
+                    )),
+                }
+            }
+            true
+        },
+        _ => true,
+    };
+    match valid {
+        true => None,
+        false => Some(format!(
+            "\"{}\" isn't a valid value of \"{}\".", value, key
+        )),
+    }
+}
+
+/// Checks the target selector (e.g. "@e[type=zombie,r=5]"). Returns the
+/// 0-based character offset in the selector and the description of the
+/// first problem.
+fn selector_problem(selector: &str) -> Option<(usize, String)> {
+    // The words without the arguments can be the text of the messages
+    let (variable, arguments) = selector.split_once('[')?;
+    if !SELECTOR_VARIABLES.contains(&variable) {
+        return Some((0, format!(
+            "Unknown selector variable \"{}\".", variable
+        )));
+    }
+    let arguments = match arguments.strip_suffix(']') {
+        Some(arguments) => arguments,
+        None => return Some((
+            0, "The selector has text after its arguments.".into()
+        )),
+    };
+    if arguments.trim().is_empty() {
+        return None;
+    }
+    for (offset, argument) in split_arguments(arguments) {
+        let column = selector[..variable.len() + 1 + offset].chars().count();
+        let (key, value) = match argument.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return Some((column, format!(
+                "The selector argument \"{}\" doesn't have a value.",
+                argument.trim()
+            ))),
+        };
+        if !SELECTOR_ARGUMENTS.contains(&key) {
+            return Some((column, format!(
+                "Unknown selector argument \"{}\".", key
+            )));
+        }
+        if let Some(problem) = argument_problem(key, value) {
+            return Some((column, problem));
+        }
+    }
+    None
+}
+
+/// Lints the command of the function. The "execute" commands are checked
+/// with the commands that they run.
+fn check_command(
+    index: &ProjectIndex, command: &str,
+    mut problem: impl FnMut(usize, String),
+) {
+    let length = command.chars().count();
+    if length > MAX_COMMAND_LENGTH {
+        problem(1, format!(
+            "The command is {} characters long, the limit is {}.",
+            length, MAX_COMMAND_LENGTH
+        ));
+    }
+    if let Some((column, message)) = bracket_problem(command) {
+        // The words can't be split reliably
+        problem(column, message);
+        return;
+    }
+    let words = words(command);
+    for (column, word) in &words {
+        if !word.starts_with('@') {
+            continue;
+        }
+        if let Some((offset, message)) = selector_problem(word) {
+            problem(column + offset, message);
+        }
+    }
+    for (i, (column, word)) in words.iter().enumerate() {
+        // The commands are at the start and after the "run" of "execute"
+        let is_command = i == 0 || words[i - 1].1 == "run";
+        if !is_command {
+            continue;
+        }
+        let name = word.trim_start_matches('/').to_lowercase();
+        if !COMMANDS.contains(&name.as_str()) {
+            problem(*column, format!("Unknown command \"{}\".", name));
+        }
+        if name != "function" {
+            continue;
+        }
+        let path = match words.get(i + 1) {
+            Some((_, path)) => path.trim_matches('"'),
+            None => continue,
+        };
+        let file = format!("functions/{}.mcfunction", path);
+        if !index.has_file(Pack::Behavior, &file) {
+            problem(words[i + 1].0, format!(
+                "The function \"{}\" isn't copied to the behavior pack.",
+                path
+            ));
+        }
+    }
+}
+
+/// A command (or a command with its first argument) from the table of the
+/// versions of the commands.
+struct CommandVersion {
+    command: String,
+    /// The version of the game that added the command.
+    version: Vec<u32>,
+    /// The description of the syntax added by the version (e.g. "The new
+    /// syntax of execute").
+    description: Option<String>,
+}
+
+/// Returns the commands of the table that aren't available in the version
+/// of the game.
+fn unavailable_commands(min_engine_version: &[u32]) -> Vec<CommandVersion> {
+    let table: serde_json::Value = serde_json::from_str(COMMAND_VERSIONS)
+        .expect("The built-in command table is not valid JSON");
+    table.as_array()
+        .expect("The built-in command table is not an array")
+        .iter()
+        .map(|entry| CommandVersion {
+            command: entry["command"].as_str()
+                .expect("Missing command in the built-in command table")
+                .to_string(),
+            version: entry["version"].as_str().and_then(parse_version)
+                .expect("Invalid version in the built-in command table"),
+            description: entry["description"].as_str().map(str::to_string),
+        })
+        .filter(|entry| entry.version.as_slice() > min_engine_version)
+        .collect()
+}
+
+/// Finds the commands (and the commands run by "execute") that aren't
+/// available in the version of the game. Returns the 1-based columns and
+/// the descriptions of the problems.
+fn version_problems(
+    command: &str, unavailable: &[CommandVersion],
+) -> Vec<(usize, String)> {
+    if unavailable.is_empty() || bracket_problem(command).is_some() {
+        return Vec::new();
+    }
+    let words = words(command);
+    let mut result = Vec::new();
+    for (i, (column, word)) in words.iter().enumerate() {
+        let is_command = i == 0 || words[i - 1].1 == "run";
+        if !is_command {
+            continue;
+        }
+        let name = word.trim_start_matches('/').to_lowercase();
+        let with_argument = words.get(i + 1)
+            .map(|(_, argument)| format!("{} {}", name, argument));
+        let entry = unavailable.iter().find(|entry| {
+            entry.command == name
+                || Some(&entry.command) == with_argument.as_ref()
+        });
+        if let Some(entry) = entry {
+            let version = entry.version.iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(".");
+            let what = match &entry.description {
+                Some(description) => description.clone(),
+                None => format!("The command \"{}\"", entry.command),
+            };
+            result.push((*column, format!(
+                "{} requires the version {} of the game, which is newer than \
+                the \"min_engine_version\".",
+                what, version
+            )));
+        }
+    }
+    result
+}
+
+/// Lints the functions of the behavior pack: the unknown commands, the
+/// unbalanced brackets of the selectors and the JSON arguments, the
+/// malformed selectors (unknown variables and arguments, invalid values and
+/// score ranges), the commands that exceed the length limit and the
+/// references to the functions that aren't copied to the behavior pack. Any
+/// of them makes the game skip the whole function. With the
+/// `min_engine_version`, the commands that aren't available in that version
+/// of the game are reported as warnings.
+pub fn check_functions(
+    index: &ProjectIndex, min_engine_version: Option<&[u32]>,
+    problems: &mut Vec<Diagnostic>,
+) {
+    let unavailable = match min_engine_version {
+        Some(version) => unavailable_commands(version),
+        None => Vec::new(),
+    };
+    for (source, _) in index.sources(Pack::Behavior, "mcfunction") {
+        let text = match fs::read(source) {
+            Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+            Err(_) => continue,
+        };
+        let text = text.trim_start_matches('\u{feff}');
+        for (number, line) in text.lines().enumerate() {
+            let indent = line.len() - line.trim_start().len();
+            let command = line.trim();
+            if command.is_empty() || command.starts_with('#') {
+                continue;
+            }
+            let column = |column| line[..indent].chars().count() + column;
+            check_command(index, command, |c, message| {
+                problems.push(Diagnostic::new(
+                    Severity::Error, source, message
+                ).at(number + 1, column(c)));
+            });
+            for (c, message) in version_problems(command, &unavailable) {
+                problems.push(Diagnostic::new(
+                    Severity::Warning, source, message
+                ).at(number + 1, column(c)));
+            }
+        }
+    }
+}
+
@@ -0,0 +1,193 @@
+/// The tag that ends a compound.
+const TAG_END: u8 = 0;
+/// The tag of a list of unnamed tags.
+const TAG_LIST: u8 = 9;
+/// The tag of a compound of named tags.
+const TAG_COMPOUND: u8 = 10;
+
+/// The maximal depth of the nested lists and compounds (the same as in the
+/// game), so the corrupted files can't overflow the stack.
+const MAX_DEPTH: usize = 512;
+
+/// The keys of the root compound of every .mcstructure file.
+static STRUCTURE_KEYS: &[&str] = &[
+    "format_version", "size", "structure", "structure_world_origin",
+];
+
+/// Reads the uncompressed little-endian NBT used by Bedrock Edition.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Returns the next `count` bytes.
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(count)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| format!(
+                "The file is truncated (byte {} is past the end of the \
+                file).",
+                self.position.saturating_add(count)
+            ))?;
+        let result = &self.data[self.position..end];
+        self.position = end;
+        Ok(result)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        let bytes = self.take(4)?;
+        let length = i32::from_le_bytes([
+            bytes[0], bytes[1], bytes[2], bytes[3]
+        ]);
+        usize::try_from(length)
+            .map_err(|_| format!("Negative length at byte {}.", self.position))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let bytes = self.take(2)?;
+        let length = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+        Ok(String::from_utf8_lossy(self.take(length)?).into_owned())
+    }
+
+    /// Skips the payload of the tag.
+    fn skip_payload(&mut self, tag: u8, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err("The tags are nested too deeply.".to_string());
+        }
+        let size = match tag {
+            1 => 1,
+            2 => 2,
+            3 | 5 => 4,
+            4 | 6 => 8,
+            7 => self.length()?,
+            8 => {
+                self.string()?;
+                return Ok(());
+            },
+            TAG_LIST => {
+                let item = self.byte()?;
+                for _ in 0..self.length()? {
+                    self.skip_payload(item, depth + 1)?;
+                }
+                return Ok(());
+            },
+            TAG_COMPOUND => {
+                self.compound_keys(depth + 1)?;
+                return Ok(());
+            },
+            11 => self.length()?.saturating_mul(4),
+            12 => self.length()?.saturating_mul(8),
+            _ => return Err(format!(
+                "Unknown tag {} at byte {}.", tag, self.position - 1
+            )),
+        };
+        self.take(size)?;
+        Ok(())
+    }
+
+    /// Reads the payload of a compound and returns the names of its tags.
+    fn compound_keys(&mut self, depth: usize) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        loop {
+            let tag = self.byte()?;
+            if tag == TAG_END {
+                return Ok(keys);
+            }
+            keys.push(self.string()?);
+            self.skip_payload(tag, depth)?;
+        }
+    }
+}
+
+/// Checks if the content of a .mcstructure file is uncompressed
+/// little-endian NBT with the keys of the Bedrock structures. Returns the
+/// description of the problem if it isn't (e.g. a Java Edition .nbt file).
+///
+/// # Arguments
+/// - `data` - the content of the file
+pub fn validate_structure(data: &[u8]) -> Result<(), String> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        return Err(
+            "The file is compressed with gzip, which looks like a Java \
+            Edition .nbt file. Bedrock uses uncompressed little-endian NBT."
+                .to_string()
+        );
+    }
+    let mut reader = Reader { data, position: 0 };
+    if reader.byte()? != TAG_COMPOUND {
+        return Err(
+            "The file isn't NBT (it doesn't start with a compound tag)."
+                .to_string()
+        );
+    }
+    // The name of the root is usually empty. A big-endian length of the
+    // name is a sign of an uncompressed Java Edition file.
+    if data.len() >= 3 && data[1] == 0 && data[2] != 0 {
+        return Err(
+            "The file is big-endian NBT, which looks like a Java Edition \
+            .nbt file. Bedrock uses little-endian NBT.".to_string()
+        );
+    }
+    reader.string()?;
+    let keys = reader.compound_keys(0)?;
+    let missing: Vec<&str> = STRUCTURE_KEYS.iter()
+        .filter(|key| !keys.iter().any(|k| k == *key))
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "The structure is missing the keys: {}.", missing.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the root compound with the int tags with the names.
+    fn structure(keys: &[&str]) -> Vec<u8> {
+        let mut data = vec![TAG_COMPOUND, 0, 0];
+        for key in keys {
+            data.push(3);
+            data.extend((key.len() as u16).to_le_bytes());
+            data.extend(key.as_bytes());
+            data.extend(1i32.to_le_bytes());
+        }
+        data.push(TAG_END);
+        data
+    }
+
+    #[test]
+    fn accepts_the_bedrock_structures() {
+        assert_eq!(validate_structure(&structure(STRUCTURE_KEYS)), Ok(()));
+        // The nested lists and compounds are skipped
+        let mut data = structure(STRUCTURE_KEYS);
+        data.pop();
+        data.extend([TAG_LIST, 1, 0, b'x', TAG_COMPOUND]);
+        data.extend(1i32.to_le_bytes());
+        data.extend([TAG_END, TAG_END]);
+        assert_eq!(validate_structure(&data), Ok(()));
+    }
+
+    #[test]
+    fn reports_the_invalid_structures() {
+        let error = |data: &[u8]| validate_structure(data).unwrap_err();
+        assert!(error(&[0x1f, 0x8b, 8]).contains("gzip"));
+        assert!(error(&[TAG_COMPOUND, 0, 4, b'r']).contains("big-endian"));
+        assert!(error(&[TAG_LIST]).contains("compound"));
+        let data = structure(STRUCTURE_KEYS);
+        assert!(error(&data[..data.len() - 3]).contains("truncated"));
+        assert_eq!(
+            error(&structure(&["format_version", "size"])),
+            "The structure is missing the keys: structure, \
+            structure_world_origin."
+        );
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::HashSet;
+#[cfg(not(target_os = "linux"))]
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+#[cfg(not(target_os = "linux"))]
+use std::time::SystemTime;
+
+/// The changes of the watched directories reported by the Watcher.
+#[derive(Debug, PartialEq)]
+pub enum Changes {
+    /// The paths to the created, modified and removed files and directories.
+    /// The files of the created directories are listed as well.
+    Paths(Vec<PathBuf>),
+    /// Some of the changes were lost (e.g. the queue of the notifications
+    /// overflowed), so all of the files have to be checked again.
+    Rescan,
+}
+
+/// Lists the directory and its subdirectories. The symbolic links to the
+/// directories are followed only if `follow_symlinks` is true and the
+/// directories that were already listed (e.g. the targets of the links that
+/// point to their own parent directories) are skipped.
+///
+/// # Arguments
+/// - `dir` - the directory to list
+/// - `follow_symlinks` - whether to follow the symbolic links
+/// - `visited` - the canonical paths of the listed directories
+/// - `on_entry` - called with every directory (including `dir`) and file
+///   and with whether it's a directory
+fn list_dir(
+    dir: &Path, follow_symlinks: bool, visited: &mut HashSet<PathBuf>,
+    on_entry: &mut dyn FnMut(&Path, bool),
+) {
+    if !fs::canonicalize(dir).is_ok_and(|c| visited.insert(c)) {
+        return;
+    }
+    on_entry(dir, true);
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.map_while(Result::ok) {
+        let path = entry.path();
+        let metadata = match follow_symlinks {
+            true => fs::metadata(&path),
+            false => fs::symlink_metadata(&path),
+        };
+        match metadata {
+            Ok(metadata) if metadata.is_dir() => {
+                list_dir(&path, follow_symlinks, visited, on_entry);
+            },
+            Ok(metadata) if metadata.is_file() => on_entry(&path, false),
+            _ => {},
+        }
+    }
+}
+
+/// Watches the directories (with their subdirectories) for the changes of
+/// their files using the notifications of the file system (inotify on
+/// Linux). On the other platforms the directories are scanned for the
+/// changed modification times and sizes of the files instead.
+#[cfg(target_os = "linux")]
+pub struct Watcher {
+    inotify: inotify::Inotify,
+    follow_symlinks: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl Watcher {
+    /// Starts watching the directories.
+    ///
+    /// # Arguments
+    /// - `dirs` - the directories to watch
+    /// - `follow_symlinks` - whether to watch the directories behind the
+    ///   symbolic links
+    pub fn new(
+        dirs: &[PathBuf], follow_symlinks: bool,
+    ) -> io::Result<Watcher> {
+        let mut watcher = Watcher {
+            inotify: inotify::Inotify::new()?,
+            follow_symlinks,
+        };
+        for dir in dirs {
+            watcher.add_dir(dir, &mut Vec::new());
+        }
+        Ok(watcher)
+    }
+
+    /// Watches the directory and its subdirectories and adds their files to
+    /// the changed paths. The files could have been created before the
+    /// directory was watched.
+    fn add_dir(&mut self, dir: &Path, paths: &mut Vec<PathBuf>) {
+        let inotify = &mut self.inotify;
+        let mut on_entry = |path: &Path, is_dir: bool| match is_dir {
+            // The directory could be removed in the meantime
+            true => {
+                let _ = inotify.add_watch(path);
+            },
+            false => paths.push(path.to_path_buf()),
+        };
+        list_dir(
+            dir, self.follow_symlinks, &mut HashSet::new(), &mut on_entry
+        );
+    }
+
+    /// Waits for the changes and returns them. After the first change, the
+    /// changes are collected until there are none for `delay`, so saving
+    /// many files at once results in a single update.
+    pub fn wait(&mut self, delay: Duration) -> io::Result<Changes> {
+        let mut paths = Vec::new();
+        let mut rescan = false;
+        let mut timeout = None;
+        while self.inotify.poll(timeout)? {
+            for event in self.inotify.read_events()? {
+                match event {
+                    inotify::Event::Overflow => rescan = true,
+                    inotify::Event::Created(path) => {
+                        self.add_dir(&path, &mut paths);
+                        paths.push(path);
+                    },
+                    inotify::Event::Changed(path) => paths.push(path),
+                }
+            }
+            timeout = Some(delay);
+        }
+        if rescan {
+            return Ok(Changes::Rescan);
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(Changes::Paths(paths))
+    }
+}
+
+/// The minimal bindings of inotify.
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::collections::HashMap;
+    use std::ffi::{CString, OsStr};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::raw::{c_char, c_int, c_short, c_ulong};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(
+            fd: c_int, path: *const c_char, mask: u32
+        ) -> c_int;
+        fn poll(fds: *mut PollFd, nfds: c_ulong, timeout: c_int) -> c_int;
+    }
+
+    #[repr(C)]
+    struct PollFd {
+        fd: c_int,
+        events: c_short,
+        revents: c_short,
+    }
+
+    const IN_NONBLOCK: c_int = 0o4000;
+    const IN_CLOEXEC: c_int = 0o2000000;
+    const IN_MODIFY: u32 = 0x2;
+    const IN_ATTRIB: u32 = 0x4;
+    const IN_CLOSE_WRITE: u32 = 0x8;
+    const IN_MOVED_FROM: u32 = 0x40;
+    const IN_MOVED_TO: u32 = 0x80;
+    const IN_CREATE: u32 = 0x100;
+    const IN_DELETE: u32 = 0x200;
+    const IN_DELETE_SELF: u32 = 0x400;
+    const IN_MOVE_SELF: u32 = 0x800;
+    const IN_Q_OVERFLOW: u32 = 0x4000;
+    const IN_IGNORED: u32 = 0x8000;
+    const IN_ISDIR: u32 = 0x4000_0000;
+    const POLLIN: c_short = 0x1;
+    /// The size of the header of an event (without the name).
+    const EVENT_SIZE: usize = 16;
+    /// The events of the watched directories.
+    const MASK: u32 = IN_MODIFY | IN_ATTRIB | IN_CLOSE_WRITE | IN_MOVED_FROM
+        | IN_MOVED_TO | IN_CREATE | IN_DELETE | IN_DELETE_SELF
+        | IN_MOVE_SELF;
+
+    /// An event of a watched directory.
+    pub enum Event {
+        /// A directory was created or moved into a watched directory.
+        Created(PathBuf),
+        /// A file or a directory was changed, created or removed.
+        Changed(PathBuf),
+        /// The queue of the events overflowed and some of them were lost.
+        Overflow,
+    }
+
+    /// An inotify instance with its watched directories.
+    pub struct Inotify {
+        file: File,
+        /// The watched directories by their watch descriptors.
+        dirs: HashMap<c_int, PathBuf>,
+    }
+
+    impl Inotify {
+        /// Creates the inotify instance without any watched directories.
+        pub fn new() -> io::Result<Inotify> {
+            // SAFETY: the call has no pointer arguments
+            let fd = unsafe { inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: the descriptor was just created and isn't owned by
+            // anything else
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+            Ok(Inotify { file: File::from(fd), dirs: HashMap::new() })
+        }
+
+        /// Watches the directory (without its subdirectories).
+        pub fn add_watch(&mut self, dir: &Path) -> io::Result<()> {
+            let path = CString::new(dir.as_os_str().as_bytes())?;
+            // SAFETY: the descriptor is valid and the path is null
+            // terminated
+            let wd = unsafe {
+                inotify_add_watch(self.file.as_raw_fd(), path.as_ptr(), MASK)
+            };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            self.dirs.insert(wd, dir.to_path_buf());
+            Ok(())
+        }
+
+        /// Waits until there are events to read. Returns false if there are
+        /// none after the timeout (None waits indefinitely).
+        pub fn poll(&self, timeout: Option<Duration>) -> io::Result<bool> {
+            let timeout = timeout.map_or(-1, |t| {
+                t.as_millis().min(c_int::MAX as u128) as c_int
+            });
+            let mut fd = PollFd {
+                fd: self.file.as_raw_fd(), events: POLLIN, revents: 0,
+            };
+            loop {
+                // SAFETY: the pointer is valid for the duration of the call
+                match unsafe { poll(&mut fd, 1, timeout) } {
+                    result if result >= 0 => return Ok(result > 0),
+                    _ => {
+                        let error = io::Error::last_os_error();
+                        if error.kind() != io::ErrorKind::Interrupted {
+                            return Err(error);
+                        }
+                    },
+                }
+            }
+        }
+
+        /// Reads the events that are waiting in the queue.
+        pub fn read_events(&mut self) -> io::Result<Vec<Event>> {
+            let mut events = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let length = match self.file.read(&mut buffer) {
+                    Ok(length) => length,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                        continue;
+                    },
+                    Err(e) => return Err(e),
+                };
+                let mut offset = 0;
+                while offset + EVENT_SIZE <= length {
+                    let u32_at = |i: usize| u32::from_ne_bytes([
+                        buffer[offset + i], buffer[offset + i + 1],
+                        buffer[offset + i + 2], buffer[offset + i + 3],
+                    ]);
+                    let (wd, mask) = (u32_at(0) as c_int, u32_at(4));
+                    let name_length = u32_at(12) as usize;
+                    let name = &buffer[offset + EVENT_SIZE..]
+                        [..name_length.min(length - offset - EVENT_SIZE)];
+                    offset += EVENT_SIZE + name_length;
+                    // The name is padded with the null bytes
+                    let end = name.iter().position(|b| *b == 0)
+                        .unwrap_or(name.len());
+                    let name = OsStr::from_bytes(&name[..end]);
+                    events.extend(self.event(wd, mask, name));
+                }
+            }
+            Ok(events)
+        }
+
+        /// Converts the raw event to the Event. Returns None for the events
+        /// that don't change the files.
+        fn event(
+            &mut self, wd: c_int, mask: u32, name: &OsStr,
+        ) -> Option<Event> {
+            if mask & IN_Q_OVERFLOW != 0 {
+                return Some(Event::Overflow);
+            }
+            if mask & IN_IGNORED != 0 {
+                self.dirs.remove(&wd);
+                return None;
+            }
+            let dir = self.dirs.get(&wd)?;
+            let path = match name.is_empty() {
+                true => dir.clone(),
+                false => dir.join(name),
+            };
+            let created = mask & IN_ISDIR != 0
+                && mask & (IN_CREATE | IN_MOVED_TO) != 0;
+            Some(match created {
+                true => Event::Created(path),
+                false => Event::Changed(path),
+            })
+        }
+    }
+}
+
+/// The modification time and the size of a file used to detect changes.
+#[cfg(not(target_os = "linux"))]
+type FileStamp = (Option<SystemTime>, u64);
+
+/// Watches the directories (with their subdirectories) for the changes of
+/// their files using the notifications of the file system (inotify on
+/// Linux). On the other platforms the directories are scanned for the
+/// changed modification times and sizes of the files instead.
+#[cfg(not(target_os = "linux"))]
+pub struct Watcher {
+    dirs: Vec<PathBuf>,
+    follow_symlinks: bool,
+    /// The stamps of the files from the last scan.
+    stamps: HashMap<PathBuf, FileStamp>,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Watcher {
+    /// Starts watching the directories.
+    ///
+    /// # Arguments
+    /// - `dirs` - the directories to watch
+    /// - `follow_symlinks` - whether to watch the directories behind the
+    ///   symbolic links
+    pub fn new(
+        dirs: &[PathBuf], follow_symlinks: bool,
+    ) -> io::Result<Watcher> {
+        let mut watcher = Watcher {
+            dirs: dirs.to_vec(),
+            follow_symlinks,
+            stamps: HashMap::new(),
+        };
+        watcher.stamps = watcher.scan();
+        Ok(watcher)
+    }
+
+    /// Returns the stamps of the files of the watched directories.
+    fn scan(&self) -> HashMap<PathBuf, FileStamp> {
+        let mut stamps = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut on_entry = |path: &Path, is_dir: bool| {
+            if let Ok(metadata) = fs::metadata(path).filter(|_| !is_dir) {
+                stamps.insert(
+                    path.to_path_buf(),
+                    (metadata.modified().ok(), metadata.len()),
+                );
+            }
+        };
+        for dir in &self.dirs {
+            list_dir(dir, self.follow_symlinks, &mut visited, &mut on_entry);
+        }
+        stamps
+    }
+
+    /// Waits for the changes and returns them. The directories are scanned
+    /// every `delay`.
+    pub fn wait(&mut self, delay: Duration) -> io::Result<Changes> {
+        loop {
+            std::thread::sleep(delay);
+            let stamps = self.scan();
+            let mut paths: Vec<PathBuf> = stamps.iter()
+                .filter(|(path, stamp)| self.stamps.get(*path) != Some(stamp))
+                .map(|(path, _)| path.clone())
+                .chain(self.stamps.keys()
+                    .filter(|path| !stamps.contains_key(*path))
+                    .cloned())
+                .collect();
+            self.stamps = stamps;
+            if !paths.is_empty() {
+                paths.sort();
+                return Ok(Changes::Paths(paths));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_changed_files_and_the_files_of_the_new_directories() {
+        let dir = std::env::temp_dir()
+            .join(format!("cpf_notify_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut watcher =
+            Watcher::new(std::slice::from_ref(&dir), true).unwrap();
+        fs::write(dir.join("a.json"), "{}").unwrap();
+        let delay = Duration::from_millis(50);
+        let changes = match watcher.wait(delay).unwrap() {
+            Changes::Paths(paths) => paths,
+            Changes::Rescan => panic!("Unexpected rescan"),
+        };
+        assert_eq!(changes, [dir.join("a.json")]);
+        fs::create_dir_all(dir.join("b/c")).unwrap();
+        fs::write(dir.join("b/c/d.json"), "{}").unwrap();
+        let changes = match watcher.wait(delay).unwrap() {
+            Changes::Paths(paths) => paths,
+            Changes::Rescan => panic!("Unexpected rescan"),
+        };
+        assert!(changes.contains(&dir.join("b/c/d.json")), "{:?}", changes);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::hash::hash_bytes;
+use crate::mapping::{resource_dir, MapResult, Mapper, Pack, TargetInfo};
+use crate::plan::PlannedFile;
+
+/// The name of the rule of the manifests of the behavior packs with the
+/// script module added by the ScriptManifest.
+pub static SCRIPT_MANIFEST_RULE: &str = "(script manifest)";
+
+/// The name of the manifest of the packs.
+pub static PACK_MANIFEST_NAME: &str = "manifest.json";
+
+/// The version of the script module added to the manifest without the
+/// version in the header.
+static DEFAULT_VERSION: [u32; 3] = [1, 0, 0];
+
+/// Adds the script module and the dependencies on the script modules of the
+/// game to the manifests of the behavior packs with the scripts (e.g.
+/// "BP/manifest.json" with "BP/scripts/main.js") copied from the data
+/// directory. See `script_manifest_contents`. The manifests that are already
+/// in the packs are updated after copying the files.
+pub struct ScriptManifest;
+
+impl Mapper for ScriptManifest {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The scripts by the packs
+        let mut scripts: BTreeMap<PathBuf, Vec<(PathBuf, PathBuf)>> =
+            BTreeMap::new();
+        for file in files.iter() {
+            let target = match &file.target {
+                Some(target) if target.pack == Pack::Behavior
+                    && target.path.extension().is_some_and(|e| e == "js") => {
+                    target
+                },
+                _ => continue,
+            };
+            let pack = resource_dir(&target.path, "scripts")
+                .and_then(|dir| Some(dir.parent()?.to_path_buf()));
+            if let Some(pack) = pack {
+                scripts.entry(pack).or_default()
+                    .push((file.source.clone(), target.path.clone()));
+            }
+        }
+        for (pack, mut inputs) in scripts {
+            inputs.sort_by(|a, b| a.1.cmp(&b.1));
+            let path = pack.join(PACK_MANIFEST_NAME);
+            let file = files.iter_mut()
+                .find(|file| file.target_path() == Some(&path));
+            if let Some(file) = file {
+                file.target = Some(
+                    TargetInfo::new(SCRIPT_MANIFEST_RULE, path)
+                        .with_inputs(inputs)
+                );
+            }
+        }
+    }
+}
+
+/// Returns the UUID of the script module derived from the seed (the UUID of
+/// the header of the manifest), so the same pack always gets the same UUID.
+/// The UUID is formatted as a random (version 4) UUID.
+pub fn derived_uuid(seed: &str) -> String {
+    let high = hash_bytes(format!("{}:script", seed).as_bytes());
+    let low = hash_bytes(format!("{}:module", seed).as_bytes());
+    let high = (high & !0xf000) | 0x4000;
+    let low = (low & !(0xc << 60)) | (0x8 << 60);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32, (high >> 16) & 0xffff, high & 0xffff, low >> 48,
+        low & 0xffff_ffff_ffff,
+    )
+}
+
+/// Adds the script module and the dependencies on the script modules of the
+/// game (e.g. "@minecraft/server") to the manifest of the behavior pack, or
+/// fixes their versions. Returns the new content of the manifest or None if
+/// it already has them.
+///
+/// # Arguments
+/// - `text` - the content of the manifest
+/// - `entry` - the entry of the script module added to the manifest
+///   relative to the pack (e.g. "scripts/main.js")
+/// - `dependencies` - the names of the modules and their versions
+pub fn script_manifest_contents(
+    text: &str, entry: &str, dependencies: &[(&str, &str)],
+) -> Result<Option<Vec<u8>>, String> {
+    let mut json: serde_json::Value =
+        serde_json::from_str(text.trim_start_matches('\u{feff}'))
+            .map_err(|e| e.to_string())?;
+    let original = json.clone();
+    let header = &json["header"];
+    let seed = header["uuid"].as_str().unwrap_or_default().to_string();
+    let version = match &header["version"] {
+        version @ serde_json::Value::Array(_) => version.clone(),
+        _ => serde_json::json!(DEFAULT_VERSION),
+    };
+    let manifest = json.as_object_mut()
+        .ok_or("The manifest isn't an object.")?;
+
+    // The script module
+    let modules = manifest.entry("modules")
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or("The \"modules\" isn't an array.")?;
+    let is_script = |module: &serde_json::Value| module["type"] == "script";
+    if !modules.iter().any(is_script) {
+        modules.push(serde_json::json!({
+            "type": "script",
+            "uuid": derived_uuid(&seed),
+            "version": version,
+        }));
+    }
+    for module in modules.iter_mut().filter(|m| is_script(m)) {
+        let module = module.as_object_mut()
+            .ok_or("The script module isn't an object.")?;
+        module.insert("language".into(), "javascript".into());
+        if !module.get("entry").is_some_and(|e| e.is_string()) {
+            module.insert("entry".into(), entry.into());
+        }
+    }
+
+    // The dependencies on the script modules of the game
+    let existing = manifest.entry("dependencies")
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or("The \"dependencies\" isn't an array.")?;
+    for (name, version) in dependencies {
+        let dependency = existing.iter_mut()
+            .find(|d| d["module_name"] == *name);
+        match dependency.and_then(|d| d.as_object_mut()) {
+            Some(dependency) => {
+                dependency.insert("version".into(), (*version).into());
+            },
+            None => existing.push(serde_json::json!({
+                "module_name": name,
+                "version": version,
+            })),
+        }
+    }
+    match json == original {
+        true => Ok(None),
+        false => Ok(Some(format!("{:#}\n", json).into_bytes())),
+    }
+}
@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use crate::config::{
+    Config, DEFAULT_MAX_DEPTH, FILTER_DATA_PATH
+};
+use crate::error::CpfError;
+use crate::mapping::{MapResult, Mapper, TargetInfo};
+use crate::report;
+
+/// A file found in the data directory together with the result of mapping it
+/// to the packs.
+#[derive(Clone)]
+pub struct PlannedFile {
+    /// The path to the source file.
+    pub source: PathBuf,
+    /// The target of the file or None if the file can't be mapped to the
+    /// packs.
+    pub target: Option<TargetInfo>,
+}
+
+impl PlannedFile {
+    /// Returns the path to the target file relative to the working directory
+    /// or None if the file can't be mapped to the packs.
+    pub fn target_path(&self) -> Option<&PathBuf> {
+        self.target.as_ref().map(|target| &target.path)
+    }
+
+    /// Returns the name of the rule that matched the file or None if no rule
+    /// matched.
+    pub fn rule(&self) -> Option<&str> {
+        self.target.as_ref().map(|target| target.rule.as_str())
+    }
+
+    /// Returns the operation that copies the file to its target or None if
+    /// the file can't be mapped to the packs.
+    pub fn operation(&self) -> Option<CopyOperation> {
+        Some(CopyOperation {
+            source: self.source.clone(),
+            target: self.target.clone()?,
+        })
+    }
+}
+
+/// A file that is going to be copied to the packs. The list of the
+/// operations is the result of planning (with the conflicts resolved) and
+/// can be inspected or modified before it's executed.
+#[derive(Clone, Debug)]
+pub struct CopyOperation {
+    /// The path to the source file.
+    pub source: PathBuf,
+    /// The target of the file.
+    pub target: TargetInfo,
+}
+
+/// The maximal number of the planned files waiting in the channel between
+/// the threads walking the directories and the consumer.
+static CHANNEL_BOUND: usize = 1024;
+
+/// The options that control how the directories of the roots are walked.
+#[derive(Clone)]
+pub struct WalkOptions {
+    /// Whether to follow the symbolic links in the data directory. The links
+    /// are skipped with a warning otherwise.
+    pub follow_symlinks: bool,
+    /// The maximal depth of the directories relative to the root. The walk
+    /// fails if a deeper directory is found, which protects against the
+    /// cycles that can't be detected (e.g. bind mounts).
+    pub max_depth: usize,
+    /// The number of threads used for walking the directories. 0 means the
+    /// number of the available CPUs.
+    pub jobs: usize,
+}
+
+impl Default for WalkOptions {
+    fn default() -> WalkOptions {
+        WalkOptions {
+            follow_symlinks: true,
+            max_depth: DEFAULT_MAX_DEPTH,
+            jobs: 0,
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Returns the walk options set in the config file.
+    pub fn from_config(config: &Config) -> WalkOptions {
+        WalkOptions {
+            follow_symlinks: config.follow_symlinks,
+            max_depth: config.max_depth,
+            jobs: config.jobs,
+        }
+    }
+}
+
+/// A directory waiting to be walked.
+struct QueuedDir {
+    path: PathBuf,
+    /// The depth of the directory relative to the root.
+    depth: usize,
+    /// The canonical paths of the directory and its ancestors used for
+    /// detecting the cycles of the symbolic links. Empty if the links aren't
+    /// followed.
+    ancestors: Vec<PathBuf>,
+}
+
+/// The queue of the directories to walk shared between the threads.
+struct WalkQueue {
+    dirs: Vec<QueuedDir>,
+    /// The number of the directories that are currently being walked.
+    active: usize,
+}
+
+/// The state shared between the threads walking the directories of a root.
+struct Walker<'a> {
+    root_dir: &'a Path,
+    mapper: &'a dyn Mapper,
+    options: &'a WalkOptions,
+    queue: Mutex<WalkQueue>,
+    condvar: Condvar,
+}
+
+/// Maps a file from the root directory to the packs.
+pub fn plan_file(
+    fp: PathBuf, root_dir: &Path, mapper: &dyn Mapper,
+) -> PlannedFile {
+    let root_len = root_dir.components().count();
+    let short_fp = fp.components().skip(root_len).collect::<PathBuf>();
+    let target = match mapper.map(&short_fp) {
+        MapResult::Mapped(target) => Some(target),
+        _ => None,
+    };
+    PlannedFile { source: fp, target }
+}
+
+impl Walker<'_> {
+    /// Adds a directory to the queue and wakes up one of the threads.
+    fn push(&self, dir: QueuedDir) {
+        self.queue.lock().unwrap().dirs.push(dir);
+        self.condvar.notify_one();
+    }
+
+    /// Waits for a directory to walk. Returns None when all of the
+    /// directories are walked.
+    fn pop(&self) -> Option<QueuedDir> {
+        let mut q = self.queue.lock().unwrap();
+        loop {
+            if let Some(dir) = q.dirs.pop() {
+                q.active += 1;
+                return Some(dir);
+            }
+            if q.active == 0 {
+                self.condvar.notify_all();
+                return None;
+            }
+            q = self.condvar.wait(q).unwrap();
+        }
+    }
+
+    /// Marks a directory returned by pop as walked. If walking it failed,
+    /// the rest of the queue is dropped to stop the walk.
+    fn done(&self, failed: bool) {
+        let mut q = self.queue.lock().unwrap();
+        if failed {
+            q.dirs.clear();
+        }
+        q.active -= 1;
+        if q.active == 0 && q.dirs.is_empty() {
+            self.condvar.notify_all();
+        }
+    }
+
+    /// Walks the files of a single directory. The files are mapped to the
+    /// packs and sent to the channel and the subdirectories are added to the
+    /// queue.
+    fn walk_dir(
+        &self, curr_dir: &QueuedDir, sender: &SyncSender<PlannedFile>,
+    ) -> Result<(), CpfError> {
+        let dir = match fs::read_dir(&curr_dir.path) {
+            Ok(dir) => dir,
+            Err(e) => {
+                eprintln!(
+                    "Failed to read directory: {}", curr_dir.path.display()
+                );
+                return Err(CpfError::io(e, &curr_dir.path));
+            }
+        };
+        for entry in dir {
+            let entry = entry.map_err(|e| CpfError::io(e, &curr_dir.path))?;
+            let fp = entry.path();
+            let file_type = entry.file_type()
+                .map_err(|e| CpfError::io(e, &fp))?;
+            let is_dir = if file_type.is_symlink() {
+                if !self.options.follow_symlinks {
+                    report::warning(
+                        &fp,
+                        "The file is a symbolic link. Skipped. Set \
+                        \"follow_symlinks\" to true in the config file to \
+                        follow the links.",
+                    );
+                    continue;
+                }
+                match fs::metadata(&fp) {
+                    Ok(metadata) => metadata.is_dir(),
+                    Err(_) => {
+                        report::warning(
+                            &fp, "The file is a broken symbolic link. Skipped."
+                        );
+                        continue;
+                    }
+                }
+            } else {
+                file_type.is_dir()
+            };
+
+            // Directory - add to the queue
+            if is_dir {
+                let depth = curr_dir.depth + 1;
+                if depth > self.options.max_depth {
+                    return Err(CpfError::Walk(format!(
+                        "The directory \"{}\" is nested deeper than the \
+                        limit of {} directories. Increase \"max_depth\" in \
+                        the config file if it's intended.",
+                        fp.display(), self.options.max_depth
+                    )));
+                }
+                let mut ancestors = Vec::new();
+                if self.options.follow_symlinks {
+                    let canonical = fs::canonicalize(&fp)
+                        .map_err(|e| CpfError::io(e, &fp))?;
+                    if curr_dir.ancestors.contains(&canonical) {
+                        report::warning(
+                            &fp,
+                            "The link points to its own parent directory. \
+                            Skipped to avoid an infinite loop.",
+                        );
+                        continue;
+                    }
+                    ancestors = curr_dir.ancestors.clone();
+                    ancestors.push(canonical);
+                }
+                self.push(QueuedDir { path: fp, depth, ancestors });
+                continue;
+            }
+
+            // Not a directory - find the rule and the target
+            // The receiver is only dropped after the walk, so sending can't
+            // fail
+            let _ = sender.send(
+                plan_file(fp, self.root_dir, self.mapper)
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Walks the files starting from the root_dir using multiple threads and
+/// sends them to the channel mapped to the packs with export paths relative
+/// to the root_dir generated by the mapper. This function is used in
+/// plan_root function.
+///
+/// # Arguments
+/// - `root_dir` - the root directory to walk
+/// - `mapper` - the mapper that generates the export paths
+/// - `options` - the options that control how the directories are walked
+/// - `sender` - the channel to which the planned files are sent
+fn walk_parallel(
+    root_dir: &Path, mapper: &dyn Mapper, options: &WalkOptions,
+    sender: SyncSender<PlannedFile>,
+) -> Result<(), CpfError> {
+    let jobs = match options.jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        jobs => jobs,
+    };
+    let mut root = QueuedDir {
+        path: root_dir.to_path_buf(),
+        depth: 0,
+        ancestors: Vec::new(),
+    };
+    if options.follow_symlinks {
+        if let Ok(canonical) = fs::canonicalize(root_dir) {
+            root.ancestors.push(canonical);
+        }
+    }
+    let walker = Walker {
+        root_dir,
+        mapper,
+        options,
+        queue: Mutex::new(WalkQueue { dirs: vec![root], active: 0 }),
+        condvar: Condvar::new(),
+    };
+    let error: Mutex<Option<CpfError>> = Mutex::new(None);
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (walker, error) = (&walker, &error);
+            s.spawn(move || {
+                while let Some(dir) = walker.pop() {
+                    let result = walker.walk_dir(&dir, &sender);
+                    let failed = result.is_err();
+                    if let Err(e) = result {
+                        error.lock().unwrap().get_or_insert(e);
+                    }
+                    walker.done(failed);
+                }
+            });
+        }
+    });
+    match error.into_inner().unwrap() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Lists the files of a single root directory and maps them to the packs.
+/// The directories are walked in parallel but the result is sorted by the
+/// paths of the source files, so it doesn't depend on the platform or the
+/// order in which the threads finish.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `mapper` - the mapper that generates the export paths
+/// - `root` - the path relative to the filter data path that serves as the
+///   root of the source files
+/// - `options` - the options that control how the directories are walked
+pub fn plan_root(
+    working_dir: &Path, mapper: &dyn Mapper, root: &str,
+    options: &WalkOptions,
+) -> Result<Vec<PlannedFile>, CpfError> {
+    let mut result = walk_root(working_dir, mapper, root, options)?;
+    mapper.group(&working_dir.join(FILTER_DATA_PATH).join(root), &mut result);
+    Ok(result)
+}
+
+/// Lists the files of a single root directory and maps them to the packs
+/// like plan_root, but without grouping them (see `Mapper::group`).
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `mapper` - the mapper that generates the export paths
+/// - `root` - the path relative to the filter data path that serves as the
+///   root of the source files
+/// - `options` - the options that control how the directories are walked
+pub fn walk_root(
+    working_dir: &Path, mapper: &dyn Mapper, root: &str,
+    options: &WalkOptions,
+) -> Result<Vec<PlannedFile>, CpfError> {
+    let root = working_dir.join(FILTER_DATA_PATH).join(root);
+    let (sender, receiver) = sync_channel(CHANNEL_BOUND);
+    let mut result = Vec::new();
+    thread::scope(|s| {
+        let walker = s.spawn(
+            || walk_parallel(&root, mapper, options, sender)
+        );
+        result.extend(receiver.iter());
+        walker.join().unwrap()
+    })?;
+    result.sort_by(|a, b| a.source.cmp(&b.source));
+    Ok(result)
+}
+
+/// Maps a virtual list of files to the packs without accessing the file
+/// system, so it can be used where there's no file system (e.g. in the
+/// WebAssembly builds for the web tools). The result is sorted by the paths
+/// like the result of plan_root.
+///
+/// # Arguments
+/// - `mapper` - the mapper that generates the export paths
+/// - `root_dir` - the path to the root that the paths are relative to, used
+///   as the prefix of the paths of the source files
+/// - `paths` - the paths to the files relative to the root
+pub fn plan_paths(
+    mapper: &dyn Mapper, root_dir: &Path, paths: &[PathBuf],
+) -> Vec<PlannedFile> {
+    let mut result: Vec<PlannedFile> = paths.iter()
+        .map(|path| plan_file(root_dir.join(path), root_dir, mapper))
+        .collect();
+    result.sort_by(|a, b| a.source.cmp(&b.source));
+    mapper.group(root_dir, &mut result);
+    result
+}
+
+/// Lists the files of all of the roots from the config file and maps them to
+/// the packs.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `config` - the config file with the roots, the export map and the
+///   options that control how the directories are walked
+pub fn plan(
+    working_dir: &Path, config: &Config,
+) -> Result<Vec<PlannedFile>, CpfError> {
+    crate::Planner::from_config(working_dir, config).plan()
+}
+
+/// Finds the targets that more than one source file is mapped to. Returns
+/// the list of the conflicting targets with the indices of the files that
+/// are mapped to them.
+///
+/// # Arguments
+/// - `files` - the planned files
+pub fn find_conflicts(files: &[PlannedFile]) -> Vec<(PathBuf, Vec<usize>)> {
+    let mut by_target: HashMap<&Path, Vec<usize>> = HashMap::new();
+    let mut order: Vec<&Path> = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        if let Some(target) = file.target_path() {
+            let sources = by_target.entry(target).or_default();
+            if sources.is_empty() {
+                order.push(target);
+            }
+            sources.push(i);
+        }
+    }
+    order.into_iter()
+        .filter(|target| by_target[target].len() > 1)
+        .map(|target| (target.to_path_buf(), by_target[target].clone()))
+        .collect()
+}
+
+/// Finds the targets that differ only by the case of the letters (e.g.
+/// "Zombie.png" and "zombie.png"). They collide on the case-insensitive
+/// file systems of Windows and macOS. Returns the groups of the indices of
+/// the files with such targets (one file for every distinct target).
+///
+/// # Arguments
+/// - `files` - the planned files
+pub fn find_case_conflicts(files: &[PlannedFile]) -> Vec<Vec<usize>> {
+    let mut by_target: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        let target = match file.target_path() {
+            Some(target) => target,
+            None => continue,
+        };
+        let key = target.to_string_lossy().to_lowercase();
+        let group = by_target.entry(key.clone()).or_default();
+        if group.is_empty() {
+            order.push(key);
+        }
+        // Only one file for every distinct target
+        if !group.iter().any(|j| files[*j].target_path() == Some(target)) {
+            group.push(i);
+        }
+    }
+    order.into_iter()
+        .filter_map(|key| by_target.remove(&key))
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapping::FallbackMapper;
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_the_symbolic_links_by_default() {
+        let dir = std::env::temp_dir()
+            .join(format!("cpf_plan_symlinks_{}", std::process::id()));
+        let root = dir.join(FILTER_DATA_PATH).join("src");
+        let shared = dir.join("shared");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&shared).unwrap();
+        fs::write(shared.join("a.txt"), "a").unwrap();
+        std::os::unix::fs::symlink(&shared, root.join("shared")).unwrap();
+        // The link to the parent directory is skipped
+        std::os::unix::fs::symlink(&root, root.join("loop")).unwrap();
+        let files = plan_root(
+            &dir, &FallbackMapper::new("BP"), "src", &WalkOptions::default()
+        ).unwrap();
+        let targets: Vec<&PathBuf> = files.iter()
+            .filter_map(|file| file.target_path())
+            .collect();
+        assert_eq!(targets, vec![&PathBuf::from("BP/shared/a.txt")]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::check::{Problem, Severity};
+use crate::mapping::{MapResult, Mapper, TargetInfo};
+use crate::report;
+
+/// The name of the rule of the files mapped by the plugins.
+pub static PLUGIN_RULE: &str = "(plugin)";
+
+/// The WASI runtime that runs the plugins. It can't be changed in the config
+/// file, so the plugins can't be used for running arbitrary commands.
+static RUNTIME: &str = "wasmtime";
+
+/// The limits of the sandbox of the plugins passed to the runtime. The
+/// modules don't get access to the file system, the network or the
+/// environment variables because the runtime doesn't grant them by default.
+static SANDBOX_FLAGS: [&str; 4] = [
+    "-W", "timeout=30s", "-W", "max-memory-size=268435456",
+];
+
+/// The first bytes of every WebAssembly module.
+static WASM_MAGIC: &[u8] = b"\0asm";
+
+/// What a plugin does with the files that it handles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PluginAction {
+    /// Maps the files to the targets.
+    Map,
+    /// Transforms the contents of the files copied to the packs.
+    Transform,
+    /// Validates the files.
+    Validate,
+}
+
+impl PluginAction {
+    /// Returns the action with the name from the config file.
+    pub fn from_name(name: &str) -> Option<PluginAction> {
+        match name {
+            "map" => Some(PluginAction::Map),
+            "transform" => Some(PluginAction::Transform),
+            "validate" => Some(PluginAction::Validate),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the action passed to the plugin.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PluginAction::Map => "map",
+            PluginAction::Transform => "transform",
+            PluginAction::Validate => "validate",
+        }
+    }
+}
+
+/// A WASM module (compiled for WASI) that maps, transforms or validates the
+/// files with its extensions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plugin {
+    /// The path to the module. It's relative to the data directory in the
+    /// config file and Config::load resolves it relative to the working
+    /// directory.
+    pub module: PathBuf,
+    /// The extensions of the files handled by the plugin (e.g. ".up.json").
+    pub extensions: Vec<String>,
+    /// What the plugin does with the files.
+    pub actions: Vec<PluginAction>,
+}
+
+impl Plugin {
+    /// Checks if the plugin does the action with the file.
+    fn handles(&self, path: &Path, action: PluginAction) -> bool {
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        self.actions.contains(&action)
+            && self.extensions.iter().any(|e| name.ends_with(e.as_str()))
+    }
+}
+
+/// Checks if the path to the module from the config file is a relative path
+/// to a ".wasm" file that stays inside of the data directory.
+pub fn is_valid_module_path(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+        && path.extension().is_some_and(|e| e == "wasm")
+}
+
+/// Checks if the file is a WebAssembly module.
+///
+/// # Arguments
+/// - `path` - the path to the module
+pub fn check_module(path: &Path) -> Result<(), String> {
+    let mut magic = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_err(|e| format!(
+            "Unable to read the plugin \"{}\": {}", path.display(), e
+        ))?;
+    if magic != WASM_MAGIC {
+        return Err(format!(
+            "The plugin \"{}\" is not a WebAssembly module.", path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// The target of a file mapped by the plugins.
+type PluginTarget = Result<Option<PathBuf>, String>;
+
+/// The plugins of the project.
+///
+/// The plugins are run by the wasmtime runtime with the path to the module,
+/// the name of the action and the path to the file relative to its root as
+/// the arguments (e.g. `wasmtime run plugins/x.wasm map zombie/_.up.json`).
+/// The content of the file is passed on the standard input. The modules only
+/// see the files that the filter passes to them.
+#[derive(Clone, Debug)]
+pub struct Plugins {
+    pub plugins: Vec<Plugin>,
+    /// The paths to the roots of the data directory. The plugins get the
+    /// paths to the files relative to their roots.
+    pub roots: Vec<PathBuf>,
+    /// The runtime that runs the modules (replaced in the tests).
+    pub(crate) runtime: PathBuf,
+    /// The targets printed by the map action of the plugins for every path,
+    /// so every file is only mapped once.
+    map_results: Arc<Mutex<HashMap<PathBuf, PluginTarget>>>,
+}
+
+impl Default for Plugins {
+    fn default() -> Plugins {
+        Plugins {
+            plugins: Vec::new(),
+            roots: Vec::new(),
+            runtime: PathBuf::from(RUNTIME),
+            map_results: Arc::default(),
+        }
+    }
+}
+
+impl Plugins {
+    /// Creates the plugins.
+    ///
+    /// # Arguments
+    /// - `plugins` - the plugins with the paths to the modules
+    /// - `roots` - the paths to the roots of the data directory
+    pub fn new(plugins: Vec<Plugin>, roots: Vec<PathBuf>) -> Plugins {
+        Plugins { plugins, roots, ..Plugins::default() }
+    }
+
+    /// Returns the plugins that do the action with the file.
+    fn handling<'a>(
+        &'a self, path: &'a Path, action: PluginAction,
+    ) -> impl Iterator<Item = &'a Plugin> {
+        self.plugins.iter().filter(move |p| p.handles(path, action))
+    }
+
+    /// Checks if any of the plugins maps the files.
+    pub fn maps_files(&self) -> bool {
+        self.plugins.iter().any(|p| p.actions.contains(&PluginAction::Map))
+    }
+
+    /// Runs the action of the plugin with the file and returns its standard
+    /// output. The error contains the standard error output of the failed
+    /// plugin.
+    ///
+    /// # Arguments
+    /// - `plugin` - the plugin to run
+    /// - `action` - the action passed to the plugin
+    /// - `path` - the path to the file relative to its root
+    /// - `input` - the content of the file
+    fn run(
+        &self, plugin: &Plugin, action: PluginAction, path: &Path,
+        input: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        check_module(&plugin.module)?;
+        let mut child = Command::new(&self.runtime)
+            .arg("run")
+            .args(SANDBOX_FLAGS)
+            .arg(&plugin.module)
+            .arg(action.name())
+            .arg(path.to_string_lossy().replace('\\', "/"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!(
+                "Unable to run the plugin runtime \"{}\": {}",
+                self.runtime.display(), e
+            ))?;
+        // The plugin can print the output before reading the whole input
+        let mut stdin = child.stdin.take().unwrap();
+        let writer = thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        let _ = writer.join();
+        if !output.status.success() {
+            return Err(format!(
+                "The plugin \"{}\" failed with {}: {}",
+                plugin.module.display(), output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output.stdout)
+    }
+
+    /// Returns the path to the source file relative to its root, which is
+    /// the path passed to the plugins by all of the actions.
+    fn root_path(&self, source: &Path) -> PathBuf {
+        self.roots.iter()
+            .filter_map(|root| source.strip_prefix(root).ok())
+            .min_by_key(|path| path.components().count())
+            .unwrap_or(source)
+            .to_path_buf()
+    }
+
+    /// Maps the file with the plugins that map it. Returns None if none of
+    /// the plugins prints a target for the file. The targets must be in the
+    /// "BP" or the "RP" directory. The result is cached, so the plugins run
+    /// only once for every path.
+    ///
+    /// # Arguments
+    /// - `path` - the path to the file relative to its root
+    pub fn map(&self, path: &Path) -> PluginTarget {
+        if let Some(result) = self.map_results.lock().unwrap().get(path) {
+            return result.clone();
+        }
+        let result = self.map_uncached(path);
+        if let Err(e) = &result {
+            report::warning(
+                path, format!("Unable to map the file with the plugins: {}", e)
+            );
+        }
+        self.map_results.lock().unwrap()
+            .insert(path.to_path_buf(), result.clone());
+        result
+    }
+
+    /// Maps the file with the plugins without using the cache.
+    fn map_uncached(&self, path: &Path) -> PluginTarget {
+        for plugin in self.handling(path, PluginAction::Map) {
+            let output =
+                self.run(plugin, PluginAction::Map, path, Vec::new())?;
+            let target = String::from_utf8_lossy(&output).trim().to_string();
+            if target.is_empty() {
+                continue;
+            }
+            let target: PathBuf = target.split('/').collect();
+            let mut components = target.components();
+            let in_packs = components.next()
+                .is_some_and(|c| c == Component::Normal("BP".as_ref())
+                    || c == Component::Normal("RP".as_ref()))
+                && components.next().is_some()
+                && target.components()
+                    .all(|c| matches!(c, Component::Normal(_)));
+            if !in_packs {
+                return Err(format!(
+                    "The plugin \"{}\" mapped the file to \"{}\" which is \
+                    outside of the \"BP\" and \"RP\" directories.",
+                    plugin.module.display(), target.display()
+                ));
+            }
+            return Ok(Some(target));
+        }
+        Ok(None)
+    }
+
+    /// Transforms the content of the source file with the plugins that
+    /// transform it, in the order of the plugins. Returns None if none of the
+    /// plugins transforms the file.
+    pub fn transform(
+        &self, source: &Path,
+    ) -> Option<Result<Vec<u8>, String>> {
+        let path = self.root_path(source);
+        let mut plugins = self.handling(&path, PluginAction::Transform)
+            .peekable();
+        plugins.peek()?;
+        let content = match fs::read(source) {
+            Ok(content) => content,
+            Err(e) => return Some(Err(e.to_string())),
+        };
+        Some(plugins.try_fold(content, |content, plugin| {
+            self.run(plugin, PluginAction::Transform, &path, content)
+        }))
+    }
+
+    /// Validates the source file with the plugins that validate it. Every
+    /// line of the output of a plugin is a problem. The lines that start with
+    /// "error:" are errors and the other lines are warnings.
+    pub fn validate(&self, source: &Path) -> Vec<Problem> {
+        let path = self.root_path(source);
+        let problem = |severity, message: &str| {
+            Problem::new(severity, source, message.trim())
+        };
+        let mut problems = Vec::new();
+        let mut content = None;
+        for plugin in self.handling(&path, PluginAction::Validate) {
+            let content = content.get_or_insert_with(|| fs::read(source));
+            let input = match content {
+                Ok(content) => content.clone(),
+                Err(e) => {
+                    let message = format!("Unable to read the file: {}", e);
+                    problems.push(problem(Severity::Error, &message));
+                    break;
+                },
+            };
+            let output = match self.run(
+                plugin, PluginAction::Validate, &path, input
+            ) {
+                Ok(output) => output,
+                Err(e) => {
+                    problems.push(problem(Severity::Error, &e));
+                    continue;
+                },
+            };
+            for line in String::from_utf8_lossy(&output).lines() {
+                let (severity, message) = match line.trim() {
+                    line if line.starts_with("error:") => {
+                        (Severity::Error, &line["error:".len()..])
+                    },
+                    line => (
+                        Severity::Warning,
+                        line.strip_prefix("warning:").unwrap_or(line),
+                    ),
+                };
+                if !message.trim().is_empty() {
+                    problems.push(problem(severity, message));
+                }
+            }
+        }
+        problems
+    }
+}
+
+/// Maps the files to the targets printed by the plugins (e.g.
+/// "BP/items/x.json"). The files that the plugins don't print any target for
+/// are mapped by the next mappers of the chain.
+pub struct PluginMapper {
+    plugins: Plugins,
+}
+
+impl PluginMapper {
+    /// Creates a mapper that uses the plugins that map the files.
+    pub fn new(plugins: Plugins) -> PluginMapper {
+        PluginMapper { plugins }
+    }
+}
+
+impl Mapper for PluginMapper {
+    fn map(&self, path: &Path) -> MapResult {
+        match self.plugins.map(path) {
+            Ok(Some(target)) => {
+                MapResult::Mapped(TargetInfo::new(PLUGIN_RULE, target))
+            },
+            Ok(None) => MapResult::Unmatched,
+            Err(_) => MapResult::Skipped("A plugin failed to map the file."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The fake runtime that runs the modules as shell scripts. The last two
+    /// arguments are the action and the path.
+    static RUNTIME_SCRIPT: &str = r#"#!/bin/sh
+for arg; do action=$path; path=$arg; done
+echo "$action $path" >> "$(dirname "$0")/calls.log"
+case "$action" in
+    map) case "$path" in
+        outside/*) echo "skins/x.json" ;;
+        *) echo "BP/items/$(basename "$path" .up.json).json" ;;
+    esac ;;
+    transform) tr a-z A-Z ;;
+    validate) grep -q bad && echo "error: The file is bad."; echo odd ;;
+esac
+"#;
+
+    /// Returns the plugins with a plugin that does all of the actions with
+    /// the ".up.json" files and the fake runtime in the directory.
+    #[cfg(unix)]
+    fn plugins(dir: &Path) -> Plugins {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let runtime = dir.join("wasmtime");
+        fs::write(&runtime, RUNTIME_SCRIPT).unwrap();
+        fs::set_permissions(&runtime, fs::Permissions::from_mode(0o755))
+            .unwrap();
+        let module = dir.join("plugin.wasm");
+        fs::write(&module, b"\0asm\x01\0\0\0").unwrap();
+        Plugins {
+            plugins: vec![Plugin {
+                module,
+                extensions: vec![".up.json".into()],
+                actions: vec![
+                    PluginAction::Map, PluginAction::Transform,
+                    PluginAction::Validate,
+                ],
+            }],
+            roots: vec![dir.join("src")],
+            runtime,
+            ..Plugins::default()
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_the_actions_with_the_paths_relative_to_the_root() {
+        let dir = std::env::temp_dir()
+            .join(format!("cpf_plugin_actions_{}", std::process::id()));
+        let plugins = plugins(&dir);
+        let path = Path::new("items/sword.up.json");
+        let target = Some(PathBuf::from("BP/items/sword.json"));
+        assert_eq!(plugins.map(path), Ok(target.clone()));
+        // The result is cached
+        assert_eq!(plugins.map(path), Ok(target));
+        assert_eq!(plugins.map(Path::new("sword.json")), Ok(None));
+        let source = dir.join("src/items/sword.up.json");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::write(&source, "bad").unwrap();
+        assert_eq!(plugins.transform(&source), Some(Ok(b"BAD".to_vec())));
+        let problems = plugins.validate(&source);
+        let severities: Vec<Severity> = problems.iter()
+            .map(|p| p.severity)
+            .collect();
+        assert!(severities == [Severity::Error, Severity::Warning]);
+        assert_eq!(problems[0].message, "The file is bad.");
+        let calls = fs::read_to_string(dir.join("calls.log")).unwrap();
+        assert_eq!(calls, "map items/sword.up.json\n\
+            transform items/sword.up.json\n\
+            validate items/sword.up.json\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_the_targets_outside_of_the_packs() {
+        let dir = std::env::temp_dir()
+            .join(format!("cpf_plugin_outside_{}", std::process::id()));
+        let plugins = plugins(&dir);
+        let error = plugins.map(Path::new("outside/a.up.json")).unwrap_err();
+        assert!(error.contains("outside of the \"BP\""), "{}", error);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn accepts_only_the_wasm_modules_in_the_data_directory() {
+        assert!(is_valid_module_path(Path::new("plugins/a.wasm")));
+        assert!(!is_valid_module_path(Path::new("../a.wasm")));
+        assert!(!is_valid_module_path(Path::new("/bin/a.wasm")));
+        assert!(!is_valid_module_path(Path::new("plugins/a.sh")));
+    }
+}
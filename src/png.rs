@@ -0,0 +1,571 @@
+use crate::zip::{crc32, crc_table};
+
+/// The signature at the start of the PNG files.
+static SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// The maximal size of the images (in pixels) that are decoded, which
+/// protects against the corrupted headers.
+const MAX_PIXELS: u64 = 1 << 26;
+
+/// An image with 8-bit RGBA pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// The pixels row by row, 4 bytes per pixel.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates a transparent image.
+    pub fn new(width: u32, height: u32) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Copies the image into this image with the top left corner at the
+    /// position. The parts outside of this image are cut off.
+    pub fn paste(&mut self, image: &Image, x: u32, y: u32) {
+        let width = image.width.min(self.width.saturating_sub(x)) as usize;
+        for row in 0..image.height.min(self.height.saturating_sub(y)) {
+            let from = row as usize * image.width as usize * 4;
+            let to = ((y + row) as usize * self.width as usize + x as usize)
+                * 4;
+            self.pixels[to..to + width * 4]
+                .copy_from_slice(&image.pixels[from..from + width * 4]);
+        }
+    }
+}
+
+/// Reads the bits of the DEFLATE stream (the least significant bits first).
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    /// Reads the number of bits (at most 16).
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        while self.count < count {
+            let byte = *self.data.get(self.position)
+                .ok_or("The compressed data is truncated.")?;
+            self.position += 1;
+            self.buffer |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let result = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(result)
+    }
+
+    /// Drops the bits up to the next byte boundary.
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+/// The canonical Huffman code used by the DEFLATE streams.
+struct Huffman {
+    /// The number of the codes of every length.
+    counts: [u16; 16],
+    /// The symbols sorted by their codes.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Creates the code from the lengths of the codes of the symbols.
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                symbols[offsets[*length as usize] as usize] = symbol as u16;
+                offsets[*length as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    /// Decodes a symbol from the stream.
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - count < first {
+                return self.symbols.get((index + code - first) as usize)
+                    .copied()
+                    .ok_or_else(|| "Invalid Huffman code.".to_string());
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("Invalid Huffman code.".into())
+    }
+}
+
+/// The base lengths of the length symbols 257..285.
+static LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59,
+    67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+
+/// The numbers of the extra bits of the length symbols.
+static LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4,
+    5, 5, 5, 5, 0,
+];
+
+/// The base distances of the distance symbols.
+static DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513,
+    769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// The numbers of the extra bits of the distance symbols.
+static DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10,
+    11, 11, 12, 12, 13, 13,
+];
+
+/// The order of the lengths of the code length codes.
+static CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Reads the dynamic Huffman codes of a block.
+fn dynamic_codes(
+    reader: &mut BitReader,
+) -> Result<(Huffman, Huffman), String> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for i in CODE_LENGTH_ORDER.iter().take(code_lengths) {
+        lengths[*i] = reader.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths);
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (value, repeat) = match code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last()
+                    .ok_or("Invalid code lengths.")?;
+                (previous, 3 + reader.bits(2)?)
+            },
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literals + distances {
+        return Err("Invalid code lengths.".into());
+    }
+    Ok((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+/// Returns the error for the image data that is longer than its size.
+fn too_long() -> String {
+    "The image data is longer than the image.".into()
+}
+
+/// Decompresses the zlib stream. The streams that decompress to more than
+/// `limit` bytes are rejected, so a small corrupted image can't exhaust
+/// the memory.
+fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 2 || data[0] & 0x0f != 8 {
+        return Err("The image data isn't a zlib stream.".into());
+    }
+    let mut reader = BitReader {
+        data: &data[2..], position: 0, buffer: 0, count: 0,
+    };
+    let mut output: Vec<u8> = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let start = reader.position;
+                let header = reader.data.get(start..start + 4)
+                    .ok_or("The compressed data is truncated.")?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                let complement = u16::from_le_bytes([header[2], header[3]]);
+                if complement != !length {
+                    return Err("Invalid length of the stored block.".into());
+                }
+                if output.len() + length as usize > limit {
+                    return Err(too_long());
+                }
+                let stored = reader.data
+                    .get(start + 4..start + 4 + length as usize)
+                    .ok_or("The compressed data is truncated.")?;
+                output.extend_from_slice(stored);
+                reader.position = start + 4 + length as usize;
+            },
+            kind @ (1 | 2) => {
+                let (literals, distances) = match kind {
+                    1 => {
+                        let mut lengths = [8u8; 288];
+                        lengths[144..256].fill(9);
+                        lengths[256..280].fill(7);
+                        (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+                    },
+                    _ => dynamic_codes(&mut reader)?,
+                };
+                loop {
+                    let symbol = literals.decode(&mut reader)? as usize;
+                    if symbol < 256 {
+                        if output.len() == limit {
+                            return Err(too_long());
+                        }
+                        output.push(symbol as u8);
+                        continue;
+                    }
+                    if symbol == 256 {
+                        break;
+                    }
+                    let i = symbol - 257;
+                    if i >= LENGTH_BASE.len() {
+                        return Err("Invalid length symbol.".into());
+                    }
+                    let length = LENGTH_BASE[i] as usize
+                        + reader.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                    let j = distances.decode(&mut reader)? as usize;
+                    if j >= DISTANCE_BASE.len() {
+                        return Err("Invalid distance symbol.".into());
+                    }
+                    let distance = DISTANCE_BASE[j] as usize
+                        + reader.bits(DISTANCE_EXTRA[j] as u32)? as usize;
+                    if distance > output.len() {
+                        return Err("Invalid distance.".into());
+                    }
+                    if output.len() + length > limit {
+                        return Err(too_long());
+                    }
+                    let start = output.len() - distance;
+                    for k in 0..length {
+                        output.push(output[start + k]);
+                    }
+                }
+            },
+            _ => return Err("Invalid block type.".into()),
+        }
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+/// Compresses the data into a zlib stream with the stored (uncompressed)
+/// blocks. The textures are small, so the compression isn't worth the
+/// complexity.
+fn deflate_stored(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x78, 0x01];
+    let mut chunks = data.chunks(0xffff).peekable();
+    if chunks.peek().is_none() {
+        output.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        output.push(chunks.peek().is_none() as u8);
+        let length = chunk.len() as u16;
+        output.extend(length.to_le_bytes());
+        output.extend((!length).to_le_bytes());
+        output.extend_from_slice(chunk);
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    output.extend(((b << 16) | a).to_be_bytes());
+    output
+}
+
+/// Returns the Paeth predictor of the PNG filters.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reverses the filters of the rows of the image data.
+fn unfilter(
+    data: &[u8], row_bytes: usize, height: usize, pixel_bytes: usize,
+) -> Result<Vec<u8>, String> {
+    let mut result = vec![0u8; row_bytes * height];
+    for y in 0..height {
+        let start = y * (row_bytes + 1);
+        let row = data.get(start..start + row_bytes + 1)
+            .ok_or("The image data is truncated.")?;
+        let (filter, row) = (row[0], &row[1..]);
+        let (previous, current) = result.split_at_mut(y * row_bytes);
+        let up = match y {
+            0 => None,
+            _ => Some(&previous[(y - 1) * row_bytes..]),
+        };
+        let current = &mut current[..row_bytes];
+        for x in 0..row_bytes {
+            let a = match x >= pixel_bytes {
+                true => current[x - pixel_bytes],
+                false => 0,
+            };
+            let b = up.map_or(0, |up| up[x]);
+            let c = match (up, x >= pixel_bytes) {
+                (Some(up), true) => up[x - pixel_bytes],
+                _ => 0,
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("Unknown filter type {}.", filter)),
+            };
+            current[x] = row[x].wrapping_add(predictor);
+        }
+    }
+    Ok(result)
+}
+
+/// Decodes the PNG image into RGBA pixels. The interlaced images aren't
+/// supported.
+pub fn decode(data: &[u8]) -> Result<Image, String> {
+    if !data.starts_with(SIGNATURE) {
+        return Err("The file isn't a PNG image.".into());
+    }
+    let mut position = SIGNATURE.len();
+    let mut header: Option<&[u8]> = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+    loop {
+        let length = data.get(position..position + 4)
+            .ok_or("The image is truncated.")?;
+        let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+        let kind = data.get(position + 4..position + 8)
+            .ok_or("The image is truncated.")?;
+        let body = data.get(position + 8..position + 8 + length)
+            .ok_or("The image is truncated.")?;
+        match kind {
+            b"IHDR" => header = Some(body),
+            b"PLTE" => palette = body,
+            b"tRNS" => transparency = body,
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {},
+        }
+        position += 12 + length;
+    }
+    let header = header.filter(|h| h.len() == 13)
+        .ok_or("The image doesn't have a valid header.")?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+    let (depth, color) = (header[8] as usize, header[9]);
+    if header[12] != 0 {
+        return Err("The interlaced images aren't supported.".into());
+    }
+    if width as u64 * height as u64 > MAX_PIXELS {
+        return Err("The image is too large.".into());
+    }
+    let channels = match (color, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (4, 8 | 16) => 2,
+        (2, 8 | 16) => 3,
+        (6, 8 | 16) => 4,
+        _ => return Err(format!(
+            "The color type {} with the bit depth {} isn't supported.",
+            color, depth
+        )),
+    };
+    let pixel_bits = channels * depth;
+    let row_bytes = (width as usize * pixel_bits).div_ceil(8);
+    let raw = unfilter(
+        &inflate(&compressed, (row_bytes + 1) * height as usize)?,
+        row_bytes, height as usize,
+        pixel_bits.div_ceil(8),
+    )?;
+    // Returns the sample of the channel of the pixel scaled to 8 bits
+    let sample = |x: usize, y: usize, channel: usize| -> u8 {
+        let bit = (x * channels + channel) * depth;
+        let byte = raw[y * row_bytes + bit / 8];
+        match depth {
+            16 | 8 => byte,
+            _ => {
+                let max = (1 << depth) - 1;
+                let value = (byte >> (8 - depth - bit % 8)) & max;
+                match color {
+                    3 => value,
+                    _ => (value as u16 * 255 / max as u16) as u8,
+                }
+            },
+        }
+    };
+    let mut image = Image::new(width, height);
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let pixel = match color {
+                0 => {
+                    let v = sample(x, y, 0);
+                    [v, v, v, 255]
+                },
+                3 => {
+                    let i = sample(x, y, 0) as usize;
+                    let rgb = palette.get(i * 3..i * 3 + 3)
+                        .ok_or("The palette index is out of range.")?;
+                    let alpha = transparency.get(i).copied().unwrap_or(255);
+                    [rgb[0], rgb[1], rgb[2], alpha]
+                },
+                4 => {
+                    let v = sample(x, y, 0);
+                    [v, v, v, sample(x, y, 1)]
+                },
+                2 => [sample(x, y, 0), sample(x, y, 1), sample(x, y, 2), 255],
+                _ => [
+                    sample(x, y, 0), sample(x, y, 1), sample(x, y, 2),
+                    sample(x, y, 3),
+                ],
+            };
+            let i = (y * width as usize + x) * 4;
+            image.pixels[i..i + 4].copy_from_slice(&pixel);
+        }
+    }
+    Ok(image)
+}
+
+/// Encodes the image as an 8-bit RGBA PNG file.
+pub fn encode(image: &Image) -> Vec<u8> {
+    let table = crc_table();
+    let mut output = SIGNATURE.to_vec();
+    let mut chunk = |kind: &[u8], body: &[u8]| {
+        output.extend((body.len() as u32).to_be_bytes());
+        let mut data = kind.to_vec();
+        data.extend_from_slice(body);
+        output.extend_from_slice(&data);
+        output.extend(crc32(&table, &data).to_be_bytes());
+    };
+    let mut header = Vec::with_capacity(13);
+    header.extend(image.width.to_be_bytes());
+    header.extend(image.height.to_be_bytes());
+    header.extend([8, 6, 0, 0, 0]);
+    chunk(b"IHDR", &header);
+    let row_bytes = image.width as usize * 4;
+    let mut raw = Vec::with_capacity((row_bytes + 1) * image.height as usize);
+    for row in image.pixels.chunks(row_bytes.max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    chunk(b"IDAT", &deflate_stored(&raw));
+    chunk(b"IEND", &[]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the PNG file with the header and the compressed image data.
+    fn png_file(width: u32, height: u32, color: u8, data: &[u8]) -> Vec<u8> {
+        let table = crc_table();
+        let mut header = Vec::new();
+        header.extend(width.to_be_bytes());
+        header.extend(height.to_be_bytes());
+        header.extend([8, color, 0, 0, 0]);
+        let mut output = SIGNATURE.to_vec();
+        for (kind, body) in [
+            (&b"IHDR"[..], &header[..]), (b"IDAT", data), (b"IEND", &[]),
+        ] {
+            output.extend((body.len() as u32).to_be_bytes());
+            let mut chunk = kind.to_vec();
+            chunk.extend_from_slice(body);
+            output.extend_from_slice(&chunk);
+            output.extend(crc32(&table, &chunk).to_be_bytes());
+        }
+        output
+    }
+
+    #[test]
+    fn decodes_the_compressed_images() {
+        // 2x2 RGB image compressed by zlib, the second row uses the Sub
+        // filter
+        let data = [
+            0x78, 0xda, 0x63, 0xf8, 0xcf, 0xc0, 0xc0, 0xf0, 0x9f, 0x81, 0x11,
+            0x48, 0xfc, 0xff, 0xcf, 0x00, 0x00, 0x1e, 0xf6, 0x04, 0xfd,
+        ];
+        let image = decode(&png_file(2, 2, 2, &data)).unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, [
+            255, 0, 0, 255, 0, 255, 0, 255,
+            0, 0, 255, 255, 255, 255, 255, 255,
+        ]);
+    }
+
+    #[test]
+    fn decodes_the_encoded_images() {
+        let mut image = Image::new(3, 2);
+        let red = Image {
+            width: 2, height: 2, pixels: [255, 0, 0, 255].repeat(4),
+        };
+        // The part outside of the image is cut off
+        image.paste(&red, 2, 1);
+        assert_eq!(&image.pixels[20..24], [255, 0, 0, 255]);
+        assert_eq!(image.pixels.iter().filter(|p| **p == 255).count(), 2);
+        assert_eq!(decode(&encode(&image)).unwrap(), image);
+    }
+
+    #[test]
+    fn rejects_the_invalid_images() {
+        assert!(decode(b"GIF89a").is_err());
+        let file = png_file(1, 1, 2, &[0x78, 0xda, 0x63]);
+        assert!(decode(&file).is_err());
+        assert!(decode(&file[..20]).is_err());
+        let file = png_file(1, 1, 5, &deflate_stored(&[0, 0]));
+        assert!(decode(&file).unwrap_err().contains("color type 5"));
+        // The 1x1 RGB image has 4 bytes of the data (with the filter type)
+        let mut data = deflate_stored(&[0; 4]);
+        assert!(decode(&png_file(1, 1, 2, &data)).is_ok());
+        data[5] ^= 1;
+        let error = decode(&png_file(1, 1, 2, &data)).unwrap_err();
+        assert!(error.contains("stored block"), "{}", error);
+        let data = deflate_stored(&[0; 5]);
+        let error = decode(&png_file(1, 1, 2, &data)).unwrap_err();
+        assert!(error.contains("longer than the image"), "{}", error);
+        // The back-references are limited too
+        // 64 zero bytes compressed by zlib
+        let data = [
+            0x78, 0xda, 0x63, 0x60, 0xa0, 0x0c, 0x00, 0x00, 0x00, 0x40, 0x00,
+            0x01,
+        ];
+        let error = decode(&png_file(1, 1, 2, &data)).unwrap_err();
+        assert!(error.contains("longer than the image"), "{}", error);
+    }
+}
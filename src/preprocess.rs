@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The maximal depth of the nested includes and macros, which protects
+/// against the infinite recursion of the macros.
+const MAX_DEPTH: usize = 16;
+
+/// A macro with its parameters and the lines of its body.
+#[derive(Clone)]
+struct Macro {
+    parameters: Vec<String>,
+    body: Vec<String>,
+    /// The file that defines the macro.
+    path: PathBuf,
+    /// The number of the first line of the body in the file.
+    line: usize,
+}
+
+/// Expands the preprocessor directives of the functions (see
+/// `preprocess_function`).
+struct Preprocessor {
+    /// The constants from the settings and the "#define" directives.
+    constants: HashMap<String, String>,
+    /// The macros by their names.
+    macros: HashMap<String, Macro>,
+    /// The files that are being included, used for detecting the cycles.
+    includes: Vec<PathBuf>,
+    /// The expanded commands.
+    output: Vec<String>,
+}
+
+/// Checks if the name of a constant, a macro or a parameter is valid (e.g.
+/// "MAX_HEALTH").
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parses the call of a macro or the signature of its definition (e.g.
+/// "give_kit(@a, 2)"). Returns the name and the trimmed arguments.
+fn parse_call(text: &str) -> Result<(&str, Vec<String>), String> {
+    let (name, rest) = text.split_once('(')
+        .ok_or_else(|| format!("\"{}\" isn't a macro call.", text))?;
+    let arguments = rest.trim_end().strip_suffix(')')
+        .ok_or_else(|| format!("\"{}\" isn't closed with ')'.", text))?;
+    let name = name.trim();
+    if !is_valid_name(name) {
+        return Err(format!("\"{}\" isn't a valid name.", name));
+    }
+    let arguments = match arguments.trim() {
+        "" => Vec::new(),
+        arguments => arguments.split(',')
+            .map(|argument| argument.trim().to_string())
+            .collect(),
+    };
+    Ok((name, arguments))
+}
+
+/// Replaces the "${NAME}" references in the line with the values of the
+/// variables.
+fn substitute(
+    line: &str, variables: &HashMap<String, String>,
+) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find('}')
+            .ok_or("The \"${\" isn't closed with '}'.")?;
+        let name = &rest[start + 2..start + end];
+        let value = variables.get(name)
+            .ok_or_else(|| format!("Unknown constant \"{}\".", name))?;
+        result.push_str(value);
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+impl Preprocessor {
+    /// Expands the lines of the file (or the body of a macro with the
+    /// arguments as the variables).
+    ///
+    /// # Arguments
+    /// - `path` - the path to the file, used for the includes and the
+    ///   errors
+    /// - `lines` - the lines to expand
+    /// - `first_line` - the number of the first line in the file
+    /// - `arguments` - the arguments of the macro
+    /// - `depth` - the depth of the nested includes and macros
+    fn expand(
+        &mut self, path: &Path, lines: &[String], first_line: usize,
+        arguments: &HashMap<String, String>, depth: usize,
+    ) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "The includes and the macros are nested deeper than {} \
+                levels.",
+                MAX_DEPTH
+            ));
+        }
+        let mut lines = lines.iter().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let error = |e: String| format!(
+                "\"{}\" line {}: {}", path.display(), first_line + i, e
+            );
+            let trimmed = line.trim();
+            let (directive, rest) = trimmed.split_once(char::is_whitespace)
+                .unwrap_or((trimmed, ""));
+            let rest = rest.trim();
+            match directive {
+                "#include" => {
+                    let name = rest.trim_matches(['<', '>', '"']);
+                    let included = path.parent().unwrap_or(Path::new(""))
+                        .join(name);
+                    if self.includes.contains(&included) {
+                        return Err(error(format!(
+                            "\"{}\" includes itself.", included.display()
+                        )));
+                    }
+                    let text = fs::read_to_string(&included).map_err(|e| {
+                        error(format!("\"{}\": {}", included.display(), e))
+                    })?;
+                    self.include(&included, &text, depth + 1)?;
+                },
+                "#define" => {
+                    let (name, value) = rest.split_once(char::is_whitespace)
+                        .unwrap_or((rest, ""));
+                    if !is_valid_name(name) {
+                        return Err(error(format!(
+                            "\"{}\" isn't a valid name.", name
+                        )));
+                    }
+                    let value = substitute(value.trim(), &self.variables(
+                        arguments
+                    )).map_err(error)?;
+                    self.constants.insert(name.to_string(), value);
+                },
+                "#macro" => {
+                    let (name, parameters) = parse_call(rest).map_err(error)?;
+                    if let Some(p) = parameters.iter()
+                        .find(|p| !is_valid_name(p))
+                    {
+                        return Err(error(format!(
+                            "\"{}\" isn't a valid name.", p
+                        )));
+                    }
+                    let mut body = Vec::new();
+                    let line = first_line + i + 1;
+                    loop {
+                        match lines.next() {
+                            Some((_, line)) if line.trim() == "#endmacro" => {
+                                break;
+                            },
+                            Some((_, line)) => body.push(line.clone()),
+                            None => return Err(error(
+                                "The macro isn't closed with \"#endmacro\"."
+                                    .into()
+                            )),
+                        }
+                    }
+                    self.macros.insert(name.to_string(), Macro {
+                        parameters, body, path: path.to_path_buf(), line,
+                    });
+                },
+                "#expand" => {
+                    let variables = self.variables(arguments);
+                    let call = substitute(rest, &variables).map_err(error)?;
+                    let (name, values) = parse_call(&call).map_err(error)?;
+                    let m = match self.macros.get(name) {
+                        Some(m) => m.clone(),
+                        None => return Err(error(format!(
+                            "Unknown macro \"{}\".", name
+                        ))),
+                    };
+                    if m.parameters.len() != values.len() {
+                        return Err(error(format!(
+                            "The macro \"{}\" takes {} arguments.",
+                            name, m.parameters.len()
+                        )));
+                    }
+                    let mut variables = arguments.clone();
+                    variables.extend(m.parameters.into_iter().zip(values));
+                    self.expand(
+                        &m.path, &m.body, m.line, &variables, depth + 1
+                    )?;
+                },
+                "#endmacro" => {
+                    return Err(error(
+                        "The \"#endmacro\" doesn't close any macro.".into()
+                    ));
+                },
+                _ => {
+                    let variables = self.variables(arguments);
+                    self.output.push(
+                        substitute(line, &variables).map_err(error)?
+                    );
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the constants and the arguments of the macro.
+    fn variables(
+        &self, arguments: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let mut variables = self.constants.clone();
+        variables.extend(arguments.clone());
+        variables
+    }
+
+    /// Expands the text of the included file.
+    fn include(
+        &mut self, path: &Path, text: &str, depth: usize,
+    ) -> Result<(), String> {
+        let lines: Vec<String> = text.trim_start_matches('\u{feff}').lines()
+            .map(str::to_string)
+            .collect();
+        self.includes.push(path.to_path_buf());
+        let result = self.expand(path, &lines, 1, &HashMap::new(), depth);
+        self.includes.pop();
+        result
+    }
+}
+
+/// Expands the preprocessor directives of the function. The directives are
+/// comments, so the functions without the preprocessing still work:
+/// - "#include <file>" - inserts the file (relative to the function),
+/// - "#define NAME value" - defines a constant,
+/// - "#macro NAME(a, b)" ... "#endmacro" - defines a macro with the
+///   parameters,
+/// - "#expand NAME(x, y)" - inserts the body of the macro with the
+///   arguments.
+///
+/// The "${NAME}" references are replaced with the constants and the
+/// arguments of the macros. The functions share the constants from the
+/// settings but the constants and the macros defined in a function (or the
+/// files it includes) are only used by that function.
+///
+/// # Arguments
+/// - `source` - the path to the function
+/// - `constants` - the constants from the settings
+pub fn preprocess_function(
+    source: &Path, constants: &HashMap<String, String>,
+) -> Result<String, String> {
+    let mut preprocessor = Preprocessor {
+        constants: constants.clone(),
+        macros: HashMap::new(),
+        includes: Vec::new(),
+        output: Vec::new(),
+    };
+    let text = fs::read_to_string(source).map_err(|e| e.to_string())?;
+    preprocessor.include(source, &text, 0)?;
+    let mut text = preprocessor.output.join("\n");
+    text.push('\n');
+    Ok(text)
+}
@@ -0,0 +1,361 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{compiled_target, MapResult, Mapper, TargetInfo};
+use crate::plan::PlannedFile;
+use crate::recipes::{check_pattern, is_valid_identifier};
+
+/// The name of the rule of the recipes generated by the RecipeShorthand.
+pub static RECIPE_RULE: &str = "(recipe)";
+
+/// The extension of the files with the recipes in the shorthand format.
+static SHORTHAND_EXTENSION: &str = ".recipes";
+
+/// The extension of the generated recipes.
+static TARGET_EXTENSION: &str = ".r.json";
+
+/// The directory of the recipes in the packs.
+static RECIPES_DIR: &str = "BP/recipes";
+
+/// The format version of the generated recipes.
+static FORMAT_VERSION: &str = "1.20.10";
+
+/// The tags of the recipes without the "@tags" line.
+static DEFAULT_TAGS: &[&str] = &["crafting_table"];
+
+/// Compiles the files with the recipes in the shorthand format (the
+/// ".recipes" files) into the recipes of the behavior pack, one file per
+/// recipe named after the identifier of the recipe (e.g. "ns:iron_frame"
+/// from "tools.recipes" -> "BP/recipes/iron_frame.r.json"). See
+/// `parse_recipes` for the format.
+pub struct RecipeShorthand;
+
+impl Mapper for RecipeShorthand {
+    fn map(&self, path: &Path) -> MapResult {
+        compiled_target(
+            path, SHORTHAND_EXTENSION, RECIPES_DIR, TARGET_EXTENSION,
+            RECIPE_RULE,
+        )
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        vec![SHORTHAND_EXTENSION.to_string()]
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        let mut recipes: Vec<PlannedFile> = Vec::new();
+        files.retain(|file| {
+            let target = match &file.target {
+                Some(target) if target.rule == RECIPE_RULE => target,
+                _ => return true,
+            };
+            // The invalid files keep their targets and are reported when
+            // they're generated
+            let names = match read_recipes(&file.source) {
+                Ok(recipes) => recipes.into_iter().map(|r| r.file_name()),
+                Err(_) => return true,
+            };
+            let dir = target.path.parent().unwrap_or(Path::new(""));
+            recipes.extend(names.map(|name| PlannedFile {
+                source: file.source.clone(),
+                target: Some(TargetInfo::new(RECIPE_RULE, dir.join(name))),
+            }));
+            false
+        });
+        files.extend(recipes);
+    }
+}
+
+/// A recipe in the shorthand format.
+struct Recipe {
+    identifier: String,
+    shapeless: bool,
+    result: (String, u64),
+    tags: Vec<String>,
+    pattern: Vec<String>,
+    key: Vec<(char, String)>,
+    ingredients: Vec<(String, u64)>,
+}
+
+impl Recipe {
+    /// Returns the name of the file of the recipe (e.g.
+    /// "iron_frame.r.json" for "ns:iron_frame").
+    fn file_name(&self) -> PathBuf {
+        let name = self.identifier.rsplit(':').next().unwrap_or_default();
+        PathBuf::from(format!("{}{}", name, TARGET_EXTENSION))
+    }
+
+    /// Returns the JSON of the recipe.
+    fn to_json(&self) -> serde_json::Value {
+        let item = |(item, count): &(String, u64)| match count {
+            1 => serde_json::json!({"item": item}),
+            _ => serde_json::json!({"item": item, "count": count}),
+        };
+        let mut recipe = serde_json::json!({
+            "description": {"identifier": self.identifier},
+            "tags": self.tags,
+        });
+        let kind = match self.shapeless {
+            true => {
+                let ingredients: Vec<_> = self.ingredients.iter()
+                    .map(item)
+                    .collect();
+                recipe["ingredients"] = ingredients.into();
+                "minecraft:recipe_shapeless"
+            },
+            false => {
+                recipe["pattern"] = self.pattern.clone().into();
+                let key: serde_json::Map<String, serde_json::Value> = self.key
+                    .iter()
+                    .map(|(symbol, i)| {
+                        (symbol.to_string(), serde_json::json!({"item": i}))
+                    })
+                    .collect();
+                recipe["key"] = key.into();
+                "minecraft:recipe_shaped"
+            },
+        };
+        recipe["result"] = item(&self.result);
+        serde_json::json!({"format_version": FORMAT_VERSION, kind: recipe})
+    }
+}
+
+/// Parses the item with the optional count (e.g. "minecraft:stick" or
+/// "minecraft:stick 4").
+fn parse_item(text: &str) -> Result<(String, u64), String> {
+    let mut parts = text.split_whitespace();
+    let item = parts.next().ok_or("The item is empty.")?;
+    if !is_valid_identifier(item) {
+        return Err(format!("\"{}\" isn't a valid identifier.", item));
+    }
+    let count = match parts.next() {
+        Some(count) => count.parse().ok().filter(|c| *c > 0)
+            .ok_or_else(|| format!("\"{}\" isn't a valid count.", count))?,
+        None => 1,
+    };
+    match parts.next() {
+        Some(_) => Err(format!("\"{}\" isn't a valid item.", text)),
+        None => Ok((item.to_string(), count)),
+    }
+}
+
+/// Parses the first line of the recipe: "<identifier> -> <result>", with
+/// the optional "shapeless" prefix and the count of the result (e.g.
+/// "shapeless ns:mix -> ns:mix 4").
+fn parse_header(line: &str) -> Result<Recipe, String> {
+    let (shapeless, line) = match line.strip_prefix("shapeless ") {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+    let (identifier, result) = line.split_once("->")
+        .ok_or("The recipe doesn't have the \"->\" with the result.")?;
+    let identifier = identifier.trim();
+    let valid = identifier.split_once(':')
+        .is_some_and(|(_, name)| !name.contains(':'))
+        && is_valid_identifier(identifier);
+    if !valid {
+        return Err(format!(
+            "\"{}\" isn't a valid identifier of a recipe.", identifier
+        ));
+    }
+    Ok(Recipe {
+        identifier: identifier.to_string(),
+        shapeless,
+        result: parse_item(result)?,
+        tags: DEFAULT_TAGS.iter().map(|tag| tag.to_string()).collect(),
+        pattern: Vec::new(),
+        key: Vec::new(),
+        ingredients: Vec::new(),
+    })
+}
+
+/// Parses the line of the body of the recipe.
+fn parse_line(recipe: &mut Recipe, line: &str) -> Result<(), String> {
+    if let Some(tags) = line.strip_prefix("@tags") {
+        recipe.tags = tags.split_whitespace().map(str::to_string).collect();
+        return match recipe.tags.is_empty() {
+            true => Err("The recipe doesn't have any tags.".into()),
+            false => Ok(()),
+        };
+    }
+    if recipe.shapeless {
+        recipe.ingredients.push(parse_item(line)?);
+        return Ok(());
+    }
+    // The key lines are "<symbol> = <item>" and the pattern lines are before
+    // them
+    let mut chars = line.chars();
+    let symbol = chars.next().unwrap_or(' ');
+    match chars.as_str().trim_start().strip_prefix('=') {
+        Some(item) if !symbol.is_whitespace() => {
+            if recipe.key.iter().any(|(s, _)| *s == symbol) {
+                return Err(format!(
+                    "The symbol '{}' is already defined.", symbol
+                ));
+            }
+            let (item, count) = parse_item(item)?;
+            if count != 1 {
+                return Err("The items of the key can't have counts.".into());
+            }
+            recipe.key.push((symbol, item));
+        },
+        _ if !recipe.key.is_empty() => {
+            return Err("The pattern has to be before the key.".into());
+        },
+        _ => recipe.pattern.push(line.to_string()),
+    }
+    Ok(())
+}
+
+/// Checks the parsed recipe.
+fn check_recipe(recipe: &Recipe) -> Result<(), String> {
+    if recipe.shapeless {
+        return match recipe.ingredients.is_empty() {
+            true => Err("The recipe doesn't have any ingredients.".into()),
+            false => Ok(()),
+        };
+    }
+    let json = recipe.to_json();
+    let mut problems = Vec::new();
+    check_pattern(&json["minecraft:recipe_shaped"], |p| problems.push(p));
+    let used = |symbol: &char| recipe.pattern.iter()
+        .any(|row| row.contains(*symbol));
+    if let Some((symbol, _)) = recipe.key.iter().find(|(s, _)| !used(s)) {
+        problems.push(format!(
+            "The symbol '{}' of the key isn't used in the pattern.", symbol
+        ));
+    }
+    match problems.into_iter().next() {
+        Some(problem) => Err(problem),
+        None => Ok(()),
+    }
+}
+
+/// Parses the recipes in the shorthand format. The recipes are separated
+/// with the empty lines and the lines starting with "//" are comments. The
+/// first line of a recipe is its identifier and its result (e.g.
+/// "ns:iron_frame -> ns:iron_frame 2" or "shapeless ns:mix -> ns:mix 4").
+/// The shaped recipes continue with the rows of the pattern (e.g. "# #")
+/// and the symbols of the key (e.g. "# = minecraft:iron_ingot"). The
+/// shapeless recipes continue with the ingredients, one per line with the
+/// optional count (e.g. "minecraft:sand 2"). The optional "@tags" line
+/// replaces the "crafting_table" tag (e.g. "@tags stonecutter").
+fn parse_recipes(text: &str) -> Result<Vec<Recipe>, String> {
+    let mut recipes: Vec<Recipe> = Vec::new();
+    let mut current: Option<(usize, Recipe)> = None;
+    let mut finish = |current: Option<(usize, Recipe)>| {
+        let (line, recipe) = match current {
+            Some(current) => current,
+            None => return Ok(()),
+        };
+        let error = |e: String| format!("Line {}: {}", line, e);
+        check_recipe(&recipe).map_err(error)?;
+        let file_name = recipe.file_name();
+        if recipes.iter().any(|r| r.file_name() == file_name) {
+            return Err(error(format!(
+                "The recipe \"{}\" is already defined.", recipe.identifier
+            )));
+        }
+        recipes.push(recipe);
+        Ok::<(), String>(())
+    };
+    for (i, line) in text.lines().enumerate() {
+        let error = |e: String| format!("Line {}: {}", i + 1, e);
+        if line.trim_start().starts_with("//") {
+            continue;
+        }
+        if line.trim().is_empty() {
+            finish(current.take())?;
+            continue;
+        }
+        match &mut current {
+            Some((_, recipe)) => {
+                parse_line(recipe, line.trim_end()).map_err(error)?;
+            },
+            None => {
+                let recipe = parse_header(line.trim()).map_err(error)?;
+                current = Some((i + 1, recipe));
+            },
+        }
+    }
+    finish(current.take())?;
+    match recipes.is_empty() {
+        true => Err("The file doesn't have any recipes.".into()),
+        false => Ok(recipes),
+    }
+}
+
+/// Reads the recipes of the file in the shorthand format.
+fn read_recipes(path: &Path) -> Result<Vec<Recipe>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_recipes(text.trim_start_matches('\u{feff}'))
+}
+
+/// Generates the recipe of the target (by the name of the target) from the
+/// file in the shorthand format.
+///
+/// # Arguments
+/// - `source` - the path to the ".recipes" file
+/// - `target` - the path to the target of the recipe
+pub fn recipe_contents(
+    source: &Path, target: &Path,
+) -> Result<Vec<u8>, String> {
+    let recipes = read_recipes(source)?;
+    let name = target.file_name().unwrap_or_default();
+    let recipe = recipes.iter()
+        .find(|recipe| recipe.file_name().as_os_str() == name)
+        .ok_or("The file doesn't define the recipe of the target.")?;
+    Ok(format!("{:#}\n", recipe.to_json()).into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static RECIPES: &str = "\
+// The frames
+ns:iron_frame -> ns:iron_frame 2
+#.#
+# = minecraft:iron_ingot
+. = minecraft:stick
+
+shapeless ns:mix -> ns:mix 4
+@tags stonecutter
+minecraft:sand 2
+minecraft:gravel
+";
+
+    #[test]
+    fn parses_the_recipes_in_the_shorthand_format() {
+        let recipes = parse_recipes(RECIPES).unwrap();
+        let names: Vec<PathBuf> = recipes.iter()
+            .map(|recipe| recipe.file_name())
+            .collect();
+        let expected = ["iron_frame.r.json", "mix.r.json"].map(PathBuf::from);
+        assert_eq!(names, expected);
+        let shaped = recipes[0].to_json();
+        let shaped = &shaped["minecraft:recipe_shaped"];
+        assert_eq!(shaped["pattern"], serde_json::json!(["#.#"]));
+        assert_eq!(shaped["key"]["#"]["item"], "minecraft:iron_ingot");
+        assert_eq!(
+            shaped["result"],
+            serde_json::json!({"item": "ns:iron_frame", "count": 2})
+        );
+        let shapeless = recipes[1].to_json();
+        let shapeless = &shapeless["minecraft:recipe_shapeless"];
+        assert_eq!(shapeless["tags"], serde_json::json!(["stonecutter"]));
+        assert_eq!(shapeless["ingredients"], serde_json::json!([
+            {"item": "minecraft:sand", "count": 2},
+            {"item": "minecraft:gravel"},
+        ]));
+    }
+
+    #[test]
+    fn reports_the_invalid_recipes_with_their_lines() {
+        let duplicate = "ns:a -> ns:a\n#\n# = ns:b\n\n\
+            ns:a -> ns:c\n#\n# = ns:b\n";
+        let e = parse_recipes(duplicate).err().unwrap();
+        assert!(e.starts_with("Line 5:"), "{}", e);
+        assert!(parse_recipes("// only comments\n").is_err());
+    }
+}
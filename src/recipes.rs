@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+
+use crate::index::{Document, ProjectIndex};
+use crate::mapping::Pack;
+use crate::report::{Diagnostic, Severity};
+
+/// The top-level keys of the recipes.
+static RECIPE_KINDS: &[&str] = &[
+    "minecraft:recipe_shaped", "minecraft:recipe_shapeless",
+    "minecraft:recipe_furnace", "minecraft:recipe_brewing_mix",
+    "minecraft:recipe_brewing_container",
+    "minecraft:recipe_smithing_transform", "minecraft:recipe_smithing_trim",
+];
+
+/// The first format version of the recipes that requires the "unlock"
+/// property of the crafting recipes.
+static UNLOCK_VERSION: &[u32] = &[1, 20, 30];
+
+/// The maximal size of the pattern of the shaped recipes (the crafting
+/// table has 3x3 slots).
+const MAX_PATTERN_SIZE: usize = 3;
+
+/// Parses the version (e.g. "1.20.30") into its numbers. Returns None if
+/// it isn't a valid version.
+pub fn parse_version(version: &str) -> Option<Vec<u32>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Checks if the identifier of an item or a block is syntactically valid
+/// (e.g. "minecraft:stick" or "stick"). The namespace and the legacy data
+/// value (e.g. "minecraft:dye:4") are optional.
+pub fn is_valid_identifier(identifier: &str) -> bool {
+    let identifier = match identifier.rsplit_once(':') {
+        Some((rest, data)) if rest.contains(':')
+            && data.chars().all(|c| c.is_ascii_digit()) => rest,
+        _ => identifier,
+    };
+    let valid_part = |part: &str| !part.is_empty() && part.chars().all(|c| {
+        c.is_ascii_lowercase() || c.is_ascii_digit()
+            || matches!(c, '_' | '-' | '.' | '/')
+    });
+    match identifier.split_once(':') {
+        Some((namespace, name)) => valid_part(namespace) && valid_part(name),
+        None => valid_part(identifier),
+    }
+}
+
+/// Returns the identifiers of the items in the ingredient or the result of
+/// a recipe (a string, an object with the "item" or an array of them).
+fn item_identifiers(value: &serde_json::Value) -> Vec<&str> {
+    match value {
+        serde_json::Value::String(item) => vec![item.as_str()],
+        serde_json::Value::Object(object) => {
+            object.get("item").and_then(|i| i.as_str()).into_iter().collect()
+        },
+        serde_json::Value::Array(items) => {
+            items.iter().flat_map(item_identifiers).collect()
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Checks the pattern and the key of the shaped recipe.
+pub fn check_pattern(
+    recipe: &serde_json::Value, mut error: impl FnMut(String),
+) {
+    let pattern: Vec<&str> = match recipe["pattern"].as_array() {
+        Some(rows) => rows.iter().filter_map(|row| row.as_str()).collect(),
+        None => {
+            error("The shaped recipe doesn't have a \"pattern\".".into());
+            return;
+        },
+    };
+    let width = pattern.iter().map(|row| row.chars().count()).max();
+    if pattern.is_empty() || pattern.len() > MAX_PATTERN_SIZE
+        || width.unwrap_or(0) > MAX_PATTERN_SIZE
+    {
+        error(format!(
+            "The pattern of the shaped recipe has to be between 1x1 and \
+            {}x{}.",
+            MAX_PATTERN_SIZE, MAX_PATTERN_SIZE
+        ));
+    }
+    let key: HashSet<char> = recipe["key"].as_object()
+        .map(|key| key.keys().filter_map(|k| k.chars().next()).collect())
+        .unwrap_or_default();
+    let mut used = HashSet::new();
+    for symbol in pattern.iter().flat_map(|row| row.chars()) {
+        if symbol != ' ' && used.insert(symbol) && !key.contains(&symbol) {
+            error(format!(
+                "The symbol '{}' of the pattern isn't defined in the \"key\".",
+                symbol
+            ));
+        }
+    }
+}
+
+/// Checks the recipe of the kind.
+fn check_recipe(
+    document: &Document, kind: &str, problems: &mut Vec<Diagnostic>,
+) {
+    let recipe = &document.json[kind];
+    let mut error = |message: String| problems.push(
+        Diagnostic::new(Severity::Error, document.source, message)
+    );
+    if recipe["description"]["identifier"].as_str().is_none() {
+        error("The recipe doesn't have an identifier.".into());
+    }
+    let crafting = kind == "minecraft:recipe_shaped"
+        || kind == "minecraft:recipe_shapeless";
+    if kind == "minecraft:recipe_shaped" {
+        check_pattern(recipe, &mut error);
+    }
+    let version = document.json["format_version"].as_str()
+        .and_then(parse_version);
+    let needs_unlock = version.is_some_and(|v| v.as_slice() >= UNLOCK_VERSION);
+    if crafting && needs_unlock && recipe["unlock"].is_null() {
+        error(format!(
+            "The recipe doesn't have the \"unlock\" property, which is \
+            required since the format version {}.",
+            UNLOCK_VERSION.iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(".")
+        ));
+    }
+    let items = ["result", "output", "input", "key", "ingredients"].iter()
+        .flat_map(|property| match &recipe[property] {
+            // The ingredients of the key are the values of the object
+            serde_json::Value::Object(object) if *property == "key" => {
+                object.values().flat_map(item_identifiers).collect()
+            },
+            value => item_identifiers(value),
+        })
+        .collect::<Vec<_>>();
+    for item in items {
+        if !is_valid_identifier(item) {
+            error(format!("\"{}\" isn't a valid identifier.", item));
+        }
+    }
+}
+
+/// Checks the recipes of the behavior pack: the symbols of the patterns of
+/// the shaped recipes have to be defined in the keys, the patterns have to
+/// fit into 3x3 slots, the identifiers of the items have to be valid and the
+/// crafting recipes of the newer format versions have to define the
+/// "unlock" property. The invalid recipes are silently missing in the game.
+pub fn check_recipes(index: &ProjectIndex, problems: &mut Vec<Diagnostic>) {
+    for kind in RECIPE_KINDS {
+        for document in index.with_key(Pack::Behavior, kind) {
+            check_recipe(document, kind, problems);
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::index::{Document, ProjectIndex};
+use crate::mapping::Pack;
+use crate::report::{Diagnostic, Severity};
+use crate::vanilla::is_vanilla_particle;
+
+/// The extensions of the texture files. The textures are referenced without
+/// the extensions.
+static TEXTURE_EXTENSIONS: &[&str] = &["png", "tga", "jpg", "jpeg"];
+
+/// The top-level keys of the resource pack files that use the geometries
+/// and the textures.
+static RENDERED_KINDS: &[&str] = &[
+    "minecraft:client_entity", "minecraft:attachable",
+];
+
+/// Returns the identifiers of the geometries defined in the resource pack,
+/// both in the current format ("minecraft:geometry") and in the legacy
+/// format (the "geometry.name" or "geometry.name:geometry.parent" keys).
+fn defined_geometries(index: &ProjectIndex) -> HashSet<String> {
+    let mut result = HashSet::new();
+    for document in &index.documents {
+        if document.target.pack != Pack::Resource {
+            continue;
+        }
+        let geometries = document.json["minecraft:geometry"].as_array();
+        if let Some(geometries) = geometries {
+            result.extend(geometries.iter().filter_map(|geometry| {
+                geometry["description"]["identifier"].as_str()
+                    .map(str::to_string)
+            }));
+        }
+        if let Some(object) = document.json.as_object() {
+            result.extend(object.keys()
+                .filter(|key| key.starts_with("geometry."))
+                .map(|key| key.split(':').next().unwrap_or(key).to_string()));
+        }
+    }
+    result
+}
+
+/// Returns the values of the object from the description of the client
+/// entity or the attachable (e.g. the "geometry" or the "textures").
+fn description_values<'a>(
+    document: &'a Document, kind: &str, key: &str,
+) -> Vec<&'a str> {
+    document.json[kind]["description"][key].as_object()
+        .map(|object| object.values().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default()
+}
+
+/// Checks if the geometries referenced by the client entities and the
+/// attachables are defined in the copied geometry files and if the
+/// referenced textures are copied to the resource pack. The references to
+/// the vanilla geometries and textures are reported too, because the filter
+/// doesn't know them.
+pub fn check_geometries_and_textures(
+    index: &ProjectIndex, problems: &mut Vec<Diagnostic>,
+) {
+    let geometries = defined_geometries(index);
+    for kind in RENDERED_KINDS {
+        for document in index.with_key(Pack::Resource, kind) {
+            for geometry in description_values(document, kind, "geometry") {
+                if !geometries.contains(geometry) {
+                    problems.push(Diagnostic::new(
+                        Severity::Warning, document.source, format!(
+                            "The geometry \"{}\" isn't defined in any of the \
+                            copied geometry files.",
+                            geometry
+                        ),
+                    ));
+                }
+            }
+            for texture in description_values(document, kind, "textures") {
+                let found = index.has_file(Pack::Resource, texture)
+                    || index.has_file_with_extension(
+                        Pack::Resource, texture, TEXTURE_EXTENSIONS
+                    );
+                if !found {
+                    problems.push(Diagnostic::new(
+                        Severity::Warning, document.source, format!(
+                            "The texture \"{}\" isn't copied to the resource \
+                            pack.",
+                            texture
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the Molang references of the kind (e.g. "Geometry" for
+/// "Geometry.default") in the string values of the JSON value. The names
+/// are lowercase, because Molang is case-insensitive.
+fn molang_references(value: &serde_json::Value, kind: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    match value {
+        serde_json::Value::String(s) => {
+            let words = s.split(|c: char| {
+                !(c.is_ascii_alphanumeric() || c == '_' || c == '.')
+            });
+            for word in words {
+                let mut parts = word.split('.');
+                let (prefix, name) = match (parts.next(), parts.next()) {
+                    (Some(prefix), Some(name)) => (prefix, name),
+                    _ => continue,
+                };
+                if prefix.eq_ignore_ascii_case(kind) && !name.is_empty() {
+                    result.push(name.to_lowercase());
+                }
+            }
+        },
+        serde_json::Value::Array(items) => {
+            for item in items {
+                result.extend(molang_references(item, kind));
+            }
+        },
+        serde_json::Value::Object(object) => {
+            for item in object.values() {
+                result.extend(molang_references(item, kind));
+            }
+        },
+        _ => {},
+    }
+    result
+}
+
+/// Returns the names of the render controllers used by the client entity.
+/// The render controllers are listed as strings or as objects with the
+/// names as the keys and the conditions as the values.
+fn used_render_controllers<'a>(document: &'a Document) -> Vec<&'a str> {
+    let controllers = &document.json["minecraft:client_entity"]
+        ["description"]["render_controllers"];
+    controllers.as_array()
+        .map(|items| items.iter()
+            .flat_map(|item| match item {
+                serde_json::Value::String(name) => vec![name.as_str()],
+                serde_json::Value::Object(object) => {
+                    object.keys().map(String::as_str).collect()
+                },
+                _ => Vec::new(),
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+/// The kinds of the resources indexed by the render controllers (the
+/// prefixes of the Molang references) and the keys of the descriptions of
+/// the client entities that declare them.
+static RENDERED_RESOURCES: &[(&str, &str)] = &[
+    ("Geometry", "geometry"),
+    ("Material", "materials"),
+    ("Texture", "textures"),
+];
+
+/// Checks if the render controllers used by the client entities are defined
+/// in the copied render controller files, if the arrays used by the render
+/// controllers are defined in them and if the geometries, the materials and
+/// the textures used by the render controllers are declared by the client
+/// entities. The missing declarations make the entities invisible in the
+/// game without any errors.
+pub fn check_render_controllers(
+    index: &ProjectIndex, problems: &mut Vec<Diagnostic>,
+) {
+    let mut controllers = HashMap::new();
+    for document in index.with_key(Pack::Resource, "render_controllers") {
+        if let Some(object) = document.json["render_controllers"].as_object() {
+            for (name, controller) in object {
+                controllers.insert(name.as_str(), controller);
+                // The arrays are defined per kind (e.g. "textures")
+                let arrays: HashSet<String> = controller["arrays"].as_object()
+                    .map(|kinds| kinds.values()
+                        .filter_map(|arrays| arrays.as_object())
+                        .flat_map(|arrays| arrays.keys())
+                        .filter_map(|key| key.split('.').nth(1))
+                        .map(str::to_lowercase)
+                        .collect())
+                    .unwrap_or_default();
+                let mut missing: Vec<String> = molang_references(
+                    controller, "Array"
+                ).into_iter().filter(|a| !arrays.contains(a)).collect();
+                missing.sort();
+                missing.dedup();
+                for array in missing {
+                    problems.push(Diagnostic::new(
+                        Severity::Error, document.source, format!(
+                            "The render controller \"{}\" uses the array \
+                            \"Array.{}\" which it doesn't define.",
+                            name, array
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+    for document in index.with_key(Pack::Resource, "minecraft:client_entity")
+    {
+        let description = &document.json["minecraft:client_entity"]
+            ["description"];
+        for name in used_render_controllers(document) {
+            let controller = match controllers.get(name) {
+                Some(controller) => controller,
+                None => {
+                    problems.push(Diagnostic::new(
+                        Severity::Warning, document.source, format!(
+                            "The render controller \"{}\" isn't defined in \
+                            any of the copied render controller files.",
+                            name
+                        ),
+                    ));
+                    continue;
+                },
+            };
+            for (kind, key) in RENDERED_RESOURCES {
+                let declared: HashSet<String> = description[key].as_object()
+                    .map(|o| o.keys().map(|k| k.to_lowercase()).collect())
+                    .unwrap_or_default();
+                let mut missing: Vec<String> = molang_references(
+                    controller, kind
+                ).into_iter().filter(|r| !declared.contains(r)).collect();
+                missing.sort();
+                missing.dedup();
+                for reference in missing {
+                    problems.push(Diagnostic::new(
+                        Severity::Error, document.source, format!(
+                            "The render controller \"{}\" uses \"{}.{}\" \
+                            which isn't declared in the \"{}\" of the client \
+                            entity, so the entity is invisible.",
+                            name, kind, reference, key
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the values of all of the properties with the key in the JSON
+/// value, including the nested objects and arrays.
+pub fn properties<'a>(
+    value: &'a serde_json::Value, key: &str,
+) -> Vec<&'a serde_json::Value> {
+    let mut result = Vec::new();
+    match value {
+        serde_json::Value::Object(object) => {
+            for (k, v) in object {
+                if k == key {
+                    result.push(v);
+                }
+                result.extend(properties(v, key));
+            }
+        },
+        serde_json::Value::Array(items) => {
+            for item in items {
+                result.extend(properties(item, key));
+            }
+        },
+        _ => {},
+    }
+    result
+}
+
+/// Checks if the file referenced from the behavior pack (e.g.
+/// "loot_tables/foo.json") is produced by the filter. The references
+/// without the ".json" extension are also accepted.
+pub fn has_behavior_file(index: &ProjectIndex, path: &str) -> bool {
+    index.has_file(Pack::Behavior, path)
+        || index.has_file_with_extension(Pack::Behavior, path, &["json"])
+}
+
+/// Returns the loot tables referenced from the file: the "minecraft:loot"
+/// and the "minecraft:equipment" components of the entities and the blocks
+/// and the "loot_table" entries of the loot tables.
+fn loot_table_references(json: &serde_json::Value) -> Vec<&str> {
+    let mut result = Vec::new();
+    for key in ["minecraft:loot", "minecraft:equipment"] {
+        for value in properties(json, key) {
+            // The blocks use the path directly
+            result.extend(value.as_str().or(value["table"].as_str()));
+        }
+    }
+    for entries in properties(json, "entries") {
+        for entry in entries.as_array().into_iter().flatten() {
+            if entry["type"] == "loot_table" {
+                result.extend(entry["name"].as_str());
+            }
+        }
+    }
+    result
+}
+
+/// Checks if the loot tables referenced from the entities, the blocks and
+/// the other loot tables are produced by the filter.
+pub fn check_loot_tables(
+    index: &ProjectIndex, problems: &mut Vec<Diagnostic>,
+) {
+    for document in &index.documents {
+        if document.target.pack != Pack::Behavior {
+            continue;
+        }
+        let json = &document.json;
+        let referencing = !json["minecraft:entity"].is_null()
+            || !json["minecraft:block"].is_null()
+            || !json["pools"].is_null();
+        if !referencing {
+            continue;
+        }
+        for table in loot_table_references(json) {
+            if !has_behavior_file(index, table) {
+                problems.push(Diagnostic::new(
+                    Severity::Warning, document.source, format!(
+                        "The loot table \"{}\" isn't copied to the behavior \
+                        pack.",
+                        table
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Checks if the particle effects of the client entities are defined in the
+/// copied particle files (or are vanilla particle effects) and if the
+/// particle effects used by the timelines of the animations and the
+/// animation controllers are declared by any of the client entities.
+pub fn check_particles(index: &ProjectIndex, problems: &mut Vec<Diagnostic>) {
+    let defined: HashSet<&str> = index
+        .with_key(Pack::Resource, "particle_effect")
+        .filter_map(|document| {
+            document.json["particle_effect"]["description"]["identifier"]
+                .as_str()
+        })
+        .collect();
+    let mut declared = HashSet::new();
+    for document in index.with_key(Pack::Resource, "minecraft:client_entity")
+    {
+        let effects = document.json["minecraft:client_entity"]
+            ["description"]["particle_effects"].as_object();
+        for (name, effect) in effects.into_iter().flatten() {
+            declared.insert(name.as_str());
+            let identifier = match effect.as_str() {
+                Some(identifier) => identifier,
+                None => continue,
+            };
+            let known = defined.contains(identifier)
+                || is_vanilla_particle(identifier);
+            if !known {
+                problems.push(Diagnostic::new(
+                    Severity::Warning, document.source, format!(
+                        "The particle effect \"{}\" isn't defined in any of \
+                        the copied particle files.",
+                        identifier
+                    ),
+                ));
+            }
+        }
+    }
+    let animations = index.with_key(Pack::Resource, "animations")
+        .chain(index.with_key(Pack::Resource, "animation_controllers"));
+    for document in animations {
+        let effects = properties(&document.json, "particle_effects")
+            .into_iter()
+            .flat_map(|timeline| properties(timeline, "effect"))
+            .filter_map(|effect| effect.as_str());
+        for effect in effects {
+            if !declared.contains(effect) {
+                problems.push(Diagnostic::new(
+                    Severity::Warning, document.source, format!(
+                        "The particle effect \"{}\" isn't declared in the \
+                        \"particle_effects\" of any of the client entities.",
+                        effect
+                    ),
+                ));
+            }
+        }
+    }
+}
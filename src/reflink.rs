@@ -0,0 +1,67 @@
+#[cfg(target_os = "linux")]
+use std::fs::File;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Tries to clone the source file using copy-on-write (FICLONE on Linux,
+/// clonefile on macOS). Cloning is instant and doesn't use additional disk
+/// space until one of the files is modified. Returns false if cloning is not
+/// supported by the platform or the file system.
+#[cfg(target_os = "linux")]
+pub fn reflink(source: &Path, target: &Path) -> io::Result<bool> {
+    use std::os::raw::{c_int, c_ulong};
+    use std::os::unix::io::AsRawFd;
+
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+    const FICLONE: c_ulong = 0x40049409;
+
+    let src = File::open(source)?;
+    let dst = File::create(target)?;
+    // SAFETY: both of the file descriptors are valid for the duration of
+    // the call
+    let result = unsafe { ioctl(dst.as_raw_fd(), FICLONE, src.as_raw_fd()) };
+    if result != 0 {
+        drop(dst);
+        fs::remove_file(target)?;
+        return Ok(false);
+    }
+    dst.set_permissions(src.metadata()?.permissions())?;
+    Ok(true)
+}
+
+/// Tries to clone the source file using copy-on-write (FICLONE on Linux,
+/// clonefile on macOS). Cloning is instant and doesn't use additional disk
+/// space until one of the files is modified. Returns false if cloning is not
+/// supported by the platform or the file system.
+#[cfg(target_os = "macos")]
+pub fn reflink(source: &Path, target: &Path) -> io::Result<bool> {
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_int};
+    use std::os::unix::ffi::OsStrExt;
+
+    extern "C" {
+        fn clonefile(
+            src: *const c_char, dst: *const c_char, flags: u32
+        ) -> c_int;
+    }
+
+    let src = CString::new(source.as_os_str().as_bytes())?;
+    let dst = CString::new(target.as_os_str().as_bytes())?;
+    // clonefile requires the target to not exist
+    if target.symlink_metadata().is_ok() {
+        fs::remove_file(target)?;
+    }
+    // SAFETY: both of the strings are valid and null terminated
+    let result = unsafe { clonefile(src.as_ptr(), dst.as_ptr(), 0) };
+    Ok(result == 0)
+}
+
+/// Tries to clone the source file using copy-on-write. Cloning is not
+/// supported on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn reflink(_source: &Path, _target: &Path) -> io::Result<bool> {
+    Ok(false)
+}
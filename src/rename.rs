@@ -0,0 +1,183 @@
+use std::path::Path;
+
+use crate::mapping::{MapResult, Mapper, TargetInfo};
+use crate::plan::PlannedFile;
+
+/// Returns the file name without the numeric ordering prefix (e.g.
+/// "setup.mcfunction" for "010_setup.mcfunction") or None if the name
+/// doesn't have the prefix. The names that would be only the extension
+/// without the prefix (e.g. "010_.mcfunction") are kept.
+fn strip_order_prefix(name: &str) -> Option<&str> {
+    let digits = name.bytes().take_while(u8::is_ascii_digit).count();
+    let rest = name[digits..].strip_prefix('_')?;
+    match digits > 0 && !rest.is_empty() && !rest.starts_with('.') {
+        true => Some(rest),
+        false => None,
+    }
+}
+
+/// Strips the numeric ordering prefixes (e.g. "010_"), used for ordering the
+/// files in the editors, from the names of the targets.
+pub struct OrderPrefixes;
+
+impl Mapper for OrderPrefixes {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        for file in files.iter_mut() {
+            let target = match &file.target {
+                Some(target) => target,
+                None => continue,
+            };
+            let name = target.path.file_name().and_then(|n| n.to_str());
+            let stripped = match name.and_then(strip_order_prefix) {
+                Some(stripped) => stripped,
+                None => continue,
+            };
+            let path = target.path.with_file_name(stripped);
+            file.target = Some(TargetInfo::new(&target.rule, path));
+        }
+    }
+}
+
+/// The kind of change of a rename rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenameKind {
+    /// Removes the text from the start of the name.
+    StripPrefix,
+    /// Removes the text from the end of the name.
+    StripSuffix,
+    /// Adds the text to the start of the name.
+    AddPrefix,
+    /// Adds the text to the end of the name.
+    AddSuffix,
+}
+
+impl RenameKind {
+    /// The kinds by the names of the properties of the rename rules in the
+    /// config file.
+    const NAMES: &'static [(&'static str, RenameKind)] = &[
+        ("strip_prefix", RenameKind::StripPrefix),
+        ("strip_suffix", RenameKind::StripSuffix),
+        ("add_prefix", RenameKind::AddPrefix),
+        ("add_suffix", RenameKind::AddSuffix),
+    ];
+}
+
+/// A rule that renames the targets. The rules change the base names of the
+/// targets (without the extensions, e.g. "zombie" of "zombie.bpe.json").
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenameRule {
+    /// The change of the name.
+    pub kind: RenameKind,
+    /// The text that is removed or added.
+    pub text: String,
+    /// The extension of the rule (e.g. ".mcfunction") whose targets are
+    /// renamed or None for all of the targets.
+    pub extension: Option<String>,
+}
+
+impl RenameRule {
+    /// Parses the rules from the "rename" property of the config file, e.g.
+    /// `[{"strip_prefix": "wip_"}, {"add_suffix": "_{namespace}",
+    /// "extension": ".mcfunction"}]`. The "{namespace}" is replaced with the
+    /// namespace. Returns None if the property has invalid structure or it
+    /// uses the namespace that isn't set.
+    ///
+    /// # Arguments
+    /// - `value` - the value of the property
+    /// - `namespace` - the namespace from the config file
+    pub fn from_json(
+        value: &serde_json::Value, namespace: Option<&str>,
+    ) -> Option<Vec<RenameRule>> {
+        let rules = match value {
+            serde_json::Value::Null => return Some(Vec::new()),
+            serde_json::Value::Array(rules) => rules,
+            _ => return None,
+        };
+        rules.iter().map(|rule| {
+            let object = rule.as_object()?;
+            let mut kinds = RenameKind::NAMES.iter()
+                .filter(|(name, _)| object.contains_key(*name));
+            let (name, kind) = kinds.next()?;
+            if kinds.next().is_some() {
+                return None;
+            }
+            let mut text = object[*name].as_str()?.to_string();
+            if text.contains("{namespace}") {
+                text = text.replace("{namespace}", namespace?);
+            }
+            let extension = match object.get("extension") {
+                None => None,
+                Some(extension) => Some(extension.as_str()?.to_string()),
+            };
+            Some(RenameRule { kind: *kind, text, extension })
+        }).collect()
+    }
+
+    /// Applies the rule to the base name. Returns None if the rule doesn't
+    /// change it.
+    fn apply(&self, name: &str) -> Option<String> {
+        let result = match self.kind {
+            RenameKind::StripPrefix => name.strip_prefix(&self.text)?.into(),
+            RenameKind::StripSuffix => name.strip_suffix(&self.text)?.into(),
+            RenameKind::AddPrefix => format!("{}{}", self.text, name),
+            RenameKind::AddSuffix => format!("{}{}", name, self.text),
+        };
+        match result.is_empty() || self.text.is_empty() {
+            true => None,
+            false => Some(result),
+        }
+    }
+}
+
+/// Renames the targets with the rename rules from the config file. The
+/// rules are applied in order.
+pub struct RenameMapper {
+    rules: Vec<RenameRule>,
+}
+
+impl RenameMapper {
+    /// Creates a mapper that renames the targets with the rules.
+    pub fn new(rules: Vec<RenameRule>) -> RenameMapper {
+        RenameMapper { rules }
+    }
+}
+
+impl Mapper for RenameMapper {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        for file in files.iter_mut() {
+            let target = match &file.target {
+                Some(target) => target,
+                None => continue,
+            };
+            let name = match target.path.file_name().and_then(|n| n.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+            // The extensions start at the first dot
+            let (base_name, extensions) = match name.find('.') {
+                Some(0) => continue,
+                Some(i) => name.split_at(i),
+                None => (name, ""),
+            };
+            let mut base_name = base_name.to_string();
+            let rules = self.rules.iter().filter(|rule| {
+                rule.extension.as_ref().is_none_or(|e| *e == target.rule)
+            });
+            for rule in rules {
+                if let Some(renamed) = rule.apply(&base_name) {
+                    base_name = renamed;
+                }
+            }
+            let path = target.path.with_file_name(base_name + extensions);
+            file.target = Some(TargetInfo::new(&target.rule, path));
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether the diagnostics are printed in the structured format.
+static STRUCTURED: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the structured format of the diagnostics.
+pub fn set_structured(structured: bool) {
+    STRUCTURED.store(structured, Ordering::Relaxed);
+}
+
+/// Checks if the diagnostics are printed in the structured format.
+pub fn is_structured() -> bool {
+    STRUCTURED.load(Ordering::Relaxed)
+}
+
+/// Checks if the filter is run by Regolith. Regolith provides the path to
+/// the root of the project in the `ROOT_DIR` environment variable.
+pub fn under_regolith() -> bool {
+    std::env::var_os("ROOT_DIR").is_some()
+}
+
+/// The severity of a diagnostic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A warning or an error reported by the filter, optionally with the file
+/// and the position in the file that it's about.
+///
+/// By default the diagnostics are printed as free-form text (e.g.
+/// `WARNING! "BP/x.json": message`). In the structured format (used under
+/// Regolith) every diagnostic is a single line tagged with its severity and
+/// the location in the `path:line:column` form, e.g.
+/// `[ERROR] data/custom_project/x.bpe.json:3:5: message`.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The path to the file with the problem.
+    pub source: Option<PathBuf>,
+    /// The line in the file (starting from 1).
+    pub line: Option<usize>,
+    /// The column in the line (starting from 1).
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic about the file.
+    pub fn new(
+        severity: Severity, source: &Path, message: impl Into<String>,
+    ) -> Diagnostic {
+        Diagnostic {
+            severity,
+            source: Some(source.to_path_buf()),
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a diagnostic that isn't about a file.
+    pub fn general(
+        severity: Severity, message: impl Into<String>,
+    ) -> Diagnostic {
+        Diagnostic {
+            severity, source: None, line: None, column: None,
+            message: message.into(),
+        }
+    }
+
+    /// Sets the position of the problem in the file.
+    pub fn at(mut self, line: usize, column: usize) -> Diagnostic {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+
+    /// Prints the diagnostic to the standard error output.
+    pub fn emit(&self) {
+        eprintln!("{}", self);
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if is_structured() {
+            let tag = match self.severity {
+                Severity::Warning => "[WARNING]",
+                Severity::Error => "[ERROR]",
+            };
+            // The paths always use "/" so the output is the same on all
+            // platforms
+            let location = self.source.as_ref()
+                .map(|s| s.to_string_lossy().replace('\\', "/"));
+            return match (location, self.line, self.column) {
+                (Some(s), Some(l), Some(c)) => {
+                    write!(f, "{} {}:{}:{}: {}", tag, s, l, c, self.message)
+                },
+                (Some(s), Some(l), None) => {
+                    write!(f, "{} {}:{}: {}", tag, s, l, self.message)
+                },
+                (Some(s), _, _) => {
+                    write!(f, "{} {}: {}", tag, s, self.message)
+                },
+                (None, _, _) => write!(f, "{} {}", tag, self.message),
+            };
+        }
+        let prefix = match self.severity {
+            Severity::Warning => "WARNING!",
+            Severity::Error => "ERROR!",
+        };
+        let position = match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                format!(" (line {}, column {})", line, column)
+            },
+            (Some(line), None) => format!(" (line {})", line),
+            _ => String::new(),
+        };
+        match &self.source {
+            Some(source) => write!(
+                f, "{} \"{}\"{}: {}",
+                prefix, source.display(), position, self.message
+            ),
+            None => write!(f, "{} {}", prefix, self.message),
+        }
+    }
+}
+
+/// Prints a warning about the file.
+pub fn warning(source: &Path, message: impl Into<String>) {
+    Diagnostic::new(Severity::Warning, source, message).emit();
+}
+
+/// Prints a warning that isn't about a file.
+pub fn general_warning(message: impl Into<String>) {
+    Diagnostic::general(Severity::Warning, message).emit();
+}
+
+/// Prints an error that isn't about a file and doesn't stop the filter
+/// (e.g. the error of a single update of the `watch` command).
+pub fn general_error(message: impl Into<String>) {
+    Diagnostic::general(Severity::Error, message).emit();
+}
+
+/// Prints the error that stops the filter. Without the structured format
+/// the message is printed without the severity prefix.
+pub fn fatal(message: &str) {
+    match is_structured() {
+        true => Diagnostic::general(Severity::Error, message).emit(),
+        false => eprintln!("{}", message),
+    }
+}
@@ -0,0 +1,65 @@
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// The number of the retries of an operation that failed because the file
+/// was locked.
+static MAX_RETRIES: u32 = 5;
+
+/// The delay before the first retry. It's doubled after every retry.
+static FIRST_DELAY: Duration = Duration::from_millis(50);
+
+/// Checks if the error is caused by a file that is briefly locked by another
+/// process (e.g. an antivirus or Minecraft on Windows).
+fn is_transient(error: &io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    #[cfg(windows)]
+    if matches!(error.raw_os_error(), Some(32) | Some(33)) {
+        return true;
+    }
+    error.kind() == io::ErrorKind::ResourceBusy
+}
+
+/// Runs the file operation and retries it with an exponential backoff if it
+/// fails because the file is locked. The other errors and the error of the
+/// last retry are returned.
+pub fn with_retries<T>(
+    mut operation: impl FnMut() -> io::Result<T>
+) -> io::Result<T> {
+    let mut delay = FIRST_DELAY;
+    for _ in 0..MAX_RETRIES {
+        match operation() {
+            Err(e) if is_transient(&e) => {
+                thread::sleep(delay);
+                delay *= 2;
+            },
+            result => return result,
+        }
+    }
+    operation()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_only_the_locked_files() {
+        let mut calls = 0;
+        let result = with_retries(|| {
+            calls += 1;
+            match calls {
+                1 | 2 => Err(io::Error::from(io::ErrorKind::ResourceBusy)),
+                _ => Ok(calls),
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+        let mut calls = 0;
+        let result: io::Result<()> = with_retries(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{MapResult, Mapper, TargetInfo};
+use crate::plan::PlannedFile;
+
+/// The suffixes of the names of the files that decide the subfolder of the
+/// textures next to them (e.g. the textures next to a client entity are
+/// entity textures).
+static TEXTURE_CONTEXTS: &[(&str, &str)] = &[
+    (".rpe.json", "entity"),
+    (".bpe.json", "entity"),
+    (".i.json", "items"),
+    (".bpi.json", "items"),
+    (".rpi.json", "items"),
+    (".bpb.json", "blocks"),
+];
+
+/// The directory of the textures in the resource pack.
+static TEXTURES_DIR: &[&str] = &["RP", "textures"];
+
+/// Returns the subfolder of the textures for the file next to them or None
+/// if the file doesn't decide it.
+fn texture_context(file: &PlannedFile) -> Option<&'static str> {
+    let name = file.source.file_name()?.to_str()?;
+    TEXTURE_CONTEXTS.iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|(_, subfolder)| *subfolder)
+}
+
+/// Routes the textures mapped directly to "RP/textures" by the extension
+/// rules into the "entity", "items" or "blocks" subfolder based on the
+/// files in the same folder (e.g. "zombie/zombie.png" next to
+/// "zombie/zombie.rpe.json" is mapped to "RP/textures/entity/zombie.png").
+/// The textures next to the files of different kinds aren't routed.
+pub struct TextureRouting;
+
+impl Mapper for TextureRouting {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The subfolders of the folders or None if they're ambiguous
+        let mut contexts: HashMap<PathBuf, Option<&str>> = HashMap::new();
+        for file in files.iter() {
+            let (folder, context) = match (
+                file.source.parent(), texture_context(file)
+            ) {
+                (Some(folder), Some(context)) => (folder, context),
+                _ => continue,
+            };
+            contexts.entry(folder.to_path_buf())
+                .and_modify(|c| if *c != Some(context) { *c = None })
+                .or_insert(Some(context));
+        }
+        let textures_dir: PathBuf = TEXTURES_DIR.iter().collect();
+        for file in files.iter_mut() {
+            let context = file.source.parent()
+                .and_then(|folder| contexts.get(folder).copied().flatten());
+            let (target, context) = match (&file.target, context) {
+                (Some(target), Some(context))
+                    if target.rule.starts_with('.') => (target, context),
+                _ => continue,
+            };
+            let rest = match target.path.strip_prefix(&textures_dir) {
+                Ok(rest) => rest,
+                Err(_) => continue,
+            };
+            if rest.starts_with(context) {
+                continue;
+            }
+            let path = textures_dir.join(context).join(rest);
+            file.target = Some(TargetInfo::new(&target.rule, path));
+        }
+    }
+}
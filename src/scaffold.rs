@@ -0,0 +1,208 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{ExtExportMap, FILTER_DATA_PATH};
+use crate::mapping::get_target_path_from_hash_map;
+
+/// A 16x16 magenta and black checkerboard used as a placeholder texture for
+/// the generated content.
+static PLACEHOLDER_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d,
+    0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x10,
+    0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0xf3, 0xff, 0x61, 0x00, 0x00, 0x00,
+    0x23, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0xf8, 0xcf, 0xf0, 0xff,
+    0x3f, 0x3e, 0xcc, 0xc0, 0xc0, 0x80, 0x1f, 0x8f, 0x1a, 0x30, 0x2c, 0x0c,
+    0x20, 0xa4, 0x80, 0x08, 0x0b, 0x46, 0x0d, 0x18, 0xfa, 0x06, 0x00, 0x00,
+    0x31, 0xb5, 0xfe, 0x10, 0x51, 0xd3, 0xbc, 0x69, 0x00, 0x00, 0x00, 0x00,
+    0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// The kinds of content that can be generated with the `new` subcommand.
+pub static CONTENT_KINDS: &[&str] = &["entity", "item", "block"];
+
+/// Converts a snake_case name into a human readable name used in the lang
+/// files (e.g. "my_mob" -> "My Mob").
+fn display_name(name: &str) -> String {
+    name.split('_')
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Pretty prints the JSON content of a generated file.
+fn json_content(value: serde_json::Value) -> String {
+    format!("{:#}\n", value)
+}
+
+/// Returns the list of files (file name and content) that make up the
+/// conventional file set of the given kind of content.
+///
+/// # Arguments
+/// - `kind` - the kind of content (one of `CONTENT_KINDS`)
+/// - `namespace` - the namespace of the project
+/// - `name` - the name of the new content (without the namespace)
+/// - `texture` - the path to the texture in the RP without the extension
+fn content_files(
+    kind: &str, namespace: &str, name: &str, texture: &str
+) -> Option<Vec<(String, Vec<u8>)>> {
+    let id = format!("{}:{}", namespace, name);
+    let display = display_name(name);
+    let files: Vec<(String, String)> = match kind {
+        "entity" => vec![
+            (format!("{}.bpe.json", name), json_content(serde_json::json!({
+                "format_version": "1.20.0",
+                "minecraft:entity": {
+                    "description": {
+                        "identifier": id,
+                        "is_spawnable": true,
+                        "is_summonable": true,
+                        "is_experimental": false
+                    },
+                    "components": {
+                        "minecraft:physics": {}
+                    }
+                }
+            }))),
+            (format!("{}.rpe.json", name), json_content(serde_json::json!({
+                "format_version": "1.10.0",
+                "minecraft:client_entity": {
+                    "description": {
+                        "identifier": id,
+                        "materials": {"default": "entity_alphatest"},
+                        "textures": {"default": texture},
+                        "geometry": {
+                            "default": format!(
+                                "geometry.{}.{}", namespace, name
+                            )
+                        },
+                        "render_controllers": ["controller.render.default"],
+                        "spawn_egg": {
+                            "base_color": "#ff00ff",
+                            "overlay_color": "#000000"
+                        }
+                    }
+                }
+            }))),
+            (format!("{}.geo.json", name), json_content(serde_json::json!({
+                "format_version": "1.12.0",
+                "minecraft:geometry": [{
+                    "description": {
+                        "identifier": format!(
+                            "geometry.{}.{}", namespace, name
+                        ),
+                        "texture_width": 16,
+                        "texture_height": 16
+                    },
+                    "bones": []
+                }]
+            }))),
+            (format!("{}.lang", name), format!(
+                "entity.{id}.name={display}\n\
+                item.spawn_egg.entity.{id}.name=Spawn {display}\n",
+                id=id, display=display
+            )),
+        ],
+        "item" => vec![
+            (format!("{}.bpi.json", name), json_content(serde_json::json!({
+                "format_version": "1.20.0",
+                "minecraft:item": {
+                    "description": {
+                        "identifier": id,
+                        "menu_category": {"category": "items"}
+                    },
+                    "components": {
+                        "minecraft:icon": {"texture": name}
+                    }
+                }
+            }))),
+            (format!("{}.lang", name), format!(
+                "item.{}.name={}\n", id, display
+            )),
+        ],
+        "block" => vec![
+            (format!("{}.bpb.json", name), json_content(serde_json::json!({
+                "format_version": "1.20.0",
+                "minecraft:block": {
+                    "description": {
+                        "identifier": id,
+                        "menu_category": {"category": "construction"}
+                    },
+                    "components": {
+                        "minecraft:material_instances": {
+                            "*": {"texture": name}
+                        }
+                    }
+                }
+            }))),
+            (format!("{}.lang", name), format!(
+                "tile.{}.name={}\n", id, display
+            )),
+        ],
+        _ => return None,
+    };
+    let mut result: Vec<(String, Vec<u8>)> = files.into_iter()
+        .map(|(file_name, content)| (file_name, content.into_bytes()))
+        .collect();
+    result.push((format!("{}.png", name), PLACEHOLDER_PNG.to_vec()));
+    Some(result)
+}
+
+/// Generates the conventional file set for new content in its own folder in
+/// the data directory of the filter.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `root` - the root directory (relative to the filter data path) to put
+///   the new content in
+/// - `export_map` - the map of file extensions and the export targets used
+///   to find where the texture of the new content will be exported
+/// - `kind` - the kind of content (one of `CONTENT_KINDS`)
+/// - `namespace` - the namespace of the project
+/// - `name` - the name of the new content (without the namespace)
+pub fn new_content(
+    working_dir: &Path, root: &str, export_map: &ExtExportMap, kind: &str,
+    namespace: &str, name: &str,
+) -> Result<(), Box<dyn Error>> {
+    // The path to the texture as referenced by the RP files (relative to the
+    // RP and without the extension)
+    let texture = get_target_path_from_hash_map(
+            &Path::new(name).join(format!("{}.png", name)), export_map
+        )
+        .and_then(|p| {
+            let p = p.strip_prefix("RP").ok()?.with_extension("");
+            Some(p.to_str()?.replace('\\', "/"))
+        })
+        .unwrap_or_else(|| format!("textures/entity/{}/{}", name, name));
+    let files = content_files(
+        kind, namespace, name, &texture
+    ).ok_or_else(|| format!(
+        "Unknown content kind \"{}\". Expected one of: {}",
+        kind, CONTENT_KINDS.join(", ")
+    ))?;
+    let target_dir = working_dir.join(FILTER_DATA_PATH).join(root).join(name);
+    // Don't overwrite anything, check all of the files before writing
+    for (file_name, _) in &files {
+        let fp = target_dir.join(file_name);
+        if fp.exists() {
+            return Err(format!(
+                "File \"{}\" already exists. Nothing was generated.",
+                fp.display()
+            ).into());
+        }
+    }
+    fs::create_dir_all(&target_dir)?;
+    for (file_name, content) in &files {
+        let fp = target_dir.join(file_name);
+        fs::write(&fp, content)?;
+        println!("Created \"{}\"", fp.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::Pack;
+use crate::mcfunction::{split_arguments, words};
+use crate::plan::PlannedFile;
+use crate::report::{Diagnostic, Severity};
+
+/// The extensions of the scripts of the behavior pack.
+static SCRIPT_EXTENSIONS: &[&str] = &["js", "ts"];
+
+/// The methods of the Script API that add the objectives or the tags and
+/// the ones that use them, with their first arguments as the names.
+static SCRIPT_OBJECTIVES: &[(&str, bool)] = &[
+    ("addObjective(", true), ("getObjective(", false),
+    ("removeObjective(", false),
+];
+static SCRIPT_TAGS: &[(&str, bool)] = &[
+    ("addTag(", true), ("hasTag(", false), ("removeTag(", false),
+];
+
+/// The methods of the Script API that run the commands.
+static SCRIPT_COMMANDS: &[&str] = &["runCommand(", "runCommandAsync("];
+
+/// A line of a source file.
+pub type Location = (PathBuf, usize);
+
+/// The places where an objective or a tag is added and used.
+#[derive(Default)]
+pub struct Usage {
+    /// The "scoreboard objectives add" and the "tag add" commands (or their
+    /// equivalents in the scripts).
+    pub added: Vec<Location>,
+    /// The other references.
+    pub used: Vec<Location>,
+}
+
+/// The scoreboard objectives and the tags of the functions and the scripts
+/// of the behavior pack.
+#[derive(Default)]
+pub struct ScoreboardReport {
+    pub objectives: BTreeMap<String, Usage>,
+    pub tags: BTreeMap<String, Usage>,
+}
+
+impl ScoreboardReport {
+    /// Records the reference to the objective or the tag.
+    fn record(
+        names: &mut BTreeMap<String, Usage>, name: &str, added: bool,
+        location: &Location,
+    ) {
+        let name = name.trim_matches('"');
+        if name.is_empty() || name == "*" {
+            return;
+        }
+        let usage = names.entry(name.to_string()).or_default();
+        match added {
+            true => usage.added.push(location.clone()),
+            false => usage.used.push(location.clone()),
+        }
+    }
+
+    /// Records the objectives and the tags of the command (and the commands
+    /// run by "execute").
+    fn scan_command(&mut self, command: &str, location: &Location) {
+        let words: Vec<&str> = words(command).into_iter()
+            .map(|(_, word)| word)
+            .collect();
+        let word = |i: usize| words.get(i).copied();
+        for (i, w) in words.iter().enumerate() {
+            // The objectives and the tags of the selectors
+            if w.starts_with('@') {
+                self.scan_selector(w, location);
+            }
+            // The "if score" and "unless score" of "execute"
+            if (*w == "if" || *w == "unless") && word(i + 1) == Some("score")
+            {
+                if let Some(objective) = word(i + 3) {
+                    Self::record(
+                        &mut self.objectives, objective, false, location
+                    );
+                }
+                if let (Some(op), Some(objective)) = (word(i + 4), word(i + 6))
+                {
+                    if op != "matches" {
+                        Self::record(
+                            &mut self.objectives, objective, false, location
+                        );
+                    }
+                }
+            }
+            // The commands are at the start and after the "run" of "execute"
+            let is_command = i == 0 || words[i - 1] == "run";
+            if !is_command {
+                continue;
+            }
+            let name = w.trim_start_matches('/');
+            if name == "tag" {
+                if let (Some(action), Some(tag)) = (word(i + 2), word(i + 3))
+                {
+                    if action == "add" || action == "remove" {
+                        Self::record(
+                            &mut self.tags, tag, action == "add", location
+                        );
+                    }
+                }
+                continue;
+            }
+            if name != "scoreboard" {
+                continue;
+            }
+            let references = match (word(i + 1), word(i + 2)) {
+                (Some("objectives"), Some("add")) => vec![(i + 3, true)],
+                (Some("objectives"), Some("remove")) => vec![(i + 3, false)],
+                (Some("objectives"), Some("setdisplay")) => {
+                    vec![(i + 4, false)]
+                },
+                (Some("players"), Some("list")) => vec![],
+                (Some("players"), Some("operation")) => {
+                    vec![(i + 4, false), (i + 7, false)]
+                },
+                (Some("players"), Some(_)) => vec![(i + 4, false)],
+                _ => vec![],
+            };
+            for (j, added) in references {
+                if let Some(objective) = word(j) {
+                    Self::record(
+                        &mut self.objectives, objective, added, location
+                    );
+                }
+            }
+        }
+    }
+
+    /// Records the "scores" and the "tag" arguments of the selector (e.g.
+    /// "@e[scores={kills=1..},tag=!boss]").
+    fn scan_selector(&mut self, selector: &str, location: &Location) {
+        let arguments = match selector.split_once('[') {
+            Some((_, arguments)) => arguments.strip_suffix(']')
+                .unwrap_or(arguments),
+            None => return,
+        };
+        for (_, argument) in split_arguments(arguments) {
+            let (key, value) = match argument.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            match key {
+                "scores" => {
+                    let scores = value.trim_start_matches('{')
+                        .trim_end_matches('}');
+                    for (_, score) in split_arguments(scores) {
+                        if let Some((objective, _)) = score.split_once('=') {
+                            Self::record(
+                                &mut self.objectives, objective.trim(),
+                                false, location,
+                            );
+                        }
+                    }
+                },
+                "tag" => Self::record(
+                    &mut self.tags, value.trim_start_matches('!').trim(),
+                    false, location,
+                ),
+                _ => {},
+            }
+        }
+    }
+
+    /// Records the objectives and the tags of the line of the script: the
+    /// string arguments of the methods of the Script API and the commands
+    /// run by the scripts.
+    fn scan_script_line(&mut self, line: &str, location: &Location) {
+        for (method, added) in SCRIPT_OBJECTIVES {
+            for name in string_arguments(line, method) {
+                Self::record(&mut self.objectives, &name, *added, location);
+            }
+        }
+        for (method, added) in SCRIPT_TAGS {
+            for name in string_arguments(line, method) {
+                Self::record(&mut self.tags, &name, *added, location);
+            }
+        }
+        for method in SCRIPT_COMMANDS {
+            for command in string_arguments(line, method) {
+                self.scan_command(command.trim(), location);
+            }
+        }
+    }
+
+    /// Returns the warnings about the objectives that are used but never
+    /// added, which makes the commands that use them fail.
+    pub fn problems(&self) -> Vec<Diagnostic> {
+        self.objectives.iter()
+            .filter(|(_, usage)| usage.added.is_empty())
+            .filter_map(|(name, usage)| {
+                let (source, line) = usage.used.first()?;
+                Some(Diagnostic::new(Severity::Warning, source, format!(
+                    "The objective \"{}\" is used but never added.", name
+                )).at(*line, 1))
+            })
+            .collect()
+    }
+}
+
+/// Returns the string literals that are the first arguments of the calls of
+/// the method in the line of the script (e.g. "kills" of
+/// `addObjective("kills", "Kills")`).
+fn string_arguments(line: &str, method: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    for (i, _) in line.match_indices(method) {
+        let rest = line[i + method.len()..].trim_start();
+        let quote = match rest.chars().next() {
+            Some(c) if c == '"' || c == '\'' || c == '`' => c,
+            _ => continue,
+        };
+        if let Some((value, _)) = rest[1..].split_once(quote) {
+            result.push(value.to_string());
+        }
+    }
+    result
+}
+
+/// Reads the lines of the source file.
+fn read_lines(source: &Path) -> Vec<String> {
+    let text = match fs::read(source) {
+        Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+        Err(_) => return Vec::new(),
+    };
+    text.trim_start_matches('\u{feff}').lines().map(str::to_string).collect()
+}
+
+/// Finds the scoreboard objectives and the tags added and used by the
+/// functions and the scripts of the behavior pack.
+pub fn scoreboard_report(files: &[PlannedFile]) -> ScoreboardReport {
+    let mut report = ScoreboardReport::default();
+    for file in files {
+        match &file.target {
+            Some(target) if target.pack == Pack::Behavior => {},
+            _ => continue,
+        }
+        let extension = file.source.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let script = SCRIPT_EXTENSIONS.contains(&extension.as_str());
+        if extension != "mcfunction" && !script {
+            continue;
+        }
+        for (number, line) in read_lines(&file.source).iter().enumerate() {
+            let location = (file.source.clone(), number + 1);
+            let command = line.trim();
+            if script {
+                report.scan_script_line(line, &location);
+            } else if !command.is_empty() && !command.starts_with('#') {
+                report.scan_command(command, &location);
+            }
+        }
+    }
+    report
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::items::item_pack;
+use crate::mapping::{MapResult, Mapper, Pack, TargetInfo, FALLBACK_RULE};
+use crate::plan::PlannedFile;
+use crate::references::properties;
+
+/// The name of the rule of the files mapped by the ContentMapper.
+pub static CONTENT_RULE: &str = "(content)";
+
+/// The top-level keys of the JSON files and the directories of the packs
+/// that the files with them are mapped to.
+static CONTENT_KEYS: &[(&str, &str)] = &[
+    ("minecraft:entity", "BP/entities"),
+    ("minecraft:block", "BP/blocks"),
+    ("minecraft:recipe_shaped", "BP/recipes"),
+    ("minecraft:recipe_shapeless", "BP/recipes"),
+    ("minecraft:recipe_furnace", "BP/recipes"),
+    ("minecraft:recipe_brewing_mix", "BP/recipes"),
+    ("minecraft:recipe_brewing_container", "BP/recipes"),
+    ("minecraft:recipe_smithing_transform", "BP/recipes"),
+    ("minecraft:recipe_smithing_trim", "BP/recipes"),
+    ("minecraft:spawn_rules", "BP/spawn_rules"),
+    ("minecraft:feature_rules", "BP/feature_rules"),
+    ("pools", "BP/loot_tables"),
+    ("tiers", "BP/trading"),
+    ("minecraft:client_entity", "RP/entity"),
+    ("minecraft:attachable", "RP/attachables"),
+    ("minecraft:geometry", "RP/models/entity"),
+    ("render_controllers", "RP/render_controllers"),
+    ("particle_effect", "RP/particles"),
+];
+
+/// Returns the directory of the packs that the JSON file belongs to based
+/// on its content or None if the content isn't recognized. The items are
+/// recognized by their format (see item_pack) and the animations and the
+/// animation controllers of the resource pack by the bones and by the lack
+/// of the commands ("on_entry" and "on_exit").
+fn content_target(json: &serde_json::Value) -> Option<&'static str> {
+    let object = json.as_object()?;
+    match item_pack(json) {
+        Some(Pack::Resource) => return Some("RP/items"),
+        Some(_) => return Some("BP/items"),
+        None => {},
+    }
+    if let Some((_, target)) = CONTENT_KEYS.iter()
+        .find(|(key, _)| object.contains_key(*key))
+    {
+        return Some(target);
+    }
+    let key = object.keys().find(|key| *key != "format_version")?;
+    match key.as_str() {
+        "animations" => match properties(json, "bones").is_empty() {
+            true => Some("BP/animations"),
+            false => Some("RP/animations"),
+        },
+        "animation_controllers" => {
+            let commands = !properties(json, "on_entry").is_empty()
+                || !properties(json, "on_exit").is_empty();
+            match commands {
+                true => Some("BP/animation_controllers"),
+                false => Some("RP/animation_controllers"),
+            }
+        },
+        // The legacy geometry format
+        key if key.starts_with("geometry.") => Some("RP/models/entity"),
+        // The features (e.g. "minecraft:ore_feature")
+        key if key.starts_with("minecraft:") && key.ends_with("_feature") => {
+            Some("BP/features")
+        },
+        _ => None,
+    }
+}
+
+/// Maps the ".json" files that aren't matched by any rule (or that are only
+/// matched by the fallback target) based on their content: the top-level
+/// key (e.g. "minecraft:entity" or "minecraft:recipe_shaped") decides the
+/// directory of the packs. The paths relative to the root are kept like
+/// with the extension rules. The files that can't be read or parsed are
+/// left as they are.
+pub struct ContentMapper;
+
+impl Mapper for ContentMapper {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        for file in files.iter_mut() {
+            let mapped = file.rule().is_some_and(|rule| rule != FALLBACK_RULE);
+            if mapped || file.source.extension().is_none_or(|e| e != "json")
+            {
+                continue;
+            }
+            let json: Option<serde_json::Value> = fs::read_to_string(
+                &file.source
+            ).ok().and_then(|text| serde_json::from_str(
+                text.trim_start_matches('\u{feff}')
+            ).ok());
+            let target = match json.as_ref().and_then(content_target) {
+                Some(target) => target,
+                None => continue,
+            };
+            let path = match file.source.strip_prefix(root_dir) {
+                Ok(path) => path,
+                Err(_) => continue,
+            };
+            // Fix the path separators (e.g "/" -> "\\")
+            let target: PathBuf = PathBuf::from(target).iter().collect();
+            file.target = Some(
+                TargetInfo::new(CONTENT_RULE, target.join(path))
+            );
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{
+    resource_dir, resource_reference, MapResult, Mapper, Pack, TargetInfo,
+};
+use crate::plan::PlannedFile;
+
+/// The name of the rule of the files generated by the SoundEvents.
+pub static SOUNDS_RULE: &str = "(sounds)";
+
+/// The extensions of the sound files.
+static SOUND_EXTENSIONS: &[&str] = &["ogg", "wav", "fsb"];
+
+/// The name of the folders with the sounds of the events of the entities.
+static EVENTS_DIR: &str = "events";
+
+/// The name of the file with the sound definitions (in "RP/sounds").
+pub static SOUND_DEFINITIONS: &str = "sound_definitions.json";
+
+/// The name of the file that binds the sounds to the events (in "RP").
+pub static SOUNDS: &str = "sounds.json";
+
+/// Returns the folder of the entity of the sound of an event (e.g.
+/// "zombie" for "zombie/events/hurt/hurt1.ogg") or None if the file isn't
+/// a sound of an event.
+fn entity_folder(source: &Path) -> Option<&Path> {
+    if source.extension()
+        .is_none_or(|e| !SOUND_EXTENSIONS.iter().any(|s| e == *s))
+    {
+        return None;
+    }
+    let events = source.parent()?.parent()?;
+    match events.file_name()? == EVENTS_DIR {
+        true => events.parent(),
+        false => None,
+    }
+}
+
+/// Returns the name of the event of the sound (e.g. "hurt" for
+/// "zombie/events/hurt/hurt1.ogg").
+fn event_name(source: &Path) -> String {
+    source.parent().and_then(|p| p.file_name()).unwrap_or_default()
+        .to_string_lossy().into_owned()
+}
+
+/// Reads the JSON file. The UTF-8 BOM is ignored.
+fn read_json(path: &Path) -> Result<serde_json::Value, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("\"{}\": {}", path.display(), e))
+}
+
+/// Returns the events of the entities with the references of their sounds
+/// (e.g. "sounds/zombie/events/hurt/hurt1") by the identifiers of the
+/// entities and the names of the sound definitions.
+///
+/// # Arguments
+/// - `inputs` - the sources and the targets of the client entities and the
+///   sounds of their events
+fn sound_events(
+    inputs: &[(PathBuf, PathBuf)],
+) -> Result<BTreeMap<(String, String), Vec<String>>, String> {
+    let (sounds, entities): (Vec<_>, Vec<_>) = inputs.iter()
+        .partition(|(source, _)| entity_folder(source).is_some());
+    // The identifiers of the client entities by their folders
+    let mut identifiers: HashMap<&Path, String> = HashMap::new();
+    for (source, _) in entities {
+        let identifier = read_json(source)?["minecraft:client_entity"]
+            ["description"]["identifier"].as_str()
+            .ok_or_else(|| format!(
+                "The client entity \"{}\" doesn't have an identifier.",
+                source.display()
+            ))?
+            .to_string();
+        if let Some(folder) = source.parent() {
+            identifiers.entry(folder).or_insert(identifier);
+        }
+    }
+    let mut events: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    for (source, target) in sounds {
+        let identifier = match entity_folder(source)
+            .and_then(|folder| identifiers.get(folder))
+        {
+            Some(identifier) => identifier.clone(),
+            None => continue,
+        };
+        events.entry((identifier, event_name(source))).or_default()
+            .push(resource_reference(target, "sounds"));
+    }
+    Ok(events)
+}
+
+/// Returns the name of the sound definition of the event of the entity
+/// (e.g. "ns.zombie.hurt" for "ns:zombie" and "hurt").
+fn definition_name(identifier: &str, event: &str) -> String {
+    format!("{}.{}", identifier.replace(':', "."), event)
+}
+
+/// Wires the sounds in the "events/<event>" folders of the entities (the
+/// folders with the client entities) into the packs: the
+/// "sound_definitions.json" gets a definition of every event (e.g.
+/// "ns.zombie.hurt" with the sounds of "zombie/events/hurt") and the
+/// "sounds.json" binds the definitions to the events of the entities. The
+/// sounds are still copied and the files from the sources are extended
+/// instead of being replaced.
+pub struct SoundEvents;
+
+impl Mapper for SoundEvents {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The client entities by their folders
+        let entities: HashMap<&Path, &PlannedFile> = files.iter()
+            .filter(|file| file.target.as_ref().is_some_and(|target| {
+                target.pack == Pack::Resource && target.category == "entity"
+            }))
+            .filter_map(|file| Some((file.source.parent()?, file)))
+            .collect();
+        // The client entities and the sounds of their events by the
+        // directories of the resource packs
+        let mut sounds: BTreeMap<PathBuf, Vec<(PathBuf, PathBuf)>> =
+            BTreeMap::new();
+        for file in files.iter() {
+            let (folder, target) = match (
+                entity_folder(&file.source), &file.target
+            ) {
+                (Some(folder), Some(target)) => (folder, target),
+                _ => continue,
+            };
+            let (sounds_dir, entity) = match (
+                resource_dir(&target.path, "sounds"), entities.get(folder)
+            ) {
+                (Some(dir), Some(entity)) => (dir, entity),
+                _ => continue,
+            };
+            let pack_dir = sounds_dir.parent().unwrap_or(Path::new(""));
+            let inputs = sounds.entry(pack_dir.to_path_buf()).or_default();
+            let entity = (
+                entity.source.clone(), entity.target_path().unwrap().clone()
+            );
+            if !inputs.contains(&entity) {
+                inputs.push(entity);
+            }
+            inputs.push((file.source.clone(), target.path.clone()));
+        }
+        for (pack_dir, inputs) in sounds {
+            let paths = [
+                pack_dir.join("sounds").join(SOUND_DEFINITIONS),
+                pack_dir.join(SOUNDS),
+            ];
+            for path in paths {
+                let existing = files.iter_mut()
+                    .find(|file| file.target_path() == Some(&path));
+                let target = TargetInfo::new(SOUNDS_RULE, path)
+                    .with_inputs(inputs.clone());
+                match existing {
+                    Some(file) => file.target = Some(target),
+                    // The last input is always a sound
+                    None => files.push(PlannedFile {
+                        source: inputs[inputs.len() - 1].0.clone(),
+                        target: Some(target),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// Reads the JSON object from the source if it's the file with the name or
+/// returns the default object.
+fn source_object(
+    source: &Path, name: &str, default: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    match source.file_name() {
+        Some(file_name) if file_name == name => match read_json(source)? {
+            json @ serde_json::Value::Object(_) => Ok(json),
+            _ => Err("The source isn't a JSON object.".into()),
+        },
+        _ => Ok(default),
+    }
+}
+
+/// Generates the "sound_definitions.json" or the "sounds.json" (based on
+/// the name of the target) with the sounds of the events of the entities
+/// added to the source file (if the source is the same file). The
+/// definitions and the events from the source are replaced.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target with the sounds of the events as the inputs
+pub fn sounds_contents(
+    source: &Path, target: &TargetInfo,
+) -> Result<Vec<u8>, String> {
+    let events = sound_events(&target.inputs)?;
+    let name = target.path.file_name().unwrap_or_default();
+    let json = match name == SOUND_DEFINITIONS {
+        true => {
+            let mut json = source_object(source, SOUND_DEFINITIONS,
+                serde_json::json!({
+                    "format_version": "1.14.0", "sound_definitions": {},
+                }),
+            )?;
+            let definitions = json.as_object_mut().unwrap()
+                .entry("sound_definitions")
+                .or_insert_with(|| serde_json::json!({}));
+            let definitions = definitions.as_object_mut()
+                .ok_or("The \"sound_definitions\" isn't an object.")?;
+            for ((identifier, event), sounds) in events {
+                definitions.insert(
+                    definition_name(&identifier, &event),
+                    serde_json::json!({
+                        "category": "neutral", "sounds": sounds,
+                    }),
+                );
+            }
+            json
+        },
+        false => {
+            let mut json = source_object(
+                source, SOUNDS, serde_json::json!({}),
+            )?;
+            // The missing objects are added but the other values can't be
+            // extended
+            let valid = |v: &serde_json::Value| v.is_null() || v.is_object();
+            if !valid(&json["entity_sounds"])
+                || !valid(&json["entity_sounds"]["entities"])
+            {
+                return Err("The \"entity_sounds\" isn't an object.".into());
+            }
+            for ((identifier, event), _) in events {
+                let entity = &mut json["entity_sounds"]["entities"]
+                    [identifier.as_str()];
+                if !valid(entity) {
+                    return Err(format!(
+                        "The sounds of \"{}\" aren't an object.", identifier
+                    ));
+                }
+                if !entity["events"].is_object() {
+                    entity["events"] = serde_json::json!({});
+                }
+                entity["events"][event.as_str()] =
+                    definition_name(&identifier, &event).into();
+            }
+            json
+        },
+    };
+    Ok(format!("{:#}\n", json).into_bytes())
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use crate::index::{Document, ProjectIndex};
+use crate::mapping::Pack;
+use crate::report::{Diagnostic, Severity};
+use crate::vanilla::vanilla_entity;
+
+/// The population control pools of the spawn rules.
+static POPULATION_POOLS: &[&str] = &[
+    "animal", "water_animal", "monster", "cat", "pillager", "ambient",
+];
+
+/// The operators of the filters.
+static FILTER_OPERATORS: &[&str] = &[
+    "==", "!=", "<", ">", "<=", ">=", "<>", "=", "equals", "not",
+];
+
+/// The properties of the filters that group other filters.
+static FILTER_GROUPS: &[&str] = &["all_of", "any_of", "none_of"];
+
+/// Checks the syntax of the filter (e.g. the "minecraft:biome_filter"): an
+/// object with the "test" or the groups of the filters, or an array of the
+/// filters. Returns the descriptions of the problems.
+fn filter_problems(filter: &serde_json::Value) -> Vec<String> {
+    let object = match filter {
+        serde_json::Value::Array(filters) => {
+            return filters.iter().flat_map(filter_problems).collect();
+        },
+        serde_json::Value::Object(object) => object,
+        _ => return vec!["The filter has to be an object or an array.".into()],
+    };
+    let mut problems = Vec::new();
+    let groups: Vec<&serde_json::Value> = FILTER_GROUPS.iter()
+        .filter_map(|group| object.get(*group))
+        .collect();
+    for group in &groups {
+        problems.extend(filter_problems(group));
+    }
+    match object.get("test").map(|test| test.as_str()) {
+        Some(Some("has_biome_tag")) if !object.contains_key("value") => {
+            problems.push(
+                "The \"has_biome_tag\" filter doesn't have a \"value\"."
+                    .into()
+            );
+        },
+        Some(Some(_)) => {},
+        Some(None) => problems.push("The \"test\" has to be a string.".into()),
+        None if groups.is_empty() => problems.push(
+            "The filter doesn't have a \"test\" or a group of filters \
+            (\"all_of\", \"any_of\" or \"none_of\").".into()
+        ),
+        None => {},
+    }
+    if let Some(operator) = object.get("operator") {
+        let valid = operator.as_str()
+            .is_some_and(|o| FILTER_OPERATORS.contains(&o));
+        if !valid {
+            problems.push(format!("The operator {} is invalid.", operator));
+        }
+    }
+    problems
+}
+
+/// Checks the spawn rule: its identifier has to match an entity of the
+/// project (or a vanilla entity), the population control pool has to be
+/// valid and the biome filters have to be valid filters.
+fn check_spawn_rule(
+    document: &Document, entities: &HashMap<&str, &Document>,
+    problems: &mut Vec<Diagnostic>,
+) {
+    let rule = &document.json["minecraft:spawn_rules"];
+    let mut problem = |severity, message: String| problems.push(
+        Diagnostic::new(severity, document.source, message)
+    );
+    match rule["description"]["identifier"].as_str() {
+        Some(identifier) => match entities.get(identifier) {
+            Some(entity) => {
+                let spawnable = entity.json["minecraft:entity"]
+                    ["description"]["is_spawnable"].as_bool();
+                if spawnable != Some(true) {
+                    problem(Severity::Warning, format!(
+                        "The entity \"{}\" has a spawn rule but it isn't \
+                        marked as spawnable (\"is_spawnable\").",
+                        identifier
+                    ));
+                }
+            },
+            None if vanilla_entity(identifier).is_some() => {},
+            None => problem(Severity::Error, format!(
+                "The spawn rule is for the entity \"{}\" which isn't \
+                defined in the behavior pack.",
+                identifier
+            )),
+        },
+        None => problem(
+            Severity::Error,
+            "The spawn rule doesn't have an identifier.".into(),
+        ),
+    }
+    let pool = &rule["description"]["population_control"];
+    let valid_pool = pool.as_str()
+        .is_some_and(|p| POPULATION_POOLS.contains(&p));
+    if !valid_pool {
+        problem(Severity::Error, format!(
+            "The population control pool {} is invalid. Use one of: {}.",
+            pool, POPULATION_POOLS.join(", ")
+        ));
+    }
+    let conditions = rule["conditions"].as_array().into_iter().flatten();
+    for condition in conditions {
+        if let Some(filter) = condition.get("minecraft:biome_filter") {
+            for message in filter_problems(filter) {
+                problem(
+                    Severity::Error,
+                    format!("Invalid biome filter: {}", message),
+                );
+            }
+        }
+    }
+}
+
+/// Checks the spawn rules of the behavior pack (see check_spawn_rule).
+pub fn check_spawn_rules(
+    index: &ProjectIndex, problems: &mut Vec<Diagnostic>,
+) {
+    let entities: HashMap<&str, &Document> = index
+        .with_key(Pack::Behavior, "minecraft:entity")
+        .filter_map(|document| {
+            let identifier = document.json["minecraft:entity"]
+                ["description"]["identifier"].as_str()?;
+            Some((identifier, document))
+        })
+        .collect();
+    for document in index.with_key(Pack::Behavior, "minecraft:spawn_rules") {
+        check_spawn_rule(document, &entities, problems);
+    }
+}
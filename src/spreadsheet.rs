@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::lang::parse_table;
+
+/// Reads the rows of the CSV spreadsheet. The UTF-8 BOM is ignored.
+pub fn read_rows(source: &Path) -> Result<Vec<Vec<String>>, String> {
+    let text = fs::read_to_string(source).map_err(|e| e.to_string())?;
+    parse_table(text.trim_start_matches('\u{feff}'), ',')
+}
+
+/// Returns the indices of the columns of the header by their names (in
+/// lowercase). All of the columns must be known and the required columns
+/// must be present.
+///
+/// # Arguments
+/// - `header` - the first row of the spreadsheet
+/// - `known` - the names of the known columns
+/// - `required` - the names of the required columns
+pub fn columns(
+    header: &[String], known: &[&str], required: &[&str],
+) -> Result<HashMap<String, usize>, String> {
+    let mut columns = HashMap::new();
+    for (i, name) in header.iter().enumerate() {
+        let name = name.trim().to_lowercase();
+        if !known.contains(&name.as_str()) {
+            return Err(format!("Unknown column \"{}\".", name));
+        }
+        columns.insert(name, i);
+    }
+    for column in required {
+        if !columns.contains_key(*column) {
+            return Err(format!(
+                "The spreadsheet doesn't have the \"{}\" column.", column
+            ));
+        }
+    }
+    Ok(columns)
+}
+
+/// A row of the spreadsheet with the values by the names of the columns.
+pub struct Row<'a> {
+    pub columns: &'a HashMap<String, usize>,
+    pub values: &'a [String],
+}
+
+impl Row<'_> {
+    /// Checks if all of the values of the row are empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.iter().all(|value| value.trim().is_empty())
+    }
+
+    /// Returns the trimmed value of the column (empty if the row doesn't
+    /// have it).
+    pub fn get(&self, column: &str) -> &str {
+        self.columns.get(column)
+            .and_then(|i| self.values.get(*i))
+            .map_or("", |value| value.trim())
+    }
+
+    /// Parses the non-negative integer from the column or returns None if
+    /// the value is empty.
+    pub fn integer(&self, column: &str) -> Result<Option<u64>, String> {
+        match self.get(column) {
+            "" => Ok(None),
+            value => value.parse().map(Some).map_err(|_| format!(
+                "The \"{}\" isn't a non-negative integer.", column
+            )),
+        }
+    }
+
+    /// Parses the number or the range (e.g. "4" or "1-3") from the column
+    /// or returns None if the value is empty.
+    pub fn range(
+        &self, column: &str,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let value = self.get(column);
+        let error = || format!("The {} \"{}\" is invalid.", column, value);
+        match value.split_once('-') {
+            _ if value.is_empty() => Ok(None),
+            Some((min, max)) => {
+                let min: u64 = min.trim().parse().map_err(|_| error())?;
+                let max: u64 = max.trim().parse().map_err(|_| error())?;
+                match min <= max {
+                    true => Ok(Some(
+                        serde_json::json!({"min": min, "max": max})
+                    )),
+                    false => Err(error()),
+                }
+            },
+            None => value.parse::<u64>()
+                .map(|value| Some(value.into()))
+                .map_err(|_| error()),
+        }
+    }
+}
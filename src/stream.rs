@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// The size of the buffer used for streaming the files.
+static BUFFER_SIZE: usize = 1024 * 1024;
+
+/// The size of the files (in bytes) from which the progress of copying is
+/// reported.
+pub static LARGE_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Copies the file by streaming its content through a large buffer. Unlike
+/// fs::copy, it never uses the copy-on-write cloning of the file system
+/// (fs::copy can use it implicitly on some platforms).
+///
+/// # Arguments
+/// - `source` - the file to copy
+/// - `target` - the path to copy the file to
+/// - `report_progress` - whether to print the progress of copying (every
+///   10%)
+pub fn stream_copy(
+    source: &Path, target: &Path, report_progress: bool,
+) -> io::Result<()> {
+    let mut src = File::open(source)?;
+    let mut dst = File::create(target)?;
+    let total = src.metadata()?.len();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut copied: u64 = 0;
+    let mut reported: u64 = 0;
+    loop {
+        let n = src.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        dst.write_all(&buffer[..n])?;
+        copied += n as u64;
+        if report_progress && total > 0 {
+            let percent = copied * 100 / total;
+            if percent / 10 > reported / 10 {
+                println!(
+                    "Copying \"{}\": {}% ({} / {} MiB)",
+                    source.display(), percent, copied / 1024 / 1024,
+                    total / 1024 / 1024
+                );
+                reported = percent;
+            }
+        }
+    }
+    dst.set_permissions(src.metadata()?.permissions())?;
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use std::path::Path;
+
+/// The byte order mark of the UTF-8 files.
+pub static UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// What to do with the byte order marks at the start of the JSON and .lang
+/// files.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum BomPolicy {
+    /// The BOM is removed from the target.
+    #[default]
+    Strip,
+    /// The BOM is kept in the target.
+    Preserve,
+}
+
+impl BomPolicy {
+    /// Returns the policy with the given name as used in the config file.
+    pub fn from_name(name: &str) -> Option<BomPolicy> {
+        match name {
+            "strip" => Some(BomPolicy::Strip),
+            "preserve" => Some(BomPolicy::Preserve),
+            _ => None,
+        }
+    }
+}
+
+/// Checks if the file is one of the text files of the packs that the game
+/// parses (the JSON and .lang files).
+pub fn is_parsed_text(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "json" || e == "lang")
+}
+
+/// Returns the content without the BOM or None if the content doesn't start
+/// with a BOM.
+pub fn strip_bom(data: &[u8]) -> Option<&[u8]> {
+    data.strip_prefix(UTF8_BOM)
+}
+
+/// Replaces the comments of the JSON text ("// ..." and "/* ... */", which
+/// the game accepts) with spaces, so it can be parsed by serde_json. The line
+/// breaks and the byte offsets are kept, so the positions of the syntax
+/// errors don't change.
+pub fn strip_json_comments(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let (mut in_string, mut escaped) = (false, false);
+    let blank = |result: &mut String, c: char| match c {
+        '\n' | '\r' => result.push(c),
+        c => result.extend(std::iter::repeat_n(' ', c.len_utf8())),
+    };
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {},
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                result.push(c);
+            },
+            ('/', Some('/')) => {
+                blank(&mut result, c);
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    blank(&mut result, c);
+                }
+            },
+            ('/', Some('*')) => {
+                blank(&mut result, c);
+                blank(&mut result, chars.next().unwrap());
+                while let Some(c) = chars.next() {
+                    blank(&mut result, c);
+                    if c == '*' && chars.next_if_eq(&'/').is_some() {
+                        blank(&mut result, '/');
+                        break;
+                    }
+                }
+            },
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// The line endings of the text files of the packs.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEndings {
+    /// The line endings of the source files are kept.
+    #[default]
+    Keep,
+    /// The line endings are converted to LF ("\n").
+    Lf,
+    /// The line endings are converted to CRLF ("\r\n").
+    Crlf,
+}
+
+impl LineEndings {
+    /// Returns the line endings with the given name as used in the config
+    /// file.
+    pub fn from_name(name: &str) -> Option<LineEndings> {
+        match name {
+            "keep" => Some(LineEndings::Keep),
+            "lf" => Some(LineEndings::Lf),
+            "crlf" => Some(LineEndings::Crlf),
+            _ => None,
+        }
+    }
+}
+
+/// Checks if the line endings of the file are normalized (the JSON, .lang
+/// and .mcfunction files).
+pub fn has_line_endings(path: &Path) -> bool {
+    is_parsed_text(path) || path.extension().is_some_and(|e| e == "mcfunction")
+}
+
+/// Returns the content with the line endings converted or None if the
+/// content already uses them.
+pub fn normalize_line_endings(
+    data: &[u8], endings: LineEndings,
+) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        // Both "\r\n" and the lone "\n" are line endings
+        let newline = match data[i] {
+            b'\r' if data.get(i + 1) == Some(&b'\n') => 2,
+            b'\n' => 1,
+            _ => 0,
+        };
+        match (newline, endings) {
+            (0, _) | (_, LineEndings::Keep) => {
+                result.push(data[i]);
+                i += 1;
+                continue;
+            },
+            (_, LineEndings::Lf) => result.push(b'\n'),
+            (_, LineEndings::Crlf) => result.extend(b"\r\n"),
+        }
+        i += newline;
+    }
+    match result == data {
+        true => None,
+        false => Some(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_the_comments_of_the_json_text() {
+        let text = "{\n  // \"a\": 1,\n  \"b\": \"//x/*\" /* é\n */ }";
+        let stripped = strip_json_comments(text);
+        assert_eq!(stripped.len(), text.len());
+        assert_eq!(stripped.lines().count(), text.lines().count());
+        let json: serde_json::Value = serde_json::from_str(&stripped)
+            .unwrap();
+        assert_eq!(json, serde_json::json!({"b": "//x/*"}));
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{resource_dir, MapResult, Mapper, Pack, TargetInfo};
+use crate::plan::PlannedFile;
+use crate::umbrella::{function_path, has_annotation};
+
+/// The name of the rule of the "tick.json" files generated by the
+/// TickFunctions.
+pub static TICK_RULE: &str = "(tick)";
+
+/// The name of the file with the functions that run every tick.
+static TICK_NAME: &str = "tick.json";
+
+/// The comment that adds the function to the "tick.json".
+static ANNOTATION: &str = "# @tick";
+
+/// Generates the "tick.json" of the functions (e.g.
+/// "BP/functions/tick.json") with the functions annotated with "# @tick".
+/// The "tick.json" from the sources is extended instead of being replaced.
+pub struct TickFunctions;
+
+impl Mapper for TickFunctions {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The annotated functions by the "functions" directories
+        let mut ticks: BTreeMap<PathBuf, Vec<(PathBuf, PathBuf)>> =
+            BTreeMap::new();
+        for file in files.iter() {
+            let target = match &file.target {
+                Some(target) if target.pack == Pack::Behavior
+                    && target.path.extension()
+                        .is_some_and(|e| e == "mcfunction") => target,
+                _ => continue,
+            };
+            let functions_dir = match resource_dir(&target.path, "functions") {
+                Some(dir) if has_annotation(&file.source, ANNOTATION) => dir,
+                _ => continue,
+            };
+            ticks.entry(functions_dir).or_default()
+                .push((file.source.clone(), target.path.clone()));
+        }
+        for (functions_dir, mut inputs) in ticks {
+            inputs.sort_by(|a, b| a.1.cmp(&b.1));
+            let path = functions_dir.join(TICK_NAME);
+            let target = TargetInfo::new(TICK_RULE, path.clone())
+                .with_inputs(inputs);
+            match files.iter_mut()
+                .find(|file| file.target_path() == Some(&path))
+            {
+                Some(file) => file.target = Some(target),
+                None => files.push(PlannedFile {
+                    source: target.inputs[0].0.clone(),
+                    target: Some(target),
+                }),
+            }
+        }
+    }
+}
+
+/// Generates the "tick.json" with the functions of the inputs. If the source
+/// is a "tick.json", its functions come first.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target with the annotated functions as the inputs
+pub fn tick_contents(
+    source: &Path, target: &TargetInfo,
+) -> Result<Vec<u8>, String> {
+    let mut json = match source.file_name() {
+        Some(name) if name == TICK_NAME => {
+            let text = fs::read_to_string(source)
+                .map_err(|e| e.to_string())?;
+            serde_json::from_str(text.trim_start_matches('\u{feff}'))
+                .map_err(|e| e.to_string())?
+        },
+        _ => serde_json::json!({}),
+    };
+    let mut values = match &json["values"] {
+        serde_json::Value::Null => Vec::new(),
+        serde_json::Value::Array(values) => values.clone(),
+        _ => return Err("The \"values\" isn't an array.".into()),
+    };
+    for (_, function) in &target.inputs {
+        let path = match function_path(function) {
+            Some(path) => serde_json::Value::from(path),
+            None => continue,
+        };
+        if !values.contains(&path) {
+            values.push(path);
+        }
+    }
+    json.as_object_mut()
+        .ok_or("The \"tick.json\" isn't an object.")?
+        .insert("values".into(), values.into());
+    Ok(format!("{:#}\n", json).into_bytes())
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The number of the slowest files shown in the report.
+static SLOWEST_FILES: usize = 10;
+
+/// The time spent in the phases of the filter and on copying the individual
+/// files, reported with the `--timings` flag.
+#[derive(Default)]
+pub struct Timings {
+    phases: Mutex<Vec<(&'static str, Duration)>>,
+    /// The parts of the phases by the names of the phases (e.g. generating
+    /// the contents while copying), added up over all of the threads.
+    parts: Mutex<Vec<(&'static str, &'static str, Duration)>>,
+    files: Mutex<Vec<(PathBuf, Duration)>>,
+}
+
+impl Timings {
+    /// Records the time of a phase that started at `start` and ends now.
+    /// The times of the phases with the same name are added up.
+    pub fn phase(&self, name: &'static str, start: Instant) {
+        let elapsed = start.elapsed();
+        let mut phases = self.phases.lock().unwrap();
+        match phases.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, total)) => *total += elapsed,
+            None => phases.push((name, elapsed)),
+        }
+    }
+
+    /// Records the time of a part of a phase that started at `start` and
+    /// ends now. The parts are measured in the threads of the phase, so their
+    /// times are added up over all of the threads and they aren't counted in
+    /// the total.
+    pub fn part(
+        &self, phase: &'static str, name: &'static str, start: Instant,
+    ) {
+        let elapsed = start.elapsed();
+        let mut parts = self.parts.lock().unwrap();
+        match parts.iter_mut().find(|(p, n, _)| *p == phase && *n == name) {
+            Some((_, _, total)) => *total += elapsed,
+            None => parts.push((phase, name, elapsed)),
+        }
+    }
+
+    /// Records the time spent on a single file.
+    pub fn file(&self, path: &Path, elapsed: Duration) {
+        self.files.lock().unwrap().push((path.to_path_buf(), elapsed));
+    }
+
+    /// Prints the report with the times of the phases and the slowest files.
+    pub fn print(&self) {
+        let phases = self.phases.lock().unwrap();
+        let parts = self.parts.lock().unwrap();
+        let total: Duration = phases.iter().map(|(_, d)| *d).sum();
+        let labels: Vec<String> = parts.iter()
+            .map(|(_, part, _)| format!("  {} (all threads)", part))
+            .collect();
+        let width = phases.iter().map(|(n, _)| n.len())
+            .chain(labels.iter().map(String::len))
+            .max().unwrap_or(0);
+        println!("Timings:");
+        for (name, duration) in phases.iter() {
+            let share = if total.is_zero() {
+                0.0
+            } else {
+                100.0 * duration.as_secs_f64() / total.as_secs_f64()
+            };
+            println!(
+                "  {:<width$}  {:>10.1} ms  {:>5.1}%",
+                name, duration.as_secs_f64() * 1000.0, share, width = width
+            );
+            let phase_parts = parts.iter().zip(&labels)
+                .filter(|((phase, _, _), _)| phase == name);
+            for ((_, _, duration), label) in phase_parts {
+                println!(
+                    "  {:<width$}  {:>10.1} ms",
+                    label, duration.as_secs_f64() * 1000.0, width = width
+                );
+            }
+        }
+        println!(
+            "  {:<width$}  {:>10.1} ms",
+            "total", total.as_secs_f64() * 1000.0, width = width
+        );
+        let mut files = self.files.lock().unwrap().clone();
+        if files.is_empty() {
+            return;
+        }
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        println!("Slowest files:");
+        for (path, duration) in files.iter().take(SLOWEST_FILES) {
+            println!(
+                "  {:>10.1} ms  {}",
+                duration.as_secs_f64() * 1000.0, path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::mapping::{compiled_target, MapResult, Mapper};
+use crate::recipes::is_valid_identifier;
+use crate::spreadsheet::{columns, read_rows, Row};
+
+/// The name of the rule of the trade tables generated by the TradeTables.
+pub static TRADE_TABLE_RULE: &str = "(trade table)";
+
+/// The extension of the spreadsheets with the trades.
+static TABLE_EXTENSION: &str = ".tt.csv";
+
+/// The extension of the generated trade tables.
+static TARGET_EXTENSION: &str = ".tt.json";
+
+/// The directory of the trade tables in the packs.
+static TRADING_DIR: &str = "BP/trading";
+
+/// The columns of the spreadsheets. The "tier", the "wants" and the "gives"
+/// are required.
+static COLUMNS: &[&str] = &[
+    "tier", "total_exp_required", "wants", "wants_quantity",
+    "wants_price_multiplier", "wants_2", "wants_2_quantity",
+    "wants_2_price_multiplier", "gives", "gives_quantity", "trader_exp",
+    "max_uses", "reward_exp",
+];
+
+/// Compiles the spreadsheets with the trades (the ".tt.csv" files) into the
+/// trade tables of the behavior pack (e.g. "villager.tt.csv" ->
+/// "BP/trading/villager.tt.json"). Every row of the spreadsheet is a trade
+/// and the trades are grouped into the tiers by the "tier" column.
+pub struct TradeTables;
+
+impl Mapper for TradeTables {
+    fn map(&self, path: &Path) -> MapResult {
+        compiled_target(
+            path, TABLE_EXTENSION, TRADING_DIR, TARGET_EXTENSION,
+            TRADE_TABLE_RULE,
+        )
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        vec![TABLE_EXTENSION.to_string()]
+    }
+}
+
+/// Returns the item from the columns with the prefix (e.g. "wants" for the
+/// "wants", the "wants_quantity" and the "wants_price_multiplier") or None
+/// if the item column is empty.
+fn parse_item(
+    row: &Row, prefix: &str,
+) -> Result<Option<serde_json::Value>, String> {
+    let identifier = row.get(prefix);
+    if identifier.is_empty() {
+        return Ok(None);
+    }
+    if !is_valid_identifier(identifier) {
+        return Err(format!("\"{}\" isn't a valid identifier.", identifier));
+    }
+    let mut item = serde_json::Map::new();
+    item.insert("item".into(), identifier.into());
+    if let Some(quantity) = row.range(&format!("{}_quantity", prefix))? {
+        item.insert("quantity".into(), quantity);
+    }
+    match row.get(&format!("{}_price_multiplier", prefix)) {
+        "" => {},
+        multiplier => match multiplier.parse::<f64>() {
+            Ok(m) if m.is_finite() && m >= 0.0 => {
+                item.insert("price_multiplier".into(), m.into());
+            },
+            _ => return Err(format!(
+                "The price multiplier \"{}\" isn't a non-negative number.",
+                multiplier
+            )),
+        },
+    }
+    Ok(Some(item.into()))
+}
+
+/// Returns the trade of the row.
+fn parse_trade(row: &Row) -> Result<serde_json::Value, String> {
+    let mut wants = Vec::new();
+    for prefix in ["wants", "wants_2"] {
+        wants.extend(parse_item(row, prefix)?);
+    }
+    if wants.is_empty() {
+        return Err("The trade doesn't want any items.".into());
+    }
+    let gives = parse_item(row, "gives")?
+        .ok_or("The trade doesn't give any items.")?;
+    let mut trade = serde_json::Map::new();
+    trade.insert("wants".into(), wants.into());
+    trade.insert("gives".into(), vec![gives].into());
+    for column in ["trader_exp", "max_uses"] {
+        if let Some(value) = row.integer(column)? {
+            trade.insert(column.into(), value.into());
+        }
+    }
+    match row.get("reward_exp") {
+        "" => {},
+        "true" => { trade.insert("reward_exp".into(), true.into()); },
+        "false" => { trade.insert("reward_exp".into(), false.into()); },
+        _ => return Err("The \"reward_exp\" isn't true or false.".into()),
+    }
+    Ok(trade.into())
+}
+
+/// Compiles the rows of the spreadsheet (with the header) into the trade
+/// table. The empty rows are skipped.
+fn compile(rows: &[Vec<String>]) -> Result<serde_json::Value, String> {
+    let header = rows.first().ok_or("The spreadsheet is empty.")?;
+    let columns = columns(header, COLUMNS, &["tier", "wants", "gives"])?;
+    // The required experience and the trades by the numbers of the tiers
+    let mut tiers: BTreeMap<u64, (Option<u64>, Vec<serde_json::Value>)> =
+        BTreeMap::new();
+    for (i, values) in rows.iter().enumerate().skip(1) {
+        let row = Row { columns: &columns, values };
+        if row.is_empty() {
+            continue;
+        }
+        let add_row = |e: String| format!("Row {}: {}", i + 1, e);
+        let number = row.integer("tier").map_err(add_row)?
+            .ok_or_else(|| add_row("The \"tier\" is empty.".into()))?;
+        let exp = row.integer("total_exp_required").map_err(add_row)?;
+        let tier = tiers.entry(number).or_default();
+        match (tier.0, exp) {
+            (Some(a), Some(b)) if a != b => return Err(add_row(format!(
+                "The tier {} already requires {} experience.", number, a
+            ))),
+            (None, Some(_)) => tier.0 = exp,
+            _ => {},
+        }
+        tier.1.push(parse_trade(&row).map_err(add_row)?);
+    }
+    if tiers.is_empty() {
+        return Err("The spreadsheet doesn't have any trades.".into());
+    }
+    let tiers: Vec<serde_json::Value> = tiers.into_values()
+        .map(|(exp, trades)| {
+            let mut tier = serde_json::Map::new();
+            if let Some(exp) = exp {
+                tier.insert("total_exp_required".into(), exp.into());
+            }
+            tier.insert("trades".into(), trades.into());
+            tier.into()
+        })
+        .collect();
+    Ok(serde_json::json!({"tiers": tiers}))
+}
+
+/// Generates the trade table from the spreadsheet.
+///
+/// # Arguments
+/// - `source` - the path to the ".tt.csv" file
+pub fn trade_table_contents(source: &Path) -> Result<Vec<u8>, String> {
+    let json = compile(&read_rows(source)?)?;
+    Ok(format!("{:#}\n", json).into_bytes())
+}
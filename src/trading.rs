@@ -0,0 +1,126 @@
+use crate::index::{Document, ProjectIndex};
+use crate::mapping::Pack;
+use crate::recipes::is_valid_identifier;
+use crate::references::{has_behavior_file, properties};
+use crate::report::{Diagnostic, Severity};
+
+/// The components of the entities that reference the trade tables.
+static TRADING_COMPONENTS: &[&str] = &[
+    "minecraft:trade_table", "minecraft:economy_trade_table",
+];
+
+/// Checks the quantity of the item of a trade: a non-negative number or a
+/// range with the "min" not greater than the "max". Returns the description
+/// of the problem.
+fn quantity_problem(quantity: &serde_json::Value) -> Option<String> {
+    let range = match quantity {
+        serde_json::Value::Null => return None,
+        serde_json::Value::Number(n) => (n.as_f64(), n.as_f64()),
+        serde_json::Value::Object(range) => (
+            range.get("min").and_then(|m| m.as_f64()),
+            range.get("max").and_then(|m| m.as_f64()),
+        ),
+        _ => (None, None),
+    };
+    match range {
+        (Some(min), Some(max)) if min < 0.0 || min > max => Some(format!(
+            "The quantity range {}..{} is invalid.", min, max
+        )),
+        (Some(_), Some(_)) => None,
+        _ => Some(
+            "The quantity has to be a number or an object with the \"min\" \
+            and the \"max\".".to_string()
+        ),
+    }
+}
+
+/// Checks the items that the trade wants or gives.
+fn check_items(
+    items: &serde_json::Value, property: &str, error: &mut impl FnMut(String),
+) {
+    let items = match items.as_array() {
+        Some(items) if !items.is_empty() => items,
+        _ => {
+            error(format!("The trade doesn't have any \"{}\".", property));
+            return;
+        },
+    };
+    for item in items {
+        // The "choice" lists the alternatives of the item
+        let alternatives = match item["choice"].as_array() {
+            Some(choice) => choice.iter().collect(),
+            None => vec![item],
+        };
+        for item in alternatives {
+            match item["item"].as_str() {
+                Some(id) if is_valid_identifier(id) => {},
+                Some(id) => {
+                    error(format!("\"{}\" isn't a valid identifier.", id));
+                },
+                None => error(format!(
+                    "An item of the \"{}\" doesn't have the \"item\".",
+                    property
+                )),
+            }
+            if let Some(problem) = quantity_problem(&item["quantity"]) {
+                error(problem);
+            }
+        }
+    }
+}
+
+/// Checks the structure of the trade table: the tiers with the trades
+/// (directly or in the groups) and the items of the trades.
+fn check_trade_table(document: &Document, problems: &mut Vec<Diagnostic>) {
+    let mut error = |message: String| problems.push(
+        Diagnostic::new(Severity::Error, document.source, message)
+    );
+    let tiers = match document.json["tiers"].as_array() {
+        Some(tiers) if !tiers.is_empty() => tiers,
+        _ => {
+            error("The trade table doesn't have any \"tiers\".".into());
+            return;
+        },
+    };
+    for (i, tier) in tiers.iter().enumerate() {
+        let groups = tier["groups"].as_array();
+        let trades: Vec<&serde_json::Value> = match groups {
+            Some(groups) => groups.iter()
+                .flat_map(|g| g["trades"].as_array().into_iter().flatten())
+                .collect(),
+            None => tier["trades"].as_array().into_iter().flatten().collect(),
+        };
+        if trades.is_empty() {
+            error(format!("The tier {} doesn't have any trades.", i + 1));
+        }
+        for trade in trades {
+            check_items(&trade["wants"], "wants", &mut error);
+            check_items(&trade["gives"], "gives", &mut error);
+        }
+    }
+}
+
+/// Checks the trade tables of the behavior pack (the tiers, the identifiers
+/// of the items and the quantity ranges) and if the trade tables referenced
+/// by the entities are copied to the behavior pack.
+pub fn check_trading(index: &ProjectIndex, problems: &mut Vec<Diagnostic>) {
+    for document in index.with_key(Pack::Behavior, "tiers") {
+        check_trade_table(document, problems);
+    }
+    for document in index.with_key(Pack::Behavior, "minecraft:entity") {
+        let tables = TRADING_COMPONENTS.iter()
+            .flat_map(|component| properties(&document.json, component))
+            .filter_map(|component| component["table"].as_str());
+        for table in tables {
+            if !has_behavior_file(index, table) {
+                problems.push(Diagnostic::new(
+                    Severity::Warning, document.source, format!(
+                        "The trade table \"{}\" isn't copied to the behavior \
+                        pack.",
+                        table
+                    ),
+                ));
+            }
+        }
+    }
+}
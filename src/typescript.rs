@@ -0,0 +1,213 @@
+use std::path::Path;
+
+use crate::bundle::Lexer;
+use crate::hooks::command_output;
+use crate::mapping::{
+    compiled_target, resource_dir, MapResult, Mapper, Pack, TargetInfo,
+};
+use crate::plan::PlannedFile;
+
+/// The name of the rule of the scripts compiled by the TypeScript mapper.
+pub static TYPESCRIPT_RULE: &str = "(typescript)";
+
+/// The extension of the TypeScript files.
+static TYPESCRIPT_EXTENSION: &str = ".ts";
+
+/// The extension of the TypeScript declaration files, which aren't
+/// compiled.
+static DECLARATION_EXTENSION: &str = ".d.ts";
+
+/// The extension of the compiled scripts.
+static TARGET_EXTENSION: &str = ".js";
+
+/// The directory of the scripts in the packs.
+static SCRIPTS_DIR: &str = "BP/scripts";
+
+/// Maps the TypeScript files (the ".ts" files) to the scripts of the
+/// behavior pack (e.g. "main.ts" -> "BP/scripts/main.js"). The ".ts" files
+/// routed to the scripts by the other rules (e.g. the passthrough of
+/// "BP/scripts/main.ts") are compiled too. The files are compiled by the
+/// command from the settings when they're copied (see `compile_script`).
+pub struct TypeScript;
+
+impl Mapper for TypeScript {
+    fn map(&self, path: &Path) -> MapResult {
+        let is_declaration = path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(DECLARATION_EXTENSION));
+        if is_declaration {
+            return MapResult::Skipped(
+                "The TypeScript declaration files aren't compiled."
+            );
+        }
+        compiled_target(
+            path, TYPESCRIPT_EXTENSION, SCRIPTS_DIR, TARGET_EXTENSION,
+            TYPESCRIPT_RULE,
+        )
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        vec![TYPESCRIPT_EXTENSION.to_string()]
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        for file in files.iter_mut() {
+            let target = match &file.target {
+                Some(target) if target.pack == Pack::Behavior
+                    && target.rule != TYPESCRIPT_RULE => target,
+                _ => continue,
+            };
+            let name = target.path.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            let compiled = name.ends_with(TYPESCRIPT_EXTENSION)
+                && !name.ends_with(DECLARATION_EXTENSION)
+                && resource_dir(&target.path, "scripts").is_some();
+            if compiled {
+                let path = target.path.with_extension("js");
+                file.target = Some(TargetInfo::new(TYPESCRIPT_RULE, path));
+            }
+        }
+    }
+}
+
+/// Returns the module specifier with the extension of the compiled scripts
+/// (e.g. "./utils.js" for "./utils" or "./utils.ts"). The game requires
+/// the extensions of the relative imports. The other specifiers (e.g.
+/// "@minecraft/server") are returned unchanged.
+pub fn rewrite_specifier(specifier: &str) -> String {
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return specifier.to_string();
+    }
+    if let Some(name) = specifier.strip_suffix(TYPESCRIPT_EXTENSION) {
+        return format!("{}{}", name, TARGET_EXTENSION);
+    }
+    let file_name = specifier.rsplit('/').next().unwrap_or_default();
+    match file_name.contains('.') {
+        true => specifier.to_string(),
+        false => format!("{}{}", specifier, TARGET_EXTENSION),
+    }
+}
+
+/// Checks if the code ends with the keyword before the module specifier
+/// (e.g. "from" of `import { x } from` or "import" of `import(`).
+fn is_specifier_position(code: &str) -> bool {
+    let code = code.trim_end().trim_end_matches('(').trim_end();
+    ["from", "import"].iter().any(|keyword| {
+        code.strip_suffix(keyword).is_some_and(|before| {
+            !before.ends_with(|c: char| {
+                c.is_alphanumeric() || c == '_' || c == '$' || c == '.'
+            })
+        })
+    })
+}
+
+/// Rewrites the relative module specifiers of the imports and the exports
+/// of the compiled script (the strings after "from" and "import", e.g.
+/// `import { x } from "./utils"` -> `import { x } from "./utils.js"`). The
+/// comments, the other strings and the template literals are kept as they
+/// are.
+pub fn rewrite_imports(script: &str) -> String {
+    let mut result = String::new();
+    let mut lexer = Lexer::default();
+    // The code before the specifier can be on the previous line (e.g. the
+    // "from" after the multiline list of the imports)
+    let mut previous = "";
+    for line in script.split_inclusive('\n') {
+        let mut end = 0;
+        for literal in lexer.scan(line) {
+            let before = &line[..literal.start - 1];
+            let code = match before.trim().is_empty() {
+                true => previous,
+                false => before,
+            };
+            if is_specifier_position(code) {
+                result.push_str(&line[end..literal.start]);
+                result.push_str(&rewrite_specifier(&line[literal.clone()]));
+                end = literal.end;
+            }
+        }
+        result.push_str(&line[end..]);
+        if !line.trim().is_empty() {
+            previous = line;
+        }
+    }
+    result
+}
+
+/// Compiles the TypeScript file with the command from the settings (e.g.
+/// `npx esbuild "$CPF_SOURCE"`). The path to the file is passed in the
+/// CPF_SOURCE environment variable and the command prints the compiled
+/// script. The relative imports of the script are rewritten to the ".js"
+/// files.
+///
+/// # Arguments
+/// - `source` - the path to the ".ts" file
+/// - `command` - the command that compiles the file
+/// - `working_dir` - the path to the working directory of regolith
+pub fn compile_script(
+    source: &Path, command: &str, working_dir: &Path,
+) -> Result<Vec<u8>, String> {
+    let output = command_output(command, working_dir, &[
+        ("CPF_SOURCE", source),
+    ])?;
+    let script = String::from_utf8(output)
+        .map_err(|_| "The compiled script isn't valid UTF-8.".to_string())?;
+    Ok(rewrite_imports(&script).into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_the_extensions_to_the_relative_specifiers() {
+        assert_eq!(rewrite_specifier("./utils"), "./utils.js");
+        assert_eq!(rewrite_specifier("../lib/math.ts"), "../lib/math.js");
+        assert_eq!(rewrite_specifier("./data.json"), "./data.json");
+        assert_eq!(rewrite_specifier("./utils.js"), "./utils.js");
+        assert_eq!(
+            rewrite_specifier("@minecraft/server"), "@minecraft/server"
+        );
+    }
+
+    #[test]
+    fn rewrites_the_imports_and_the_exports() {
+        let script = concat!(
+            "import { world } from \"@minecraft/server\";\n",
+            "import { add } from './math';\n",
+            "import \"./setup.ts\";\n",
+            "export * from \"../lib/utils\";\n",
+            "const m = await import(\"./lazy\");\n",
+        );
+        assert_eq!(rewrite_imports(script), concat!(
+            "import { world } from \"@minecraft/server\";\n",
+            "import { add } from './math.js';\n",
+            "import \"./setup.js\";\n",
+            "export * from \"../lib/utils.js\";\n",
+            "const m = await import(\"./lazy.js\");\n",
+        ));
+    }
+
+    #[test]
+    fn keeps_the_comments_and_the_strings() {
+        let script = concat!(
+            "// import \"./a\"\n",
+            "/* export * from \"./b\";\n",
+            "import \"./c\"; */\n",
+            "const text = \"import \" + 'from';\n",
+            "const message = `\n",
+            "import \"./d\";\n",
+            "${reimport(\"./e\")}`;\n",
+            "const path = fromage(\"./f\");\n",
+            "import {\n",
+            "    x,\n",
+            "} from\n",
+            "    \"./g\";\n",
+        );
+        assert_eq!(
+            rewrite_imports(script),
+            script.replace("\"./g\"", "\"./g.js\"")
+        );
+    }
+}
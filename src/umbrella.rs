@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::index::slash_path;
+use crate::mapping::{resource_dir, MapResult, Mapper, Pack, TargetInfo};
+use crate::plan::PlannedFile;
+
+/// The name of the rule of the umbrella functions generated by the
+/// UmbrellaFunctions.
+pub static UMBRELLA_RULE: &str = "(umbrella)";
+
+/// The name of the umbrella function of a folder.
+static UMBRELLA_NAME: &str = "_all.mcfunction";
+
+/// The comment that adds the folder of the function to the folders with
+/// the umbrella functions.
+static ANNOTATION: &str = "# @umbrella";
+
+/// Checks if the comments at the start of the function have the annotation
+/// (e.g. "# @umbrella").
+pub fn has_annotation(source: &Path, annotation: &str) -> bool {
+    let file = match fs::File::open(source) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+    BufReader::new(file).lines()
+        .map_while(Result::ok)
+        .map(|line| line.trim().to_string())
+        .take_while(|line| line.is_empty() || line.starts_with('#'))
+        .any(|line| line == annotation)
+}
+
+/// Returns the path of the function as it's called by the "function"
+/// command (e.g. "setup/scores" for "BP/functions/setup/scores.mcfunction").
+pub fn function_path(target: &Path) -> Option<String> {
+    let functions_dir = resource_dir(target, "functions")?;
+    let path = target.strip_prefix(functions_dir).ok()?;
+    Some(slash_path(&path.with_extension("")))
+}
+
+/// Generates the umbrella functions ("_all.mcfunction") that call every
+/// function of their folders (e.g. "BP/functions/setup/_all.mcfunction"
+/// calls the functions of "setup"), for the folders from the settings and
+/// the folders with the functions annotated with "# @umbrella". The
+/// functions of the subfolders aren't called. The "_all.mcfunction" files
+/// from the sources are extended instead of being replaced.
+pub struct UmbrellaFunctions {
+    /// The folders of the functions relative to the "functions" directory
+    /// (e.g. "setup").
+    folders: Vec<String>,
+}
+
+impl UmbrellaFunctions {
+    /// Creates the mapper with the folders of the functions relative to the
+    /// "functions" directory (e.g. "setup").
+    pub fn new(folders: Vec<String>) -> UmbrellaFunctions {
+        UmbrellaFunctions { folders }
+    }
+}
+
+impl Mapper for UmbrellaFunctions {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        // The functions and the annotations by the folders
+        let mut folders: BTreeMap<PathBuf, (bool, Vec<(PathBuf, PathBuf)>)> =
+            BTreeMap::new();
+        for file in files.iter() {
+            let target = match &file.target {
+                Some(target) if target.pack == Pack::Behavior
+                    && target.path.extension()
+                        .is_some_and(|e| e == "mcfunction")
+                    && target.path.file_name()
+                        .is_some_and(|n| n != UMBRELLA_NAME) => target,
+                _ => continue,
+            };
+            let (folder, path) = match (
+                target.path.parent(), function_path(&target.path)
+            ) {
+                (Some(folder), Some(path)) => (folder, path),
+                _ => continue,
+            };
+            let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+            let listed = self.folders.iter()
+                .any(|f| f.trim_matches('/') == dir);
+            let folder = folders.entry(folder.to_path_buf()).or_default();
+            folder.0 |= listed || has_annotation(&file.source, ANNOTATION);
+            folder.1.push((file.source.clone(), target.path.clone()));
+        }
+        for (folder, (enabled, mut inputs)) in folders {
+            if !enabled {
+                continue;
+            }
+            inputs.sort_by(|a, b| a.1.cmp(&b.1));
+            let path = folder.join(UMBRELLA_NAME);
+            let target = TargetInfo::new(UMBRELLA_RULE, path.clone())
+                .with_inputs(inputs);
+            match files.iter_mut()
+                .find(|file| file.target_path() == Some(&path))
+            {
+                Some(file) => file.target = Some(target),
+                None => files.push(PlannedFile {
+                    source: target.inputs[0].0.clone(),
+                    target: Some(target),
+                }),
+            }
+        }
+    }
+}
+
+/// Generates the umbrella function that calls the functions of the inputs.
+/// If the source is an umbrella function, its commands come first.
+///
+/// # Arguments
+/// - `source` - the path to the source file
+/// - `target` - the target with the functions of the folder as the inputs
+pub fn umbrella_contents(
+    source: &Path, target: &TargetInfo,
+) -> Result<Vec<u8>, String> {
+    let mut text = match source.file_name() {
+        Some(name) if name == UMBRELLA_NAME => {
+            let mut text = fs::read_to_string(source)
+                .map_err(|e| e.to_string())?;
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text
+        },
+        _ => String::new(),
+    };
+    for (_, function) in &target.inputs {
+        if let Some(path) = function_path(function) {
+            text.push_str(&format!("function {}\n", path));
+        }
+    }
+    Ok(text.into_bytes())
+}
@@ -0,0 +1,90 @@
+use crate::mapping::{Pack, TargetInfo};
+
+/// The names of the vanilla entities (the names of their files in the
+/// vanilla packs, e.g. "zombie" for "BP/entities/zombie.json").
+static VANILLA_ENTITIES: &[&str] = &[
+    "agent", "allay", "armadillo", "armor_stand", "arrow", "axolotl", "bat",
+    "bee", "blaze", "boat", "bogged", "breeze",
+    "breeze_wind_charge_projectile", "camel", "cat", "cave_spider",
+    "chest_boat", "chest_minecart", "chicken", "cod",
+    "command_block_minecart", "cow", "creaking", "creeper", "dolphin",
+    "donkey", "dragon_fireball", "drowned", "egg", "elder_guardian",
+    "ender_crystal", "ender_dragon", "ender_pearl", "enderman", "endermite",
+    "evocation_fang", "evocation_illager", "eye_of_ender_signal", "fireball",
+    "fireworks_rocket", "fishing_hook", "fox", "frog", "ghast", "glow_squid",
+    "goat", "guardian", "hoglin", "hopper_minecart", "horse", "husk",
+    "iron_golem", "lightning_bolt", "llama", "llama_spit", "magma_cube",
+    "minecart", "mooshroom", "mule", "npc", "ocelot", "panda", "parrot",
+    "phantom", "pig", "piglin", "piglin_brute", "pillager", "player",
+    "polar_bear", "pufferfish", "rabbit", "ravager", "salmon", "sheep",
+    "shulker", "shulker_bullet", "silverfish", "skeleton", "skeleton_horse",
+    "slime", "small_fireball", "sniffer", "snow_golem", "snowball", "spider",
+    "squid", "stray", "strider", "tadpole", "thrown_trident", "tnt",
+    "tnt_minecart", "trader_llama", "tripod_camera", "tropicalfish",
+    "turtle", "vex", "villager_v2", "vindicator", "wandering_trader",
+    "warden", "witch", "wither", "wither_skeleton", "wither_skull",
+    "wither_skull_dangerous", "wolf", "xp_bottle", "xp_orb", "zoglin",
+    "zombie", "zombie_horse", "zombie_pigman", "zombie_villager_v2",
+];
+
+/// The paths of the vanilla files of every entity in the packs. The "{}" is
+/// replaced with the name of the entity.
+static ENTITY_FILES: &[(Pack, &str)] = &[
+    (Pack::Behavior, "entities/{}.json"),
+    (Pack::Behavior, "spawn_rules/{}.json"),
+    (Pack::Behavior, "loot_tables/entities/{}.json"),
+    (Pack::Resource, "entity/{}.entity.json"),
+];
+
+/// Returns the name of the vanilla entity whose file is overridden by the
+/// target (e.g. "zombie" for "BP/entities/zombie.json") or None if the
+/// target doesn't override any of the known vanilla files.
+pub fn overridden_entity(target: &TargetInfo) -> Option<&'static str> {
+    // The path in the pack without the "BP" or "RP" directory
+    let path: Vec<String> = target.path.iter().skip(1)
+        .map(|c| c.to_string_lossy().into_owned())
+        .collect();
+    let path = path.join("/");
+    ENTITY_FILES.iter()
+        .filter(|(pack, _)| *pack == target.pack)
+        .find_map(|(_, pattern)| {
+            let (prefix, suffix) = pattern.split_once("{}")?;
+            let name = path.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            VANILLA_ENTITIES.iter().find(|entity| **entity == name).copied()
+        })
+}
+
+/// Returns the name of the vanilla entity with the identifier (e.g. "zombie"
+/// for "minecraft:zombie") or None if it isn't a vanilla entity.
+pub fn vanilla_entity(identifier: &str) -> Option<&'static str> {
+    let name = identifier.strip_prefix("minecraft:")?;
+    VANILLA_ENTITIES.iter().find(|entity| **entity == name).copied()
+}
+
+/// The identifiers of the vanilla particle effects that are commonly used by
+/// the client entities.
+static VANILLA_PARTICLES: &[&str] = &[
+    "basic_bubble_particle", "basic_crit_particle", "basic_flame_particle",
+    "basic_portal_particle", "basic_smoke_particle",
+    "bubble_column_up_particle", "campfire_smoke_particle",
+    "conduit_particle", "critical_hit_emitter", "death_explosion_emitter",
+    "dolphin_move_particle", "dragon_breath_trail", "egg_destroy_emitter",
+    "electric_spark_particle", "endrod", "evocation_fang_particle",
+    "evoker_spell", "explosion_manual", "glow_particle", "heart_particle",
+    "huge_explosion_emitter", "ink_emitter", "knockback_roar_particle",
+    "large_explosion", "lava_particle", "mob_portal", "mobflame_emitter",
+    "mobspell_emitter", "note_particle", "portal_directional",
+    "rain_splash_particle", "redstone_wire_dust_particle",
+    "sculk_soul_particle", "shulker_bullet", "snowflake_particle",
+    "soul_particle", "sparkler_emitter", "splash_spell_emitter",
+    "stunned_emitter", "totem_particle", "villager_angry", "villager_happy",
+    "water_splash_particle", "wax_particle", "witchspell_emitter",
+    "wither_boss_invulnerable",
+];
+
+/// Checks if the identifier (e.g. "minecraft:heart_particle") is one of the
+/// vanilla particle effects.
+pub fn is_vanilla_particle(identifier: &str) -> bool {
+    identifier.strip_prefix("minecraft:")
+        .is_some_and(|name| VANILLA_PARTICLES.contains(&name))
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mapping::{MapResult, Mapper, TargetInfo};
+use crate::plan::PlannedFile;
+use crate::png;
+
+/// The name of the rule of the textures generated by the TextureVariants.
+pub static VARIANT_RULE: &str = "(variant)";
+
+/// The extension of the files that declare the variants of the textures
+/// (e.g. "sheep.variants.json" for "sheep.png").
+static VARIANTS_EXTENSION: &str = "variants.json";
+
+/// Returns the path to the file that declares the variants of the texture.
+pub fn variants_path(texture: &Path) -> PathBuf {
+    texture.with_extension(VARIANTS_EXTENSION)
+}
+
+/// Parses the tint of a variant, either "#rrggbb" or `[r, g, b]`.
+fn parse_tint(value: &serde_json::Value) -> Option<[u8; 3]> {
+    match value {
+        serde_json::Value::String(color) => {
+            let hex = color.strip_prefix('#').filter(|h| h.len() == 6)?;
+            let channel = |i: usize| {
+                u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()
+            };
+            Some([channel(0)?, channel(1)?, channel(2)?])
+        },
+        serde_json::Value::Array(channels) if channels.len() == 3 => {
+            let channel = |i: usize| {
+                channels[i].as_u64().and_then(|c| u8::try_from(c).ok())
+            };
+            Some([channel(0)?, channel(1)?, channel(2)?])
+        },
+        _ => None,
+    }
+}
+
+/// Reads the variants declared in the file, e.g. `{"red": "#b02e26"}`.
+/// Returns None if the file can't be read or it's invalid. The names of
+/// the variants can only use the letters, the digits, "_" and "-".
+fn read_variants(path: &Path) -> Option<Vec<(String, [u8; 3])>> {
+    let text = fs::read_to_string(path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(
+        text.trim_start_matches('\u{feff}')
+    ).ok()?;
+    json.as_object()?.iter().map(|(name, tint)| {
+        let valid = !name.is_empty() && name.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        match valid {
+            true => Some((name.clone(), parse_tint(tint)?)),
+            false => None,
+        }
+    }).collect()
+}
+
+/// Generates the variants of the textures declared in the
+/// ".variants.json" files next to them. Every variant is a copy of the
+/// texture with the name suffixed with the name of the variant (e.g.
+/// "sheep_red.png") and the colors multiplied by the tint of the variant.
+/// The declarations aren't copied to the packs. The invalid declarations
+/// are left to the other rules.
+pub struct TextureVariants;
+
+impl Mapper for TextureVariants {
+    fn map(&self, _path: &Path) -> MapResult {
+        MapResult::Unmatched
+    }
+
+    fn group(&self, _root_dir: &Path, files: &mut Vec<PlannedFile>) {
+        let mut declarations = HashSet::new();
+        let mut generated = Vec::new();
+        for file in files.iter() {
+            let target = match &file.target {
+                Some(target) if file.source.extension()
+                    .is_some_and(|e| e == "png") => target,
+                _ => continue,
+            };
+            let path = variants_path(&file.source);
+            let variants = match read_variants(&path) {
+                Some(variants) => variants,
+                None => continue,
+            };
+            let stem = match target.path.file_stem()
+                .and_then(|s| s.to_str())
+            {
+                Some(stem) => stem,
+                None => continue,
+            };
+            for (name, _) in variants {
+                let path = target.path.with_file_name(
+                    format!("{}_{}.png", stem, name)
+                );
+                generated.push(PlannedFile {
+                    source: file.source.clone(),
+                    target: Some(TargetInfo::new(VARIANT_RULE, path)),
+                });
+            }
+            declarations.insert(path);
+        }
+        files.retain(|file| !declarations.contains(&file.source));
+        files.extend(generated);
+    }
+}
+
+/// Generates the PNG file of the texture variant. The variant is found by
+/// the suffix of the name of the target (the longest matching name wins,
+/// e.g. "light_blue" over "blue").
+///
+/// # Arguments
+/// - `source` - the path to the texture
+/// - `target` - the path to the target of the variant
+pub fn variant_contents(
+    source: &Path, target: &Path,
+) -> Result<Vec<u8>, String> {
+    let variants = read_variants(&variants_path(source))
+        .ok_or("The declaration of the variants is missing or invalid.")?;
+    let stem = target.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let (_, tint) = variants.iter()
+        .filter(|(name, _)| stem.ends_with(&format!("_{}", name)))
+        .max_by_key(|(name, _)| name.len())
+        .ok_or("The variant isn't declared anymore.")?;
+    let data = fs::read(source).map_err(|e| e.to_string())?;
+    let mut image = png::decode(&data)?;
+    for pixel in image.pixels.chunks_mut(4) {
+        for (channel, tint) in pixel.iter_mut().zip(tint) {
+            *channel = (*channel as u16 * *tint as u16 / 255) as u8;
+        }
+    }
+    Ok(png::encode(&image))
+}
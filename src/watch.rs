@@ -0,0 +1,213 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{Config, CopyMode, OverwritePolicy, FILTER_DATA_PATH};
+use crate::copy::{execute_operations, resolve_operations, CopyOptions};
+use crate::error::CpfError;
+use crate::hooks::run_hooks;
+use crate::mapping::Mapper;
+use crate::notify::{Changes, Watcher};
+use crate::plan::{plan_file, walk_root, PlannedFile, WalkOptions};
+use crate::report;
+use crate::MapperChain;
+
+/// The files of a root of the data directory mapped to the packs. Only the
+/// changed files are mapped again.
+struct WatchedRoot {
+    /// The path to the root directory.
+    dir: PathBuf,
+    /// The planned files (before grouping them) by their sources.
+    files: BTreeMap<PathBuf, PlannedFile>,
+}
+
+impl WatchedRoot {
+    /// Lists the files of the root and maps them to the packs.
+    ///
+    /// # Arguments
+    /// - `working_dir` - the path to the working directory of regolith
+    /// - `mapper` - the mapper that maps the source files to the targets
+    /// - `root` - the path relative to the filter data path that serves as
+    ///   the root of the source files
+    /// - `options` - the options that control how the directories are walked
+    fn walk(
+        working_dir: &Path, mapper: &dyn Mapper, root: &str,
+        options: &WalkOptions,
+    ) -> Result<WatchedRoot, CpfError> {
+        let files = walk_root(working_dir, mapper, root, options)?;
+        Ok(WatchedRoot {
+            dir: working_dir.join(FILTER_DATA_PATH).join(root),
+            files: files.into_iter()
+                .map(|file| (file.source.clone(), file))
+                .collect(),
+        })
+    }
+
+    /// Maps the changed file of the root again. The removed files and the
+    /// files of the removed directories are forgotten.
+    fn update(&mut self, mapper: &dyn Mapper, path: &Path, follow: bool) {
+        let metadata = match follow {
+            true => fs::metadata(path),
+            false => fs::symlink_metadata(path),
+        };
+        match metadata {
+            Ok(metadata) if metadata.is_file() => {
+                let file = plan_file(path.to_path_buf(), &self.dir, mapper);
+                self.files.insert(path.to_path_buf(), file);
+            },
+            // The files of the new directories are reported separately
+            Ok(metadata) if metadata.is_dir() => {},
+            _ => self.files.retain(|source, _| !source.starts_with(path)),
+        }
+    }
+
+    /// Returns the planned files of the root grouped by the mapper.
+    fn planned_files(&self, mapper: &dyn Mapper) -> Vec<PlannedFile> {
+        let mut files: Vec<PlannedFile> = self.files.values()
+            .cloned()
+            .collect();
+        mapper.group(&self.dir, &mut files);
+        files
+    }
+}
+
+/// Lists the files of all of the roots and maps them to the packs.
+fn walk_roots(
+    working_dir: &Path, mapper: &dyn Mapper, roots: &[String],
+    options: &WalkOptions,
+) -> Result<Vec<WatchedRoot>, CpfError> {
+    roots.iter()
+        .map(|root| WatchedRoot::walk(working_dir, mapper, root, options))
+        .collect()
+}
+
+/// Validates the files of the roots like the default command and produces
+/// the targets of the changed files. The targets that are no longer produced
+/// are removed from the packs and the manifest.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `mapper` - the mapper that maps the source files to the targets
+/// - `roots` - the mapped files of the roots
+/// - `changed` - the paths to the changed files relative to the data
+///   directory or None if all of the targets should be produced
+/// - `sources` - the sources of the targets produced by the previous update,
+///   the targets mapped from other files than before are produced as well
+/// - `options` - the options that control how the files are copied
+fn copy_changes(
+    working_dir: &Path, mapper: &dyn Mapper, roots: &[WatchedRoot],
+    changed: Option<&HashSet<PathBuf>>,
+    sources: &mut HashMap<PathBuf, PathBuf>, options: &CopyOptions,
+) -> Result<(), CpfError> {
+    let files = roots.iter()
+        .flat_map(|root| root.planned_files(mapper))
+        .collect();
+    let operations = resolve_operations(mapper, files, options)?;
+    let data_dir = working_dir.join(FILTER_DATA_PATH);
+    let changed = changed.map(|changed| {
+        let mut changed = changed.clone();
+        // E.g. the conflicting file of a removed file
+        changed.extend(operations.iter()
+            .filter(|op| sources.get(&op.target.path) != Some(&op.source))
+            .filter_map(|op| op.source.strip_prefix(&data_dir).ok())
+            .map(Path::to_path_buf));
+        Arc::new(changed)
+    });
+    let options = CopyOptions { changed, ..options.clone() };
+    execute_operations(working_dir, &operations, &options)?;
+    *sources = operations.into_iter()
+        .map(|op| (op.target.path, op.source))
+        .collect();
+    Ok(())
+}
+
+/// Watches the data directory and continuously copies the new and changed
+/// files to the packs. The changes are detected with the notifications of
+/// the file system and only the changed files are mapped again. Every
+/// update validates the files and runs the "per_file" and "after" hooks
+/// like the default command, records the manifest and removes the targets
+/// that are no longer produced (e.g. of the files removed from the data
+/// directory). The "before" hooks run once before the first update. The
+/// errors of the updates (e.g. a JSON file with syntax errors) are reported
+/// and the watching continues. This function never returns unless watching
+/// fails.
+///
+/// # Arguments
+/// - `working_dir` - the path to the working directory of regolith
+/// - `config` - the config of the filter
+/// - `delay` - the time without changes after which the collected changes
+///   are copied
+/// - `options` - the options that control how the files are copied, the
+///   existing targets are always overwritten and the files are always
+///   copied
+pub fn watch(
+    working_dir: &Path, config: &Config, delay: Duration,
+    options: &CopyOptions,
+) -> Result<(), Box<dyn Error>> {
+    let options = CopyOptions {
+        overwrite: OverwritePolicy::Always,
+        // Moving the files would remove them from the data directory
+        mode: CopyMode::Copy,
+        sync: true,
+        ..options.clone()
+    };
+    let mapper = MapperChain::from_config(config);
+    let data_dir = working_dir.join(FILTER_DATA_PATH);
+    run_hooks(&options.hooks.before, working_dir, &[])?;
+    // The changes made during the first walk aren't missed
+    let dirs: Vec<PathBuf> = config.roots.iter()
+        .map(|root| data_dir.join(root))
+        .collect();
+    let follow_symlinks = options.walk.follow_symlinks;
+    let mut watcher = Watcher::new(&dirs, follow_symlinks)?;
+    let mut roots =
+        walk_roots(working_dir, &mapper, &config.roots, &options.walk)?;
+    let mut sources = HashMap::new();
+    // The changes of the failed updates are copied with the next update
+    let mut pending: Option<HashSet<PathBuf>> = None;
+    let mut initial = true;
+    println!("Copying files to packs...");
+    loop {
+        let result = copy_changes(
+            working_dir, &mapper, &roots, pending.as_ref(), &mut sources,
+            &options,
+        );
+        match result {
+            Ok(()) => pending = Some(HashSet::new()),
+            Err(e) => report::general_error(e.to_string()),
+        }
+        if initial {
+            println!("Watching for changes (press Ctrl+C to stop)...");
+            initial = false;
+        }
+        let paths = match watcher.wait(delay)? {
+            Changes::Paths(paths) => paths,
+            Changes::Rescan => {
+                match walk_roots(
+                    working_dir, &mapper, &config.roots, &options.walk
+                ) {
+                    Ok(walked) => roots = walked,
+                    Err(e) => report::general_error(e.to_string()),
+                }
+                pending = None;
+                continue;
+            },
+        };
+        for path in &paths {
+            let root = roots.iter_mut()
+                .find(|root| path.starts_with(&root.dir));
+            if let Some(root) = root {
+                root.update(&mapper, path, follow_symlinks);
+            }
+            if let Ok(relative) = path.strip_prefix(&data_dir) {
+                println!("Changed \"{}\"", relative.display());
+                if let Some(pending) = &mut pending {
+                    pending.insert(relative.to_path_buf());
+                }
+            }
+        }
+    }
+}
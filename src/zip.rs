@@ -0,0 +1,196 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// The date of the entries in the MS-DOS format (1980-01-01). The archives
+/// don't depend on the modification times of the files, so exporting the
+/// same packs always produces the same archives.
+static DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Returns the table used for calculating the CRC-32 checksums.
+pub fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut crc = i as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => 0xedb88320 ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+        }
+        *entry = crc;
+    }
+    table
+}
+
+/// Calculates the CRC-32 checksum of the bytes as used in the ZIP files.
+pub fn crc32(table: &[u32; 256], bytes: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for byte in bytes {
+        crc = table[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// An entry of the central directory written at the end of the archive.
+struct CentralEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Writes the ZIP archives. The files are stored without compression, which
+/// is enough for the .mcpack and .mcaddon files (most of the pack files are
+/// small JSON files or already compressed images and sounds). The archives
+/// larger than 4 GiB (ZIP64) aren't supported.
+pub struct ZipWriter {
+    writer: BufWriter<File>,
+    table: [u32; 256],
+    entries: Vec<CentralEntry>,
+    offset: u32,
+}
+
+/// Returns the error for the archive that exceeds the limits of the ZIP
+/// format without the ZIP64 extension.
+fn too_large() -> io::Error {
+    io::Error::other("The archive is too large (4 GiB or more)")
+}
+
+impl ZipWriter {
+    /// Creates the archive at the path.
+    pub fn create(path: &Path) -> io::Result<ZipWriter> {
+        Ok(ZipWriter {
+            writer: BufWriter::new(File::create(path)?),
+            table: crc_table(),
+            entries: Vec::new(),
+            offset: 0,
+        })
+    }
+
+    /// Adds a file to the archive.
+    ///
+    /// # Arguments
+    /// - `name` - the path of the file in the archive with "/" separators
+    /// - `data` - the content of the file
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let name_length = u16::try_from(name.len()).map_err(|_| {
+            io::Error::other(format!("The path \"{}\" is too long", name))
+        })?;
+        let crc = crc32(&self.table, data);
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend(0x04034b50u32.to_le_bytes());
+        header.extend(20u16.to_le_bytes()); // Version needed to extract
+        header.extend(0x0800u16.to_le_bytes()); // UTF-8 names
+        header.extend(0u16.to_le_bytes()); // Stored (no compression)
+        header.extend(0u16.to_le_bytes()); // Time
+        header.extend(DOS_DATE.to_le_bytes());
+        header.extend(crc.to_le_bytes());
+        header.extend(size.to_le_bytes()); // Compressed size
+        header.extend(size.to_le_bytes()); // Uncompressed size
+        header.extend(name_length.to_le_bytes());
+        header.extend(0u16.to_le_bytes()); // Extra field length
+        header.extend(name.as_bytes());
+        self.writer.write_all(&header)?;
+        self.writer.write_all(data)?;
+        self.entries.push(CentralEntry {
+            name: name.to_string(), crc, size, offset: self.offset,
+        });
+        self.offset = self.offset
+            .checked_add(header.len() as u32)
+            .and_then(|offset| offset.checked_add(size))
+            .ok_or_else(too_large)?;
+        Ok(())
+    }
+
+    /// Writes the central directory and closes the archive.
+    pub fn finish(mut self) -> io::Result<()> {
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend(0x02014b50u32.to_le_bytes());
+            directory.extend(20u16.to_le_bytes()); // Version made by
+            directory.extend(20u16.to_le_bytes()); // Version needed
+            directory.extend(0x0800u16.to_le_bytes()); // UTF-8 names
+            directory.extend(0u16.to_le_bytes()); // Stored
+            directory.extend(0u16.to_le_bytes()); // Time
+            directory.extend(DOS_DATE.to_le_bytes());
+            directory.extend(entry.crc.to_le_bytes());
+            directory.extend(entry.size.to_le_bytes());
+            directory.extend(entry.size.to_le_bytes());
+            // The length of the name is checked when the file is added
+            directory.extend((entry.name.len() as u16).to_le_bytes());
+            directory.extend(0u16.to_le_bytes()); // Extra field length
+            directory.extend(0u16.to_le_bytes()); // Comment length
+            directory.extend(0u16.to_le_bytes()); // Disk number
+            directory.extend(0u16.to_le_bytes()); // Internal attributes
+            directory.extend(0u32.to_le_bytes()); // External attributes
+            directory.extend(entry.offset.to_le_bytes());
+            directory.extend(entry.name.as_bytes());
+        }
+        let count = u16::try_from(self.entries.len())
+            .map_err(|_| io::Error::other("Too many files in the archive"))?;
+        let mut end = Vec::with_capacity(22);
+        end.extend(0x06054b50u32.to_le_bytes());
+        end.extend(0u16.to_le_bytes()); // Number of this disk
+        end.extend(0u16.to_le_bytes()); // Disk with the central directory
+        end.extend(count.to_le_bytes()); // Entries on this disk
+        end.extend(count.to_le_bytes()); // Total entries
+        end.extend((directory.len() as u32).to_le_bytes());
+        end.extend(self.offset.to_le_bytes());
+        end.extend(0u16.to_le_bytes()); // Comment length
+        self.writer.write_all(&directory)?;
+        self.writer.write_all(&end)?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculates_the_crc32_checksums() {
+        let table = crc_table();
+        assert_eq!(crc32(&table, b""), 0);
+        assert_eq!(crc32(&table, b"123456789"), 0xcbf43926);
+    }
+
+    #[test]
+    fn writes_the_archives_with_the_central_directory() {
+        let path = std::env::temp_dir()
+            .join(format!("cpf_zip_archive_{}.zip", std::process::id()));
+        let mut writer = ZipWriter::create(&path).unwrap();
+        writer.add_file("manifest.json", b"{}").unwrap();
+        writer.add_file("texts/en_US.lang", b"a=b").unwrap();
+        writer.finish().unwrap();
+        let data = std::fs::read(&path).unwrap();
+        let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([
+            data[i], data[i + 1], data[i + 2], data[i + 3]
+        ]);
+        assert_eq!(u32_at(0), 0x04034b50);
+        assert_eq!(&data[30..43], b"manifest.json");
+        assert_eq!(&data[43..45], b"{}");
+        // The second file starts right after the first one
+        assert_eq!(u32_at(45), 0x04034b50);
+        let end = data.len() - 22;
+        assert_eq!(u32_at(end), 0x06054b50);
+        assert_eq!(u16_at(end + 10), 2);
+        let directory = u32_at(end + 16) as usize;
+        assert_eq!(directory + u32_at(end + 12) as usize, end);
+        assert_eq!(u32_at(directory), 0x02014b50);
+        assert_eq!(u32_at(directory + 16), crc32(&crc_table(), b"{}"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_the_names_longer_than_the_limit() {
+        let path = std::env::temp_dir()
+            .join(format!("cpf_zip_long_{}.zip", std::process::id()));
+        let mut writer = ZipWriter::create(&path).unwrap();
+        let name = "a/".repeat(u16::MAX as usize / 2 + 1);
+        assert!(writer.add_file(&name, b"").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
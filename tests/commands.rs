@@ -0,0 +1,872 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{data, wait_until, Project, CONFIG_PATH};
+
+#[test]
+fn new_generates_the_files_of_the_content() {
+    let project = Project::new("new", r#"{
+        "roots": ["src"],
+        "namespace": "test",
+        "extensions_map": {".png": "RP/textures/entity"}
+    }"#);
+    project.run_ok(&["new", "entity", "my_mob"]);
+    let entity = project.read(&data("src/my_mob/my_mob.rpe.json"));
+    assert!(entity.contains("\"test:my_mob\""), "{}", entity);
+    assert!(entity.contains("\"textures/entity/my_mob/my_mob\""));
+    let lang = project.read(&data("src/my_mob/my_mob.lang"));
+    assert!(lang.contains("entity.test:my_mob.name=My Mob"), "{}", lang);
+    assert!(project.exists(&data("src/my_mob/my_mob.png")));
+    // The existing files are never overwritten
+    let error = project.run_err(&["new", "item", "my_mob"]);
+    assert!(error.contains("already exists"), "{}", error);
+    assert!(!project.exists(&data("src/my_mob/my_mob.bpi.json")));
+}
+
+#[test]
+fn plan_lists_the_targets_without_copying() {
+    let project = Project::new("plan", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".bpe.json": "BP/entities", ".rpe.json": "RP/entity"
+        }
+    }"#);
+    project.write(&data("src/zombie/_.bpe.json"), "{}");
+    project.write(&data("src/zombie/_.rpe.json"), "{}");
+    project.write(&data("src/notes.txt"), "");
+    let table = project.run_ok(&["plan"]);
+    assert!(table.starts_with("SOURCE"), "{}", table);
+    let row = table.lines().find(|l| l.contains("_.bpe.json")).unwrap();
+    assert!(row.contains("BP/entities/zombie.bpe.json"), "{}", row);
+    let row = table.lines().find(|l| l.contains("notes.txt")).unwrap();
+    assert!(row.contains("(unmapped)"), "{}", row);
+    assert!(!project.exists("BP"));
+    let table = project.run_ok(&["plan", "--category", "RP/entity"]);
+    assert_eq!(table.lines().count(), 2, "{}", table);
+    assert!(table.contains("RP/entity/zombie.rpe.json"), "{}", table);
+}
+
+#[test]
+fn check_fails_on_the_invalid_json_files() {
+    let project = Project::new("check", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    let output = project.run_ok(&["check"]);
+    assert!(output.contains("0 errors"), "{}", output);
+    project.write(&data("src/broken.bpe.json"), "{\"a\": }");
+    let output = project.run(&["check"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("broken.bpe.json"), "{}", stderr);
+    assert!(stderr.contains("Invalid JSON"), "{}", stderr);
+    assert!(!project.exists("BP"));
+}
+
+#[test]
+fn doctor_reports_the_missing_roots() {
+    let project = Project::new("doctor", r#"{
+        "roots": ["src", "missing"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    let output = project.run(&["doctor"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(stdout.contains("is valid (1 rules, 2 roots)"), "{}", stdout);
+    let missing = stdout.lines()
+        .find(|line| line.contains("missing"))
+        .unwrap();
+    assert!(missing.starts_with("[ERROR]"), "{}", missing);
+    assert!(stdout.contains("\"RP\" doesn't exist but it can be created."));
+}
+
+#[test]
+fn import_groups_the_files_of_the_features() {
+    let project = Project::new("import", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".bpe.json": "BP/entities", ".rpe.json": "RP/entity",
+            ".bpi.json": "BP/items"
+        }
+    }"#);
+    project.write("old_rp/entity/zombie.json", "rp");
+    project.write("old_bp/entities/zombie.json", "bp");
+    project.write("old_bp/items/sword.json", "sword");
+    project.write(&data("src/sword.bpi.json"), "existing");
+    project.run_ok(&["import", "old_rp", "old_bp"]);
+    assert_eq!(project.read(&data("src/zombie/_.rpe.json")), "rp");
+    assert_eq!(project.read(&data("src/zombie/_.bpe.json")), "bp");
+    // The existing files are skipped
+    assert_eq!(project.read(&data("src/sword.bpi.json")), "existing");
+}
+
+#[test]
+fn explain_finds_the_source_of_a_pack_file() {
+    let project = Project::new("explain", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie/_.bpe.json"), "{}");
+    let output = project.run_ok(&[
+        "explain", "BP/entities/zombie.bpe.json"
+    ]);
+    assert!(output.contains("_.bpe.json"), "{}", output);
+    assert!(
+        output.contains("rule: \".bpe.json\" -> \"BP/entities\""),
+        "{}", output
+    );
+    let error = project.run_err(&["explain", "BP/entities/skeleton.json"]);
+    assert!(error.contains("No file from the data directory"), "{}", error);
+}
+
+#[test]
+fn map_path_prints_the_target_or_the_skip_reason() {
+    let project = Project::new("map_path", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let output = project.run_ok(&["map-path", "mobs/zombie/_.bpe.json"]);
+    assert_eq!(output.trim(), "BP/entities/mobs/zombie.bpe.json");
+    let error = project.run_err(&["map-path", "zombie.txt"]);
+    assert!(error.contains("None of the rules"), "{}", error);
+    let error = project.run_err(&["map-path", "_.bpe.json"]);
+    assert!(error.contains("parent folder"), "{}", error);
+}
+
+#[test]
+fn plan_walks_every_directory() {
+    let project = Project::new("plan_walk", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let mut expected = Vec::new();
+    for a in 0..4 {
+        for b in 0..4 {
+            for c in 0..4 {
+                let path = format!("{}/{}/{}/mob.bpe.json", a, b, c);
+                project.write(&data(&format!("src/{}", path)), "{}");
+                expected.push(format!("BP/entities/{}", path));
+            }
+        }
+    }
+    let table = project.run_ok(&["plan"]);
+    // The header and the rows
+    assert_eq!(table.lines().count(), expected.len() + 1, "{}", table);
+    for target in &expected {
+        assert!(table.contains(target.as_str()), "{}", target);
+    }
+}
+
+#[test]
+fn plan_lists_the_files_in_the_order_of_their_paths() {
+    let project = Project::new("plan_order", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    for name in ["c", "a/b", "b", "a", "d/e/f"] {
+        project.write(&data(&format!("src/{}.bpe.json", name)), "{}");
+    }
+    let table = project.run_ok(&["plan"]);
+    // The paths are compared by their components
+    let sources: Vec<&Path> = table.lines().skip(1)
+        .map(|row| Path::new(row.split_whitespace().next().unwrap()))
+        .collect();
+    let mut sorted = sources.clone();
+    sorted.sort();
+    assert_eq!(sources, sorted);
+}
+
+#[test]
+fn plan_fails_on_the_directories_deeper_than_the_limit() {
+    let project = Project::new("max_depth", r#"{
+        "roots": ["src"],
+        "max_depth": 2,
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/a/b/zombie.bpe.json"), "{}");
+    project.run_ok(&["plan"]);
+    project.write(&data("src/a/b/c/zombie.bpe.json"), "{}");
+    let error = project.run_err(&["plan"]);
+    assert!(
+        error.contains("is nested deeper than the limit of 2"), "{}", error
+    );
+}
+
+#[test]
+fn plugins_must_be_wasm_modules_in_the_data_directory() {
+    let config = |module: &str| format!(r#"{{
+        "roots": ["src"],
+        "extensions_map": {{".bpe.json": "BP/entities"}},
+        "plugins": [{{
+            "module": "{}",
+            "extensions": [".up.json"],
+            "actions": ["map"]
+        }}]
+    }}"#, module);
+    let project = Project::new("plugins", &config("../plugin.wasm"));
+    let error = project.run_err(&["plan"]);
+    assert!(error.contains("\"plugins\" property"), "{}", error);
+    project.write(CONFIG_PATH, config("plugin.wasm"));
+    project.write(&data("plugin.wasm"), "#!/bin/sh");
+    let error = project.run_err(&["plan"]);
+    assert!(error.contains("is not a WebAssembly module"), "{}", error);
+}
+
+#[test]
+fn structured_diagnostics_have_the_severity_and_the_location() {
+    let project = Project::new("structured", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{\n  \"a\": 1,\n}");
+    let output = project.run(&["check", "--structured"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let line = stderr.lines()
+        .find(|line| line.starts_with("[ERROR] "))
+        .unwrap_or_else(|| panic!("{}", stderr));
+    let location = format!("{}:3:1: ", data("src/zombie.bpe.json"));
+    assert!(line.contains(&location), "{}", line);
+}
+
+#[test]
+fn check_reports_the_identifiers_defined_twice_in_a_pack() {
+    let project = Project::new("duplicate_identifiers", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".bpe.json": "BP/entities",
+            ".rpe.json": "RP/entity"
+        }
+    }"#);
+    let entity = r#"{"minecraft:entity": {
+        "description": {"identifier": "foo:zombie"}
+    }}"#;
+    project.write(&data("src/zombie/_.bpe.json"), entity);
+    project.write(&data("src/zombie/_.rpe.json"), entity);
+    project.run_ok(&["check"]);
+    project.write(&data("src/zombie2/_.bpe.json"), entity);
+    let error = project.run_err(&["check"]);
+    let message = "The identifier \"foo:zombie\" of \"minecraft:entity\"";
+    assert!(error.contains(message), "{}", error);
+}
+
+#[test]
+fn check_warns_about_the_names_that_break_the_game() {
+    let project = Project::new("file_names", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".bpe.json": "BP/entities",
+            ".mcfunction": "BP/functions"
+        }
+    }"#);
+    project.write(&data("src/Zombie.bpe.json"), "{}");
+    project.write(&data("src/say hi.mcfunction"), "say hi");
+    let output = project.run(&["check"]);
+    // The names only produce warnings
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("contains uppercase letters"), "{}", stderr);
+    assert!(stderr.contains("contains spaces"), "{}", stderr);
+}
+
+#[test]
+fn check_warns_about_the_lang_files_that_are_not_utf8() {
+    let project = Project::new("lang_encoding", r#"{
+        "roots": ["src"],
+        "extensions_map": {".lang": "RP/texts"}
+    }"#);
+    project.write(&data("src/en_US.lang"), b"a=ok\nb=caf\xe9\n");
+    project.write(&data("src/pl_PL.lang"), b"\xff\xfea\0=\0");
+    project.write(&data("src/de_DE.lang"), "a=Käse\n");
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("invalid byte 0xe9"), "{}", stderr);
+    assert!(stderr.contains("encoded in UTF-16"), "{}", stderr);
+    assert!(!stderr.contains("de_DE.lang"), "{}", stderr);
+}
+
+#[test]
+fn check_suggests_the_closest_extension_of_the_unmapped_files() {
+    let project = Project::new("suggest_extension", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.pbe.json"), "{}");
+    project.write(&data("src/notes.xyz"), "notes");
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Did you mean \".bpe.json\"?"), "{}", stderr);
+    let notes = stderr.lines().find(|line| line.contains("notes.xyz"))
+        .unwrap();
+    assert!(!notes.contains("Did you mean"), "{}", notes);
+}
+
+/// Builds the header of a .wav file with the format tag, the number of the
+/// channels and the sample rate.
+fn wav_header(encoding: u16, channels: u16, sample_rate: u32) -> Vec<u8> {
+    let mut header = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+    header.extend(encoding.to_le_bytes());
+    header.extend(channels.to_le_bytes());
+    header.extend(sample_rate.to_le_bytes());
+    header.extend([0; 8]);
+    header
+}
+
+#[test]
+fn check_warns_about_the_audio_formats_that_break_the_game() {
+    let project = Project::new("audio_formats", r#"{
+        "roots": ["src"],
+        "extensions_map": {".ogg": "RP/sounds", ".wav": "RP/sounds"}
+    }"#);
+    project.write(&data("src/ok.wav"), wav_header(1, 2, 44100));
+    project.write(&data("src/float.wav"), wav_header(3, 2, 44100));
+    project.write(&data("src/odd.wav"), wav_header(1, 6, 11025));
+    let mut opus = b"OggS".to_vec();
+    opus.extend([0; 22]);
+    opus.push(1);
+    opus.push(19);
+    opus.extend(b"OpusHead");
+    project.write(&data("src/opus.ogg"), opus);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("format tag 3"), "{}", stderr);
+    assert!(stderr.contains("11025 Hz"), "{}", stderr);
+    assert!(stderr.contains("6 channels"), "{}", stderr);
+    assert!(stderr.contains("encoded with Opus"), "{}", stderr);
+    assert!(!stderr.contains("ok.wav"), "{}", stderr);
+}
+
+#[test]
+fn check_warns_about_the_files_that_override_vanilla_entities() {
+    let project = Project::new("vanilla_overrides", r#"{
+        "roots": ["src"],
+        "extensions_map": {".json": "BP/entities"},
+        "vanilla_overrides": ["BP/entities/husk.json"]
+    }"#);
+    project.write(&data("src/zombie.json"), "{}");
+    project.write(&data("src/husk.json"), "{}");
+    project.write(&data("src/mob.json"), r#"{"minecraft:entity": {
+        "description": {"identifier": "minecraft:creeper"}
+    }}"#);
+    project.write(&data("src/custom.json"), r#"{"minecraft:entity": {
+        "description": {"identifier": "foo:custom"}
+    }}"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("vanilla \"minecraft:zombie\""), "{}", stderr);
+    assert!(stderr.contains("vanilla \"minecraft:creeper\""), "{}", stderr);
+    assert!(!stderr.contains("husk"), "{}", stderr);
+    assert!(!stderr.contains("custom.json"), "{}", stderr);
+}
+
+#[test]
+fn check_warns_about_the_targets_too_long_for_com_mojang() {
+    let project = Project::new("installed_path", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let dir = format!("{}/{}", "a".repeat(60), "b".repeat(90));
+    project.write(&data(&format!("src/{}/zombie.bpe.json", dir)), "{}");
+    project.write(&data("src/skeleton.bpe.json"), "{}");
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("development_behavior_packs"), "{}", stderr);
+    assert!(stderr.contains(&"b".repeat(90)), "{}", stderr);
+    assert!(!stderr.contains("skeleton"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn check_warns_about_the_names_reserved_or_invalid_on_windows() {
+    let project = Project::new("windows_names", r#"{
+        "roots": ["src"],
+        "extensions_map": {".json": "BP/entities"}
+    }"#);
+    project.write(&data("src/con.json"), "{}");
+    project.write(&data("src/what?.json"), "{}");
+    project.write(&data("src/dot./mob.json"), "{}");
+    project.write(&data("src/console.json"), "{}");
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("reserved for a device"), "{}", stderr);
+    assert!(stderr.contains("characters that are invalid"), "{}", stderr);
+    assert!(stderr.contains("ends with a dot"), "{}", stderr);
+    assert!(!stderr.contains("console.json"), "{}", stderr);
+}
+
+#[test]
+fn check_reports_the_missing_geometries_and_textures() {
+    let project = Project::new("references", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".entity.json": "RP/entity",
+            ".geo.json": "RP/models/entity",
+            ".png": "RP/textures/entity"
+        }
+    }"#);
+    project.write(&data("src/ghost.entity.json"), r#"{
+        "minecraft:client_entity": {"description": {
+            "identifier": "foo:ghost",
+            "geometry": {
+                "default": "geometry.ghost",
+                "legacy": "geometry.old",
+                "missing": "geometry.missing"
+            },
+            "textures": {
+                "default": "textures/entity/ghost",
+                "missing": "textures/entity/missing"
+            }
+        }}
+    }"#);
+    project.write(&data("src/ghost.geo.json"), r#"{
+        "minecraft:geometry": [
+            {"description": {"identifier": "geometry.ghost"}}
+        ]
+    }"#);
+    project.write(
+        &data("src/old.geo.json"), r#"{"geometry.old:geometry.base": {}}"#
+    );
+    project.write(&data("src/ghost.png"), b"\x89PNG");
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"geometry.missing\""), "{}", stderr);
+    assert!(stderr.contains("\"textures/entity/missing\""), "{}", stderr);
+    assert!(!stderr.contains("\"geometry.ghost\""), "{}", stderr);
+    assert!(!stderr.contains("\"geometry.old\""), "{}", stderr);
+    assert!(!stderr.contains("entity/ghost\""), "{}", stderr);
+}
+
+#[test]
+fn check_reports_the_broken_render_controller_references() {
+    let project = Project::new("render_controllers", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".entity.json": "RP/entity",
+            ".rc.json": "RP/render_controllers"
+        }
+    }"#);
+    project.write(&data("src/ghost.entity.json"), r#"{
+        "minecraft:client_entity": {"description": {
+            "identifier": "foo:ghost",
+            "geometry": {"default": "geometry.ghost"},
+            "render_controllers": [
+                "controller.render.ghost", "controller.render.missing"
+            ]
+        }}
+    }"#);
+    project.write(&data("src/ghost.rc.json"), r#"{
+        "render_controllers": {"controller.render.ghost": {
+            "arrays": {"textures": {"Array.skins": ["Texture.default"]}},
+            "geometry": "Geometry.default",
+            "materials": [{"*": "Material.default"}],
+            "textures": ["Array.skins[0]", "Array.colors[0]"]
+        }}
+    }"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"controller.render.missing\""), "{}", stderr);
+    assert!(stderr.contains("\"Array.colors\""), "{}", stderr);
+    assert!(stderr.contains("\"Material.default\""), "{}", stderr);
+    assert!(stderr.contains("\"Texture.default\""), "{}", stderr);
+    assert!(!stderr.contains("\"Geometry.default\""), "{}", stderr);
+    assert!(!stderr.contains("\"Array.skins\""), "{}", stderr);
+}
+
+#[test]
+fn check_reports_the_invalid_recipes() {
+    let project = Project::new("recipes", r#"{
+        "roots": ["src"],
+        "extensions_map": {".recipe.json": "BP/recipes"}
+    }"#);
+    project.write(&data("src/broken.recipe.json"), r#"{
+        "format_version": "1.20.30",
+        "minecraft:recipe_shaped": {
+            "description": {"identifier": "foo:broken"},
+            "pattern": ["XY", "XXXX"],
+            "key": {"X": {"item": "minecraft:stick"}},
+            "result": {"item": "Foo:Wand"}
+        }
+    }"#);
+    project.write(&data("src/valid.recipe.json"), r#"{
+        "format_version": "1.20.30",
+        "minecraft:recipe_shaped": {
+            "description": {"identifier": "foo:valid"},
+            "pattern": ["X", "X"],
+            "key": {"X": "minecraft:stick"},
+            "unlock": [{"item": "minecraft:stick"}],
+            "result": "foo:wand"
+        }
+    }"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("between 1x1 and 3x3"), "{}", stderr);
+    assert!(stderr.contains("symbol 'Y'"), "{}", stderr);
+    assert!(stderr.contains("\"unlock\" property"), "{}", stderr);
+    assert!(stderr.contains("\"Foo:Wand\" isn't a valid"), "{}", stderr);
+    assert!(!stderr.contains("valid.recipe.json"), "{}", stderr);
+}
+
+#[test]
+fn check_reports_the_loot_tables_that_are_not_copied() {
+    let project = Project::new("loot_tables", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".bpe.json": "BP/entities",
+            ".loot.json": "BP/loot_tables"
+        }
+    }"#);
+    project.write(&data("src/ghost.bpe.json"), r#"{
+        "minecraft:entity": {"components": {
+            "minecraft:loot": {"table": "loot_tables/ghost.loot.json"},
+            "minecraft:equipment": {"table": "loot_tables/missing.json"}
+        }}
+    }"#);
+    project.write(&data("src/ghost.loot.json"), r#"{"pools": [{
+        "entries": [{"type": "loot_table", "name": "loot_tables/gone"}]
+    }]}"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"loot_tables/missing.json\""), "{}", stderr);
+    assert!(stderr.contains("\"loot_tables/gone\""), "{}", stderr);
+    assert!(!stderr.contains("\"loot_tables/ghost"), "{}", stderr);
+}
+
+#[test]
+fn check_reports_the_invalid_trade_tables() {
+    let project = Project::new("trading", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".bpe.json": "BP/entities",
+            ".trade.json": "BP/trading"
+        }
+    }"#);
+    project.write(&data("src/trader.bpe.json"), r#"{
+        "minecraft:entity": {"components": {
+            "minecraft:trade_table": {"table": "trading/trader.trade.json"},
+            "minecraft:economy_trade_table": {"table": "trading/missing"}
+        }}
+    }"#);
+    project.write(&data("src/trader.trade.json"), r#"{"tiers": [
+        {"trades": [{
+            "wants": [{"item": "minecraft:emerald", "quantity": 2}],
+            "gives": [{
+                "item": "Foo:Gem",
+                "quantity": {"min": 3, "max": 1}
+            }]
+        }]},
+        {"groups": [{"trades": []}]}
+    ]}"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"trading/missing\""), "{}", stderr);
+    assert!(stderr.contains("\"Foo:Gem\" isn't a valid"), "{}", stderr);
+    assert!(stderr.contains("range 3..1 is invalid"), "{}", stderr);
+    assert!(stderr.contains("tier 2 doesn't have"), "{}", stderr);
+    assert!(!stderr.contains("\"trading/trader"), "{}", stderr);
+}
+
+#[test]
+fn check_reports_the_invalid_spawn_rules() {
+    let project = Project::new("spawn_rules", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".bpe.json": "BP/entities",
+            ".spawn.json": "BP/spawn_rules"
+        }
+    }"#);
+    project.write(&data("src/ghost.bpe.json"), r#"{
+        "minecraft:entity": {"description": {"identifier": "foo:ghost"}}
+    }"#);
+    project.write(&data("src/ghost.spawn.json"), r#"{
+        "minecraft:spawn_rules": {
+            "description": {
+                "identifier": "foo:ghost",
+                "population_control": "ghosts"
+            },
+            "conditions": [{"minecraft:biome_filter": {"value": "forest"}}]
+        }
+    }"#);
+    project.write(&data("src/orphan.spawn.json"), r#"{
+        "minecraft:spawn_rules": {"description": {
+            "identifier": "foo:orphan", "population_control": "monster"
+        }}
+    }"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("isn't marked as spawnable"), "{}", stderr);
+    assert!(stderr.contains("pool \"ghosts\" is invalid"), "{}", stderr);
+    assert!(stderr.contains("Invalid biome filter"), "{}", stderr);
+    assert!(stderr.contains("\"foo:orphan\" which isn't"), "{}", stderr);
+}
+
+#[test]
+fn check_reports_the_invalid_feature_rules() {
+    let project = Project::new("feature_rules", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".feature.json": "BP/features",
+            ".feature_rule.json": "BP/feature_rules"
+        }
+    }"#);
+    project.write(&data("src/ore.feature.json"), r#"{
+        "minecraft:ore_feature": {"description": {"identifier": "foo:iron"}}
+    }"#);
+    project.write(&data("src/iron.feature_rule.json"), r#"{
+        "minecraft:feature_rules": {"description": {
+            "identifier": "foo:iron_rule", "places_feature": "foo:iron"
+        }}
+    }"#);
+    project.write(&data("src/gold.feature_rule.json"), r#"{
+        "minecraft:feature_rules": {"description": {
+            "identifier": "foo:gold_rule", "places_feature": "foo:gold"
+        }}
+    }"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"foo:iron\" doesn't match"), "{}", stderr);
+    assert!(stderr.contains("\"foo:gold\" which isn't"), "{}", stderr);
+    assert!(!stderr.contains("\"foo:iron\" which isn't"), "{}", stderr);
+}
+
+#[test]
+fn check_lints_the_mcfunction_files() {
+    let project = Project::new("mcfunction", r#"{
+        "roots": ["src"],
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/setup.mcfunction"), concat!(
+        "# Comment\n",
+        "say hello\n",
+        "execute as @a run fly\n",
+        "tp @e[type=foo:ghost ~ ~ ~\n",
+        "function teleport\n",
+        "function missing\n",
+    ));
+    project.write(&data("src/teleport.mcfunction"), "tp @s 0 64 0\n");
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("(line 3, column 19): Unknown command \"fly\""),
+        "{}", stderr
+    );
+    assert!(stderr.contains("The '[' isn't closed."), "{}", stderr);
+    assert!(stderr.contains("\"missing\" isn't copied"), "{}", stderr);
+    assert!(!stderr.contains("\"teleport\""), "{}", stderr);
+    assert!(!stderr.contains("\"say\""), "{}", stderr);
+}
+
+#[test]
+fn check_validates_the_target_selectors() {
+    let project = Project::new("selectors", r#"{
+        "roots": ["src"],
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/kill.mcfunction"), concat!(
+        "kill @e[type=zombie,r=5,scores={kills=1..}]\n",
+        "kill @e[radius=5]\n",
+        "kill @e[c=x]\n",
+        "kill @e[scores={kills=1...}]\n",
+        "kill @x[r=1]\n",
+    ));
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("(line 1,"), "{}", stderr);
+    assert!(stderr.contains("argument \"radius\""), "{}", stderr);
+    assert!(stderr.contains("\"x\" isn't a valid value of \"c\""),
+        "{}", stderr);
+    assert!(stderr.contains("\"kills=1...\" isn't a valid score"),
+        "{}", stderr);
+    assert!(stderr.contains("variable \"@x\""), "{}", stderr);
+}
+
+#[test]
+fn check_warns_about_the_commands_newer_than_the_min_engine_version() {
+    let project = Project::new("min_engine_version", r#"{
+        "roots": ["src"],
+        "min_engine_version": "1.19.0",
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/intro.mcfunction"), concat!(
+        "damage @s 1\n",
+        "camera @s clear\n",
+        "execute as @a run say hi\n",
+    ));
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("(line 1,"), "{}", stderr);
+    assert!(stderr.contains(
+        "(line 2, column 1): The command \"camera\" requires the version \
+        1.20.30"
+    ), "{}", stderr);
+    assert!(stderr.contains(
+        "(line 3, column 1): The new syntax of execute requires the version \
+        1.19.50"
+    ), "{}", stderr);
+}
+
+#[test]
+fn check_reports_the_missing_particle_effects() {
+    let project = Project::new("particles", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".entity.json": "RP/entity",
+            ".particle.json": "RP/particles",
+            ".animation.json": "RP/animations"
+        }
+    }"#);
+    project.write(&data("src/ghost.entity.json"), r#"{
+        "minecraft:client_entity": {"description": {
+            "identifier": "foo:ghost",
+            "particle_effects": {
+                "trail": "foo:trail",
+                "heart": "minecraft:heart_particle",
+                "smoke": "foo:smoke"
+            }
+        }}
+    }"#);
+    project.write(&data("src/trail.particle.json"), r#"{
+        "particle_effect": {"description": {"identifier": "foo:trail"}}
+    }"#);
+    project.write(&data("src/ghost.animation.json"), r#"{
+        "animations": {"animation.ghost.idle": {"particle_effects": {
+            "0.0": [{"effect": "trail"}, {"effect": "sparkle"}]
+        }}}
+    }"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"foo:smoke\" isn't defined"), "{}", stderr);
+    assert!(stderr.contains("\"sparkle\" isn't declared"), "{}", stderr);
+    assert!(!stderr.contains("foo:trail"), "{}", stderr);
+    assert!(!stderr.contains("heart_particle"), "{}", stderr);
+    assert!(!stderr.contains("\"trail\""), "{}", stderr);
+}
+
+#[test]
+fn items_are_mapped_and_checked_by_their_format() {
+    let project = Project::new("item_formats", r#"{
+        "roots": ["src"],
+        "sniff_json": true,
+        "extensions_map": {".rpi.json": "RP/items"}
+    }"#);
+    project.write(&data("src/wand.rpi.json"), r#"{
+        "format_version": "1.20.50",
+        "minecraft:item": {"description": {"identifier": "foo:wand"}}
+    }"#);
+    project.write(&data("src/gem.json"), r#"{
+        "format_version": "1.20.50",
+        "minecraft:item": {"description": {"identifier": "foo:gem"}}
+    }"#);
+    project.write(&data("src/coin.json"), r#"{
+        "format_version": "1.10.0",
+        "minecraft:item": {
+            "description": {"identifier": "foo:coin"},
+            "components": {"minecraft:icon": "coin"}
+        }
+    }"#);
+    let output = project.run(&["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("wand.rpi.json"), "{}", stderr);
+    assert!(stderr.contains("format of the behavior pack"), "{}", stderr);
+    assert!(!stderr.contains("gem.json"), "{}", stderr);
+    assert!(!stderr.contains("coin.json"), "{}", stderr);
+    project.run_ok(&[]);
+    assert!(project.exists("BP/items/gem.json"));
+    assert!(project.exists("RP/items/coin.json"));
+}
+
+#[test]
+fn lang_tables_are_exported_imported_and_generated() {
+    let project = Project::new("lang_tables", r#"{
+        "roots": ["src"],
+        "lang_tables": true,
+        "extensions_map": {".lang": "RP/texts"}
+    }"#);
+    project.write(&data("src/en_US.lang"), "a=A\nb=B\n");
+    project.write(&data("src/de_DE.lang"), "a=Ä\n");
+    project.run_ok(&["lang", "export", "texts.csv"]);
+    assert_eq!(project.read("texts.csv"), "key,en_US,de_DE\na,A,Ä\nb,B,\n");
+    project.write("texts.csv", "key,en_US,de_DE,fr_FR\na,A,Ä,À\nb,B2,,\n");
+    project.run_ok(&["lang", "import", "texts.csv"]);
+    assert_eq!(project.read(&data("src/en_US.lang")), "a=A\nb=B2\n");
+    assert_eq!(project.read(&data("src/fr_FR.lang")), "a=À\n");
+    // The tables in the data directory generate the .lang files
+    project.write(&data("src/extra.lang.csv"), "key,en_US\nc,C\n");
+    project.run_ok(&[]);
+    assert_eq!(project.read("RP/texts/en_US.lang"), "a=A\nb=B2\nc=C\n");
+    assert!(!project.exists("RP/texts/extra.lang.csv"));
+}
+
+#[test]
+fn scores_reports_the_objectives_and_the_tags() {
+    let project = Project::new("scores", r#"{
+        "roots": ["src"],
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/setup.mcfunction"), concat!(
+        "scoreboard objectives add kills dummy\n",
+        "tag @s add boss\n",
+        "scoreboard players add @s kills 1\n",
+        "scoreboard players set @s deaths 0\n",
+    ));
+    let output = project.run(&["scores"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let source = data("src/setup.mcfunction");
+    assert!(stdout.contains(&format!(
+        "  kills\n    added: {0}:1\n    used: {0}:3\n", source
+    )), "{}", stdout);
+    assert!(stdout.contains(&format!(
+        "Tags:\n  boss\n    added: {}:2\n", source
+    )), "{}", stdout);
+    assert!(
+        stderr.contains("(line 4, column 1): The objective \"deaths\""),
+        "{}", stderr
+    );
+}
+
+#[test]
+fn watch_copies_the_changes_and_keeps_going_after_the_errors() {
+    let project = Project::new("watch", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    let mut child = project.spawn(&["watch", "--interval", "50"]);
+    wait_until(|| project.exists("BP/entities/zombie.bpe.json"));
+    // The update fails, but the watching continues
+    project.write(&data("src/zombie.bpe.json"), "{\"a\": }");
+    wait_until(|| project.read("stderr.log").contains("syntax errors"));
+    project.write(&data("src/zombie.bpe.json"), "{\"a\": 1}");
+    project.write(&data("src/mobs/ghost.bpe.json"), "{}");
+    wait_until(|| {
+        project.exists("BP/entities/mobs/ghost.bpe.json")
+            && project.read("BP/entities/zombie.bpe.json") == "{\"a\": 1}"
+    });
+    fs::remove_dir_all(project.path(&data("src/mobs"))).unwrap();
+    wait_until(|| !project.exists("BP/entities/mobs/ghost.bpe.json"));
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn export_skips_the_archives_and_the_linked_parents() {
+    let project = Project::new("export", r#"{"roots": ["src"]}"#);
+    project.write("BP/manifest.json", "{}");
+    std::os::unix::fs::symlink(
+        project.path("BP"), project.path("BP/loop")
+    ).unwrap();
+    // The archives are written into the pack
+    project.run_ok(&["export", "--name", "a", "--out", "BP/dist"]);
+    project.run_ok(&["export", "--name", "a", "--out", "BP/dist"]);
+    let archive = fs::read(project.path("BP/dist/a_BP.mcpack")).unwrap();
+    let contains = |name: &str| {
+        archive.windows(name.len()).any(|w| w == name.as_bytes())
+    };
+    assert!(contains("manifest.json"));
+    assert!(!contains("loop/"));
+    assert!(!contains("a_BP.mcpack"));
+}
@@ -0,0 +1,185 @@
+//! The helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The path to the config file relative to the working directory.
+pub static CONFIG_PATH: &str = "data/custom_project/config.json";
+
+/// Returns the path relative to the working directory to the file in the
+/// data directory of the filter.
+pub fn data(path: &str) -> String {
+    format!("data/custom_project/{}", path)
+}
+
+/// Returns the CRC-32 of the data, as used by the PNG chunks.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+/// Returns the PNG file of the RGBA image with the pixels stored without the
+/// compression.
+///
+/// # Arguments
+/// - `width` - the width of the image
+/// - `height` - the height of the image
+/// - `pixels` - the RGBA values of the pixels, row by row
+pub fn png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::new();
+    for row in pixels.chunks(width as usize * 4) {
+        // Every row starts with the type of its filter (none)
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in &raw {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    // A zlib stream with a single stored block
+    let length = raw.len() as u16;
+    let mut data = vec![0x78, 0x01, 0x01];
+    data.extend(length.to_le_bytes());
+    data.extend((!length).to_le_bytes());
+    data.extend(&raw);
+    data.extend(((b << 16) | a).to_be_bytes());
+    let mut header = Vec::new();
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    header.extend([8, 6, 0, 0, 0]);
+    let mut result = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, body) in [
+        (&b"IHDR"[..], &header[..]), (b"IDAT", &data[..]), (b"IEND", &[]),
+    ] {
+        result.extend((body.len() as u32).to_be_bytes());
+        let mut chunk = kind.to_vec();
+        chunk.extend_from_slice(body);
+        let crc = crc32(&chunk);
+        result.extend(chunk);
+        result.extend(crc.to_be_bytes());
+    }
+    result
+}
+
+/// A temporary working directory of Regolith with the config file of the
+/// filter. The directory is removed at the end of the test.
+pub struct Project {
+    pub dir: PathBuf,
+}
+
+impl Project {
+    /// Creates the working directory with the config file.
+    ///
+    /// # Arguments
+    /// - `name` - the name of the directory, unique for every test
+    /// - `config` - the content of the config file
+    pub fn new(name: &str, config: &str) -> Project {
+        let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let project = Project { dir };
+        project.write(CONFIG_PATH, config);
+        project
+    }
+
+    /// Returns the path to the file relative to the working directory.
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.dir.join(path)
+    }
+
+    /// Writes the file (relative to the working directory) and creates its
+    /// parent directories.
+    pub fn write(&self, path: &str, content: impl AsRef<[u8]>) {
+        let path = self.path(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    /// Reads the file relative to the working directory.
+    pub fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.path(path))
+            .unwrap_or_else(|e| panic!("Unable to read \"{}\": {}", path, e))
+    }
+
+    /// Checks if the file (relative to the working directory) exists.
+    pub fn exists(&self, path: &str) -> bool {
+        self.path(path).exists()
+    }
+
+    /// Runs the filter with the arguments in the working directory.
+    pub fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_custom-project"))
+            .args(args)
+            .current_dir(&self.dir)
+            .env_remove("ROOT_DIR")
+            .output()
+            .unwrap()
+    }
+
+    /// Starts the filter with the arguments in the working directory without
+    /// waiting for it. The standard error output is written to the
+    /// "stderr.log" file of the working directory.
+    pub fn spawn(&self, args: &[&str]) -> Child {
+        let log = fs::File::create(self.path("stderr.log")).unwrap();
+        Command::new(env!("CARGO_BIN_EXE_custom-project"))
+            .args(args)
+            .current_dir(&self.dir)
+            .env_remove("ROOT_DIR")
+            .stdout(Stdio::null())
+            .stderr(log)
+            .spawn()
+            .unwrap()
+    }
+
+    /// Runs the filter with the arguments and returns its standard output.
+    /// Panics if the filter fails.
+    pub fn run_ok(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        assert!(
+            output.status.success(), "{}{}",
+            stdout, String::from_utf8_lossy(&output.stderr)
+        );
+        stdout
+    }
+
+    /// Runs the filter with the arguments and returns its standard error
+    /// output. Panics if the filter succeeds.
+    pub fn run_err(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(
+            !output.status.success(), "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Waits until the condition is met. Panics after 10 seconds.
+pub fn wait_until(mut condition: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(10), "Timed out");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
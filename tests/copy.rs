@@ -0,0 +1,1082 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+use std::time::{Duration, UNIX_EPOCH};
+
+use common::{data, png, Project, CONFIG_PATH};
+
+#[test]
+fn diff_shows_the_changes_to_the_existing_targets() {
+    let project = Project::new("diff", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "[\n\"a\",\n\"newer\"\n]\n");
+    project.write("BP/entities/zombie.bpe.json", "[\n\"a\",\n\"old\"\n]\n");
+    let output = project.run_ok(&["--diff"]);
+    assert!(output.contains("-\"old\"\n"), "{}", output);
+    assert!(output.contains("+\"newer\"\n"), "{}", output);
+    // The existing target is still skipped
+    let target = project.read("BP/entities/zombie.bpe.json");
+    assert_eq!(target, "[\n\"a\",\n\"old\"\n]\n");
+}
+
+#[test]
+fn interactive_requires_a_terminal() {
+    let project = Project::new("interactive", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"first\"");
+    project.write(&data("src/zombie/_.bpe.json"), "\"second\"");
+    let output = project.run(&["--interactive"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("outside of a terminal"), "{}", stderr);
+    // One of the conflicting files is copied, the other one is skipped
+    assert!(stderr.contains("is already mapped from"), "{}", stderr);
+    let target = project.read("BP/entities/zombie.bpe.json");
+    assert!(target == "\"first\"" || target == "\"second\"");
+}
+
+#[test]
+fn jobs_copies_every_file() {
+    let project = Project::new("jobs", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    for i in 0..20 {
+        project.write(&data(&format!("src/mob{}.bpe.json", i)), "{}");
+    }
+    project.run_ok(&["--jobs", "3"]);
+    for i in 0..20 {
+        let target = format!("BP/entities/mob{}.bpe.json", i);
+        assert!(project.exists(&target), "{}", target);
+    }
+    let error = project.run_err(&["--jobs", "many"]);
+    assert!(error.contains("Invalid --jobs value"), "{}", error);
+}
+
+#[test]
+fn up_to_date_targets_are_skipped_silently() {
+    let project = Project::new("incremental", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    // A newer target with the same size but a different content
+    project.write("BP/entities/zombie.bpe.json", "[]");
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("already exists"), "{}", stderr);
+    let output = project.run(&["--no-incremental", "--no-cache"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already exists"), "{}", stderr);
+}
+
+#[test]
+fn sync_removes_the_targets_of_the_deleted_files() {
+    let project = Project::new("sync", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/mobs/zombie.bpe.json"), "{}");
+    project.write(&data("src/skeleton.bpe.json"), "{}");
+    project.run_ok(&[]);
+    assert!(project.exists("BP/entities/mobs/zombie.bpe.json"));
+    fs::remove_file(project.path(&data("src/mobs/zombie.bpe.json")))
+        .unwrap();
+    project.run_ok(&["--sync"]);
+    // The empty directories are removed as well
+    assert!(!project.exists("BP/entities/mobs"));
+    assert!(project.exists("BP/entities/skeleton.bpe.json"));
+    let output = project.run_ok(&["clean"]);
+    assert!(output.contains("Removed 1 targets."), "{}", output);
+    assert!(!project.exists("BP/entities/skeleton.bpe.json"));
+}
+
+#[test]
+fn sync_keeps_the_targets_that_were_not_written() {
+    let project = Project::new("sync_skipped", r#"{
+        "roots": ["src"],
+        "preprocess_functions": true,
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/setup.mcfunction"), "say hi\n");
+    project.run_ok(&[]);
+    // The function can't be generated, so its target isn't written
+    project.write(&data("src/setup.mcfunction"), "#expand missing()\n");
+    let output = project.run(&["--sync"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unable to generate"), "{}", stderr);
+    assert_eq!(project.read("BP/functions/setup.mcfunction"), "say hi\n");
+    let output = project.run_ok(&["clean"]);
+    assert!(output.contains("Removed 1 targets."), "{}", output);
+}
+
+#[test]
+fn cache_copies_the_files_again_after_a_failed_write() {
+    let project = Project::new("cache_failed_write", r#"{
+        "roots": ["src"],
+        "overwrite": "always",
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"old\"");
+    project.run_ok(&[]);
+    project.write(&data("src/zombie.bpe.json"), "\"new\"");
+    // The temporary file can't be created in place of a directory
+    let temp = project.path("BP/entities/.zombie.bpe.json.cpf_tmp");
+    fs::create_dir(&temp).unwrap();
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unable to copy the file"), "{}", stderr);
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"old\"");
+    fs::remove_dir(&temp).unwrap();
+    project.run_ok(&[]);
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"new\"");
+}
+
+#[test]
+fn files_from_only_plans_and_validates_the_listed_files() {
+    let project = Project::new("files_from", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    project.write(&data("src/broken.bpe.json"), "{");
+    project.write("files.txt", "src/zombie.bpe.json\n");
+    let output = project.run(&["--files-from", "files.txt"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(!stderr.contains("broken"), "{}", stderr);
+    assert!(project.exists("BP/entities/zombie.bpe.json"));
+    assert!(!project.exists("BP/entities/broken.bpe.json"));
+}
+
+#[test]
+fn move_mode_moves_the_files_into_place() {
+    let project = Project::new("move", r#"{
+        "roots": ["src"],
+        "mode": "move",
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.run_ok(&[]);
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"zombie\"");
+    assert!(!project.exists(&data("src/zombie.bpe.json")));
+}
+
+#[cfg(unix)]
+#[test]
+fn hardlink_mode_links_the_targets_to_the_sources() {
+    use std::os::unix::fs::MetadataExt;
+
+    let project = Project::new("hardlink", r#"{
+        "roots": ["src"],
+        "mode": "hardlink",
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.run_ok(&[]);
+    let source = fs::metadata(project.path(&data("src/zombie.bpe.json")))
+        .unwrap();
+    let target = fs::metadata(project.path("BP/entities/zombie.bpe.json"))
+        .unwrap();
+    assert_eq!(source.ino(), target.ino());
+}
+
+#[test]
+fn symlink_mode_is_disabled_in_regolith() {
+    let config = r#"{
+        "roots": ["src"],
+        "mode": "symlink",
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#;
+    let project = Project::new("symlink", config);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.run_ok(&[]);
+    let target = project.path("BP/entities/zombie.bpe.json");
+    assert!(fs::symlink_metadata(&target).unwrap().is_symlink());
+    // The temporary directory of Regolith
+    let project = Project::new("regolith_symlink/tmp", config);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    let output = project.run_ok(&[]);
+    assert!(output.contains("Copying the files instead."), "{}", output);
+    let target = project.path("BP/entities/zombie.bpe.json");
+    assert!(!fs::symlink_metadata(&target).unwrap().is_symlink());
+}
+
+#[test]
+fn reflink_falls_back_to_copying() {
+    let project = Project::new("reflink", r#"{
+        "roots": ["src"],
+        "reflink": true,
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let content = format!("\"{}\"", "zombie".repeat(10000));
+    project.write(&data("src/zombie.bpe.json"), &content);
+    project.run_ok(&[]);
+    // The file is cloned or copied depending on the file system
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), content);
+    project.write(common::CONFIG_PATH, r#"{
+        "roots": ["src"],
+        "reflink": "yes",
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"reflink\" property"), "{}", stderr);
+}
+
+#[test]
+fn large_files_report_the_progress() {
+    let project = Project::new("stream", r#"{
+        "roots": ["src"],
+        "reflink": false,
+        "extensions_map": {".mcstructure": "BP/structures"}
+    }"#);
+    project.write(&data("src/house.mcstructure"), "");
+    // A sparse file doesn't use the disk space
+    fs::File::options()
+        .write(true)
+        .open(project.path(&data("src/house.mcstructure")))
+        .unwrap()
+        .set_len(64 * 1024 * 1024)
+        .unwrap();
+    let output = project.run_ok(&[]);
+    assert!(output.contains(": 10% (7 / 64 MiB)"), "{}", output);
+    assert!(output.contains("100% (64 / 64 MiB)"), "{}", output);
+    let target = fs::metadata(project.path("BP/structures/house.mcstructure"))
+        .unwrap();
+    assert_eq!(target.len(), 64 * 1024 * 1024);
+}
+
+#[test]
+fn identical_targets_are_not_rewritten() {
+    let project = Project::new("identical", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.write("BP/entities/zombie.bpe.json", "\"zombie\"");
+    // The target is older than the source
+    let old = UNIX_EPOCH + Duration::from_secs(1_000_000);
+    fs::File::options()
+        .write(true)
+        .open(project.path("BP/entities/zombie.bpe.json"))
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+    let output = project.run(&["--no-cache"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("already exists"), "{}", stderr);
+    let target = fs::metadata(project.path("BP/entities/zombie.bpe.json"))
+        .unwrap();
+    assert_eq!(target.modified().unwrap(), old);
+}
+
+#[cfg(unix)]
+#[test]
+fn dedupe_links_the_identical_targets() {
+    use std::os::unix::fs::MetadataExt;
+
+    let project = Project::new("dedupe", r#"{
+        "roots": ["src"],
+        "dedupe": true,
+        "extensions_map": {".png": "RP/textures/blocks"}
+    }"#);
+    project.write(&data("src/stone.png"), "texture");
+    project.write(&data("src/cobblestone.png"), "texture");
+    project.write(&data("src/dirt.png"), "other");
+    let output = project.run_ok(&[]);
+    assert!(output.contains("Deduplicated 1 files"), "{}", output);
+    let inode = |path: &str| fs::metadata(project.path(path)).unwrap().ino();
+    assert_eq!(
+        inode("RP/textures/blocks/stone.png"),
+        inode("RP/textures/blocks/cobblestone.png")
+    );
+    assert_ne!(
+        inode("RP/textures/blocks/stone.png"),
+        inode("RP/textures/blocks/dirt.png")
+    );
+}
+
+#[test]
+fn overwrite_policy_controls_the_existing_targets() {
+    let config = |policy: &str| format!(r#"{{
+        "roots": ["src"],
+        "overwrite": "{}",
+        "extensions_map": {{".bpe.json": "BP/entities"}}
+    }}"#, policy);
+    let project = Project::new("overwrite", &config("error"));
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.write("BP/entities/zombie.bpe.json", "\"old\"");
+    let error = project.run_err(&[]);
+    assert!(error.contains("can't be overwritten"), "{}", error);
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"old\"");
+    project.write(common::CONFIG_PATH, config("always"));
+    project.run_ok(&[]);
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"zombie\"");
+}
+
+#[test]
+fn conflicts_fail_the_filter_on_request() {
+    let project = Project::new("conflicts", r#"{
+        "roots": ["src"],
+        "conflicts": "error",
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"first\"");
+    project.write(&data("src/zombie/_.bpe.json"), "\"second\"");
+    let error = project.run_err(&[]);
+    assert!(error.contains("mapped to the same targets"), "{}", error);
+    assert!(error.contains("zombie.bpe.json"), "{}", error);
+    assert!(error.contains("_.bpe.json"), "{}", error);
+    assert!(!project.exists("BP/entities/zombie.bpe.json"));
+    let error = project.run_err(&["check"]);
+    assert!(error.contains("which is already mapped from"), "{}", error);
+}
+
+#[test]
+fn targets_differing_only_by_case_fail_the_filter() {
+    let project = Project::new("case", r#"{
+        "roots": ["src"],
+        "extensions_map": {".png": "RP/textures/entity"}
+    }"#);
+    project.write(&data("src/Zombie.png"), "upper");
+    project.write(&data("src/zombie.png"), "lower");
+    let error = project.run_err(&[]);
+    assert!(error.contains("differ only by case"), "{}", error);
+    assert!(!project.exists("RP"));
+    let error = project.run_err(&["check"]);
+    assert!(error.contains("differs only by case"), "{}", error);
+}
+
+#[test]
+fn long_paths_are_copied() {
+    let project = Project::new("long_paths", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    // Longer than the MAX_PATH limit of Windows
+    let dir = ["a_very_long_directory_name"; 12].join("/");
+    let name = format!("{}/zombie.bpe.json", dir);
+    project.write(&data(&format!("src/{}", name)), "\"zombie\"");
+    project.run_ok(&[]);
+    let target = format!("BP/entities/{}", name);
+    assert!(project.path(&target).as_os_str().len() > 260);
+    assert_eq!(project.read(&target), "\"zombie\"");
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_names_are_copied_without_changes() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let project = Project::new("non_utf8", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let name = OsStr::from_bytes(b"zomb\xffie.bpe.json");
+    fs::create_dir_all(project.path(&data("src"))).unwrap();
+    fs::write(project.path(&data("src")).join(name), "\"zombie\"").unwrap();
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("is not valid UTF-8"), "{}", stderr);
+    let target = project.path("BP/entities").join(name);
+    assert_eq!(fs::read_to_string(target).unwrap(), "\"zombie\"");
+}
+
+#[cfg(unix)]
+#[test]
+fn preserve_metadata_copies_the_times_and_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = Project::new("preserve_metadata", r#"{
+        "roots": ["src"],
+        "preserve_metadata": true,
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let source = project.path(&data("src/zombie.bpe.json"));
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    let old = UNIX_EPOCH + Duration::from_secs(1_000_000);
+    fs::File::options().write(true).open(&source).unwrap()
+        .set_modified(old).unwrap();
+    fs::set_permissions(&source, fs::Permissions::from_mode(0o640))
+        .unwrap();
+    project.run_ok(&[]);
+    let target = fs::metadata(project.path("BP/entities/zombie.bpe.json"))
+        .unwrap();
+    assert_eq!(target.modified().unwrap(), old);
+    assert_eq!(target.permissions().mode() & 0o777, 0o640);
+}
+
+#[test]
+fn timings_reports_the_phases_and_the_slowest_files() {
+    let project = Project::new("timings", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    let output = project.run_ok(&["--timings"]);
+    let report = output.split("Timings:\n").nth(1).expect(&output);
+    assert!(report.contains("  total  "), "{}", report);
+    let slowest = report.split("Slowest files:\n").nth(1).expect(report);
+    assert!(slowest.contains("zombie.bpe.json"), "{}", slowest);
+    let output = project.run_ok(&[]);
+    assert!(!output.contains("Timings:"), "{}", output);
+}
+
+#[test]
+fn changed_since_copies_only_the_changed_files() {
+    let project = Project::new("changed_since", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@test"])
+            .args(args)
+            .current_dir(&project.dir)
+            .env_remove("GIT_DIR")
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    };
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.write(&data("src/skeleton.bpe.json"), "\"skeleton\"");
+    git(&["init", "-q"]);
+    git(&["add", "-A"]);
+    git(&["commit", "-q", "-m", "init"]);
+    project.write(&data("src/zombie.bpe.json"), "\"changed zombie\"");
+    project.write(&data("src/creeper.bpe.json"), "\"creeper\"");
+    project.run_ok(&["--changed-since", "HEAD"]);
+    assert_eq!(
+        project.read("BP/entities/zombie.bpe.json"), "\"changed zombie\""
+    );
+    assert!(project.exists("BP/entities/creeper.bpe.json"));
+    assert!(!project.exists("BP/entities/skeleton.bpe.json"));
+}
+
+#[cfg(unix)]
+#[test]
+fn targets_are_renamed_into_place() {
+    use std::os::unix::fs::MetadataExt;
+
+    let project = Project::new("atomic", r#"{
+        "roots": ["src"],
+        "overwrite": "always",
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.write("BP/entities/zombie.bpe.json", "\"old\"");
+    let target = project.path("BP/entities/zombie.bpe.json");
+    let inode = fs::metadata(&target).unwrap().ino();
+    project.run_ok(&[]);
+    // The old file is replaced instead of being written in place
+    assert_ne!(fs::metadata(&target).unwrap().ino(), inode);
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"zombie\"");
+    let names: Vec<_> = fs::read_dir(project.path("BP/entities")).unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["zombie.bpe.json"]);
+}
+
+#[test]
+fn jobs_setting_is_read_from_the_config() {
+    let project = Project::new("jobs_setting", r#"{
+        "roots": ["src"],
+        "jobs": 1,
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    for i in 0..5 {
+        project.write(&data(&format!("src/{}/mob.bpe.json", i)), "{}");
+    }
+    project.run_ok(&[]);
+    for i in 0..5 {
+        assert!(project.exists(&format!("BP/entities/{}/mob.bpe.json", i)));
+    }
+    project.write(common::CONFIG_PATH, r#"{
+        "roots": ["src"],
+        "jobs": "many",
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"jobs\" property"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn hooks_run_around_copying_the_files() {
+    let project = Project::new("hooks", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"},
+        "hooks": {
+            "before": ["sh gen.sh"],
+            "per_file": ["echo \"$CPF_TARGET\" >> per_file.log"],
+            "after": ["echo done > after.log"]
+        }
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    project.write("gen.sh", format!(
+        "echo 1 > {}", data("src/gen.bpe.json")
+    ));
+    project.run_ok(&[]);
+    assert_eq!(project.read("BP/entities/gen.bpe.json"), "1\n");
+    let mut log: Vec<String> = project.read("per_file.log").lines()
+        .map(|line| line.replace('\\', "/"))
+        .collect();
+    log.sort();
+    assert_eq!(log, [
+        "BP/entities/gen.bpe.json", "BP/entities/zombie.bpe.json"
+    ]);
+    assert_eq!(project.read("after.log"), "done\n");
+    project.write(CONFIG_PATH, r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"},
+        "hooks": {"before": ["exit 3"]}
+    }"#);
+    let output = project.run(&[]);
+    assert_eq!(output.status.code(), Some(6));
+}
+
+#[test]
+fn passthrough_and_fallback_map_the_unmatched_files() {
+    let project = Project::new("mapper_chain", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"},
+        "passthrough": true,
+        "fallback": "RP/misc"
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    project.write(&data("src/BP/manifest.json"), "{}");
+    project.write(&data("src/notes/todo.txt"), "todo");
+    project.run_ok(&[]);
+    assert!(project.exists("BP/entities/zombie.bpe.json"));
+    assert!(project.exists("BP/manifest.json"));
+    assert_eq!(project.read("RP/misc/notes/todo.txt"), "todo");
+}
+
+#[test]
+fn outputs_write_the_tagged_rules_to_the_additional_packs() {
+    let project = Project::new("outputs", r#"{
+        "roots": ["src"],
+        "extensions_map": {
+            ".bpe.json": "BP/entities",
+            ".debug.mcfunction": "debug:BP/functions"
+        },
+        "outputs": {"debug": {"BP": "debug_packs/BP", "RP": "debug_packs/RP"}}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    project.write(&data("src/say.debug.mcfunction"), "say hi");
+    project.run_ok(&[]);
+    assert!(project.exists("BP/entities/zombie.bpe.json"));
+    let target = "debug_packs/BP/functions/say.debug.mcfunction";
+    assert_eq!(project.read(target), "say hi");
+    assert!(!project.exists("BP/functions"));
+}
+
+#[test]
+fn empty_and_truncated_json_files_are_not_copied() {
+    let project = Project::new("incomplete_json", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/empty.bpe.json"), "  \n");
+    project.write(&data("src/truncated.bpe.json"), "{\"a\": [1, 2");
+    project.write(&data("src/zombie.bpe.json"), "{}");
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("The JSON file is empty."), "{}", stderr);
+    assert!(stderr.contains("The JSON file is truncated"), "{}", stderr);
+    assert!(!project.exists("BP/entities/empty.bpe.json"));
+    assert!(!project.exists("BP/entities/truncated.bpe.json"));
+    assert!(project.exists("BP/entities/zombie.bpe.json"));
+}
+
+#[test]
+fn byte_order_marks_are_stripped_from_the_json_files() {
+    let project = Project::new("bom", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities", ".png": "RP/textures"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\u{FEFF}{}");
+    project.write(&data("src/zombie.png"), "\u{FEFF}png");
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("zombie.bpe.json"), "{}", stderr);
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "{}");
+    // The other files aren't changed
+    assert_eq!(project.read("RP/textures/zombie.png"), "\u{FEFF}png");
+    project.write(CONFIG_PATH, r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"},
+        "bom": "preserve"
+    }"#);
+    project.write(&data("src/skeleton.bpe.json"), "\u{FEFF}{}");
+    project.run_ok(&[]);
+    let target = project.read("BP/entities/skeleton.bpe.json");
+    assert_eq!(target, "\u{FEFF}{}");
+}
+
+#[test]
+fn line_endings_of_the_text_files_are_normalized() {
+    let project = Project::new("line_endings", r#"{
+        "roots": ["src"],
+        "extensions_map": {".mcfunction": "BP/functions", ".png": "RP/x"},
+        "line_endings": "lf"
+    }"#);
+    project.write(&data("src/say.mcfunction"), "say a\r\nsay b\r\n");
+    project.write(&data("src/a.png"), "\r\n");
+    project.run_ok(&[]);
+    assert_eq!(project.read("BP/functions/say.mcfunction"), "say a\nsay b\n");
+    assert_eq!(project.read("RP/x/a.png"), "\r\n");
+}
+
+#[test]
+fn root_subfolders_separate_the_targets_of_the_roots() {
+    let project = Project::new("root_subfolders", r#"{
+        "roots": ["combat", "farming"],
+        "root_subfolders": true,
+        "texture_routing": true,
+        "extensions_map": {
+            ".bpe.json": "BP/entities",
+            ".geo.json": "RP/models/entity",
+            ".png": "RP/textures"
+        }
+    }"#);
+    project.write(&data("combat/zombie.bpe.json"), "{}");
+    project.write(&data("farming/mobs/cow.bpe.json"), "{}");
+    project.write(&data("combat/zombie.geo.json"), "{}");
+    project.write(&data("combat/zombie.png"), "png");
+    project.run_ok(&[]);
+    assert!(project.exists("BP/entities/combat/zombie.bpe.json"));
+    assert!(project.exists("BP/entities/farming/mobs/cow.bpe.json"));
+    // The root follows the whole export directory of the rule
+    assert!(project.exists("RP/models/entity/combat/zombie.geo.json"));
+    assert!(project.exists("RP/textures/entity/combat/zombie.png"));
+}
+
+#[test]
+fn shared_folder_is_copied_into_both_packs() {
+    let project = Project::new("shared", r#"{
+        "roots": ["src"],
+        "shared": true,
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/SHARED/texts/en_US.lang"), "a=b\n");
+    project.write(&data("src/SHARED/zombie.bpe.json"), "{}");
+    project.run_ok(&[]);
+    assert_eq!(project.read("BP/texts/en_US.lang"), "a=b\n");
+    assert_eq!(project.read("RP/texts/en_US.lang"), "a=b\n");
+    // The files matched by the rules are only copied to their targets
+    assert!(project.exists("BP/entities/SHARED/zombie.bpe.json"));
+    assert!(!project.exists("RP/zombie.bpe.json"));
+}
+
+#[test]
+fn texture_routing_uses_the_files_next_to_the_textures() {
+    let project = Project::new("texture_routing", r#"{
+        "roots": ["src"],
+        "texture_routing": true,
+        "extensions_map": {
+            ".png": "RP/textures",
+            ".i.json": "BP/items",
+            ".bpe.json": "BP/entities"
+        }
+    }"#);
+    project.write(&data("src/sword/sword.i.json"), "{}");
+    project.write(&data("src/sword/sword.png"), "png");
+    project.write(&data("src/ghost/ghost.bpe.json"), "{}");
+    project.write(&data("src/ghost/ghost.png"), "png");
+    project.write(&data("src/mixed/mixed.i.json"), "{}");
+    project.write(&data("src/mixed/mixed.bpe.json"), "{}");
+    project.write(&data("src/mixed/mixed.png"), "png");
+    project.run_ok(&[]);
+    assert!(project.exists("RP/textures/items/sword/sword.png"));
+    assert!(project.exists("RP/textures/entity/ghost/ghost.png"));
+    assert!(project.exists("RP/textures/mixed/mixed.png"));
+}
+
+#[test]
+fn sniff_json_maps_the_plain_json_files_by_their_content() {
+    let project = Project::new("sniff_json", r#"{
+        "roots": ["src"],
+        "sniff_json": true,
+        "extensions_map": {}
+    }"#);
+    project.write(&data("src/mobs/ghost.json"), r#"{
+        "minecraft:entity": {"description": {"identifier": "foo:ghost"}}
+    }"#);
+    project.write(&data("src/ghost_drops.json"), r#"{"pools": []}"#);
+    project.write(&data("src/notes.json"), r#"{"notes": []}"#);
+    let output = project.run_ok(&["plan"]);
+    assert!(output.contains("(content)"), "{}", output);
+    project.run_ok(&[]);
+    assert!(project.exists("BP/entities/mobs/ghost.json"));
+    assert!(project.exists("BP/loot_tables/ghost_drops.json"));
+    assert!(!project.exists("BP/notes.json"));
+}
+
+#[test]
+fn order_prefixes_are_stripped_from_the_target_names() {
+    let project = Project::new("order_prefixes", r#"{
+        "roots": ["src"],
+        "strip_order_prefixes": true,
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/010_setup.mcfunction"), "say setup\n");
+    project.write(&data("src/020_/tick.mcfunction"), "say tick\n");
+    project.write(&data("src/010_.mcfunction"), "say empty\n");
+    project.write(&data("src/v2_spawn.mcfunction"), "say spawn\n");
+    project.run_ok(&[]);
+    assert_eq!(project.read("BP/functions/setup.mcfunction"), "say setup\n");
+    // Only the names of the files are stripped
+    assert!(project.exists("BP/functions/020_/tick.mcfunction"));
+    assert!(project.exists("BP/functions/010_.mcfunction"));
+    assert!(project.exists("BP/functions/v2_spawn.mcfunction"));
+}
+
+#[test]
+fn rename_rules_change_the_names_of_the_targets() {
+    let project = Project::new("rename", r#"{
+        "roots": ["src"],
+        "namespace": "ns",
+        "rename": [
+            {"strip_prefix": "wip_"},
+            {"add_suffix": "_{namespace}", "extension": ".mcfunction"}
+        ],
+        "extensions_map": {
+            ".mcfunction": "BP/functions",
+            ".bpe.json": "BP/entities"
+        }
+    }"#);
+    project.write(&data("src/wip_boss.mcfunction"), "say boss\n");
+    project.write(&data("src/wip_ghost.bpe.json"), "{}");
+    project.run_ok(&[]);
+    assert_eq!(project.read("BP/functions/boss_ns.mcfunction"), "say boss\n");
+    assert!(project.exists("BP/entities/ghost.bpe.json"));
+}
+
+#[test]
+fn texture_atlases_stitch_the_sprites_of_the_atlas_folders() {
+    let project = Project::new("texture_atlases", r#"{
+        "roots": ["src"],
+        "texture_atlases": true,
+        "extensions_map": {".png": "RP/textures"}
+    }"#);
+    let red = [255, 0, 0, 255].repeat(4);
+    let blue = [0, 0, 255, 255].repeat(2);
+    project.write(&data("src/ui/buttons.atlas/ok.png"), png(2, 2, &red));
+    project.write(&data("src/ui/buttons.atlas/cancel.png"), png(2, 1, &blue));
+    project.run_ok(&[]);
+    let atlas = fs::read(project.path("RP/textures/ui/buttons.png")).unwrap();
+    assert!(atlas.starts_with(b"\x89PNG"));
+    let uvs: serde_json::Value = serde_json::from_str(
+        &project.read("RP/textures/ui/buttons.atlas.json")
+    ).unwrap();
+    assert_eq!(uvs["texture"], "textures/ui/buttons");
+    assert_eq!(uvs["sprites"]["ok"]["uv_size"], serde_json::json!([2, 2]));
+    assert_eq!(
+        uvs["sprites"]["cancel"]["uv_size"], serde_json::json!([2, 1])
+    );
+    // The sprites aren't copied
+    assert!(!project.exists("RP/textures/ui/buttons.atlas"));
+}
+
+#[test]
+fn flipbooks_are_generated_from_the_frame_folders() {
+    let project = Project::new("flipbooks", r#"{
+        "roots": ["src"],
+        "flipbooks": true,
+        "extensions_map": {".png": "RP/textures"}
+    }"#);
+    let frame = [255, 128, 0, 255].repeat(4);
+    for i in 0..3 {
+        let name = format!("src/blocks/lava_custom/frame_{:02}.png", i);
+        project.write(&data(&name), png(2, 2, &frame));
+    }
+    project.write(
+        &data("src/blocks/lava_custom/flipbook.json"),
+        r#"{"ticks_per_frame": 2}"#,
+    );
+    project.run_ok(&[]);
+    let strip = fs::read(project.path("RP/textures/blocks/lava_custom.png"))
+        .unwrap();
+    // The frames are stacked vertically (the size in the header)
+    assert_eq!(strip[16..24], [0, 0, 0, 2, 0, 0, 0, 6]);
+    let entries: serde_json::Value = serde_json::from_str(
+        &project.read("RP/textures/flipbook_textures.json")
+    ).unwrap();
+    assert_eq!(entries, serde_json::json!([{
+        "flipbook_texture": "textures/blocks/lava_custom",
+        "atlas_tile": "lava_custom",
+        "ticks_per_frame": 2
+    }]));
+    assert!(!project.exists("RP/textures/blocks/lava_custom"));
+}
+
+#[test]
+fn sound_events_are_wired_into_the_packs() {
+    let project = Project::new("sound_events", r#"{
+        "roots": ["src"],
+        "sound_events": true,
+        "extensions_map": {".rpe.json": "RP/entity", ".ogg": "RP/sounds"}
+    }"#);
+    project.write(&data("src/zombie/zombie.rpe.json"), r#"{
+        "minecraft:client_entity": {
+            "description": {"identifier": "ns:zombie"}
+        }
+    }"#);
+    project.write(&data("src/zombie/events/hurt/hurt1.ogg"), "ogg");
+    project.write(&data("src/zombie/events/hurt/hurt2.ogg"), "ogg");
+    project.run_ok(&[]);
+    assert!(project.exists("RP/sounds/zombie/events/hurt/hurt1.ogg"));
+    let definitions: serde_json::Value = serde_json::from_str(
+        &project.read("RP/sounds/sound_definitions.json")
+    ).unwrap();
+    assert_eq!(
+        definitions["sound_definitions"]["ns.zombie.hurt"]["sounds"],
+        serde_json::json!([
+            "sounds/zombie/events/hurt/hurt1",
+            "sounds/zombie/events/hurt/hurt2"
+        ])
+    );
+    let sounds: serde_json::Value = serde_json::from_str(
+        &project.read("RP/sounds.json")
+    ).unwrap();
+    assert_eq!(
+        sounds["entity_sounds"]["entities"]["ns:zombie"]["events"]["hurt"],
+        "ns.zombie.hurt"
+    );
+}
+
+#[test]
+fn trade_tables_are_compiled_from_the_spreadsheets() {
+    let project = Project::new("trade_tables", r#"{
+        "roots": ["src"],
+        "trade_tables": true,
+        "extensions_map": {}
+    }"#);
+    project.write(&data("src/villager.tt.csv"), concat!(
+        "tier,wants,wants_quantity,gives,gives_quantity,max_uses\n",
+        "2,minecraft:emerald,5,minecraft:diamond,1,3\n",
+        "1,minecraft:wheat,20-30,minecraft:emerald,,\n",
+    ));
+    project.run_ok(&[]);
+    let table: serde_json::Value = serde_json::from_str(
+        &project.read("BP/trading/villager.tt.json")
+    ).unwrap();
+    assert_eq!(table, serde_json::json!({"tiers": [
+        {"trades": [{
+            "wants": [{
+                "item": "minecraft:wheat",
+                "quantity": {"min": 20, "max": 30}
+            }],
+            "gives": [{"item": "minecraft:emerald"}]
+        }]},
+        {"trades": [{
+            "wants": [{"item": "minecraft:emerald", "quantity": 5}],
+            "gives": [{"item": "minecraft:diamond", "quantity": 1}],
+            "max_uses": 3
+        }]}
+    ]}));
+    assert!(!project.exists("BP/trading/villager.tt.csv"));
+}
+
+#[test]
+fn loot_tables_are_compiled_from_the_spreadsheets() {
+    let project = Project::new("loot_tables", r#"{
+        "roots": ["src"],
+        "loot_tables": true,
+        "extensions_map": {}
+    }"#);
+    project.write(&data("src/chest.lt.csv"), concat!(
+        "pool,rolls,type,name,weight,count,functions\n",
+        "1,1-3,item,minecraft:apple,5,2-4,\n",
+        "1,,empty,,1,,\n",
+        "2,,item,minecraft:diamond,,,enchant_randomly\n",
+    ));
+    project.write(&data("src/broken.lt.csv"), concat!(
+        "pool,name,weight\n",
+        "1,minecraft:apple,0\n",
+    ));
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"weight\" has to be positive"), "{}", stderr);
+    assert!(!project.exists("BP/loot_tables/broken.lt.json"));
+    let table: serde_json::Value = serde_json::from_str(
+        &project.read("BP/loot_tables/chest.lt.json")
+    ).unwrap();
+    assert_eq!(table, serde_json::json!({"pools": [
+        {"rolls": {"min": 1, "max": 3}, "entries": [
+            {
+                "type": "item", "name": "minecraft:apple", "weight": 5,
+                "functions": [
+                    {"function": "set_count", "count": {"min": 2, "max": 4}}
+                ]
+            },
+            {"type": "empty", "weight": 1}
+        ]},
+        {"rolls": 1, "entries": [{
+            "type": "item", "name": "minecraft:diamond",
+            "functions": [{"function": "enchant_randomly"}]
+        }]}
+    ]}));
+}
+
+#[test]
+fn markdown_dialogues_are_compiled_into_npc_dialogues() {
+    let project = Project::new("markdown_dialogue", r#"{
+        "roots": ["src"],
+        "markdown_dialogue": true,
+        "extensions_map": {}
+    }"#);
+    project.write(&data("src/villager.d.md"), concat!(
+        "---\n",
+        "npc_name: Bob\n",
+        "---\n",
+        "# intro\n",
+        "Hello!\n",
+        "> open /say hi\n",
+        "- [Trade](/dialogue open @s @initiator shop)\n",
+        "  - /say trading\n",
+        "# shop: Shopkeeper\n",
+        "What do you need?\n",
+    ));
+    project.run_ok(&[]);
+    let dialogue: serde_json::Value = serde_json::from_str(
+        &project.read("BP/dialogue/villager.d.json")
+    ).unwrap();
+    assert_eq!(dialogue, serde_json::json!({
+        "format_version": "1.17.0",
+        "minecraft:npc_dialogue": {"scenes": [
+            {
+                "scene_tag": "intro",
+                "npc_name": "Bob",
+                "text": "Hello!",
+                "on_open_commands": ["/say hi"],
+                "buttons": [{
+                    "name": "Trade",
+                    "commands": [
+                        "/dialogue open @s @initiator shop", "/say trading"
+                    ]
+                }]
+            },
+            {
+                "scene_tag": "shop",
+                "npc_name": "Shopkeeper",
+                "text": "What do you need?"
+            }
+        ]}
+    }));
+}
+
+#[test]
+fn recipe_shorthand_is_compiled_into_the_recipes() {
+    let project = Project::new("recipe_shorthand", r#"{
+        "roots": ["src"],
+        "recipe_shorthand": true,
+        "extensions_map": {}
+    }"#);
+    project.write(&data("src/tools.recipes"), concat!(
+        "// The frames\n",
+        "ns:iron_frame -> ns:iron_frame 2\n",
+        "###\n",
+        "# #\n",
+        "# = minecraft:iron_ingot\n",
+        "\n",
+        "shapeless ns:mix -> ns:mix 4\n",
+        "@tags stonecutter\n",
+        "minecraft:sand 2\n",
+        "minecraft:gravel\n",
+    ));
+    project.run_ok(&[]);
+    let frame: serde_json::Value = serde_json::from_str(
+        &project.read("BP/recipes/iron_frame.r.json")
+    ).unwrap();
+    assert_eq!(frame["minecraft:recipe_shaped"], serde_json::json!({
+        "description": {"identifier": "ns:iron_frame"},
+        "tags": ["crafting_table"],
+        "pattern": ["###", "# #"],
+        "key": {"#": {"item": "minecraft:iron_ingot"}},
+        "result": {"item": "ns:iron_frame", "count": 2}
+    }));
+    let mix: serde_json::Value = serde_json::from_str(
+        &project.read("BP/recipes/mix.r.json")
+    ).unwrap();
+    assert_eq!(mix["minecraft:recipe_shapeless"], serde_json::json!({
+        "description": {"identifier": "ns:mix"},
+        "tags": ["stonecutter"],
+        "ingredients": [
+            {"item": "minecraft:sand", "count": 2},
+            {"item": "minecraft:gravel"}
+        ],
+        "result": {"item": "ns:mix", "count": 4}
+    }));
+}
+
+#[test]
+fn functions_are_preprocessed_with_the_directives() {
+    let project = Project::new("preprocess", r#"{
+        "roots": ["src"],
+        "preprocess_functions": true,
+        "constants": {"MAX_HEALTH": 40},
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/macros.mcfunction"), concat!(
+        "#macro give_kit(target, count)\n",
+        "give ${target} bread ${count}\n",
+        "#endmacro\n",
+    ));
+    project.write(&data("src/setup.mcfunction"), concat!(
+        "#include <macros.mcfunction>\n",
+        "#define TEAM red\n",
+        "say ${TEAM} ${MAX_HEALTH}\n",
+        "#expand give_kit(@a, 2)\n",
+    ));
+    project.run_ok(&[]);
+    assert_eq!(
+        project.read("BP/functions/setup.mcfunction"),
+        "say red 40\ngive @a bread 2\n"
+    );
+}
+
+#[test]
+fn umbrella_functions_call_the_functions_of_the_folders() {
+    let project = Project::new("umbrella", r#"{
+        "roots": ["src"],
+        "umbrella_functions": ["setup"],
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/setup/scores.mcfunction"), "say scores\n");
+    project.write(&data("src/setup/teams.mcfunction"), "say teams\n");
+    project.write(&data("src/setup/deep/x.mcfunction"), "say x\n");
+    project.write(&data("src/tick/loop.mcfunction"), "say loop\n");
+    project.run_ok(&[]);
+    assert_eq!(
+        project.read("BP/functions/setup/_all.mcfunction"),
+        "function setup/scores\nfunction setup/teams\n"
+    );
+    assert!(!project.exists("BP/functions/setup/deep/_all.mcfunction"));
+    assert!(!project.exists("BP/functions/tick/_all.mcfunction"));
+}
+
+#[test]
+fn tick_annotations_add_the_functions_to_the_tick_json() {
+    let project = Project::new("tick_annotations", r#"{
+        "roots": ["src"],
+        "tick_annotations": true,
+        "extensions_map": {".mcfunction": "BP/functions"}
+    }"#);
+    project.write(&data("src/loop.mcfunction"), "# @tick\nsay loop\n");
+    project.write(&data("src/setup.mcfunction"), "say setup\n");
+    project.run_ok(&[]);
+    let tick: serde_json::Value = serde_json::from_str(
+        &project.read("BP/functions/tick.json")
+    ).unwrap();
+    assert_eq!(tick, serde_json::json!({"values": ["loop"]}));
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use common::{data, Project};
+use custom_project::config::Config;
+use custom_project::mapping::{Pack, RuleMapper};
+use custom_project::{Executor, MapperChain, Planner};
+
+#[test]
+fn mapper_maps_the_paths_to_the_packs() {
+    let mapper = MapperChain::new().with(RuleMapper::new(HashMap::from([
+        (".bpe.json".to_string(), "BP/entities".to_string()),
+    ])));
+    let path = Path::new("mobs/zombie/_.bpe.json");
+    assert_eq!(mapper.rule(path).as_deref(), Some(".bpe.json"));
+    let target = mapper.target(path).unwrap();
+    assert_eq!(target.path, Path::new("BP/entities/mobs/zombie.bpe.json"));
+    assert_eq!(target.pack, Pack::Behavior);
+    assert_eq!(target.category, "entities");
+    assert_eq!(target.rule, ".bpe.json");
+    let path = Path::new("notes.txt");
+    assert_eq!(mapper.target(path), None);
+    assert!(mapper.skip_reason(path).starts_with("None of the rules"));
+}
+
+#[test]
+fn planner_and_executor_copy_the_files() {
+    let project = Project::new("library", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    let config = Config::load(&project.dir).unwrap();
+    let files = Planner::from_config(&project.dir, &config).plan().unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(
+        files[0].target.as_ref().map(|target| target.path.as_path()),
+        Some(Path::new("BP/entities/zombie.bpe.json"))
+    );
+    // Nothing is copied before executing the plan
+    assert!(!project.exists("BP"));
+    Executor::new(&project.dir, Default::default())
+        .execute(&MapperChain::from_config(&config), &config.roots)
+        .unwrap();
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"zombie\"");
+}
+
+#[test]
+fn operations_can_be_modified_before_executing_them() {
+    let project = Project::new("library_operations", r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#);
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.write(&data("src/skeleton.bpe.json"), "\"skeleton\"");
+    let config = Config::load(&project.dir).unwrap();
+    let executor = Executor::new(&project.dir, Default::default());
+    let mut operations = executor
+        .plan(&MapperChain::from_config(&config), &config.roots)
+        .unwrap();
+    assert_eq!(operations.len(), 2);
+    // Nothing is copied before executing the operations
+    assert!(!project.exists("BP"));
+    operations.retain(|operation| {
+        !operation.source.ends_with("skeleton.bpe.json")
+    });
+    executor.execute_plan(&operations).unwrap();
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"zombie\"");
+    assert!(!project.exists("BP/entities/skeleton.bpe.json"));
+}
+
+#[test]
+fn virtual_paths_are_mapped_without_the_file_system() {
+    let config = Config::parse(r#"{
+        "roots": ["src"],
+        "extensions_map": {".bpe.json": "BP/entities"}
+    }"#).unwrap();
+    // The working directory doesn't exist
+    let planner = Planner::from_config(Path::new("missing"), &config);
+    let files = planner.plan_paths("src", &[
+        PathBuf::from("zombie/_.bpe.json"), PathBuf::from("notes.txt"),
+    ]);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].target_path(), None);
+    assert_eq!(
+        files[1].target_path().unwrap(),
+        Path::new("BP/entities/zombie.bpe.json")
+    );
+}
+
+#[test]
+fn settings_builder_configures_the_filter_in_code() {
+    let project = Project::new("library_builder", "{}");
+    project.write(&data("src/zombie.bpe.json"), "\"zombie\"");
+    project.write(&data("src/notes/todo.txt"), "todo");
+    let config = Config::builder()
+        .root("src")
+        .rule(".bpe.json", "BP/entities")
+        .fallback("RP/misc")
+        .build()
+        .unwrap();
+    let executor = Executor::new(&project.dir, Default::default());
+    let operations = executor
+        .plan(&MapperChain::from_config(&config), &config.roots)
+        .unwrap();
+    executor.execute_plan(&operations).unwrap();
+    assert_eq!(project.read("BP/entities/zombie.bpe.json"), "\"zombie\"");
+    assert_eq!(project.read("RP/misc/notes/todo.txt"), "todo");
+    // The outputs that don't exist are rejected like in the config file
+    let error = Config::builder().rule(".x", "debug:BP/x").build();
+    assert!(error.is_err());
+}